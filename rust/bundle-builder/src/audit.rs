@@ -0,0 +1,111 @@
+//! Audit log of policy changes, published as Kubernetes Events on the OpaCluster.
+//!
+//! Every bundle builder of the OpaCluster sees the same changes, so only the bundle builder that
+//! stores the resulting bundle snapshot publishes them.
+
+use std::sync::Mutex;
+
+use stackable_opa_crd::{policy::BUNDLE_CLUSTER_LABEL, OpaCluster};
+use stackable_operator::{
+    k8s_openapi::api::core::v1::{ConfigMap, ObjectReference},
+    kube::{
+        runtime::{
+            events::{Event, EventType, Recorder, Reporter},
+            reflector::ObjectRef,
+        },
+        Resource, ResourceExt,
+    },
+};
+
+use crate::{status::MAX_EVENT_NOTE_LENGTH, APP_NAME};
+
+struct PolicyChange {
+    config_map: ObjectRef<ConfigMap>,
+    /// `None` if the ConfigMap was deleted.
+    resource_version: Option<String>,
+}
+
+pub struct PolicyAuditor {
+    recorder: Recorder,
+    cluster_name: String,
+    cluster: ObjectReference,
+    /// The changes since the last bundle was built.
+    pending_changes: Mutex<Vec<PolicyChange>>,
+}
+
+impl PolicyAuditor {
+    pub fn new(
+        client: stackable_operator::kube::Client,
+        cluster_name: &str,
+        namespace: &str,
+    ) -> Self {
+        Self {
+            recorder: Recorder::new(
+                client,
+                Reporter {
+                    controller: APP_NAME.to_string(),
+                    instance: None,
+                },
+            ),
+            cluster_name: cluster_name.to_string(),
+            cluster: ObjectReference {
+                api_version: Some(OpaCluster::api_version(&()).into_owned()),
+                kind: Some(OpaCluster::kind(&()).into_owned()),
+                name: Some(cluster_name.to_string()),
+                namespace: Some(namespace.to_string()),
+                ..ObjectReference::default()
+            },
+            pending_changes: Mutex::default(),
+        }
+    }
+
+    /// Records a changed (or deleted) bundle ConfigMap, to be published along with the next bundle.
+    pub fn record_change(&self, cm: &ConfigMap, deleted: bool) {
+        if cm
+            .labels()
+            .get(BUNDLE_CLUSTER_LABEL)
+            .is_some_and(|cm_cluster_name| *cm_cluster_name != self.cluster_name)
+        {
+            return;
+        }
+        self.pending_changes.lock().unwrap().push(PolicyChange {
+            config_map: ObjectRef::from_obj(cm),
+            resource_version: cm.resource_version().filter(|_| !deleted),
+        });
+    }
+
+    /// Publishes the changes that led to the bundle `revision`, if `publish` is set.
+    ///
+    /// The pending changes are discarded either way, since they are part of the bundle.
+    pub async fn bundle_built(&self, revision: &str, publish: bool) {
+        let changes = std::mem::take(&mut *self.pending_changes.lock().unwrap());
+        if !publish || changes.is_empty() {
+            return;
+        }
+        let changes = changes
+            .iter()
+            .map(|change| match &change.resource_version {
+                Some(resource_version) => {
+                    format!("{} (resourceVersion {resource_version})", change.config_map)
+                }
+                None => format!("{} (deleted)", change.config_map),
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        tracing::info!(bundle.revision = revision, changes, "policies changed");
+        let note = format!("Built bundle revision {revision} after changes to {changes}");
+        let event = Event {
+            type_: EventType::Normal,
+            reason: "PoliciesChanged".to_string(),
+            note: Some(note.chars().take(MAX_EVENT_NOTE_LENGTH).collect()),
+            action: "BuildBundle".to_string(),
+            secondary: None,
+        };
+        if let Err(error) = self.recorder.publish(&event, &self.cluster).await {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to publish Event for policy changes"
+            );
+        }
+    }
+}
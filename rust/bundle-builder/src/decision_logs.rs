@@ -0,0 +1,163 @@
+//! Receives the decision logs uploaded by OPA's decision log plugin and writes them to a dedicated
+//! log file (one JSON object per line), so that they are not intermixed with the server logs and
+//! can be shipped to the Vector aggregator as structured events.
+//!
+//! See <https://www.openpolicyagent.org/docs/latest/management-decision-logs/>.
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use axum::{body::Bytes, http};
+use flate2::read::GzDecoder;
+use snafu::{ensure, ResultExt, Snafu};
+
+/// The maximum size of a decompressed upload, so that small gzip bombs can't exhaust the memory of
+/// the bundle builder. OPA's default upload size limit is 32KiB (compressed).
+const MAX_DECOMPRESSED_UPLOAD_SIZE: u64 = 64 * 1024 * 1024;
+
+#[derive(clap::Args)]
+pub struct DecisionLogArgs {
+    /// The file to write the decision logs uploaded by OPA to.
+    /// Uploaded decision logs are rejected if not specified.
+    #[clap(long, env)]
+    decision_log_file: Option<PathBuf>,
+
+    /// The size after which the decision log file is rotated. Only the previous file is kept
+    /// (with the suffix `.1`).
+    #[clap(long, env, default_value_t = 10 * 1024 * 1024)]
+    decision_log_file_max_size_bytes: u64,
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to decompress decision logs"))]
+    Decompress { source: std::io::Error },
+
+    #[snafu(display("decompressed decision logs exceed {limit} bytes"))]
+    TooLarge { limit: u64 },
+
+    #[snafu(display("failed to parse decision logs"))]
+    Parse { source: serde_json::Error },
+
+    #[snafu(display("failed to open decision log file {path:?}"))]
+    Open {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to rotate decision log file {path:?}"))]
+    Rotate {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to write decision log file {path:?}"))]
+    Write {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+}
+
+impl Error {
+    pub fn status_code(&self) -> http::StatusCode {
+        match self {
+            Error::Decompress { .. } | Error::Parse { .. } => http::StatusCode::BAD_REQUEST,
+            Error::TooLarge { .. } => http::StatusCode::PAYLOAD_TOO_LARGE,
+            Error::Open { .. } | Error::Rotate { .. } | Error::Write { .. } => {
+                http::StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+pub struct DecisionLogWriter {
+    path: PathBuf,
+    max_size_bytes: u64,
+    /// The open decision log file along with its size, opened on the first upload.
+    file: Mutex<Option<(File, u64)>>,
+}
+
+impl DecisionLogWriter {
+    pub fn new(args: &DecisionLogArgs) -> Option<Self> {
+        Some(Self {
+            path: args.decision_log_file.clone()?,
+            max_size_bytes: args.decision_log_file_max_size_bytes,
+            file: Mutex::new(None),
+        })
+    }
+
+    /// Writes an upload of OPA's decision log plugin (a JSON array of decisions, usually gzipped)
+    /// to the decision log file.
+    pub fn write_upload(&self, headers: &http::HeaderMap, body: &Bytes) -> Result<usize, Error> {
+        let gzipped = headers
+            .get(http::header::CONTENT_ENCODING)
+            .is_some_and(|encoding| encoding.as_bytes() == b"gzip");
+        let mut decompressed = Vec::new();
+        let body = if gzipped {
+            // Reading one byte more than the limit tells uploads at the limit from larger ones
+            GzDecoder::new(&**body)
+                .take(MAX_DECOMPRESSED_UPLOAD_SIZE + 1)
+                .read_to_end(&mut decompressed)
+                .context(DecompressSnafu)?;
+            ensure!(
+                decompressed.len() as u64 <= MAX_DECOMPRESSED_UPLOAD_SIZE,
+                TooLargeSnafu {
+                    limit: MAX_DECOMPRESSED_UPLOAD_SIZE
+                }
+            );
+            &decompressed
+        } else {
+            &**body
+        };
+        let decisions =
+            serde_json::from_slice::<Vec<serde_json::Value>>(body).context(ParseSnafu)?;
+        let mut lines = Vec::new();
+        for decision in &decisions {
+            serde_json::to_writer(&mut lines, decision).context(ParseSnafu)?;
+            lines.push(b'\n');
+        }
+        self.append(&lines)?;
+        Ok(decisions.len())
+    }
+
+    fn append(&self, lines: &[u8]) -> Result<(), Error> {
+        let mut open_file = self.file.lock().unwrap();
+        if open_file
+            .as_ref()
+            .is_some_and(|(_, size)| *size + lines.len() as u64 > self.max_size_bytes)
+        {
+            *open_file = None;
+            std::fs::rename(&self.path, rotated_path(&self.path))
+                .context(RotateSnafu { path: &self.path })?;
+        }
+        if open_file.is_none() {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .context(OpenSnafu { path: &self.path })?;
+            let size = file
+                .metadata()
+                .context(OpenSnafu { path: &self.path })?
+                .len();
+            *open_file = Some((file, size));
+        }
+        let (file, size) = open_file
+            .as_mut()
+            .expect("decision log file must have been opened");
+        file.write_all(lines)
+            .context(WriteSnafu { path: &self.path })?;
+        *size += lines.len() as u64;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(".1");
+    rotated.into()
+}
@@ -0,0 +1,165 @@
+//! Reports the bundle that has been delivered to OPA in the bundle delivery ConfigMap of the
+//! OpaCluster (see [`BundleDelivery`]), so that the operator can hold back the deletion of OpaPolicies
+//! until their Rego is no longer served.
+//!
+//! The bundle builders are only allowed to patch this ConfigMap, so the operator does not need any
+//! access to the Pods (such as `pods/proxy`) to find out what they serve.
+
+use std::{sync::Arc, time::Duration};
+
+use serde_json::json;
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::policy::{bundle_delivery_key, BundleDelivery};
+use stackable_operator::{
+    k8s_openapi::{api::core::v1::ConfigMap, chrono::Utc},
+    kube::{
+        api::{Patch, PatchParams},
+        Api,
+    },
+};
+use tokio::sync::watch;
+
+use crate::{
+    downloads::{DownloadTracker, ServedBundle},
+    BundleFuture, APP_NAME,
+};
+
+/// How often the delivery of the latest bundle is checked.
+const REPORT_INTERVAL: Duration = Duration::from_secs(10);
+/// Clients that have not polled for this long are not waited for.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(5 * 60);
+
+#[derive(clap::Args)]
+pub struct DeliveryArgs {
+    /// Name of the ConfigMap (in the watched namespace) to report the bundle delivered to OPA in.
+    #[clap(long, env)]
+    bundle_delivery_config_map: Option<String>,
+
+    /// The node the bundle builder runs on, identifies its report together with the role group.
+    #[clap(long, env)]
+    node_name: Option<String>,
+
+    /// The role group the bundle builder belongs to.
+    #[clap(long, env)]
+    role_group: Option<String>,
+
+    /// The Pod the bundle builder runs in.
+    #[clap(long, env)]
+    pod_name: Option<String>,
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to serialize the delivered bundle"))]
+    Serialize { source: serde_json::Error },
+
+    #[snafu(display("failed to report the delivered bundle in ConfigMap {name:?}"))]
+    Report {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+}
+
+pub struct DeliveryReporter {
+    api: Api<ConfigMap>,
+    name: String,
+    key: String,
+    pod: String,
+}
+
+impl DeliveryReporter {
+    /// Returns `None` if delivery reports are not configured.
+    pub fn new(
+        args: &DeliveryArgs,
+        client: stackable_operator::kube::Client,
+        namespace: &str,
+    ) -> Option<Self> {
+        Some(Self {
+            api: Api::namespaced(client, namespace),
+            name: args.bundle_delivery_config_map.clone()?,
+            key: bundle_delivery_key(args.node_name.as_deref()?, args.role_group.as_deref()?),
+            pod: args.pod_name.clone()?,
+        })
+    }
+
+    /// Regularly reports the latest bundle, once all OPA instances polling it have received it.
+    pub async fn run(
+        self,
+        bundle: Arc<watch::Sender<BundleFuture>>,
+        downloads: Arc<DownloadTracker>,
+    ) {
+        let mut reported = None;
+        let mut interval = tokio::time::interval(REPORT_INTERVAL);
+        loop {
+            interval.tick().await;
+            let Some(delivery) = self.delivered(&bundle, &downloads).await else {
+                continue;
+            };
+            if reported.as_ref() == Some(&delivery) {
+                continue;
+            }
+            match self.report(&delivery).await {
+                Ok(()) => reported = Some(delivery),
+                Err(error) => tracing::warn!(
+                    error = &error as &dyn std::error::Error,
+                    "failed to report the delivered bundle"
+                ),
+            }
+        }
+    }
+
+    /// The latest bundle, if all OPA instances polling it have received it.
+    async fn delivered(
+        &self,
+        bundle: &watch::Sender<BundleFuture>,
+        downloads: &DownloadTracker,
+    ) -> Option<BundleDelivery> {
+        let bundle = bundle.borrow().clone();
+        let bundle = bundle.await;
+        // The policies of stale bundles are not known
+        let bundle = bundle
+            .as_ref()
+            .as_ref()
+            .ok()
+            .filter(|bundle| !bundle.stale)?;
+        let revision = bundle.revision();
+        let pending = downloads.clients().into_iter().any(|(_, downloads)| {
+            downloads.bundle == ServedBundle::Latest
+                && downloads.revision != revision
+                // Clients that stopped polling (such as deleted Pods) are not waited for
+                && (Utc::now() - downloads.last_poll.0)
+                    .to_std()
+                    .map_or(true, |since_last_poll| since_last_poll < CLIENT_TIMEOUT)
+        });
+        (!pending).then(|| BundleDelivery {
+            pod: self.pod.clone(),
+            revision: revision.to_string(),
+            policies: bundle.policies.clone(),
+        })
+    }
+
+    async fn report(&self, delivery: &BundleDelivery) -> Result<(), Error> {
+        tracing::info!(
+            config_map = self.name,
+            revision = delivery.revision,
+            "reporting delivered bundle"
+        );
+        let delivery = serde_json::to_string(delivery).context(SerializeSnafu)?;
+        self.api
+            .patch(
+                &self.name,
+                &PatchParams {
+                    field_manager: Some(APP_NAME.to_string()),
+                    ..PatchParams::default()
+                },
+                &Patch::Merge(json!({
+                    "data": {
+                        &self.key: delivery,
+                    },
+                })),
+            )
+            .await
+            .context(ReportSnafu { name: &self.name })?;
+        Ok(())
+    }
+}
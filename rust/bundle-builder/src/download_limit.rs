@@ -0,0 +1,101 @@
+//! Smooths out the load after a new bundle has been built, when all OPA instances download it at
+//! the same time.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+};
+
+use axum::{
+    body::Body,
+    http,
+    response::{IntoResponse, Response},
+};
+use futures::{stream, StreamExt};
+use stackable_operator::time::Duration;
+use tokio::{
+    sync::{OwnedSemaphorePermit, Semaphore},
+    time::Instant,
+};
+
+/// How long clients should wait before retrying a rejected download.
+const RETRY_AFTER_SECONDS: u64 = 1;
+
+#[derive(clap::Args)]
+pub struct DownloadLimitArgs {
+    /// The maximum number of bundles that are downloaded at the same time.
+    /// Further downloads are rejected with `429 Too Many Requests`, OPA retries them after its backoff.
+    /// Unlimited if not specified.
+    #[clap(long, env)]
+    max_concurrent_bundle_downloads: Option<usize>,
+
+    /// Delays the answers to long polls by a random duration of up to this after a new bundle has
+    /// been built, so that the waiting OPA instances don't all download it at once.
+    #[clap(long, env)]
+    long_polling_jitter: Option<Duration>,
+}
+
+pub struct DownloadLimiter {
+    permits: Option<Arc<Semaphore>>,
+    long_polling_jitter: Option<Duration>,
+}
+
+/// Permits a bundle download, see [`DownloadLimiter::try_acquire`].
+pub struct DownloadPermit(Option<OwnedSemaphorePermit>);
+
+impl DownloadLimiter {
+    pub fn new(args: &DownloadLimitArgs) -> Self {
+        Self {
+            permits: args
+                .max_concurrent_bundle_downloads
+                .map(|max| Arc::new(Semaphore::new(max))),
+            long_polling_jitter: args.long_polling_jitter,
+        }
+    }
+
+    /// Returns `None` if the maximum number of concurrent downloads has been reached.
+    pub fn try_acquire(&self) -> Option<DownloadPermit> {
+        match &self.permits {
+            Some(permits) => permits
+                .clone()
+                .try_acquire_owned()
+                .ok()
+                .map(|permit| DownloadPermit(Some(permit))),
+            None => Some(DownloadPermit(None)),
+        }
+    }
+
+    /// Waits for a random duration of up to the configured jitter, but not past the `deadline` of
+    /// the long poll, so that clients are answered before they give up on the poll.
+    pub async fn long_polling_jitter(&self, deadline: Instant) {
+        if let Some(jitter) = self.long_polling_jitter {
+            // RandomState is randomly seeded, which is good enough for jitter and saves a dependency
+            let random = RandomState::new().build_hasher().finish();
+            let delay = jitter.mul_f64((random as f64) / (u64::MAX as f64));
+            tokio::time::sleep_until(deadline.min(Instant::now() + delay)).await;
+        }
+    }
+}
+
+impl DownloadPermit {
+    /// Sends the tarball, holding the permit until the whole body has been sent.
+    pub fn into_body(self, tarball: Vec<u8>) -> Body {
+        Body::from_stream(
+            stream::iter([Ok::<_, std::convert::Infallible>(tarball)]).map(move |chunk| {
+                let _permit = &self.0;
+                chunk
+            }),
+        )
+    }
+}
+
+/// The response to downloads exceeding the limit.
+pub fn too_many_downloads() -> Response {
+    (
+        http::StatusCode::TOO_MANY_REQUESTS,
+        [(http::header::RETRY_AFTER, RETRY_AFTER_SECONDS.to_string())],
+        "too many concurrent bundle downloads",
+    )
+        .into_response()
+}
@@ -0,0 +1,220 @@
+//! Keeps track of which bundle revision each client (OPA instance) last received, so that OPA
+//! instances that never picked up the latest policies can be identified.
+//!
+//! The OPA instances identify themselves by the [`BUNDLE_CLIENT_POD_HEADER`] and
+//! [`BUNDLE_CLIENT_NODE_HEADER`] headers, clients without them are identified by their address.
+//! The downloads are exposed as JSON at `/status/downloads`, and as Prometheus metrics at `/metrics`.
+
+use std::{collections::BTreeMap, fmt::Write, net::SocketAddr, sync::Mutex};
+
+use axum::http;
+use serde::Serialize;
+use stackable_opa_crd::{BUNDLE_CLIENT_NODE_HEADER, BUNDLE_CLIENT_POD_HEADER};
+use stackable_operator::k8s_openapi::{
+    apimachinery::pkg::apis::meta::v1::Time,
+    chrono::{DateTime, Utc},
+};
+
+/// Clients that have not polled for the longest time are forgotten beyond this, so that clients
+/// identified by (changing) addresses can not grow the state indefinitely.
+const MAX_TRACKED_CLIENTS: usize = 256;
+
+/// The bundle a client polls.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ServedBundle {
+    Latest,
+    Stable,
+}
+
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadClient {
+    /// The name of the OPA Pod, if it was sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pod: Option<String>,
+    /// The node the OPA Pod runs on, if it was sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node: Option<String>,
+    /// The address of the client, only set for clients that don't identify themselves.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+}
+
+impl DownloadClient {
+    pub fn from_request(headers: &http::HeaderMap, address: SocketAddr) -> Self {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        let pod = header(BUNDLE_CLIENT_POD_HEADER);
+        Self {
+            address: pod.is_none().then(|| address.ip().to_string()),
+            node: header(BUNDLE_CLIENT_NODE_HEADER),
+            pod,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientDownloads {
+    pub bundle: ServedBundle,
+    /// The revision of the bundle the client last received (or confirmed to have by polling).
+    pub revision: String,
+    /// The number of bundles downloaded by the client.
+    pub downloads: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_download: Option<Time>,
+    pub last_poll: Time,
+}
+
+#[derive(Default)]
+pub struct DownloadTracker {
+    clients: Mutex<BTreeMap<DownloadClient, ClientDownloads>>,
+}
+
+impl DownloadTracker {
+    /// Records that the bundle `revision` was served to the client, or that the client already had
+    /// it if `downloaded` is `false`.
+    pub fn record(
+        &self,
+        client: DownloadClient,
+        bundle: ServedBundle,
+        revision: &str,
+        downloaded: bool,
+    ) {
+        let now = Time(Utc::now());
+        let mut clients = self.clients.lock().unwrap();
+        if !clients.contains_key(&client) && clients.len() >= MAX_TRACKED_CLIENTS {
+            let least_recently_polled = clients
+                .iter()
+                .min_by_key(|(_, downloads)| downloads.last_poll.0)
+                .map(|(client, _)| client.clone());
+            if let Some(least_recently_polled) = least_recently_polled {
+                clients.remove(&least_recently_polled);
+            }
+        }
+        let downloads = clients.entry(client).or_insert_with(|| ClientDownloads {
+            bundle,
+            revision: revision.to_string(),
+            downloads: 0,
+            last_download: None,
+            last_poll: now.clone(),
+        });
+        downloads.bundle = bundle;
+        downloads.revision = revision.to_string();
+        downloads.last_poll = now.clone();
+        if downloaded {
+            downloads.downloads += 1;
+            downloads.last_download = Some(now);
+        }
+    }
+
+    pub fn clients(&self) -> Vec<(DownloadClient, ClientDownloads)> {
+        self.clients
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(client, downloads)| (client.clone(), downloads.clone()))
+            .collect()
+    }
+}
+
+/// The downloads of a client, as returned by `GET /status/downloads`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClientStatus {
+    #[serde(flatten)]
+    pub client: DownloadClient,
+    #[serde(flatten)]
+    pub downloads: ClientDownloads,
+    /// Whether the client has the revision that is currently served, `None` if no bundle is
+    /// currently available.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub up_to_date: Option<bool>,
+}
+
+/// Renders the downloads in the Prometheus text format.
+pub fn render_metrics(clients: &[ClientStatus]) -> String {
+    let mut metrics = String::new();
+    let mut family =
+        |name: &str, kind: &str, help: &str, value: &dyn Fn(&ClientStatus) -> Option<f64>| {
+            let _ = writeln!(metrics, "# HELP {name} {help}");
+            let _ = writeln!(metrics, "# TYPE {name} {kind}");
+            for client in clients {
+                if let Some(value) = value(client) {
+                    let _ = writeln!(metrics, "{name}{{{}}} {value}", labels(client));
+                }
+            }
+        };
+    family(
+        "opa_bundle_builder_bundle_downloads_total",
+        "counter",
+        "The number of bundles downloaded by the client.",
+        &|client| Some(client.downloads.downloads as f64),
+    );
+    family(
+        "opa_bundle_builder_last_bundle_download_timestamp_seconds",
+        "gauge",
+        "When the client last downloaded a bundle.",
+        &|client| {
+            client
+                .downloads
+                .last_download
+                .as_ref()
+                .map(|time| unix_seconds(&time.0))
+        },
+    );
+    family(
+        "opa_bundle_builder_last_bundle_poll_timestamp_seconds",
+        "gauge",
+        "When the client last polled for a bundle.",
+        &|client| Some(unix_seconds(&client.downloads.last_poll.0)),
+    );
+    family(
+        "opa_bundle_builder_bundle_up_to_date",
+        "gauge",
+        "Whether the client has the bundle revision that is currently served (1) or not (0).",
+        &|client| {
+            client
+                .up_to_date
+                .map(|up_to_date| u8::from(up_to_date).into())
+        },
+    );
+    metrics
+}
+
+fn labels(client: &ClientStatus) -> String {
+    let labels = [
+        ("pod", client.client.pod.as_deref()),
+        ("node", client.client.node.as_deref()),
+        ("address", client.client.address.as_deref()),
+    ];
+    let mut rendered = labels
+        .into_iter()
+        .filter_map(|(name, value)| Some(format!("{name}=\"{}\"", escape_label_value(value?))))
+        .collect::<Vec<_>>();
+    rendered.push(format!(
+        "bundle=\"{}\"",
+        match client.downloads.bundle {
+            ServedBundle::Latest => "latest",
+            ServedBundle::Stable => "stable",
+        }
+    ));
+    rendered.join(",")
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn unix_seconds(time: &DateTime<Utc>) -> f64 {
+    time.timestamp_millis() as f64 / 1000.0
+}
@@ -0,0 +1,116 @@
+//! Injects the faults configured for the bundle builder, see [`stackable_opa_crd::fault_injection`].
+//!
+//! Only compiled with the `fault-injection` feature, which must never be enabled for production builds.
+
+use std::{
+    collections::BTreeMap,
+    future::Future,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use axum::{
+    extract::{Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use stackable_opa_crd::fault_injection::{Faults, BUNDLE_BUILDER_COMPONENT};
+
+use crate::{bundle_error, Bundle, BundleError};
+
+#[derive(clap::Args)]
+pub struct FaultInjectionArgs {
+    /// Path to the fault injection file written by the operator.
+    /// It is read again for every request, so that faults can be changed without restarting.
+    #[clap(long, env)]
+    fault_injection_file: Option<PathBuf>,
+}
+
+pub struct FaultInjector {
+    file: Option<PathBuf>,
+    /// The number of requests so far, used to fail the configured fraction of them.
+    requests: AtomicU64,
+}
+
+impl FaultInjector {
+    pub fn new(args: &FaultInjectionArgs) -> Self {
+        if let Some(file) = &args.fault_injection_file {
+            tracing::warn!(
+                ?file,
+                "fault injection is enabled, do not use this build in production"
+            );
+        }
+        Self {
+            file: args.fault_injection_file.clone(),
+            requests: AtomicU64::new(0),
+        }
+    }
+
+    async fn faults(&self) -> Faults {
+        let Some(file) = &self.file else {
+            return Faults::default();
+        };
+        let annotations = match tokio::fs::read(file).await {
+            Ok(data) => serde_json::from_slice::<BTreeMap<String, String>>(&data)
+                .inspect_err(|error| {
+                    tracing::warn!(
+                        error = error as &dyn std::error::Error,
+                        "ignoring invalid fault injection file"
+                    )
+                })
+                .unwrap_or_default(),
+            // The operator only writes the file if any faults are configured
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(error) => {
+                tracing::warn!(
+                    error = &error as &dyn std::error::Error,
+                    "failed to read fault injection file"
+                );
+                BTreeMap::new()
+            }
+        };
+        Faults::from_annotations(&annotations, BUNDLE_BUILDER_COMPONENT)
+    }
+
+    /// Fails the bundle build if `rebuild-failure` is configured.
+    pub async fn inject_rebuild_faults(
+        self: Arc<Self>,
+        build: impl Future<Output = Result<Bundle, BundleError>>,
+    ) -> Result<Bundle, BundleError> {
+        if self.faults().await.rebuild_failure {
+            tracing::warn!("injecting bundle rebuild failure");
+            return bundle_error::InjectedFaultSnafu.fail();
+        }
+        build.await
+    }
+}
+
+/// Delays requests by `latency`, and fails `error-rate` of them with `503 Service Unavailable`.
+///
+/// The status endpoint used by the probes is left alone, so that the Pods are not restarted.
+pub async fn inject_request_faults(
+    State(injector): State<Arc<FaultInjector>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if request.uri().path() == "/status" {
+        return next.run(request).await;
+    }
+    let faults = injector.faults().await;
+    if let Some(latency) = faults.latency {
+        tokio::time::sleep(*latency).await;
+    }
+    // Deterministic rather than random, so that resilience tests are reproducible
+    let request_number = injector.requests.fetch_add(1, Ordering::Relaxed) as f64;
+    if ((request_number + 1.0) * faults.error_rate).floor()
+        > (request_number * faults.error_rate).floor()
+    {
+        tracing::warn!(path = %request.uri().path(), "injecting request failure");
+        return (StatusCode::SERVICE_UNAVAILABLE, "injected fault").into_response();
+    }
+    next.run(request).await
+}
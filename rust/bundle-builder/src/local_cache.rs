@@ -0,0 +1,72 @@
+//! A copy of the last built bundle on the local disk, for warm starts after the bundle builder
+//! container has been restarted.
+//!
+//! Unlike the bundle snapshot ConfigMap, the local cache works without access to the API server and
+//! for bundles of any size, but it does not survive the Pod.
+
+use std::path::{Path, PathBuf};
+
+use snafu::{ResultExt, Snafu};
+
+#[derive(clap::Args)]
+pub struct LocalCacheArgs {
+    /// Path to store a copy of the last built bundle at, which is served (as stale) after restarts
+    /// until the first bundle has been built, if no bundle snapshot is available.
+    #[clap(long, env)]
+    bundle_cache_file: Option<PathBuf>,
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to read cached bundle from {path:?}"))]
+    Read {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to write cached bundle to {path:?}"))]
+    Write {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+}
+
+pub struct LocalCache {
+    file: PathBuf,
+}
+
+impl LocalCache {
+    /// Returns `None` if the local cache is not configured.
+    pub fn new(args: &LocalCacheArgs) -> Option<Self> {
+        Some(Self {
+            file: args.bundle_cache_file.clone()?,
+        })
+    }
+
+    /// Loads the cached bundle, returns `None` if no bundle has been cached yet.
+    pub async fn load(&self) -> Result<Option<Vec<u8>>, Error> {
+        match tokio::fs::read(&self.file).await {
+            Ok(tarball) => Ok(Some(tarball)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(error) => Err(error).context(ReadSnafu { path: &self.file }),
+        }
+    }
+
+    /// Stores the bundle, replacing the cached bundle atomically so that a crash never leaves a
+    /// truncated bundle behind.
+    pub async fn store(&self, tarball: &[u8]) -> Result<(), Error> {
+        let tmp_file = tmp_path(&self.file);
+        tokio::fs::write(&tmp_file, tarball)
+            .await
+            .context(WriteSnafu { path: &tmp_file })?;
+        tokio::fs::rename(&tmp_file, &self.file)
+            .await
+            .context(WriteSnafu { path: &self.file })
+    }
+}
+
+fn tmp_path(file: &Path) -> PathBuf {
+    let mut tmp_file = file.as_os_str().to_owned();
+    tmp_file.push(".tmp");
+    tmp_file.into()
+}
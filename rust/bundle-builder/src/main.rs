@@ -1,53 +1,249 @@
 use std::{
     collections::{BTreeMap, BTreeSet},
+    future::IntoFuture,
+    io::Read,
+    net::SocketAddr,
     num::TryFromIntError,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
 };
 
-use axum::{extract::State, http, response::IntoResponse, routing::get, Router};
+use audit::PolicyAuditor;
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    http,
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
 use clap::Parser;
+use decision_logs::DecisionLogWriter;
+use delivery::DeliveryReporter;
+use download_limit::DownloadLimiter;
+use downloads::{ClientStatus, DownloadClient, DownloadTracker, ServedBundle};
 use flate2::write::GzEncoder;
 use futures::{
     future::{self, BoxFuture},
     pin_mut, FutureExt, StreamExt, TryFutureExt,
 };
-use snafu::{ResultExt, Snafu};
+use local_cache::LocalCache;
+use manifest::{Manifest, WasmResolver, MANIFEST_FILE_NAME};
+use replication::{PulledBundle, Replicator};
+use scope::{BundleQuery, BundleScope, ScopedBundles};
+use segments::{Segment, SegmentCache};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use signing::BundleSigner;
+use simulation::{BundleHistory, SimulationRequest};
+use snafu::{OptionExt, ResultExt, Snafu};
+use snapshot::Snapshotter;
+use stackable_opa_crd::{
+    policy::{rego_package_name, BUNDLE_CLUSTER_LABEL, BUNDLE_LABEL, BUNDLE_POLICY_LABEL},
+    OpaCluster,
+};
 use stackable_operator::{
     k8s_openapi::api::core::v1::ConfigMap,
     kube::{
-        api::ObjectMeta,
         runtime::{
             reflector::{self, ObjectRef, Store},
             watcher,
         },
+        Api, ResourceExt,
     },
+    namespace::WatchNamespace,
 };
-use tokio::net::TcpListener;
+use status::{StatusCollector, StatusReport};
+use tokio::{net::TcpListener, sync::watch};
+
+mod audit;
+mod decision_logs;
+mod delivery;
+mod download_limit;
+mod downloads;
+#[cfg(feature = "fault-injection")]
+mod fault_injection;
+mod local_cache;
+mod manifest;
+mod replication;
+mod scope;
+mod segments;
+mod signing;
+mod simulation;
+mod snapshot;
+mod stable;
+mod status;
 
-const OPERATOR_NAME: &str = "opa.stackable.tech";
 pub const APP_NAME: &str = "opa-bundle-builder";
+/// How long pushing a bundle to the replication peer or storing the snapshot may take, so that a
+/// hanging peer or API server can not hold up the bundle builds.
+const SIDE_EFFECT_TIMEOUT: Duration = Duration::from_secs(30);
 
 #[derive(clap::Parser)]
 pub struct Args {
+    /// The name of the OpaCluster the bundle is built for.
+    /// ConfigMaps restricted to other OpaClusters are not added to the bundle.
+    #[clap(long, env)]
+    cluster_name: Option<String>,
+
+    /// Whether to add the built-in regorule library (such as `stackable.opa.userinfo.v1`) to the bundle.
+    #[clap(long, env, default_value_t = true, action = clap::ArgAction::Set)]
+    include_builtin_rego_rules: bool,
+
+    /// Create the `active`, `incoming` and `tmp` directories in this directory at startup (emptying
+    /// `tmp`), which is otherwise done by the `prepare` init container.
+    #[clap(long, env)]
+    prepare_bundles_dir: Option<PathBuf>,
+
+    /// Namespaces whose ConfigMaps are never added to the bundle, even if they are labeled as
+    /// bundle ConfigMaps (such as `kube-system`, or namespaces of untrusted tenants).
+    /// Mostly relevant when watching all namespaces.
+    #[clap(long, env, value_delimiter = ',')]
+    excluded_namespaces: Vec<String>,
+
+    #[clap(flatten)]
+    signing: signing::SigningArgs,
+
+    #[clap(flatten)]
+    replication: replication::ReplicationArgs,
+
+    #[clap(flatten)]
+    snapshot: snapshot::SnapshotArgs,
+
+    #[clap(flatten)]
+    delivery: delivery::DeliveryArgs,
+
+    #[clap(flatten)]
+    stable: stable::StableBundleArgs,
+
+    #[clap(flatten)]
+    local_cache: local_cache::LocalCacheArgs,
+
+    #[clap(flatten)]
+    download_limit: download_limit::DownloadLimitArgs,
+
+    #[clap(flatten)]
+    decision_logs: decision_logs::DecisionLogArgs,
+
+    #[clap(flatten)]
+    simulation: simulation::SimulationArgs,
+
+    #[cfg(feature = "fault-injection")]
+    #[clap(flatten)]
+    fault_injection: fault_injection::FaultInjectionArgs,
+
     #[clap(flatten)]
     common: stackable_operator::cli::ProductOperatorRun,
 }
 
-type Bundle = Vec<u8>;
+struct Bundle {
+    tarball: Vec<u8>,
+    packages: Vec<RegoPackage>,
+    /// The SHA-256 hash of the tarball, so that OPA only downloads changed bundles.
+    etag: String,
+    /// The revision of the manifest of the bundle, see [`Bundle::revision`].
+    revision: String,
+    /// Set for bundles served after a restart (or built before the ConfigMaps have been loaded),
+    /// which might not reflect the current policies.
+    stale: bool,
+    /// The OpaPolicies (see [`BUNDLE_POLICY_LABEL`]) whose Rego is part of the bundle, empty for
+    /// bundles that were not built locally.
+    policies: BTreeSet<String>,
+}
+
+impl Bundle {
+    fn new(tarball: Vec<u8>, packages: Vec<RegoPackage>, revision: String) -> Self {
+        let etag = format!("\"{:x}\"", Sha256::digest(&tarball));
+        Self {
+            tarball,
+            packages,
+            etag,
+            revision,
+            stale: false,
+            policies: BTreeSet::new(),
+        }
+    }
+
+    /// The revision of the manifest of the bundle (see [`Manifest::new`]), which OPA reports as the
+    /// active revision of the bundle. It identifies the bundle everywhere else as well, such as in
+    /// the bundle snapshot and the status of the OpaCluster.
+    fn revision(&self) -> &str {
+        &self.revision
+    }
+}
+
+/// A Rego package that is part of the bundle, as returned by `GET /packages`.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RegoPackage {
+    /// The name of the package, e.g. `stackable.opa.userinfo.v1`.
+    package: String,
+    /// The path of the file inside the bundle that declares the package.
+    file: String,
+    /// The ConfigMap the file was collected from, or `None` for the built-in regorule library.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    config_map: Option<RegoPackageConfigMap>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RegoPackageConfigMap {
+    name: String,
+    namespace: String,
+}
+
 type BundleFuture = future::Shared<BoxFuture<'static, Arc<Result<Bundle, BundleError>>>>;
 
 #[derive(Clone)]
 struct AppState {
-    bundle: Arc<Mutex<BundleFuture>>,
+    /// The current bundle, long polls subscribe to it to be notified about new bundles.
+    bundle: Arc<watch::Sender<BundleFuture>>,
+    /// The bundle served to role groups that are not part of the canary, the same as `bundle` if
+    /// canary rollouts are disabled.
+    stable_bundle: Arc<watch::Sender<BundleFuture>>,
+    /// Only set if the OpaCluster is known, which is required to publish Events.
+    status_collector: Option<Arc<StatusCollector>>,
+    /// Signs the scoped bundles, if bundle signing is enabled.
+    signer: Option<Arc<BundleSigner>>,
+    scoped_bundles: Arc<ScopedBundles>,
+    /// Limits the concurrent bundle downloads.
+    download_limiter: Arc<DownloadLimiter>,
+    /// The bundle revisions last received by the clients.
+    downloads: Arc<DownloadTracker>,
+    /// Writes the decision logs uploaded by OPA, if a decision log file is configured.
+    decision_log_writer: Option<Arc<DecisionLogWriter>>,
+    /// The last built bundle revisions, which decision inputs can be evaluated against.
+    bundle_history: Arc<BundleHistory>,
+}
+
+impl AppState {
+    fn served_bundle(&self, served: ServedBundle) -> &watch::Sender<BundleFuture> {
+        match served {
+            ServedBundle::Latest => &self.bundle,
+            ServedBundle::Stable => &self.stable_bundle,
+        }
+    }
 }
 
 #[derive(Snafu, Debug)]
 enum StartupError {
+    #[snafu(display("failed to prepare bundle directory {path:?}"))]
+    PrepareBundlesDir {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
     #[snafu(display("failed to initialize Kubernetes client"))]
     InitKube {
         source: stackable_operator::client::Error,
     },
 
+    #[snafu(display("failed to load bundle signing key"))]
+    LoadSigningKey { source: signing::Error },
+
+    #[snafu(display("failed to initialize bundle replication"))]
+    InitReplication { source: replication::Error },
+
     #[snafu(display("failed to get listener address"))]
     GetListenerAddr { source: std::io::Error },
 
@@ -61,6 +257,46 @@ enum StartupError {
     RunServer { source: std::io::Error },
 }
 
+/// Creates the bundle directories, removing leftovers of previous runs from the temporary directory.
+async fn prepare_bundles_dir(bundles_dir: &Path) -> Result<(), StartupError> {
+    let tmp_dir = bundles_dir.join("tmp");
+    match tokio::fs::remove_dir_all(&tmp_dir).await {
+        Ok(()) => {}
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+        Err(error) => return Err(error).context(PrepareBundlesDirSnafu { path: tmp_dir }),
+    }
+    for dir in ["active", "incoming", "tmp"] {
+        let path = bundles_dir.join(dir);
+        tokio::fs::create_dir_all(&path)
+            .await
+            .context(PrepareBundlesDirSnafu { path: &path })?;
+        tracing::debug!(?path, "created bundle directory");
+    }
+    Ok(())
+}
+
+/// Selects the labeled bundle ConfigMaps, except for those in the excluded namespaces.
+///
+/// The namespaces are excluded by a field selector, so that the ConfigMaps are never even seen by the
+/// bundle builder (regardless of whether a single namespace or all namespaces are watched).
+fn bundle_config_map_watcher_config(excluded_namespaces: &[String]) -> watcher::Config {
+    let config = watcher::Config::default().labels(BUNDLE_LABEL);
+    if excluded_namespaces.is_empty() {
+        return config;
+    }
+    tracing::info!(
+        ?excluded_namespaces,
+        "ConfigMaps in excluded namespaces are not added to the bundle"
+    );
+    config.fields(
+        &excluded_namespaces
+            .iter()
+            .map(|namespace| format!("metadata.namespace!={namespace}"))
+            .collect::<Vec<_>>()
+            .join(","),
+    )
+}
+
 #[tokio::main]
 async fn main() -> Result<(), StartupError> {
     let args = Args::parse();
@@ -71,18 +307,119 @@ async fn main() -> Result<(), StartupError> {
         args.common.tracing_target,
     );
 
+    if let Some(bundles_dir) = &args.prepare_bundles_dir {
+        prepare_bundles_dir(bundles_dir).await?;
+    }
+
     let client =
         stackable_operator::client::initialize_operator(None, &args.common.cluster_info_opts)
             .await
             .context(InitKubeSnafu)?;
 
+    let signer = BundleSigner::from_args(&args.signing)
+        .context(LoadSigningKeySnafu)?
+        .map(Arc::new);
+    if signer.is_some() {
+        tracing::info!("bundles will be signed");
+    }
+
+    let replicator = Arc::new(Replicator::new(&args.replication).context(InitReplicationSnafu)?);
+
+    let snapshotter = match &args.common.watch_namespace {
+        WatchNamespace::One(namespace) => {
+            // Promoted bundles are taken from the snapshot, so every bundle needs to be snapshotted
+            Snapshotter::new(
+                &args.snapshot,
+                client.as_kube_client(),
+                namespace,
+                args.stable.config_map().is_some(),
+            )
+            .map(Arc::new)
+        }
+        WatchNamespace::All => None,
+    };
+    let delivery_reporter = match &args.common.watch_namespace {
+        WatchNamespace::One(namespace) => {
+            DeliveryReporter::new(&args.delivery, client.as_kube_client(), namespace)
+        }
+        WatchNamespace::All => None,
+    };
+    let local_cache = LocalCache::new(&args.local_cache).map(Arc::new);
+    let mut snapshot = match &snapshotter {
+        Some(snapshotter) => load_snapshot(snapshotter).await,
+        None => None,
+    };
+    if snapshot.is_none() {
+        if let Some(local_cache) = &local_cache {
+            snapshot = load_cached_bundle(local_cache).await;
+        }
+    }
+
+    let auditor = match (&args.cluster_name, &args.common.watch_namespace) {
+        (Some(cluster_name), WatchNamespace::One(namespace)) if snapshotter.is_some() => {
+            Some(Arc::new(PolicyAuditor::new(
+                client.as_kube_client(),
+                cluster_name,
+                namespace,
+            )))
+        }
+        _ => {
+            tracing::info!("policy changes will not be published as Events");
+            None
+        }
+    };
+
+    #[cfg(feature = "fault-injection")]
+    let fault_injector = Arc::new(fault_injection::FaultInjector::new(&args.fault_injection));
+
     let (store, store_w) = reflector::store();
-    let rebuild_bundle = || {
+    let segment_cache = Arc::new(SegmentCache::default());
+    let bundle_history = Arc::new(BundleHistory::new(&args.simulation));
+    // Bundles are only snapshotted once the reflector has caught up, so that a bundle built from an
+    // incomplete view of the ConfigMaps can never replace a good snapshot
+    let rebuild_bundle = |snapshot_bundle: bool| {
         tracing::info!("bundle invalidated, will be rebuilt on next request");
-        // Even if build_bundle is completely synchronous (currently),
-        // storing a Future acts as a primitive laziness/debouncing mechanism,
+        let replicator = replicator.clone();
+        let snapshotter = snapshotter.clone().filter(|_| snapshot_bundle);
+        let local_cache = local_cache.clone().filter(|_| snapshot_bundle);
+        let auditor = auditor.clone();
+        let bundle_history = Some(bundle_history.clone()).filter(|_| snapshot_bundle);
+        // Storing a Future acts as a primitive laziness/debouncing mechanism,
         // the bundle will only actually be built once it is requested.
-        build_bundle(store.clone())
+        let build = build_bundle(
+            store.clone(),
+            segment_cache.clone(),
+            args.cluster_name.clone(),
+            args.include_builtin_rego_rules,
+            signer.clone(),
+        );
+        #[cfg(feature = "fault-injection")]
+        let build = fault_injector.clone().inject_rebuild_faults(build);
+        build
+            .and_then(move |bundle| async move {
+                let bundle = Bundle {
+                    stale: !snapshot_bundle,
+                    ..bundle
+                };
+                // A stale bundle may have been built before all policies were known, it must not
+                // replace the bundle of the replication peer
+                if !bundle.stale {
+                    push_bundle(&replicator, &bundle).await;
+                }
+                if let Some(bundle_history) = &bundle_history {
+                    bundle_history.record(&bundle);
+                }
+                if let Some(local_cache) = &local_cache {
+                    store_cached_bundle(local_cache, &bundle).await;
+                }
+                if let Some(snapshotter) = &snapshotter {
+                    let stored = store_snapshot(snapshotter, &bundle).await;
+                    if let Some(auditor) = &auditor {
+                        auditor.bundle_built(bundle.revision(), stored).await;
+                    }
+                }
+                Ok(bundle)
+            })
             .inspect_err(|error| {
                 tracing::error!(
                     error = error as &dyn std::error::Error,
@@ -93,52 +430,97 @@ async fn main() -> Result<(), StartupError> {
             .boxed()
             .shared()
     };
-    let bundle = Arc::new(Mutex::new(rebuild_bundle()));
-    let reflector = std::pin::pin!(reflector::reflector(
-        store_w,
-        watcher(
-            args.common.watch_namespace.get_api::<ConfigMap>(&client),
-            watcher::Config::default().labels(&format!("{OPERATOR_NAME}/bundle")),
-        ),
-    )
-    .for_each(|ev| async {
-        let rebuild = match ev {
-            Ok(watcher::Event::Apply(o)) => {
-                tracing::info!(object = %ObjectRef::from_obj(&o), "saw updated object");
-                true
-            }
-            Ok(watcher::Event::Delete(o)) => {
-                tracing::info!(object = %ObjectRef::from_obj(&o), "saw deleted object");
-                true
-            }
-            Ok(watcher::Event::Init) => {
-                tracing::info!("restart initiated");
-                false
-            }
-            Ok(watcher::Event::InitApply(o)) => {
-                tracing::info!(object = %ObjectRef::from_obj(&o), "saw updated object (waiting for restart to complete before rebuilding)");
-                false
-            }
-            Ok(watcher::Event::InitDone) => {
-                tracing::info!("restart done");
-                true
-            }
-            Err(error) => {
-                tracing::error!(
-                    error = &error as &dyn std::error::Error,
-                    "failed to update reflector"
-                );
-                false
+    let bundle = Arc::new(watch::Sender::new(if let Some(snapshot) = snapshot {
+        tracing::info!("serving stale bundle until the first bundle is ready");
+        future::ready(Arc::new(Ok(snapshot))).boxed().shared()
+    } else if replicator.is_pulling() {
+        future::ready(Arc::new(bundle_error::NotReplicatedYetSnafu.fail()))
+            .boxed()
+            .shared()
+    } else {
+        rebuild_bundle(false)
+    }));
+    let bundle_source = if replicator.is_pulling() {
+        tracing::info!(
+            "bundles will be pulled from the replication primary instead of being built"
+        );
+        future::Either::Left(pull_bundles(
+            replicator.clone(),
+            snapshotter.clone(),
+            local_cache.clone(),
+            bundle.clone(),
+        ))
+    } else {
+        future::Either::Right(reflector::reflector(
+            store_w,
+            watcher(
+                args.common.watch_namespace.get_api::<ConfigMap>(&client),
+                bundle_config_map_watcher_config(&args.excluded_namespaces),
+            ),
+        )
+        .for_each(|ev| async {
+            let rebuild = match ev {
+                Ok(watcher::Event::Apply(o)) => {
+                    tracing::info!(object = %ObjectRef::from_obj(&o), "saw updated object");
+                    if let Some(auditor) = &auditor {
+                        auditor.record_change(&o, false);
+                    }
+                    true
+                }
+                Ok(watcher::Event::Delete(o)) => {
+                    tracing::info!(object = %ObjectRef::from_obj(&o), "saw deleted object");
+                    if let Some(auditor) = &auditor {
+                        auditor.record_change(&o, true);
+                    }
+                    true
+                }
+                Ok(watcher::Event::Init) => {
+                    tracing::info!("restart initiated");
+                    false
+                }
+                Ok(watcher::Event::InitApply(o)) => {
+                    tracing::info!(object = %ObjectRef::from_obj(&o), "saw updated object (waiting for restart to complete before rebuilding)");
+                    false
+                }
+                Ok(watcher::Event::InitDone) => {
+                    tracing::info!("restart done");
+                    true
+                }
+                Err(error) => {
+                    tracing::error!(
+                        error = &error as &dyn std::error::Error,
+                        "failed to update reflector"
+                    );
+                    false
+                }
+            };
+            if rebuild {
+                tracing::info!("rebuilding bundle");
+                bundle.send_replace(rebuild_bundle(true));
+            } else {
+                tracing::debug!("change should have no effect, not rebuilding bundle");
             }
-        };
-        if rebuild {
-            tracing::info!("rebuilding bundle");
-            *bundle.lock().unwrap() = rebuild_bundle();
-        } else {
-            tracing::debug!("change should have no effect, not rebuilding bundle");
+        })
+        .map(Ok))
+    };
+    let bundle_source = std::pin::pin!(bundle_source);
+
+    let stable_bundle = match (args.stable.config_map(), &args.common.watch_namespace) {
+        (Some(stable_bundle_config_map), WatchNamespace::One(namespace)) => {
+            tracing::info!(
+                config_map = stable_bundle_config_map,
+                "canary rollout is enabled, serving promoted stable bundle"
+            );
+            let stable_bundle = Arc::new(watch::Sender::new(stable::not_promoted_yet()));
+            tokio::spawn(stable::watch_stable_bundle(
+                Api::namespaced(client.as_kube_client(), namespace),
+                stable_bundle_config_map.to_string(),
+                stable_bundle.clone(),
+            ));
+            stable_bundle
         }
-    })
-    .map(Ok));
+        _ => bundle.clone(),
+    };
 
     let shutdown_requested = tokio::signal::ctrl_c().map(|_| ());
     #[cfg(unix)]
@@ -152,12 +534,54 @@ async fn main() -> Result<(), StartupError> {
         }
     };
 
+    let status_collector = match (&args.cluster_name, &args.common.watch_namespace) {
+        (Some(cluster_name), WatchNamespace::One(namespace)) => Some(Arc::new(
+            StatusCollector::new(client.as_kube_client(), cluster_name, namespace),
+        )),
+        _ => {
+            tracing::info!(
+                "OpaCluster is unknown, status reports of OPA will not be published as Events"
+            );
+            None
+        }
+    };
+
+    let state = AppState {
+        bundle: bundle.clone(),
+        stable_bundle,
+        status_collector,
+        signer,
+        scoped_bundles: Arc::default(),
+        download_limiter: Arc::new(DownloadLimiter::new(&args.download_limit)),
+        downloads: Arc::default(),
+        decision_log_writer: DecisionLogWriter::new(&args.decision_logs).map(Arc::new),
+        bundle_history,
+    };
+    if let Some(delivery_reporter) = delivery_reporter {
+        tokio::spawn(delivery_reporter.run(bundle.clone(), state.downloads.clone()));
+    }
     let app = Router::new()
         .route("/opa/v1/opa/bundle.tar.gz", get(get_bundle))
+        .route("/opa/v1/opa/stable/bundle.tar.gz", get(get_stable_bundle))
+        .route("/packages", get(get_packages))
         .route("/status", get(get_status))
-        .with_state(AppState {
-            bundle: bundle.clone(),
-        });
+        .route("/status/fresh", get(get_freshness))
+        .route("/status/downloads", get(get_downloads))
+        .route("/metrics", get(get_metrics))
+        .with_state(state.clone());
+    // Only OPA itself may upload its status reports and decision logs, and simulations evaluate
+    // arbitrary Rego
+    let local_app = Router::new()
+        .route("/opa/v1/status", post(post_status_report))
+        .route("/opa/v1/logs", post(post_decision_logs))
+        .route("/simulate", post(post_simulate))
+        .route("/simulate/revisions", get(get_simulation_revisions))
+        .with_state(state);
+    #[cfg(feature = "fault-injection")]
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        fault_injector.clone(),
+        fault_injection::inject_request_faults,
+    ));
     // FIXME: can we restrict access to localhost?
     // kubelet probes run from outside the container netns
     let listener = TcpListener::bind("0.0.0.0:3030")
@@ -165,15 +589,287 @@ async fn main() -> Result<(), StartupError> {
         .context(BindListenerSnafu)?;
     let address = listener.local_addr().context(GetListenerAddrSnafu)?;
     tracing::info!(%address, "listening");
+    let local_listener = TcpListener::bind("127.0.0.1:3031")
+        .await
+        .context(BindListenerSnafu)?;
+    let local_address = local_listener.local_addr().context(GetListenerAddrSnafu)?;
+    tracing::info!(address = %local_address, "listening (local)");
 
+    let shutdown_requested = shutdown_requested.shared();
     let server = std::pin::pin!(async {
-        axum::serve(listener, app.into_make_service())
-            .with_graceful_shutdown(shutdown_requested)
-            .await
-            .context(RunServerSnafu)
+        tokio::try_join!(
+            // The address identifies clients that don't send the client headers
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(shutdown_requested.clone())
+            .into_future(),
+            axum::serve(local_listener, local_app.into_make_service())
+                .with_graceful_shutdown(shutdown_requested)
+                .into_future(),
+        )
+        .context(RunServerSnafu)?;
+        Ok(())
+    });
+
+    let result = future::select(bundle_source, server).await.factor_first().0;
+    if let (Some(snapshotter), Some(cluster_name), WatchNamespace::One(namespace)) = (
+        &snapshotter,
+        &args.cluster_name,
+        &args.common.watch_namespace,
+    ) {
+        snapshot_stopped_cluster(
+            snapshotter,
+            &bundle,
+            client.as_kube_client(),
+            namespace,
+            cluster_name,
+        )
+        .await;
+    }
+    result
+}
+
+/// Snapshots the served bundle if the bundle builder is shutting down because the OpaCluster has been
+/// stopped, so that it can be served when the OpaCluster is resumed.
+async fn snapshot_stopped_cluster(
+    snapshotter: &Snapshotter,
+    bundle: &watch::Sender<BundleFuture>,
+    client: stackable_operator::kube::Client,
+    namespace: &str,
+    cluster_name: &str,
+) {
+    if snapshotter.is_continuous() {
+        return;
+    }
+    let bundle = bundle.borrow().clone();
+    let Some(bundle) = bundle.peek() else {
+        return;
+    };
+    let Ok(bundle) = &**bundle else {
+        return;
+    };
+    if bundle.stale {
+        return;
+    }
+    let stopped = match Api::<OpaCluster>::namespaced(client, namespace)
+        .get_opt(cluster_name)
+        .await
+    {
+        Ok(opa) => opa.is_some_and(|opa| opa.spec.cluster_operation.stopped),
+        Err(error) => {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to check whether the OpaCluster has been stopped"
+            );
+            false
+        }
+    };
+    if stopped {
+        tracing::info!("OpaCluster has been stopped, snapshotting the served bundle");
+        store_snapshot_now(snapshotter, bundle).await;
+    }
+}
+
+/// Pushes the bundle to the replication peer (if configured) in the background, failures are only
+/// logged since they must not prevent the bundle from being served locally.
+///
+/// Every bundle builder pushes the bundles it builds, so the peer receives one identical `PUT` per
+/// OPA Pod for every bundle.
+async fn push_bundle(replicator: &Arc<Replicator>, bundle: &Bundle) {
+    let replicator = replicator.clone();
+    let tarball = bundle.tarball.clone();
+    tokio::spawn(async move {
+        match tokio::time::timeout(SIDE_EFFECT_TIMEOUT, replicator.push(&tarball)).await {
+            Ok(Ok(())) => {}
+            Ok(Err(error)) => tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to push bundle to replication peer"
+            ),
+            Err(_) => tracing::error!(
+                timeout = ?SIDE_EFFECT_TIMEOUT,
+                "timed out pushing bundle to replication peer"
+            ),
+        }
     });
+}
+
+/// Loads the bundle snapshot, failures are only logged since the bundle can still be built from scratch.
+async fn load_snapshot(snapshotter: &Snapshotter) -> Option<Bundle> {
+    let tarball = match snapshotter.load().await {
+        Ok(tarball) => tarball?,
+        Err(error) => {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to load bundle snapshot"
+            );
+            return None;
+        }
+    };
+    match bundle_from_tarball(tarball) {
+        Ok(bundle) => Some(Bundle {
+            stale: true,
+            ..bundle
+        }),
+        Err(error) => {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to read bundle snapshot"
+            );
+            None
+        }
+    }
+}
+
+/// Loads the bundle cached on the local disk, failures are only logged since the bundle can still be
+/// built from scratch.
+async fn load_cached_bundle(local_cache: &LocalCache) -> Option<Bundle> {
+    let tarball = match local_cache.load().await {
+        Ok(tarball) => tarball?,
+        Err(error) => {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to load cached bundle"
+            );
+            return None;
+        }
+    };
+    match bundle_from_tarball(tarball) {
+        Ok(bundle) => Some(Bundle {
+            stale: true,
+            ..bundle
+        }),
+        Err(error) => {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to read cached bundle"
+            );
+            None
+        }
+    }
+}
+
+/// Caches the bundle on the local disk, failures are only logged since they must not prevent the
+/// bundle from being served.
+async fn store_cached_bundle(local_cache: &LocalCache, bundle: &Bundle) {
+    if let Err(error) = local_cache.store(&bundle.tarball).await {
+        tracing::error!(
+            error = &error as &dyn std::error::Error,
+            "failed to cache bundle"
+        );
+    }
+}
 
-    future::select(reflector, server).await.factor_first().0
+/// Records the bundle in the snapshot ConfigMap (see [`Snapshotter::record`]), failures are only
+/// logged since they must not prevent the bundle from being served.
+///
+/// Returns whether this bundle builder updated the ConfigMap.
+async fn store_snapshot(snapshotter: &Snapshotter, bundle: &Bundle) -> bool {
+    match tokio::time::timeout(
+        SIDE_EFFECT_TIMEOUT,
+        snapshotter.record(&bundle.tarball, bundle.revision()),
+    )
+    .await
+    {
+        Ok(Ok(stored)) => stored,
+        Ok(Err(error)) => {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to store bundle snapshot"
+            );
+            false
+        }
+        Err(_) => {
+            tracing::error!(
+                timeout = ?SIDE_EFFECT_TIMEOUT,
+                "timed out storing bundle snapshot"
+            );
+            false
+        }
+    }
+}
+
+/// Stores the bundle as the new snapshot, failures are only logged since the bundle builder is
+/// shutting down anyway.
+async fn store_snapshot_now(snapshotter: &Snapshotter, bundle: &Bundle) {
+    match tokio::time::timeout(
+        SIDE_EFFECT_TIMEOUT,
+        snapshotter.store(&bundle.tarball, bundle.revision()),
+    )
+    .await
+    {
+        Ok(Ok(_)) => {}
+        Ok(Err(error)) => tracing::error!(
+            error = &error as &dyn std::error::Error,
+            "failed to store bundle snapshot"
+        ),
+        Err(_) => tracing::error!(
+            timeout = ?SIDE_EFFECT_TIMEOUT,
+            "timed out storing bundle snapshot"
+        ),
+    }
+}
+
+/// Periodically pulls the bundle from the replication primary, replacing the served bundle whenever it changes.
+async fn pull_bundles(
+    replicator: Arc<Replicator>,
+    snapshotter: Option<Arc<Snapshotter>>,
+    local_cache: Option<Arc<LocalCache>>,
+    bundle: Arc<watch::Sender<BundleFuture>>,
+) -> Result<(), StartupError> {
+    let mut interval = tokio::time::interval(replicator.pull_interval());
+    let mut current_tarball = None::<Vec<u8>>;
+    let mut current_etag = None::<String>;
+    loop {
+        interval.tick().await;
+        // Primaries that support revalidation skip sending unchanged bundles
+        let tarball = match replicator.pull(current_etag.as_deref()).await {
+            Ok(Some(PulledBundle { tarball, etag })) => {
+                current_etag = etag;
+                tarball
+            }
+            Ok(None) => {
+                tracing::debug!("replicated bundle is unchanged");
+                continue;
+            }
+            Err(error) => {
+                tracing::error!(
+                    error = &error as &dyn std::error::Error,
+                    "failed to pull bundle from replication primary"
+                );
+                continue;
+            }
+        };
+        if current_tarball.as_ref() == Some(&tarball) {
+            tracing::debug!("replicated bundle is unchanged");
+            continue;
+        }
+        let replicated_bundle = match bundle_from_tarball(tarball.clone()) {
+            Ok(bundle) => bundle,
+            Err(error) => {
+                tracing::error!(
+                    error = &error as &dyn std::error::Error,
+                    "failed to read bundle pulled from replication primary"
+                );
+                continue;
+            }
+        };
+        tracing::info!("replicated updated bundle");
+        push_bundle(&replicator, &replicated_bundle).await;
+        if let Some(snapshotter) = &snapshotter {
+            store_snapshot(snapshotter, &replicated_bundle).await;
+        }
+        if let Some(local_cache) = &local_cache {
+            store_cached_bundle(local_cache, &replicated_bundle).await;
+        }
+        bundle.send_replace(
+            future::ready(Arc::new(Ok(replicated_bundle)))
+                .boxed()
+                .shared(),
+        );
+        current_tarball = Some(tarball);
+    }
 }
 
 #[derive(Snafu, Debug)]
@@ -189,21 +885,51 @@ enum BundleError {
         file_size: usize,
     },
 
-    #[snafu(display("failed to add static file {file_path:?} to tarball"))]
-    AddStaticRuleToTarball {
+    #[snafu(display("failed to add file {file_path:?} to tarball"))]
+    EncodeSegment {
         source: std::io::Error,
         file_path: String,
     },
 
-    #[snafu(display("failed to add file {file_name:?} from {config_map} to tarball"))]
-    AddFileToTarball {
+    #[snafu(display("failed to encode ConfigMap"))]
+    EncodeSegmentTask { source: tokio::task::JoinError },
+
+    #[snafu(display("failed to sign bundle"))]
+    SignBundle { source: signing::Error },
+
+    #[snafu(display("failed to serialize bundle manifest"))]
+    SerializeManifest { source: serde_json::Error },
+
+    #[snafu(display("failed to add manifest to tarball"))]
+    AddManifestToTarball { source: std::io::Error },
+
+    #[snafu(display("failed to add file {file_path:?} to scoped tarball"))]
+    AddScopedFileToTarball {
         source: std::io::Error,
-        config_map: ObjectRef<ConfigMap>,
-        file_name: String,
+        file_path: String,
     },
 
+    #[snafu(display("failed to add signatures to tarball"))]
+    AddSignaturesToTarball { source: std::io::Error },
+
+    #[snafu(display("bundle has not been pulled from the replication primary yet"))]
+    NotReplicatedYet,
+
+    #[snafu(display("no bundle has been promoted to the stable bundle yet"))]
+    NotPromotedYet,
+
+    #[snafu(display("failed to read tarball"))]
+    ReadTarball { source: std::io::Error },
+
+    #[snafu(display("failed to read bundle manifest"))]
+    ReadManifest { source: serde_json::Error },
+
     #[snafu(display("failed to build tarball"))]
     BuildTarball { source: std::io::Error },
+
+    #[cfg(feature = "fault-injection")]
+    #[snafu(display("injected bundle rebuild failure"))]
+    InjectedFault,
 }
 
 impl BundleError {
@@ -215,89 +941,570 @@ impl BundleError {
     }
 }
 
-async fn build_bundle(store: Store<ConfigMap>) -> Result<Vec<u8>, BundleError> {
+async fn build_bundle(
+    store: Store<ConfigMap>,
+    segment_cache: Arc<SegmentCache>,
+    cluster_name: Option<String>,
+    include_builtin_rego_rules: bool,
+    signer: Option<Arc<BundleSigner>>,
+) -> Result<Bundle, BundleError> {
     use bundle_error::*;
-    fn file_header(file_path: &str, data: &[u8]) -> Result<tar::Header, BundleError> {
-        let mut header = tar::Header::new_gnu();
-        header.set_mode(0o644);
-        let file_size = data.len();
-        header.set_size(
-            file_size
-                .try_into()
-                .with_context(|_| FileSizeOverflowSnafu {
-                    file_path,
-                    file_size,
-                })?,
-        );
-        header.set_entry_type(tar::EntryType::Regular);
-        header.set_cksum();
-        Ok(header)
-    }
 
     tracing::info!("building bundle");
-    let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), flate2::Compression::default()));
+    let mut tarball = Vec::new();
     let mut resource_versions = BTreeMap::<String, String>::new();
     let mut bundle_file_paths = BTreeSet::<String>::new();
+    let mut packages = Vec::<RegoPackage>::new();
+    let mut signed_files = Vec::<(String, Vec<u8>)>::new();
+    // The paths of the data documents, which (together with the packages) make up the roots of the bundle
+    let mut data_paths = BTreeSet::<String>::new();
+    // The roots can only be determined if the packages of all Rego files are known
+    let mut roots_known = true;
+    let mut wasm_resolvers = Vec::<WasmResolver>::new();
 
-    for (file_path, data) in stackable_opa_regorule_library::REGORULES {
-        let mut header = file_header(file_path, data.as_bytes())?;
-        tar.append_data(&mut header, file_path, data.as_bytes())
-            .context(AddStaticRuleToTarballSnafu {
-                file_path: *file_path,
-            })?;
-        bundle_file_paths.insert(file_path.to_string());
+    let mut segments = Vec::new();
+    if include_builtin_rego_rules {
+        segments.push((None, Arc::new(Segment::builtin_rego_rules()?)));
+    } else {
+        tracing::debug!("not adding built-in regorule library to the bundle");
     }
 
+    let mut config_maps = Vec::new();
     for cm in store.state() {
-        let ObjectMeta {
-            name: Some(cm_ns),
-            namespace: Some(cm_name),
-            resource_version: Some(cm_version),
-            ..
-        } = &cm.metadata
-        else {
-            return ConfigMapMetadataMissingSnafu.fail();
-        };
         let cm_ref = ObjectRef::from_obj(&*cm);
-        for (file_name, data) in cm.data.iter().flatten() {
-            let file_path = format!("configmap/{cm_ns}/{cm_name}/{file_name}");
-            let mut header = file_header(&file_path, data.as_bytes())?;
-            tar.append_data(&mut header, &file_path, data.as_bytes())
-                .with_context(|_| AddFileToTarballSnafu {
-                    config_map: cm_ref.clone(),
-                    file_name,
-                })?;
-            bundle_file_paths.insert(file_path);
-        }
-        resource_versions.insert(cm_ref.to_string(), cm_version.clone());
-    }
-    let tar = tar
-        .into_inner()
-        .context(BuildTarballSnafu)?
-        .finish()
-        .context(BuildTarballSnafu)?;
+        if let Some(cm_cluster_name) = cm.labels().get(BUNDLE_CLUSTER_LABEL) {
+            if Some(cm_cluster_name) != cluster_name.as_ref() {
+                tracing::debug!(config_map = %cm_ref, cluster = %cm_cluster_name, "skipping ConfigMap restricted to other OpaCluster");
+                continue;
+            }
+        }
+        resource_versions.insert(
+            cm_ref.to_string(),
+            cm.resource_version()
+                .context(ConfigMapMetadataMissingSnafu)?,
+        );
+        config_maps.push(cm);
+    }
+    let cm_segments = segment_cache.segments(&config_maps).await?;
+    segments.extend(
+        config_maps
+            .iter()
+            .map(|cm| Some(ObjectRef::from_obj(&**cm)))
+            .zip(cm_segments),
+    );
+
+    for (cm_ref, mut segment) in segments {
+        let conflicting_files = segment
+            .files
+            .iter()
+            .filter(|file| file.placed_at_package && bundle_file_paths.contains(&file.path))
+            .map(|file| file.path.clone())
+            .collect::<BTreeSet<_>>();
+        if !conflicting_files.is_empty() {
+            for file in &segment.files {
+                if conflicting_files.contains(&file.path) {
+                    tracing::error!(
+                        config_map = cm_ref.as_ref().map(tracing::field::display),
+                        file_name = %file.file_name,
+                        file_path = %file.path,
+                        "skipping data document that conflicts with a data document of another ConfigMap"
+                    );
+                }
+            }
+            // The segment depends on the other ConfigMaps in this case, so it is not cached
+            segment = Arc::new(Segment::encode(
+                segment
+                    .files
+                    .iter()
+                    .filter(|file| !conflicting_files.contains(&file.path))
+                    .cloned()
+                    .collect(),
+            )?);
+        }
+        tarball.extend_from_slice(&segment.encoded);
+        for file in &segment.files {
+            if let Some(package) = &file.package {
+                packages.push(RegoPackage {
+                    package: package.clone(),
+                    file: file.path.clone(),
+                    config_map: cm_ref.as_ref().map(|cm_ref| RegoPackageConfigMap {
+                        name: cm_ref.name.clone(),
+                        namespace: cm_ref.namespace.clone().unwrap_or_default(),
+                    }),
+                });
+            }
+            if file.unknown_package {
+                roots_known = false;
+            }
+            data_paths.extend(file.data_path.clone());
+            // Wasm modules define the documents of their entrypoints
+            data_paths.extend(file.wasm_entrypoints.iter().cloned());
+            wasm_resolvers.extend(file.wasm_entrypoints.iter().map(|entrypoint| WasmResolver {
+                entrypoint: entrypoint.clone(),
+                module: format!("/{}", file.path),
+            }));
+            signed_files.push((file.path.clone(), file.data.clone()));
+            bundle_file_paths.insert(file.path.clone());
+        }
+    }
+
+    // The manifest, the signatures and the end-of-archive marker are appended as a last segment
+    let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), flate2::Compression::default()));
+    let mut manifest = Manifest::new(
+        signed_files
+            .iter()
+            .map(|(file_path, data)| (file_path.as_str(), data.as_slice())),
+    );
+    if roots_known {
+        manifest.roots = Some(bundle_roots(
+            packages
+                .iter()
+                .map(|package| package.package.replace('.', "/"))
+                .chain(data_paths),
+        ));
+    } else {
+        tracing::warn!("the package of some Rego files is unknown, the bundle claims all roots");
+    }
+    manifest.wasm = wasm_resolvers;
+    if let Some(cluster_name) = &cluster_name {
+        manifest
+            .metadata
+            .insert("opaCluster".to_string(), cluster_name.clone().into());
+    }
+    let revision = manifest.revision.clone();
+    let manifest = manifest.encode()?;
+    let mut header = file_header(MANIFEST_FILE_NAME, &manifest)?;
+    tar.append_data(&mut header, MANIFEST_FILE_NAME, &*manifest)
+        .context(AddManifestToTarballSnafu)?;
+    signed_files.push((MANIFEST_FILE_NAME.to_string(), manifest));
+    bundle_file_paths.insert(MANIFEST_FILE_NAME.to_string());
+    if let Some(signer) = signer {
+        let signatures = signer
+            .sign(
+                signed_files
+                    .iter()
+                    .map(|(file_path, data)| (file_path.as_str(), data.as_slice())),
+            )
+            .context(SignBundleSnafu)?;
+        let mut header = file_header(signing::SIGNATURES_FILE_NAME, &signatures)?;
+        tar.append_data(&mut header, signing::SIGNATURES_FILE_NAME, &*signatures)
+            .context(AddSignaturesToTarballSnafu)?;
+    }
+    tarball.extend(
+        tar.into_inner()
+            .context(BuildTarballSnafu)?
+            .finish()
+            .context(BuildTarballSnafu)?,
+    );
     tracing::info!(bundle.files = ?bundle_file_paths, bundle.versions = ?resource_versions, "finished building bundle");
-    Ok(tar)
+    Ok(Bundle {
+        policies: config_maps
+            .iter()
+            .filter_map(|cm| cm.labels().get(BUNDLE_POLICY_LABEL).cloned())
+            .collect(),
+        ..Bundle::new(tarball, packages, revision)
+    })
+}
+
+fn file_header(file_path: &str, data: &[u8]) -> Result<tar::Header, BundleError> {
+    let mut header = tar::Header::new_gnu();
+    header.set_mode(0o644);
+    let file_size = data.len();
+    header.set_size(file_size.try_into().with_context(|_| {
+        bundle_error::FileSizeOverflowSnafu {
+            file_path,
+            file_size,
+        }
+    })?);
+    header.set_entry_type(tar::EntryType::Regular);
+    header.set_cksum();
+    Ok(header)
+}
+
+/// Reduces the package and data paths of a bundle to its roots, dropping paths nested in other paths.
+fn bundle_roots(paths: impl IntoIterator<Item = String>) -> Vec<String> {
+    let mut roots = Vec::<String>::new();
+    for path in paths.into_iter().collect::<BTreeSet<_>>() {
+        if !roots
+            .iter()
+            .any(|root| path == *root || path.starts_with(&format!("{root}/")))
+        {
+            roots.push(path);
+        }
+    }
+    roots
+}
+
+/// Reads a bundle that has been built before, such as a snapshot or a bundle pulled from the
+/// replication primary.
+///
+/// Bundles without a manifest fall back to the SHA-256 hash of the tarball as their revision.
+fn bundle_from_tarball(tarball: Vec<u8>) -> Result<Bundle, BundleError> {
+    use bundle_error::*;
+    let mut archive = tar::Archive::new(flate2::read::MultiGzDecoder::new(tarball.as_slice()));
+    let mut packages = Vec::new();
+    let mut revision = None;
+    for entry in archive.entries().context(ReadTarballSnafu)? {
+        let mut entry = entry.context(ReadTarballSnafu)?;
+        let file = entry
+            .path()
+            .context(ReadTarballSnafu)?
+            .to_string_lossy()
+            .into_owned();
+        if file.trim_start_matches('/') == MANIFEST_FILE_NAME {
+            let manifest: Manifest = serde_json::from_reader(entry).context(ReadManifestSnafu)?;
+            revision = Some(manifest.revision);
+            continue;
+        }
+        if !file.ends_with(".rego") {
+            continue;
+        }
+        let mut rego = String::new();
+        entry.read_to_string(&mut rego).context(ReadTarballSnafu)?;
+        if let Some(package) = rego_package_name(&rego) {
+            packages.push(RegoPackage {
+                package: package.to_string(),
+                file,
+                config_map: None,
+            });
+        }
+    }
+    let revision = revision.unwrap_or_else(|| format!("{:x}", Sha256::digest(&tarball)));
+    Ok(Bundle::new(tarball, packages, revision))
 }
 
 async fn get_status(State(state): State<AppState>) -> impl IntoResponse {
-    let bundle = future::Shared::clone(&*state.bundle.lock().unwrap());
-    if let Err(err) = bundle.await.as_deref() {
+    let bundle = state.bundle.borrow().clone();
+    if let Err(err) = &*bundle.await {
         return Err(err.to_http_response());
     }
     Ok("ready")
 }
 
-async fn get_bundle(State(state): State<AppState>) -> impl IntoResponse {
-    let bundle = future::Shared::clone(&*state.bundle.lock().unwrap());
-    Ok((
+/// Reports whether the served bundle reflects the current policies, rather than being a stale bundle
+/// served after a restart. Unlike `/status`, this is not suitable as a readiness probe, since stale
+/// bundles are still better than no bundle at all.
+async fn get_freshness(State(state): State<AppState>) -> axum::response::Response {
+    let bundle = state.bundle.borrow().clone();
+    match &*bundle.await {
+        Ok(bundle) if bundle.stale => (
+            http::StatusCode::SERVICE_UNAVAILABLE,
+            "serving a stale bundle until the current policies have been loaded",
+        )
+            .into_response(),
+        Ok(_) => "fresh".into_response(),
+        Err(err) => err.to_http_response().into_response(),
+    }
+}
+
+/// Serves the bundle to OPA.
+async fn get_bundle(
+    State(state): State<AppState>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Query(query): Query<BundleQuery>,
+    headers: http::HeaderMap,
+) -> impl IntoResponse {
+    serve_bundle(
+        &state,
+        ServedBundle::Latest,
+        query.scope().as_ref(),
+        &headers,
+        address,
+    )
+    .await
+}
+
+/// Serves the stable bundle to OPA, for role groups that are not part of the canary.
+async fn get_stable_bundle(
+    State(state): State<AppState>,
+    ConnectInfo(address): ConnectInfo<SocketAddr>,
+    Query(query): Query<BundleQuery>,
+    headers: http::HeaderMap,
+) -> impl IntoResponse {
+    serve_bundle(
+        &state,
+        ServedBundle::Stable,
+        query.scope().as_ref(),
+        &headers,
+        address,
+    )
+    .await
+}
+
+/// Serves a bundle (or a variant of it) to OPA, restricted to the packages of `scope` if set.
+///
+/// Supports OPA's long polling (see <https://www.openpolicyagent.org/docs/latest/management-bundles/#bundle-service-api>):
+/// if the client already has the current bundle (`If-None-Match`), the response is delayed until a new
+/// bundle is available or the timeout requested by `Prefer: wait=<seconds>` has passed.
+async fn serve_bundle(
+    state: &AppState,
+    served: ServedBundle,
+    scope: Option<&BundleScope>,
+    headers: &http::HeaderMap,
+    address: SocketAddr,
+) -> Result<axum::response::Response, impl IntoResponse> {
+    let client = DownloadClient::from_request(headers, address);
+    let bundle = state.served_bundle(served);
+    let known_etag = headers.get(http::header::IF_NONE_MATCH);
+    let long_polling_timeout = headers
+        .get(http::header::HeaderName::from_static("prefer"))
+        .and_then(|prefer| prefer.to_str().ok())
+        .and_then(|prefer| prefer.trim().strip_prefix("wait="))
+        .and_then(|wait| wait.parse::<u64>().ok())
+        .map(Duration::from_secs);
+    let content_type = if long_polling_timeout.is_some() {
+        // Tells OPA that long polling is supported
+        "application/vnd.openpolicyagent.bundles"
+    } else {
+        "application/gzip"
+    };
+    let deadline = tokio::time::Instant::now() + long_polling_timeout.unwrap_or_default();
+    let mut bundle_updates = bundle.subscribe();
+    loop {
+        let bundle = bundle_updates.borrow_and_update().clone();
+        let bundle = bundle.await;
+        let bundle = match &*bundle {
+            Ok(bundle) => bundle,
+            Err(err) => return Err(err.to_http_response()),
+        };
+        // Scoped bundles are tracked by the revision of the bundle they were built from
+        let revision = bundle.revision().to_string();
+        let scoped_bundle;
+        let bundle = match scope {
+            Some(scope) => {
+                scoped_bundle =
+                    match state
+                        .scoped_bundles
+                        .get(bundle, scope, state.signer.as_deref())
+                    {
+                        Ok(scoped_bundle) => scoped_bundle,
+                        Err(err) => {
+                            tracing::error!(
+                                error = &err as &dyn std::error::Error,
+                                ?scope,
+                                "failed to build scoped bundle"
+                            );
+                            return Err(err.to_http_response());
+                        }
+                    };
+                &*scoped_bundle
+            }
+            None => bundle,
+        };
+        let headers = [
+            (
+                http::header::CONTENT_TYPE,
+                http::HeaderValue::from_static(content_type),
+            ),
+            (
+                http::header::ETAG,
+                http::HeaderValue::from_str(&bundle.etag)
+                    .expect("etag must be a valid header value"),
+            ),
+            // Caches (such as proxies between clusters) must revalidate the bundle on every poll
+            (
+                http::header::CACHE_CONTROL,
+                http::HeaderValue::from_static("no-cache"),
+            ),
+        ];
+        if !if_none_match(known_etag, &bundle.etag) {
+            let Some(permit) = state.download_limiter.try_acquire() else {
+                return Ok(download_limit::too_many_downloads());
+            };
+            state.downloads.record(client, served, &revision, true);
+            return Ok((headers, permit.into_body(bundle.tarball.to_vec())).into_response());
+        }
+        // The bundle may be rebuilt without changes, so keep waiting until the timeout
+        if long_polling_timeout.is_none()
+            || !matches!(
+                tokio::time::timeout_at(deadline, bundle_updates.changed()).await,
+                Ok(Ok(()))
+            )
+        {
+            state.downloads.record(client, served, &revision, false);
+            return Ok((http::StatusCode::NOT_MODIFIED, headers).into_response());
+        }
+        state.download_limiter.long_polling_jitter(deadline).await;
+    }
+}
+
+/// Whether the `If-None-Match` header lists the `etag`, that is whether the client already has the
+/// bundle.
+///
+/// Uses the weak comparison of [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-13.1.2),
+/// so that clients behind proxies that weaken the `ETag` (for example when compressing responses)
+/// can still revalidate their bundle.
+fn if_none_match(if_none_match: Option<&http::HeaderValue>, etag: &str) -> bool {
+    let Some(if_none_match) = if_none_match.and_then(|header| header.to_str().ok()) else {
+        return false;
+    };
+    let etag = etag.trim_start_matches("W/");
+    if_none_match
+        .split(',')
+        .map(str::trim)
+        .any(|candidate| candidate == "*" || candidate.trim_start_matches("W/") == etag)
+}
+
+/// The bundle revisions last received by the clients, see [`downloads`].
+async fn get_downloads(State(state): State<AppState>) -> impl IntoResponse {
+    Json(client_statuses(&state).await)
+}
+
+/// The bundle downloads in the Prometheus text format.
+async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    (
         [(
             http::header::CONTENT_TYPE,
-            http::HeaderValue::from_static("application/gzip"),
+            "text/plain; version=0.0.4; charset=utf-8",
         )],
-        match bundle.await.as_deref() {
-            Ok(bundle) => bundle.to_vec(),
-            Err(err) => return Err(err.to_http_response()),
-        },
-    ))
+        downloads::render_metrics(&client_statuses(&state).await),
+    )
+}
+
+async fn client_statuses(state: &AppState) -> Vec<ClientStatus> {
+    let mut current_revisions = BTreeMap::new();
+    for served in [ServedBundle::Latest, ServedBundle::Stable] {
+        let bundle = state.served_bundle(served).borrow().clone();
+        if let Ok(bundle) = &*bundle.await {
+            current_revisions.insert(served, bundle.revision().to_string());
+        }
+    }
+    state
+        .downloads
+        .clients()
+        .into_iter()
+        .map(|(client, downloads)| ClientStatus {
+            up_to_date: current_revisions
+                .get(&downloads.bundle)
+                .map(|current_revision| *current_revision == downloads.revision),
+            client,
+            downloads,
+        })
+        .collect()
+}
+
+/// Receives the status reports pushed by OPA's status plugin.
+async fn post_status_report(
+    State(state): State<AppState>,
+    Json(report): Json<StatusReport>,
+) -> impl IntoResponse {
+    if let Some(status_collector) = &state.status_collector {
+        status_collector.handle_report(report).await;
+    }
+    http::StatusCode::OK
+}
+
+/// Receives the decision logs uploaded by OPA's decision log plugin.
+async fn post_decision_logs(
+    State(state): State<AppState>,
+    headers: http::HeaderMap,
+    body: axum::body::Bytes,
+) -> impl IntoResponse {
+    let Some(decision_log_writer) = state.decision_log_writer.clone() else {
+        return (
+            http::StatusCode::NOT_FOUND,
+            "no decision log file is configured",
+        )
+            .into_response();
+    };
+    let written =
+        tokio::task::spawn_blocking(move || decision_log_writer.write_upload(&headers, &body))
+            .await;
+    match written {
+        Ok(Ok(_)) => http::StatusCode::OK.into_response(),
+        Ok(Err(error)) => {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to write decision logs"
+            );
+            error.status_code().into_response()
+        }
+        Err(error) => {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to write decision logs"
+            );
+            http::StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SimulationQuery {
+    /// The bundle revision to evaluate against, defaults to the last built revision.
+    revision: Option<String>,
+}
+
+/// Evaluates a decision input against a retained bundle revision, see [`simulation`].
+async fn post_simulate(
+    State(state): State<AppState>,
+    Query(query): Query<SimulationQuery>,
+    Json(request): Json<SimulationRequest>,
+) -> impl IntoResponse {
+    match state
+        .bundle_history
+        .clone()
+        .simulate(query.revision, request)
+        .await
+    {
+        Ok(result) => Json(result).into_response(),
+        Err(error) => {
+            tracing::info!(
+                error = &error as &dyn std::error::Error,
+                "failed to simulate decision"
+            );
+            (error.status_code(), error.to_string()).into_response()
+        }
+    }
+}
+
+async fn get_simulation_revisions(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.bundle_history.revisions())
+}
+
+async fn get_packages(State(state): State<AppState>) -> impl IntoResponse {
+    let bundle = state.bundle.borrow().clone();
+    match &*bundle.await {
+        Ok(bundle) => Ok(Json(bundle.packages.clone())),
+        Err(err) => Err(err.to_http_response()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(header: &str, etag: &str) -> bool {
+        if_none_match(Some(&http::HeaderValue::from_str(header).unwrap()), etag)
+    }
+
+    #[test]
+    fn if_none_match_without_header_does_not_match() {
+        assert!(!if_none_match(None, "\"abc\""));
+    }
+
+    #[test]
+    fn if_none_match_compares_etags() {
+        assert!(matches("\"abc\"", "\"abc\""));
+        assert!(!matches("\"abd\"", "\"abc\""));
+    }
+
+    #[test]
+    fn if_none_match_uses_weak_comparison() {
+        assert!(matches("W/\"abc\"", "\"abc\""));
+        assert!(matches("\"abc\"", "W/\"abc\""));
+        assert!(matches("W/\"abc\"", "W/\"abc\""));
+    }
+
+    #[test]
+    fn if_none_match_matches_any_etag_with_wildcard() {
+        assert!(matches("*", "\"abc\""));
+    }
+
+    #[test]
+    fn if_none_match_matches_any_etag_of_list() {
+        assert!(matches("\"old\", W/\"abc\"", "\"abc\""));
+        assert!(matches("\"old\",\"abc\"", "\"abc\""));
+        assert!(!matches("\"old\", \"older\"", "\"abc\""));
+    }
 }
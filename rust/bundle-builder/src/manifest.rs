@@ -0,0 +1,67 @@
+//! The bundle manifest, see <https://www.openpolicyagent.org/docs/latest/management-bundles/#bundle-file-format>.
+//!
+//! OPA reports the revision of the manifest as the active revision of the bundle, and Wasm-target
+//! consumers (such as the OPA SDKs) find the compiled policies of the bundle through it.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use snafu::ResultExt;
+
+use crate::{bundle_error, BundleError};
+
+/// The path of the manifest inside the bundle.
+pub const MANIFEST_FILE_NAME: &str = ".manifest";
+
+#[derive(Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    /// Identifies the contents of the bundle, see [`Manifest::new`].
+    pub revision: String,
+    /// The paths the bundle owns, `None` if they are unknown, in which case the bundle claims all
+    /// roots.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub roots: Option<Vec<String>>,
+    /// The Wasm modules of the bundle, along with the entrypoints they evaluate.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub wasm: Vec<WasmResolver>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Clone, Deserialize, Serialize)]
+pub struct WasmResolver {
+    /// The path of the rule evaluated by the module, e.g. `example/allow`.
+    pub entrypoint: String,
+    /// The absolute path of the module inside the bundle, e.g. `/example/policy.wasm`.
+    pub module: String,
+}
+
+impl Manifest {
+    /// Creates a manifest for a bundle containing the `files` (as path and data).
+    ///
+    /// The revision is the SHA-256 hash of the paths and contents of the files, so it does not depend
+    /// on their order or on the compression of the bundle. It is also the revision of the bundle
+    /// everywhere else (see `Bundle::revision`).
+    pub fn new<'a>(files: impl IntoIterator<Item = (&'a str, &'a [u8])>) -> Self {
+        let mut files = files.into_iter().collect::<Vec<_>>();
+        files.sort();
+        let mut hasher = Sha256::new();
+        for (file_path, data) in files {
+            // Length prefixes keep the boundaries between paths and contents unambiguous
+            hasher.update((file_path.len() as u64).to_be_bytes());
+            hasher.update(file_path);
+            hasher.update((data.len() as u64).to_be_bytes());
+            hasher.update(data);
+        }
+        Self {
+            revision: format!("{:x}", hasher.finalize()),
+            ..Self::default()
+        }
+    }
+
+    pub fn encode(&self) -> Result<Vec<u8>, BundleError> {
+        serde_json::to_vec(self).context(bundle_error::SerializeManifestSnafu)
+    }
+}
@@ -0,0 +1,167 @@
+//! Replication of bundles between clusters, for example to keep a disaster recovery cluster in sync
+//! with a primary cluster.
+//!
+//! Bundles are pushed to and pulled from plain HTTP endpoints, such as the bundle builder of another
+//! cluster. Object storages that require signed requests (such as S3) are not supported.
+
+use std::{path::PathBuf, time::Duration as StdDuration};
+
+use reqwest::{header, Url};
+use snafu::{ResultExt, Snafu};
+use stackable_operator::time::Duration;
+
+#[derive(clap::Args)]
+pub struct ReplicationArgs {
+    /// URL to push (using HTTP `PUT`) every published bundle to.
+    #[clap(long, env)]
+    replication_push_url: Option<Url>,
+
+    /// URL to pull (using HTTP `GET`) bundles from.
+    /// If set, the bundle is replicated from there instead of being built from the local ConfigMaps.
+    #[clap(long, env)]
+    replication_pull_url: Option<Url>,
+
+    /// How often to pull bundles from `--replication-pull-url`.
+    #[clap(long, env, default_value = "30s")]
+    replication_pull_interval: Duration,
+
+    /// Value of the `Authorization` header sent when pushing or pulling bundles.
+    #[clap(long, env, hide_env_values = true)]
+    replication_authorization: Option<String>,
+
+    /// Path to PEM encoded CA certificates to trust when pushing or pulling bundles, in addition to
+    /// the system CA certificates.
+    #[clap(long, env)]
+    trust_store_file: Option<PathBuf>,
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to construct http client"))]
+    ConstructHttpClient { source: reqwest::Error },
+
+    #[snafu(display("failed to read trust store {path:?}"))]
+    ReadTrustStore {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to parse trust store {path:?}"))]
+    ParseTrustStore {
+        source: reqwest::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to push bundle to {url}"))]
+    Push { source: reqwest::Error, url: Url },
+
+    #[snafu(display("failed to pull bundle from {url}"))]
+    Pull { source: reqwest::Error, url: Url },
+}
+
+/// A bundle pulled from the primary.
+pub struct PulledBundle {
+    pub tarball: Vec<u8>,
+    /// The `ETag` of the bundle, sent as `If-None-Match` by the next pull.
+    pub etag: Option<String>,
+}
+
+/// How long a single push or pull may take.
+const REQUEST_TIMEOUT: StdDuration = StdDuration::from_secs(30);
+
+pub struct Replicator {
+    http: reqwest::Client,
+    push_url: Option<Url>,
+    pull_url: Option<Url>,
+    pull_interval: Duration,
+    authorization: Option<String>,
+}
+
+impl Replicator {
+    pub fn new(args: &ReplicationArgs) -> Result<Self, Error> {
+        let mut http = reqwest::Client::builder().timeout(REQUEST_TIMEOUT);
+        if let Some(path) = &args.trust_store_file {
+            let ca_certs = std::fs::read(path).context(ReadTrustStoreSnafu { path })?;
+            for ca_cert in reqwest::Certificate::from_pem_bundle(&ca_certs)
+                .context(ParseTrustStoreSnafu { path })?
+            {
+                http = http.add_root_certificate(ca_cert);
+            }
+        }
+        Ok(Self {
+            http: http.build().context(ConstructHttpClientSnafu)?,
+            push_url: args.replication_push_url.clone(),
+            pull_url: args.replication_pull_url.clone(),
+            pull_interval: args.replication_pull_interval,
+            authorization: args.replication_authorization.clone(),
+        })
+    }
+
+    /// Whether bundles are pulled from a primary instead of being built locally.
+    pub fn is_pulling(&self) -> bool {
+        self.pull_url.is_some()
+    }
+
+    pub fn pull_interval(&self) -> std::time::Duration {
+        *self.pull_interval
+    }
+
+    fn authorize(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.authorization {
+            Some(authorization) => request.header(header::AUTHORIZATION, authorization),
+            None => request,
+        }
+    }
+
+    /// Pushes the bundle to the configured peer, does nothing if pushing is not configured.
+    pub async fn push(&self, tarball: &[u8]) -> Result<(), Error> {
+        let Some(url) = &self.push_url else {
+            return Ok(());
+        };
+        tracing::info!(%url, "pushing bundle");
+        self.authorize(self.http.put(url.clone()))
+            .header(header::CONTENT_TYPE, "application/gzip")
+            .body(tarball.to_vec())
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context(PushSnafu { url: url.clone() })?;
+        Ok(())
+    }
+
+    /// Pulls the current bundle from the configured primary, returns `None` if it still has the
+    /// bundle with the `known_etag`.
+    ///
+    /// Must only be called if [`Self::is_pulling`].
+    pub async fn pull(&self, known_etag: Option<&str>) -> Result<Option<PulledBundle>, Error> {
+        let url = self
+            .pull_url
+            .as_ref()
+            .expect("pull must only be called if a pull URL is configured");
+        let mut request = self.authorize(self.http.get(url.clone()));
+        if let Some(known_etag) = known_etag {
+            request = request.header(header::IF_NONE_MATCH, known_etag);
+        }
+        let response = request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context(PullSnafu { url: url.clone() })?;
+        if response.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return Ok(None);
+        }
+        let etag = response
+            .headers()
+            .get(header::ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(str::to_string);
+        let tarball = response
+            .bytes()
+            .await
+            .context(PullSnafu { url: url.clone() })?;
+        Ok(Some(PulledBundle {
+            tarball: tarball.to_vec(),
+            etag,
+        }))
+    }
+}
@@ -0,0 +1,213 @@
+//! Scoped bundles, which only contain the packages relevant to some role groups (for example the
+//! role groups serving a single product).
+//!
+//! OPA requests a scoped bundle by adding `?packages=<package>,...` to the bundle resource.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    io::Read,
+    sync::{Arc, Mutex},
+};
+
+use flate2::write::GzEncoder;
+use serde::Deserialize;
+use snafu::ResultExt;
+use stackable_opa_crd::policy::rego_package_name;
+
+use crate::{
+    bundle_error, bundle_roots, file_header,
+    manifest::{Manifest, MANIFEST_FILE_NAME},
+    signing::BundleSigner,
+    Bundle, BundleError,
+};
+
+#[derive(Deserialize)]
+pub struct BundleQuery {
+    /// Comma separated packages (including their subpackages) to restrict the bundle to.
+    packages: Option<String>,
+}
+
+impl BundleQuery {
+    pub fn scope(&self) -> Option<BundleScope> {
+        let packages = self
+            .packages
+            .iter()
+            .flat_map(|packages| packages.split(','))
+            .map(str::trim)
+            .filter(|package| !package.is_empty())
+            .map(str::to_string)
+            .collect::<BTreeSet<_>>();
+        (!packages.is_empty()).then_some(BundleScope { packages })
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct BundleScope {
+    packages: BTreeSet<String>,
+}
+
+impl BundleScope {
+    fn contains_package(&self, package: &str) -> bool {
+        self.packages.iter().any(|scope_package| {
+            package == scope_package
+                || package
+                    .strip_prefix(scope_package.as_str())
+                    .is_some_and(|subpackage| subpackage.starts_with('.'))
+        })
+    }
+
+    /// The data paths of the packages in the scope.
+    fn paths(&self) -> impl Iterator<Item = String> + '_ {
+        self.packages
+            .iter()
+            .map(|package| package.replace('.', "/"))
+    }
+
+    fn contains_path(&self, path: &str) -> bool {
+        self.paths().any(|scope_path| {
+            path == scope_path
+                || path
+                    .strip_prefix(scope_path.as_str())
+                    .is_some_and(|subpath| subpath.starts_with('/'))
+        })
+    }
+}
+
+/// Caches the scoped variants of the current bundle, so that they are only rebuilt when the bundle changes.
+#[derive(Default)]
+pub struct ScopedBundles {
+    /// The scoped bundles, along with the etag of the bundle they were built from.
+    bundles: Mutex<HashMap<BundleScope, (String, Arc<Bundle>)>>,
+}
+
+impl ScopedBundles {
+    pub fn get(
+        &self,
+        bundle: &Bundle,
+        scope: &BundleScope,
+        signer: Option<&BundleSigner>,
+    ) -> Result<Arc<Bundle>, BundleError> {
+        if let Some((source_etag, scoped_bundle)) = self.bundles.lock().unwrap().get(scope) {
+            if *source_etag == bundle.etag {
+                return Ok(scoped_bundle.clone());
+            }
+        }
+        let scoped_bundle = Arc::new(build_scoped_bundle(bundle, scope, signer)?);
+        self.bundles
+            .lock()
+            .unwrap()
+            .insert(scope.clone(), (bundle.etag.clone(), scoped_bundle.clone()));
+        Ok(scoped_bundle)
+    }
+}
+
+fn build_scoped_bundle(
+    bundle: &Bundle,
+    scope: &BundleScope,
+    signer: Option<&BundleSigner>,
+) -> Result<Bundle, BundleError> {
+    use bundle_error::*;
+
+    let mut archive = tar::Archive::new(flate2::read::MultiGzDecoder::new(&*bundle.tarball));
+    let mut files = Vec::<(String, Vec<u8>)>::new();
+    for entry in archive.entries().context(ReadTarballSnafu)? {
+        let mut entry = entry.context(ReadTarballSnafu)?;
+        let file_path = entry
+            .path()
+            .context(ReadTarballSnafu)?
+            .to_string_lossy()
+            .into_owned();
+        let mut data = Vec::new();
+        entry.read_to_end(&mut data).context(ReadTarballSnafu)?;
+        files.push((file_path, data));
+    }
+    // The Wasm modules are only known from the manifest, which is the last file of the bundle
+    let source_manifest = files
+        .iter()
+        .find(|(file_path, _)| file_path == MANIFEST_FILE_NAME)
+        .and_then(|(_, data)| serde_json::from_slice::<Manifest>(data).ok())
+        .unwrap_or_default();
+    let wasm_resolvers = source_manifest
+        .wasm
+        .iter()
+        .filter(|resolver| scope.contains_path(&resolver.entrypoint))
+        .cloned()
+        .collect::<Vec<_>>();
+
+    let mut tar = tar::Builder::new(GzEncoder::new(Vec::new(), flate2::Compression::default()));
+    let mut signed_files = Vec::<(String, Vec<u8>)>::new();
+    for (file_path, data) in files {
+        let in_scope = match file_path.rsplit_once('/') {
+            _ if file_path.ends_with(".rego") => std::str::from_utf8(&data)
+                .ok()
+                .and_then(rego_package_name)
+                .is_some_and(|package| scope.contains_package(package)),
+            Some((dir_path, "data.json" | "data.yaml")) => scope.contains_path(dir_path),
+            _ if file_path.ends_with(".wasm") => wasm_resolvers
+                .iter()
+                .any(|resolver| resolver.module.trim_start_matches('/') == file_path),
+            // The manifest and signatures are rebuilt for the scoped bundle
+            _ => false,
+        };
+        if !in_scope {
+            continue;
+        }
+        let mut header = file_header(&file_path, &data)?;
+        tar.append_data(&mut header, &file_path, &*data)
+            .context(AddScopedFileToTarballSnafu {
+                file_path: &file_path,
+            })?;
+        signed_files.push((file_path, data));
+    }
+
+    let manifest = Manifest {
+        roots: Some(bundle_roots(scope.paths())),
+        wasm: wasm_resolvers,
+        metadata: source_manifest.metadata,
+        ..Manifest::new(
+            signed_files
+                .iter()
+                .map(|(file_path, data)| (file_path.as_str(), data.as_slice())),
+        )
+    };
+    let revision = manifest.revision.clone();
+    let manifest = manifest.encode()?;
+    let mut header = file_header(MANIFEST_FILE_NAME, &manifest)?;
+    tar.append_data(&mut header, MANIFEST_FILE_NAME, &*manifest)
+        .context(AddManifestToTarballSnafu)?;
+    signed_files.push((MANIFEST_FILE_NAME.to_string(), manifest));
+    if let Some(signer) = signer {
+        let signatures = signer
+            .sign(
+                signed_files
+                    .iter()
+                    .map(|(file_path, data)| (file_path.as_str(), data.as_slice())),
+            )
+            .context(SignBundleSnafu)?;
+        let mut header = file_header(crate::signing::SIGNATURES_FILE_NAME, &signatures)?;
+        tar.append_data(
+            &mut header,
+            crate::signing::SIGNATURES_FILE_NAME,
+            &*signatures,
+        )
+        .context(AddSignaturesToTarballSnafu)?;
+    }
+    let tarball = tar
+        .into_inner()
+        .context(BuildTarballSnafu)?
+        .finish()
+        .context(BuildTarballSnafu)?;
+
+    let packages = bundle
+        .packages
+        .iter()
+        .filter(|package| scope.contains_package(&package.package))
+        .cloned()
+        .collect();
+    tracing::info!(
+        ?scope,
+        bundle.files = ?signed_files.iter().map(|(file_path, _)| file_path).collect::<Vec<_>>(),
+        "built scoped bundle"
+    );
+    Ok(Bundle::new(tarball, packages, revision))
+}
@@ -0,0 +1,261 @@
+//! Incremental bundle builds.
+//!
+//! The files of each ConfigMap are encoded into a separate segment: a gzip member containing their
+//! tar entries, without the end-of-archive marker. Concatenated, the segments form a valid gzipped
+//! tarball (gzip allows multiple members, and OPA reads all of them), so a rebuild only has to encode
+//! the ConfigMaps that changed since the previous build.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use flate2::write::GzEncoder;
+use futures::future;
+use snafu::{OptionExt, ResultExt};
+use stackable_opa_crd::policy::{
+    data_document_path, rego_package_name, BUNDLE_WASM_ENTRYPOINTS_ANNOTATION,
+};
+use stackable_operator::{
+    k8s_openapi::api::core::v1::ConfigMap,
+    kube::{api::ObjectMeta, runtime::reflector::ObjectRef, ResourceExt},
+};
+
+use crate::{bundle_error, file_header, BundleError};
+
+/// The name of Wasm modules in ConfigMaps and bundles, OPA only loads Wasm modules with this name.
+pub const WASM_MODULE_FILE_NAME: &str = "policy.wasm";
+
+/// A file that is added to the bundle.
+#[derive(Clone)]
+pub struct BundleFile {
+    /// The name of the file in its ConfigMap, or its path for the built-in regorule library.
+    pub file_name: String,
+    /// The path of the file inside the bundle.
+    pub path: String,
+    pub data: Vec<u8>,
+    /// The package declared by the file, if it is a Rego file.
+    pub package: Option<String>,
+    /// Set for Rego files that declare no package, in which case the roots of the bundle are unknown.
+    pub unknown_package: bool,
+    /// The path that the file adds to the roots of the bundle, if it is a data document.
+    pub data_path: Option<String>,
+    /// Set for data documents placed at the path of their package, which can conflict with the data
+    /// documents of other ConfigMaps.
+    pub placed_at_package: bool,
+    /// The entrypoints of the file, if it is a Wasm module.
+    pub wasm_entrypoints: Vec<String>,
+}
+
+/// The files of a ConfigMap (or of the built-in regorule library), along with their encoded tar entries.
+pub struct Segment {
+    pub files: Vec<BundleFile>,
+    /// The gzipped tar entries of the files.
+    pub encoded: Vec<u8>,
+}
+
+impl Segment {
+    pub fn encode(files: Vec<BundleFile>) -> Result<Self, BundleError> {
+        use bundle_error::*;
+
+        let mut tar = tar::Builder::new(Vec::new());
+        for file in &files {
+            let mut header = file_header(&file.path, &file.data)?;
+            tar.append_data(&mut header, &file.path, &*file.data)
+                .context(EncodeSegmentSnafu {
+                    file_path: &file.path,
+                })?;
+        }
+        // Taking the entries directly (instead of finishing the tarball) leaves out the end-of-archive
+        // marker, which may only be written once at the end of the bundle
+        let entries = std::mem::take(tar.get_mut());
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(&entries).context(BuildTarballSnafu)?;
+        let encoded = encoder.finish().context(BuildTarballSnafu)?;
+        Ok(Self { files, encoded })
+    }
+
+    /// The segment of the built-in regorule library (such as `stackable.opa.userinfo.v1`).
+    pub fn builtin_rego_rules() -> Result<Self, BundleError> {
+        Self::encode(
+            stackable_opa_regorule_library::REGORULES
+                .iter()
+                .map(|(file_path, data)| BundleFile {
+                    file_name: file_path.to_string(),
+                    path: file_path.to_string(),
+                    data: data.as_bytes().to_vec(),
+                    package: rego_package_name(data).map(str::to_string),
+                    unknown_package: file_path.ends_with(".rego")
+                        && rego_package_name(data).is_none(),
+                    data_path: None,
+                    placed_at_package: false,
+                    wasm_entrypoints: Vec::new(),
+                })
+                .collect(),
+        )
+    }
+
+    fn from_config_map(cm: &ConfigMap) -> Result<Self, BundleError> {
+        let ObjectMeta {
+            name: Some(cm_ns),
+            namespace: Some(cm_name),
+            ..
+        } = &cm.metadata
+        else {
+            return bundle_error::ConfigMapMetadataMissingSnafu.fail();
+        };
+        let cm_ref = ObjectRef::from_obj(cm);
+        let mut files = Vec::new();
+        for (file_name, data) in cm.data.iter().flatten() {
+            let data_document_path = data_document_path(cm, file_name);
+            let placed_at_package = data_document_path.is_some();
+            let (path, data_path) = match data_document_path {
+                Some(data_path) => {
+                    if let Err(error) = serde_json::from_str::<serde_json::Value>(data) {
+                        tracing::error!(
+                            config_map = %cm_ref,
+                            file_name,
+                            error = &error as &dyn std::error::Error,
+                            "skipping data document that is not valid JSON"
+                        );
+                        continue;
+                    }
+                    // OPA loads data documents from files named data.json, at the path of their directory
+                    (format!("{data_path}/data.json"), Some(data_path))
+                }
+                None => {
+                    let dir_path = format!("configmap/{cm_ns}/{cm_name}");
+                    let data_path = (file_name == "data.json" || file_name == "data.yaml")
+                        .then(|| dir_path.clone());
+                    (format!("{dir_path}/{file_name}"), data_path)
+                }
+            };
+            let is_rego = file_name.ends_with(".rego");
+            let package = is_rego
+                .then(|| rego_package_name(data))
+                .flatten()
+                .map(str::to_string);
+            files.push(BundleFile {
+                file_name: file_name.clone(),
+                path,
+                data: data.as_bytes().to_vec(),
+                unknown_package: is_rego && package.is_none(),
+                package,
+                data_path,
+                placed_at_package,
+                wasm_entrypoints: Vec::new(),
+            });
+        }
+
+        let wasm_module = cm
+            .binary_data
+            .as_ref()
+            .and_then(|binary_data| binary_data.get(WASM_MODULE_FILE_NAME));
+        match (
+            cm.annotations().get(BUNDLE_WASM_ENTRYPOINTS_ANNOTATION),
+            wasm_module,
+        ) {
+            (Some(entrypoints), Some(wasm_module)) => {
+                let wasm_entrypoints = entrypoints
+                    .split(',')
+                    .map(|entrypoint| entrypoint.trim().trim_matches('/'))
+                    .filter(|entrypoint| !entrypoint.is_empty())
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                if wasm_entrypoints.is_empty() {
+                    tracing::error!(
+                        config_map = %cm_ref,
+                        "skipping Wasm module without entrypoints"
+                    );
+                } else {
+                    files.push(BundleFile {
+                        file_name: WASM_MODULE_FILE_NAME.to_string(),
+                        path: format!("configmap/{cm_ns}/{cm_name}/{WASM_MODULE_FILE_NAME}"),
+                        data: wasm_module.0.clone(),
+                        package: None,
+                        unknown_package: false,
+                        data_path: None,
+                        placed_at_package: false,
+                        wasm_entrypoints,
+                    });
+                }
+            }
+            (Some(_), None) => tracing::error!(
+                config_map = %cm_ref,
+                "ConfigMap has Wasm entrypoints, but no {WASM_MODULE_FILE_NAME} binary data key"
+            ),
+            (None, Some(_)) => tracing::warn!(
+                config_map = %cm_ref,
+                "skipping Wasm module without the {BUNDLE_WASM_ENTRYPOINTS_ANNOTATION} annotation"
+            ),
+            (None, None) => {}
+        }
+        Self::encode(files)
+    }
+}
+
+/// The segments of the previous build, so that unchanged ConfigMaps are not encoded again.
+#[derive(Default)]
+pub struct SegmentCache {
+    /// The segments by ConfigMap, along with the resource version they were encoded from.
+    segments: Mutex<HashMap<ObjectRef<ConfigMap>, (String, Arc<Segment>)>>,
+}
+
+impl SegmentCache {
+    /// Returns the segments of the ConfigMaps (in the same order), encoding the changed ConfigMaps
+    /// concurrently.
+    ///
+    /// Segments of ConfigMaps that are not passed are dropped from the cache.
+    pub async fn segments(
+        &self,
+        config_maps: &[Arc<ConfigMap>],
+    ) -> Result<Vec<Arc<Segment>>, BundleError> {
+        use bundle_error::*;
+
+        let mut encoded = 0;
+        let segments = future::try_join_all(config_maps.iter().map(|cm| {
+            let cm_ref = ObjectRef::from_obj(&**cm);
+            let resource_version = cm.resource_version();
+            let cached = self
+                .segments
+                .lock()
+                .unwrap()
+                .get(&cm_ref)
+                .filter(|(cached_version, _)| Some(cached_version) == resource_version.as_ref())
+                .map(|(_, segment)| segment.clone());
+            if cached.is_none() {
+                encoded += 1;
+            }
+            let cm = cm.clone();
+            async move {
+                let resource_version = resource_version.context(ConfigMapMetadataMissingSnafu)?;
+                let segment = match cached {
+                    Some(segment) => segment,
+                    None => Arc::new(
+                        tokio::task::spawn_blocking(move || Segment::from_config_map(&cm))
+                            .await
+                            .context(EncodeSegmentTaskSnafu)??,
+                    ),
+                };
+                Ok::<_, BundleError>((cm_ref, resource_version, segment))
+            }
+        }))
+        .await?;
+        tracing::debug!(
+            segments.encoded = encoded,
+            segments.reused = segments.len() - encoded,
+            "encoded changed ConfigMaps"
+        );
+        *self.segments.lock().unwrap() = segments
+            .iter()
+            .map(|(cm_ref, resource_version, segment)| {
+                (cm_ref.clone(), (resource_version.clone(), segment.clone()))
+            })
+            .collect();
+        Ok(segments
+            .into_iter()
+            .map(|(_, _, segment)| segment)
+            .collect())
+    }
+}
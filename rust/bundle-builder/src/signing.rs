@@ -0,0 +1,228 @@
+//! Signing of bundles, see <https://www.openpolicyagent.org/docs/latest/management-bundles/#signing>.
+
+use std::{path::PathBuf, str::FromStr};
+
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, Snafu};
+
+/// The name of the file inside the bundle that contains the signatures.
+pub const SIGNATURES_FILE_NAME: &str = ".signatures.json";
+
+#[derive(clap::Args)]
+pub struct SigningArgs {
+    /// Path to the key used to sign bundles.
+    /// This is the PEM encoded private key for the asymmetric algorithms or the shared secret for the HMAC algorithms.
+    /// Bundles are not signed if not specified.
+    #[clap(long, env)]
+    bundle_signing_key_file: Option<PathBuf>,
+
+    /// The algorithm used to sign bundles, such as `RS256` or `HS256`.
+    #[clap(long, env, default_value = "RS256")]
+    bundle_signing_algorithm: String,
+
+    /// The ID of the key OPA should use to verify the signatures.
+    #[clap(long, env)]
+    bundle_signing_key_id: Option<String>,
+
+    /// The scope of the signatures, which OPA checks against the scope it is configured with.
+    #[clap(long, env)]
+    bundle_signing_scope: Option<String>,
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to parse signing algorithm {algorithm:?}"))]
+    ParseAlgorithm {
+        source: jsonwebtoken::errors::Error,
+        algorithm: String,
+    },
+
+    #[snafu(display("signing algorithm {algorithm:?} is not supported"))]
+    UnsupportedAlgorithm { algorithm: Algorithm },
+
+    #[snafu(display("failed to read signing key from {path:?}"))]
+    ReadKey {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to parse signing key from {path:?}"))]
+    ParseKey {
+        source: jsonwebtoken::errors::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to parse file {file_path:?} as JSON"))]
+    ParseJsonFile {
+        source: serde_json::Error,
+        file_path: String,
+    },
+
+    #[snafu(display("failed to sign bundle"))]
+    Sign { source: jsonwebtoken::errors::Error },
+
+    #[snafu(display("failed to serialize signatures"))]
+    SerializeSignatures { source: serde_json::Error },
+}
+
+pub struct BundleSigner {
+    header: Header,
+    key: EncodingKey,
+    key_id: Option<String>,
+    scope: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SignedFiles<'a> {
+    files: Vec<SignedFile<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keyid: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct SignedFile<'a> {
+    name: &'a str,
+    hash: String,
+    algorithm: &'static str,
+}
+
+#[derive(Serialize)]
+struct Signatures {
+    signatures: Vec<String>,
+}
+
+impl BundleSigner {
+    /// Loads the signing key, returns `None` if bundle signing is not configured.
+    pub fn from_args(args: &SigningArgs) -> Result<Option<Self>, Error> {
+        let Some(path) = &args.bundle_signing_key_file else {
+            return Ok(None);
+        };
+        let algorithm = Algorithm::from_str(&args.bundle_signing_algorithm).with_context(|_| {
+            ParseAlgorithmSnafu {
+                algorithm: &args.bundle_signing_algorithm,
+            }
+        })?;
+        let key_data = std::fs::read(path).context(ReadKeySnafu { path })?;
+        let key = match algorithm {
+            Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => {
+                Ok(EncodingKey::from_secret(&key_data))
+            }
+            Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512 => EncodingKey::from_rsa_pem(&key_data),
+            Algorithm::ES256 | Algorithm::ES384 => EncodingKey::from_ec_pem(&key_data),
+            algorithm => return UnsupportedAlgorithmSnafu { algorithm }.fail(),
+        }
+        .context(ParseKeySnafu { path })?;
+        let mut header = Header::new(algorithm);
+        header.kid.clone_from(&args.bundle_signing_key_id);
+        Ok(Some(Self {
+            header,
+            key,
+            key_id: args.bundle_signing_key_id.clone(),
+            scope: args.bundle_signing_scope.clone(),
+        }))
+    }
+
+    /// Builds the contents of the [`SIGNATURES_FILE_NAME`] file, covering all given files of the bundle.
+    pub fn sign<'a>(
+        &self,
+        files: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+    ) -> Result<Vec<u8>, Error> {
+        let files = files
+            .into_iter()
+            .map(|(file_path, data)| {
+                Ok(SignedFile {
+                    name: file_path,
+                    hash: file_hash(file_path, data)?,
+                    algorithm: "SHA-256",
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let token = jsonwebtoken::encode(
+            &self.header,
+            &SignedFiles {
+                files,
+                keyid: self.key_id.as_deref(),
+                scope: self.scope.as_deref(),
+            },
+            &self.key,
+        )
+        .context(SignSnafu)?;
+        serde_json::to_vec(&Signatures {
+            signatures: vec![token],
+        })
+        .context(SerializeSignaturesSnafu)
+    }
+}
+
+/// Hashes a file the same way OPA does when verifying a bundle.
+///
+/// OPA hashes JSON files in their canonical form (compact, with sorted object keys), and all
+/// other files byte by byte.
+fn file_hash(file_path: &str, data: &[u8]) -> Result<String, Error> {
+    let mut hasher = Sha256::new();
+    if file_path.ends_with(".json") {
+        let value = serde_json::from_slice::<serde_json::Value>(data)
+            .context(ParseJsonFileSnafu { file_path })?;
+        let mut canonical = String::new();
+        write_canonical_json(&mut canonical, &value);
+        hasher.update(canonical);
+    } else {
+        hasher.update(data);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn write_canonical_json(out: &mut String, value: &serde_json::Value) {
+    match value {
+        serde_json::Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical_json(out, item);
+            }
+            out.push(']');
+        }
+        serde_json::Value::Object(entries) => {
+            let mut entries = entries.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(key, _)| *key);
+            out.push('{');
+            for (i, (key, item)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical_json_string(out, key);
+                out.push(':');
+                write_canonical_json(out, item);
+            }
+            out.push('}');
+        }
+        serde_json::Value::String(string) => write_canonical_json_string(out, string),
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+/// Go (and thereby OPA) escapes HTML-sensitive characters when encoding JSON strings.
+fn write_canonical_json_string(out: &mut String, string: &str) {
+    let encoded = serde_json::Value::from(string).to_string();
+    for c in encoded.chars() {
+        match c {
+            '<' => out.push_str("\\u003c"),
+            '>' => out.push_str("\\u003e"),
+            '&' => out.push_str("\\u0026"),
+            '\u{2028}' => out.push_str("\\u2028"),
+            '\u{2029}' => out.push_str("\\u2029"),
+            c => out.push(c),
+        }
+    }
+}
@@ -0,0 +1,332 @@
+//! Evaluates decision inputs against previously built bundle revisions, as a debugging aid to compare
+//! how the policies of different revisions treat an input.
+//!
+//! The last built bundles are retained in memory (if enabled), and evaluated with [regorus] (a Rego
+//! interpreter) instead of OPA, so the results are not guaranteed to match OPA's decisions. Built-in
+//! functions that depend on the environment (such as `http.send`, which is used by the
+//! user-info-fetcher regorule library) are not available, so decisions depending on them fail to
+//! evaluate.
+//!
+//! The endpoints are only served on the local port of the bundle builder, since the queries are
+//! arbitrary Rego.
+//!
+//! [regorus]: https://github.com/microsoft/regorus
+
+use std::{
+    collections::VecDeque,
+    io::Read,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+use tokio::sync::Semaphore;
+
+use crate::Bundle;
+
+/// How long the client waits for an evaluation.
+const EVALUATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(clap::Args)]
+pub struct SimulationArgs {
+    /// How many of the last built bundle revisions are kept in memory for `POST /simulate`.
+    /// Simulations are disabled if set to 0 (the default).
+    #[clap(long, env, default_value_t = 0)]
+    retained_bundle_revisions: usize,
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("simulations are disabled, since no bundle revisions are retained"))]
+    Disabled,
+
+    #[snafu(display("bundle revision {revision:?} is not retained"))]
+    UnknownRevision { revision: String },
+
+    #[snafu(display("no bundle has been built yet"))]
+    NoRevisions,
+
+    #[snafu(display("failed to read bundle"))]
+    ReadTarball { source: std::io::Error },
+
+    #[snafu(display("failed to parse data document {path:?}"))]
+    ParseDataDocument {
+        source: serde_json::Error,
+        path: String,
+    },
+
+    #[snafu(display("failed to load policy {path:?}: {message}"))]
+    LoadPolicy { path: String, message: String },
+
+    #[snafu(display("failed to load data documents: {message}"))]
+    LoadData { message: String },
+
+    #[snafu(display("failed to load input: {message}"))]
+    LoadInput { message: String },
+
+    #[snafu(display("failed to evaluate query {query:?}: {message}"))]
+    Evaluate { query: String, message: String },
+
+    #[snafu(display("failed to serialize result"))]
+    SerializeResult { source: serde_json::Error },
+
+    #[snafu(display("another simulation is still being evaluated"))]
+    EvaluationRunning,
+
+    #[snafu(display("evaluation did not finish within {timeout:?}"))]
+    EvaluationTimedOut { timeout: Duration },
+
+    #[snafu(display("evaluation failed"))]
+    EvaluationTask { source: tokio::task::JoinError },
+}
+
+impl Error {
+    pub fn status_code(&self) -> axum::http::StatusCode {
+        use axum::http::StatusCode;
+        match self {
+            Error::Disabled | Error::UnknownRevision { .. } => StatusCode::NOT_FOUND,
+            Error::NoRevisions => StatusCode::SERVICE_UNAVAILABLE,
+            Error::LoadInput { .. } | Error::Evaluate { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            Error::EvaluationRunning => StatusCode::TOO_MANY_REQUESTS,
+            Error::EvaluationTimedOut { .. } => StatusCode::GATEWAY_TIMEOUT,
+            Error::ReadTarball { .. }
+            | Error::ParseDataDocument { .. }
+            | Error::LoadPolicy { .. }
+            | Error::LoadData { .. }
+            | Error::SerializeResult { .. }
+            | Error::EvaluationTask { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// The body of `POST /simulate`.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationRequest {
+    /// The Rego query to evaluate, e.g. `data.kafka.authz.allow`.
+    query: String,
+    /// The decision input, as it would be sent to OPA.
+    #[serde(default)]
+    input: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationResult {
+    /// The bundle revision the query was evaluated against.
+    revision: String,
+    /// The results of the query, in the format of OPA's query API (a list of `expressions` and
+    /// `bindings`).
+    result: serde_json::Value,
+}
+
+/// A retained bundle revision, as returned by `GET /simulate/revisions`.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetainedRevision {
+    revision: String,
+    /// Seconds since the UNIX epoch.
+    built_at: u64,
+}
+
+pub struct BundleHistory {
+    max_revisions: usize,
+    /// The retained revisions along with their tarballs, from the oldest to the newest.
+    revisions: Mutex<VecDeque<(RetainedRevision, Arc<[u8]>)>>,
+    /// Only one evaluation runs at a time, see [`Self::simulate`].
+    evaluation: Arc<Semaphore>,
+}
+
+impl BundleHistory {
+    pub fn new(args: &SimulationArgs) -> Self {
+        Self {
+            max_revisions: args.retained_bundle_revisions,
+            revisions: Mutex::default(),
+            evaluation: Arc::new(Semaphore::new(1)),
+        }
+    }
+
+    /// Retains the bundle, dropping the oldest revision if the limit is exceeded.
+    pub fn record(&self, bundle: &Bundle) {
+        if self.max_revisions == 0 {
+            return;
+        }
+        let revision = bundle.revision();
+        let mut revisions = self.revisions.lock().unwrap();
+        // Rebuilds without changes produce the same revision again
+        if revisions
+            .back()
+            .is_some_and(|(retained, _)| retained.revision == revision)
+        {
+            return;
+        }
+        revisions.push_back((
+            RetainedRevision {
+                revision: revision.to_string(),
+                built_at: SystemTime::now()
+                    .duration_since(SystemTime::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_secs(),
+            },
+            Arc::from(bundle.tarball.as_slice()),
+        ));
+        while revisions.len() > self.max_revisions {
+            revisions.pop_front();
+        }
+    }
+
+    /// The retained revisions, from the newest to the oldest.
+    pub fn revisions(&self) -> Vec<RetainedRevision> {
+        let revisions = self.revisions.lock().unwrap();
+        revisions
+            .iter()
+            .rev()
+            .map(|(retained, _)| retained.clone())
+            .collect()
+    }
+
+    /// Evaluates the query against the bundle `revision` (or the newest retained revision), giving up
+    /// after [`EVALUATION_TIMEOUT`].
+    ///
+    /// Evaluations can't be cancelled, so an evaluation that timed out keeps running in the
+    /// background. Only one evaluation runs at a time, so that runaway queries can't use up the CPU
+    /// of the bundle builder.
+    pub async fn simulate(
+        self: Arc<Self>,
+        revision: Option<String>,
+        request: SimulationRequest,
+    ) -> Result<SimulationResult, Error> {
+        let permit = self
+            .evaluation
+            .clone()
+            .try_acquire_owned()
+            .ok()
+            .context(EvaluationRunningSnafu)?;
+        let evaluation = tokio::task::spawn_blocking(move || {
+            let _permit = permit;
+            self.evaluate(revision.as_deref(), request)
+        });
+        tokio::time::timeout(EVALUATION_TIMEOUT, evaluation)
+            .await
+            .ok()
+            .context(EvaluationTimedOutSnafu {
+                timeout: EVALUATION_TIMEOUT,
+            })?
+            .context(EvaluationTaskSnafu)?
+    }
+
+    fn evaluate(
+        &self,
+        revision: Option<&str>,
+        request: SimulationRequest,
+    ) -> Result<SimulationResult, Error> {
+        if self.max_revisions == 0 {
+            return DisabledSnafu.fail();
+        }
+        let (revision, tarball) = {
+            let revisions = self.revisions.lock().unwrap();
+            let retained = match revision {
+                Some(revision) => revisions
+                    .iter()
+                    .find(|(retained, _)| retained.revision == revision)
+                    .context(UnknownRevisionSnafu { revision })?,
+                None => revisions.back().context(NoRevisionsSnafu)?,
+            };
+            (retained.0.revision.clone(), retained.1.clone())
+        };
+
+        let mut engine = load_bundle(&tarball)?;
+        if let Some(input) = request.input {
+            engine.set_input(regorus::Value::from_json_str(&input.to_string()).map_err(
+                |error| Error::LoadInput {
+                    message: error.to_string(),
+                },
+            )?);
+        }
+        let results = engine
+            .eval_query(request.query.clone(), false)
+            .map_err(|error| Error::Evaluate {
+                query: request.query,
+                message: error.to_string(),
+            })?;
+        Ok(SimulationResult {
+            revision,
+            result: serde_json::to_value(results).context(SerializeResultSnafu)?,
+        })
+    }
+}
+
+/// Loads the policies and data documents of a bundle tarball into a new Rego engine.
+fn load_bundle(tarball: &[u8]) -> Result<regorus::Engine, Error> {
+    let mut engine = regorus::Engine::new();
+    let mut data = serde_json::Map::new();
+    let mut archive = tar::Archive::new(flate2::read::MultiGzDecoder::new(tarball));
+    for entry in archive.entries().context(ReadTarballSnafu)? {
+        let mut entry = entry.context(ReadTarballSnafu)?;
+        let path = entry
+            .path()
+            .context(ReadTarballSnafu)?
+            .to_string_lossy()
+            .trim_start_matches('/')
+            .to_string();
+        let mut contents = String::new();
+        if path.ends_with(".rego") {
+            entry
+                .read_to_string(&mut contents)
+                .context(ReadTarballSnafu)?;
+            engine
+                .add_policy(path.clone(), contents)
+                .map_err(|error| Error::LoadPolicy {
+                    path,
+                    message: error.to_string(),
+                })?;
+        } else if path == "data.json" || path.ends_with("/data.json") {
+            entry
+                .read_to_string(&mut contents)
+                .context(ReadTarballSnafu)?;
+            let document = serde_json::from_str::<serde_json::Value>(&contents)
+                .context(ParseDataDocumentSnafu { path: &path })?;
+            // The data document is placed at the directory it is contained in
+            let data_path = path.trim_end_matches("data.json").trim_end_matches('/');
+            insert_data_document(&mut data, data_path, document);
+        }
+    }
+    engine
+        .add_data(
+            regorus::Value::from_json_str(&serde_json::Value::Object(data).to_string()).map_err(
+                |error| Error::LoadData {
+                    message: error.to_string(),
+                },
+            )?,
+        )
+        .map_err(|error| Error::LoadData {
+            message: error.to_string(),
+        })?;
+    Ok(engine)
+}
+
+/// Merges the `document` into `data` at the slash-separated `path`.
+fn insert_data_document(
+    data: &mut serde_json::Map<String, serde_json::Value>,
+    path: &str,
+    document: serde_json::Value,
+) {
+    let mut segments = path.split('/').filter(|segment| !segment.is_empty());
+    let Some(segment) = segments.next() else {
+        if let serde_json::Value::Object(document) = document {
+            data.extend(document);
+        }
+        return;
+    };
+    let rest = segments.collect::<Vec<_>>().join("/");
+    let child = data
+        .entry(segment)
+        .or_insert_with(|| serde_json::Value::Object(serde_json::Map::new()));
+    if !child.is_object() {
+        *child = serde_json::Value::Object(serde_json::Map::new());
+    }
+    if let serde_json::Value::Object(child) = child {
+        insert_data_document(child, &rest, document);
+    }
+}
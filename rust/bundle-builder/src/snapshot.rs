@@ -0,0 +1,260 @@
+//! Snapshots of the last served bundle, stored in a ConfigMap.
+//!
+//! When the OpaCluster is stopped and resumed, the snapshot is served until the bundle builder has
+//! caught up with the current policies. Otherwise, OPA would briefly be served a bundle without any
+//! policies, racing the startup of the products using OPA.
+//!
+//! The bundle itself is only snapshotted when the bundle builders shut down because the OpaCluster has
+//! been stopped, or continuously if the bundles are promoted from the snapshot (see [`crate::stable`]).
+//! Otherwise, only the revision of the latest bundle is recorded.
+
+use std::collections::BTreeMap;
+
+use serde_json::json;
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::{
+    BUNDLE_LATEST_REVISION_ANNOTATION, BUNDLE_SNAPSHOT_ERROR_ANNOTATION,
+    BUNDLE_SNAPSHOT_HASH_ANNOTATION, BUNDLE_SNAPSHOT_KEY,
+};
+use stackable_operator::{
+    k8s_openapi::{api::core::v1::ConfigMap, ByteString},
+    kube::{
+        api::{Patch, PatchParams},
+        Api, ResourceExt,
+    },
+};
+
+use crate::APP_NAME;
+/// Kubernetes limits ConfigMaps to 1MiB, leave some room for the metadata.
+const MAX_SNAPSHOT_SIZE: usize = 1000 * 1000;
+
+#[derive(clap::Args)]
+pub struct SnapshotArgs {
+    /// Name of the ConfigMap (in the watched namespace) to store a snapshot of the last served bundle in.
+    /// The snapshot is served after restarts until the first bundle has been built.
+    #[clap(long, env)]
+    bundle_snapshot_config_map: Option<String>,
+}
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to load snapshot from ConfigMap {name:?}"))]
+    Load {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to store snapshot in ConfigMap {name:?}"))]
+    Store {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to record the bundle revision in ConfigMap {name:?}"))]
+    RecordRevision {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("bundle is too large to be snapshotted ({size} bytes)"))]
+    TooLarge { size: usize },
+}
+
+pub struct Snapshotter {
+    api: Api<ConfigMap>,
+    name: String,
+    /// Whether every bundle is snapshotted, rather than only when the OpaCluster is stopped.
+    continuous: bool,
+    /// The revision of the snapshot that is known to be stored.
+    stored_revision: tokio::sync::Mutex<Option<String>>,
+    /// The revision of the latest bundle that is known to be recorded.
+    recorded_revision: tokio::sync::Mutex<Option<String>>,
+}
+
+impl Snapshotter {
+    /// Returns `None` if snapshots are not configured.
+    pub fn new(
+        args: &SnapshotArgs,
+        client: stackable_operator::kube::Client,
+        namespace: &str,
+        continuous: bool,
+    ) -> Option<Self> {
+        Some(Self {
+            api: Api::namespaced(client, namespace),
+            name: args.bundle_snapshot_config_map.clone()?,
+            continuous,
+            stored_revision: tokio::sync::Mutex::default(),
+            recorded_revision: tokio::sync::Mutex::default(),
+        })
+    }
+
+    /// Whether every bundle is snapshotted, so that there is nothing left to do on shutdown.
+    pub fn is_continuous(&self) -> bool {
+        self.continuous
+    }
+
+    /// Loads the snapshotted bundle, returns `None` if no snapshot has been stored yet.
+    pub async fn load(&self) -> Result<Option<Vec<u8>>, Error> {
+        let cm = self
+            .api
+            .get_opt(&self.name)
+            .await
+            .context(LoadSnafu { name: &self.name })?;
+        let Some(cm) = cm else {
+            return Ok(None);
+        };
+        let tarball = cm
+            .binary_data
+            .and_then(|mut binary_data| binary_data.remove(BUNDLE_SNAPSHOT_KEY))
+            .map(|tarball| tarball.0);
+        if tarball.is_some() {
+            *self.stored_revision.lock().await = cm
+                .metadata
+                .annotations
+                .and_then(|mut annotations| annotations.remove(BUNDLE_SNAPSHOT_HASH_ANNOTATION));
+        }
+        Ok(tarball)
+    }
+
+    /// Records a newly built bundle with the given `revision`, by snapshotting it if snapshots are
+    /// continuous and by only recording its revision otherwise.
+    ///
+    /// Returns whether this bundle builder was the one to update the ConfigMap, see [`Self::store`].
+    pub async fn record(&self, tarball: &[u8], revision: &str) -> Result<bool, Error> {
+        if self.continuous {
+            self.store(tarball, revision).await
+        } else {
+            self.record_revision(revision).await
+        }
+    }
+
+    /// Stores the bundle as the new snapshot, unless it is unchanged.
+    ///
+    /// Every bundle builder of the OpaCluster stores the bundles it builds, but since they all build
+    /// the same bundles only the first one actually updates the ConfigMap.
+    /// Returns whether this bundle builder was the one to update it.
+    ///
+    /// Bundles that are too large are not snapshotted. The reason is recorded in the ConfigMap, so
+    /// that the operator can report it in the status of the OpaCluster.
+    pub async fn store(&self, tarball: &[u8], revision: &str) -> Result<bool, Error> {
+        let revision = revision.to_string();
+        if tarball.len() > MAX_SNAPSHOT_SIZE {
+            let error = TooLargeSnafu {
+                size: tarball.len(),
+            }
+            .build();
+            self.patch_annotations(json!({
+                BUNDLE_LATEST_REVISION_ANNOTATION: revision,
+                BUNDLE_SNAPSHOT_ERROR_ANNOTATION: error.to_string(),
+            }))
+            .await
+            .context(RecordRevisionSnafu { name: &self.name })?;
+            *self.recorded_revision.lock().await = Some(revision);
+            return Err(error);
+        }
+        let mut stored_revision = self.stored_revision.lock().await;
+        if stored_revision.as_ref() == Some(&revision) {
+            tracing::debug!("bundle is unchanged, not updating snapshot");
+            return Ok(false);
+        }
+        let current_revision = self
+            .api
+            .get_opt(&self.name)
+            .await
+            .context(LoadSnafu { name: &self.name })?
+            .and_then(|cm| {
+                cm.annotations()
+                    .get(BUNDLE_SNAPSHOT_HASH_ANNOTATION)
+                    .cloned()
+            });
+        if current_revision.as_ref() == Some(&revision) {
+            tracing::debug!("bundle has already been snapshotted by another bundle builder");
+            *stored_revision = Some(revision);
+            return Ok(false);
+        }
+        tracing::info!(config_map = self.name, "storing bundle snapshot");
+        self.api
+            .patch(
+                &self.name,
+                &PatchParams::apply(APP_NAME).force(),
+                &Patch::Apply(json!({
+                    "apiVersion": "v1",
+                    "kind": "ConfigMap",
+                    "metadata": {
+                        "annotations": {
+                            BUNDLE_SNAPSHOT_HASH_ANNOTATION: revision,
+                            BUNDLE_LATEST_REVISION_ANNOTATION: revision,
+                        },
+                    },
+                    "binaryData": BTreeMap::from([(
+                        BUNDLE_SNAPSHOT_KEY,
+                        ByteString(tarball.to_vec()),
+                    )]),
+                })),
+            )
+            .await
+            .context(StoreSnafu { name: &self.name })?;
+        // A previous bundle might have been too large
+        self.patch_annotations(json!({ BUNDLE_SNAPSHOT_ERROR_ANNOTATION: null }))
+            .await
+            .context(StoreSnafu { name: &self.name })?;
+        *self.recorded_revision.lock().await = Some(revision.clone());
+        *stored_revision = Some(revision);
+        Ok(true)
+    }
+
+    /// Records the `revision` of the latest bundle, without snapshotting the bundle itself.
+    ///
+    /// Returns whether this bundle builder was the one to update the ConfigMap, see [`Self::store`].
+    async fn record_revision(&self, revision: &str) -> Result<bool, Error> {
+        let mut recorded_revision = self.recorded_revision.lock().await;
+        if recorded_revision.as_deref() == Some(revision) {
+            tracing::debug!("bundle is unchanged, not recording revision");
+            return Ok(false);
+        }
+        let current_revision = self
+            .api
+            .get_opt(&self.name)
+            .await
+            .context(LoadSnafu { name: &self.name })?
+            .and_then(|cm| {
+                cm.annotations()
+                    .get(BUNDLE_LATEST_REVISION_ANNOTATION)
+                    .cloned()
+            });
+        if current_revision.as_deref() == Some(revision) {
+            tracing::debug!("bundle revision has already been recorded by another bundle builder");
+            *recorded_revision = Some(revision.to_string());
+            return Ok(false);
+        }
+        tracing::info!(config_map = self.name, "recording bundle revision");
+        self.patch_annotations(json!({ BUNDLE_LATEST_REVISION_ANNOTATION: revision }))
+            .await
+            .context(RecordRevisionSnafu { name: &self.name })?;
+        *recorded_revision = Some(revision.to_string());
+        Ok(true)
+    }
+
+    /// Merges the `annotations` into the ConfigMap, leaving the snapshot untouched (`null` removes an
+    /// annotation).
+    async fn patch_annotations(
+        &self,
+        annotations: serde_json::Value,
+    ) -> Result<(), stackable_operator::kube::Error> {
+        self.api
+            .patch(
+                &self.name,
+                &PatchParams {
+                    field_manager: Some(APP_NAME.to_string()),
+                    ..PatchParams::default()
+                },
+                &Patch::Merge(json!({
+                    "metadata": {
+                        "annotations": annotations,
+                    },
+                })),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+//! The stable bundle, which is served to the role groups that are not part of the canary.
+//!
+//! The operator promotes the latest bundle (as stored in the bundle snapshot) to the stable bundle
+//! ConfigMap once it has been served to the canary role groups for the configured soak period.
+
+use std::sync::Arc;
+
+use futures::{future, pin_mut, FutureExt, StreamExt};
+use stackable_opa_crd::BUNDLE_SNAPSHOT_KEY;
+use stackable_operator::{
+    k8s_openapi::api::core::v1::ConfigMap,
+    kube::{
+        runtime::{watcher, WatchStreamExt},
+        Api,
+    },
+};
+use tokio::sync::watch;
+
+use crate::{bundle_error, bundle_from_tarball, BundleFuture};
+
+#[derive(clap::Args)]
+pub struct StableBundleArgs {
+    /// Name of the ConfigMap (in the watched namespace) containing the stable bundle.
+    /// If set, it is served at `/opa/v1/opa/stable/bundle.tar.gz`.
+    #[clap(long, env)]
+    stable_bundle_config_map: Option<String>,
+}
+
+impl StableBundleArgs {
+    pub fn config_map(&self) -> Option<&str> {
+        self.stable_bundle_config_map.as_deref()
+    }
+}
+
+/// The stable bundle served until the first bundle has been promoted.
+pub fn not_promoted_yet() -> BundleFuture {
+    future::ready(Arc::new(bundle_error::NotPromotedYetSnafu.fail()))
+        .boxed()
+        .shared()
+}
+
+/// Watches the stable bundle ConfigMap, replacing the served stable bundle whenever a bundle is promoted.
+pub async fn watch_stable_bundle(
+    api: Api<ConfigMap>,
+    name: String,
+    stable_bundle: Arc<watch::Sender<BundleFuture>>,
+) {
+    let config_maps = watcher(
+        api,
+        watcher::Config::default().fields(&format!("metadata.name={name}")),
+    )
+    .applied_objects();
+    pin_mut!(config_maps);
+    while let Some(cm) = config_maps.next().await {
+        let cm = match cm {
+            Ok(cm) => cm,
+            Err(error) => {
+                tracing::error!(
+                    error = &error as &dyn std::error::Error,
+                    "failed to watch stable bundle"
+                );
+                continue;
+            }
+        };
+        let Some(tarball) = cm
+            .binary_data
+            .and_then(|mut binary_data| binary_data.remove(BUNDLE_SNAPSHOT_KEY))
+        else {
+            tracing::debug!(config_map = name, "no bundle has been promoted yet");
+            continue;
+        };
+        match bundle_from_tarball(tarball.0) {
+            Ok(promoted_bundle) => {
+                // The watcher sees the ConfigMap again whenever it relists
+                let unchanged = stable_bundle.borrow().peek().is_some_and(|current| {
+                    matches!(&**current, Ok(current) if current.etag == promoted_bundle.etag)
+                });
+                if unchanged {
+                    continue;
+                }
+                tracing::info!(
+                    bundle.etag = promoted_bundle.etag,
+                    "serving promoted stable bundle"
+                );
+                stable_bundle.send_replace(
+                    future::ready(Arc::new(Ok(promoted_bundle)))
+                        .boxed()
+                        .shared(),
+                );
+            }
+            Err(error) => tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to read stable bundle"
+            ),
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Collects the status reports pushed by OPA's status plugin, and surfaces bundle activation failures
+//! as Kubernetes Events on the OpaCluster.
+//!
+//! See <https://www.openpolicyagent.org/docs/latest/management-status/>.
+
+use std::{collections::BTreeMap, sync::Mutex};
+
+use serde::Deserialize;
+use stackable_opa_crd::OpaCluster;
+use stackable_operator::{
+    k8s_openapi::api::core::v1::ObjectReference,
+    kube::{
+        runtime::events::{Event, EventType, Recorder, Reporter},
+        Resource,
+    },
+};
+
+use crate::APP_NAME;
+
+/// Kubernetes rejects Events with longer notes.
+pub const MAX_EVENT_NOTE_LENGTH: usize = 1024;
+
+#[derive(Deserialize)]
+pub struct StatusReport {
+    #[serde(default)]
+    labels: BTreeMap<String, String>,
+    #[serde(default)]
+    bundles: BTreeMap<String, BundleStatus>,
+}
+
+#[derive(Deserialize)]
+struct BundleStatus {
+    #[serde(default)]
+    active_revision: String,
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    errors: Vec<serde_json::Value>,
+}
+
+impl BundleStatus {
+    /// Describes why the bundle failed to activate, or `None` if it was activated successfully.
+    fn failure(&self) -> Option<String> {
+        let code = self.code.as_deref()?;
+        let mut failure = format!("{code}: {}", self.message.as_deref().unwrap_or_default());
+        for error in &self.errors {
+            failure.push_str(&format!("\n{error}"));
+        }
+        Some(failure)
+    }
+}
+
+pub struct StatusCollector {
+    recorder: Recorder,
+    cluster: ObjectReference,
+    /// The last known failure of each bundle, so that only changes are published as Events.
+    bundle_failures: Mutex<BTreeMap<String, Option<String>>>,
+}
+
+impl StatusCollector {
+    pub fn new(
+        client: stackable_operator::kube::Client,
+        cluster_name: &str,
+        namespace: &str,
+    ) -> Self {
+        Self {
+            recorder: Recorder::new(
+                client,
+                Reporter {
+                    controller: APP_NAME.to_string(),
+                    instance: None,
+                },
+            ),
+            cluster: ObjectReference {
+                api_version: Some(OpaCluster::api_version(&()).into_owned()),
+                kind: Some(OpaCluster::kind(&()).into_owned()),
+                name: Some(cluster_name.to_string()),
+                namespace: Some(namespace.to_string()),
+                ..ObjectReference::default()
+            },
+            bundle_failures: Mutex::default(),
+        }
+    }
+
+    pub async fn handle_report(&self, report: StatusReport) {
+        let instance = report
+            .labels
+            .get("id")
+            .map(String::as_str)
+            .unwrap_or("unknown");
+        for (bundle_name, bundle) in &report.bundles {
+            let failure = bundle.failure();
+            let previous_failure = self
+                .bundle_failures
+                .lock()
+                .unwrap()
+                .insert(bundle_name.clone(), failure.clone())
+                .flatten();
+            let event = match (failure, previous_failure) {
+                (Some(failure), previous_failure)
+                    if Some(&failure) != previous_failure.as_ref() =>
+                {
+                    tracing::warn!(
+                        bundle.name = bundle_name,
+                        opa.id = instance,
+                        failure,
+                        "OPA failed to activate bundle"
+                    );
+                    Event {
+                        type_: EventType::Warning,
+                        reason: "BundleActivationFailed".to_string(),
+                        note: Some(format!(
+                            "OPA instance {instance} failed to activate bundle {bundle_name:?}: {failure}"
+                        )),
+                        action: "ActivateBundle".to_string(),
+                        secondary: None,
+                    }
+                }
+                (None, Some(_)) => Event {
+                    type_: EventType::Normal,
+                    reason: "BundleActivated".to_string(),
+                    note: Some(format!(
+                        "OPA instance {instance} activated revision {revision:?} of bundle {bundle_name:?} again",
+                        revision = bundle.active_revision
+                    )),
+                    action: "ActivateBundle".to_string(),
+                    secondary: None,
+                },
+                _ => continue,
+            };
+            let event = Event {
+                note: event
+                    .note
+                    .map(|note| note.chars().take(MAX_EVENT_NOTE_LENGTH).collect()),
+                ..event
+            };
+            if let Err(error) = self.recorder.publish(&event, &self.cluster).await {
+                tracing::error!(
+                    error = &error as &dyn std::error::Error,
+                    "failed to publish Event for bundle status"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,74 @@
+//! Developer-only fault injection, used to test how dependent products cope with failures of the
+//! bundle builder and the user-info-fetcher.
+//!
+//! Faults are configured by annotations on the OpaCluster, for example
+//! `fault-injection.opa.stackable.tech/user-info-fetcher-error-rate: "0.5"`. The operator passes them
+//! to the role group ConfigMaps, but they only take effect if the bundle builder and user-info-fetcher
+//! were built with the `fault-injection` feature.
+
+use std::{collections::BTreeMap, str::FromStr};
+
+use stackable_operator::time::Duration;
+
+/// Prefix of the OpaCluster annotations configuring the faults.
+pub const FAULT_INJECTION_ANNOTATION_PREFIX: &str = "fault-injection.opa.stackable.tech/";
+/// The key of the role group ConfigMaps containing the fault injection annotations (without the prefix) as JSON.
+pub const FAULT_INJECTION_FILE_NAME: &str = "fault-injection.json";
+
+pub const BUNDLE_BUILDER_COMPONENT: &str = "bundle-builder";
+pub const USER_INFO_FETCHER_COMPONENT: &str = "user-info-fetcher";
+
+/// The faults to inject into a component.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Faults {
+    /// Fail every bundle (re)build (`<component>-rebuild-failure`, bundle builder only).
+    pub rebuild_failure: bool,
+    /// The fraction of requests to fail with `503 Service Unavailable` (`<component>-error-rate`).
+    pub error_rate: f64,
+    /// The delay to add to every request (`<component>-latency`).
+    pub latency: Option<Duration>,
+}
+
+impl Faults {
+    /// Reads the faults of `component` (such as [`BUNDLE_BUILDER_COMPONENT`]) from the fault injection
+    /// annotations, without their prefix. Invalid values are ignored.
+    pub fn from_annotations(annotations: &BTreeMap<String, String>, component: &str) -> Self {
+        fn parse<T: FromStr>(
+            annotations: &BTreeMap<String, String>,
+            component: &str,
+            fault: &str,
+        ) -> Option<T> {
+            let key = format!("{component}-{fault}");
+            let value = annotations.get(&key)?;
+            let parsed = value.parse().ok();
+            if parsed.is_none() {
+                tracing::warn!(key, value, "ignoring invalid fault injection annotation");
+            }
+            parsed
+        }
+
+        Self {
+            rebuild_failure: parse(annotations, component, "rebuild-failure").unwrap_or_default(),
+            error_rate: parse::<f64>(annotations, component, "error-rate")
+                .unwrap_or_default()
+                .clamp(0.0, 1.0),
+            latency: parse(annotations, component, "latency"),
+        }
+    }
+}
+
+/// The fault injection annotations of an OpaCluster, with their prefix stripped.
+pub fn fault_injection_annotations(
+    annotations: &BTreeMap<String, String>,
+) -> BTreeMap<String, String> {
+    annotations
+        .iter()
+        .filter_map(|(key, value)| {
+            Some((
+                key.strip_prefix(FAULT_INJECTION_ANNOTATION_PREFIX)?
+                    .to_string(),
+                value.clone(),
+            ))
+        })
+        .collect()
+}
@@ -6,6 +6,7 @@ use stackable_operator::{
     commons::{
         affinity::StackableAffinity,
         cluster_operation::ClusterOperation,
+        pdb::PdbConfig,
         product_image_selection::ProductImage,
         resources::{
             CpuLimitsFragment, MemoryLimitsFragment, NoRuntimeLimits, NoRuntimeLimitsFragment,
@@ -14,10 +15,13 @@ use stackable_operator::{
     },
     config::{
         fragment::{self, Fragment, ValidationError},
-        merge::Merge,
+        merge::{Atomic, Merge},
     },
-    k8s_openapi::apimachinery::pkg::api::resource::Quantity,
-    kube::CustomResource,
+    k8s_openapi::{
+        api::core::v1::{Toleration, TopologySpreadConstraint},
+        apimachinery::pkg::{api::resource::Quantity, apis::meta::v1::Time},
+    },
+    kube::{CustomResource, ResourceExt},
     product_config_utils::Configuration,
     product_logging::{self, spec::Logging},
     role_utils::{
@@ -30,14 +34,50 @@ use stackable_operator::{
 };
 use strum::{Display, EnumIter, EnumString};
 
+pub mod fault_injection;
+pub mod policy;
 pub mod user_info_fetcher;
 
 pub const APP_NAME: &str = "opa";
 pub const OPERATOR_NAME: &str = "opa.stackable.tech";
+/// Prefix of the annotations that consumers of an OpaCluster set on its discovery ConfigMap to
+/// register themselves, for example `consumers.opa.stackable.tech/trino-default-trino: TrinoCluster default/trino`.
+pub const CONSUMER_ANNOTATION_PREFIX: &str = "consumers.opa.stackable.tech/";
+/// The key of the bundle snapshot (and stable bundle) ConfigMaps that contains the gzipped bundle.
+pub const BUNDLE_SNAPSHOT_KEY: &str = "bundle.tar.gz";
+/// The annotation containing the revision (from the manifest) of a snapshotted (or promoted) bundle.
+pub const BUNDLE_SNAPSHOT_HASH_ANNOTATION: &str = "opa.stackable.tech/bundle-snapshot-hash";
+/// The annotation of the bundle snapshot ConfigMap containing the revision of the latest bundle
+/// built by the bundle builders, which is only snapshotted itself if that is needed.
+pub const BUNDLE_LATEST_REVISION_ANNOTATION: &str = "opa.stackable.tech/bundle-latest-revision";
+/// The annotation of the bundle snapshot ConfigMap explaining why the latest bundle could not be
+/// snapshotted, removed once a bundle has been snapshotted again.
+pub const BUNDLE_SNAPSHOT_ERROR_ANNOTATION: &str = "opa.stackable.tech/bundle-snapshot-error";
+/// The annotation of the DaemonSets containing the revision of the bundle served to the role group.
+pub const BUNDLE_REVISION_ANNOTATION: &str = "opa.stackable.tech/bundle-revision";
+/// The header OPA identifies its Pod with when polling the bundle builder.
+pub const BUNDLE_CLIENT_POD_HEADER: &str = "x-opa-pod";
+/// The header OPA identifies the node of its Pod with when polling the bundle builder.
+pub const BUNDLE_CLIENT_NODE_HEADER: &str = "x-opa-node";
+/// Set to `"true"` on an OpaCluster to write the merged configuration of its role groups to a
+/// ConfigMap, see [`OpaCluster::merged_config_config_map_name`].
+pub const DEBUG_MERGED_CONFIG_ANNOTATION: &str = "opa.stackable.tech/debug-merged-config";
+/// The annotation of the regorule library ConfigMap containing the version of the library, see
+/// [`OpaRegoruleLibrarySource::Operator`].
+pub const REGORULE_LIBRARY_VERSION_ANNOTATION: &str = "opa.stackable.tech/regorule-library-version";
 
 pub const DEFAULT_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_minutes_unchecked(2);
+pub const DEFAULT_PERSISTENT_STORAGE_HOST_PATH: &str = "/var/lib/stackable/opa";
+/// Only succeeds once OPA has activated all of its bundles.
+pub const DEFAULT_READINESS_PROBE_PATH: &str = "/health?bundles=true";
+pub const DEFAULT_STARTUP_PROBE_TIMEOUT: Duration = Duration::from_minutes_unchecked(5);
 /// Safety puffer to guarantee the graceful shutdown works every time.
 pub const SERVER_GRACEFUL_SHUTDOWN_SAFETY_OVERHEAD: Duration = Duration::from_secs(5);
+/// Shorter timeouts are used up by the safety overhead before OPA has a chance to shut down.
+pub const MIN_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT: Duration = SERVER_GRACEFUL_SHUTDOWN_SAFETY_OVERHEAD;
+/// Node drains (e.g. during node upgrades by cloud providers) don't wait longer than an hour for Pods
+/// to terminate, and longer timeouts block drains that do.
+pub const MAX_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT: Duration = Duration::from_minutes_unchecked(60);
 
 #[derive(Snafu, Debug)]
 pub enum Error {
@@ -64,6 +104,10 @@ pub enum Error {
     kind = "OpaCluster",
     shortname = "opa",
     status = "OpaClusterStatus",
+    printcolumn = r#"{"name":"Ready", "type":"string", "description":"Role groups whose Pods are all ready", "jsonPath":".status.readyRoleGroups"}"#,
+    printcolumn = r#"{"name":"Version", "type":"string", "description":"Deployed product version", "jsonPath":".status.deployedProductVersion"}"#,
+    printcolumn = r#"{"name":"Bundle", "type":"string", "description":"Revision of the latest bundle", "jsonPath":".status.bundleRevision"}"#,
+    printcolumn = r#"{"name":"Age", "type":"date", "jsonPath":".metadata.creationTimestamp"}"#,
     namespaced,
     crates(
         kube_core = "stackable_operator::kube::core",
@@ -85,7 +129,7 @@ pub struct OpaSpec {
     pub image: ProductImage,
 }
 
-#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct OpaClusterConfig {
     /// Name of the Vector aggregator discovery ConfigMap.
@@ -109,6 +153,592 @@ pub struct OpaClusterConfig {
     /// from an external directory service.
     #[serde(default)]
     pub user_info: Option<user_info_fetcher::Config>,
+    /// Configures the metrics exposed by OPA.
+    #[serde(default)]
+    pub metrics: OpaMetricsConfig,
+    /// Configures how often OPA polls the bundle builder for new policies.
+    #[serde(default)]
+    pub bundle_polling: OpaBundlePollingConfig,
+    /// Signs the bundles built from the policies and lets OPA verify the signatures,
+    /// so that tampered bundles are rejected.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_signing: Option<OpaBundleSigningConfig>,
+    /// Replicates bundles between clusters, for example to keep the policies of a disaster recovery
+    /// cluster in sync with a primary cluster.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_replication: Option<OpaBundleReplicationConfig>,
+    /// Loads the bundle from an external bundle service (such as Nexus or Artifactory) instead of
+    /// building it from the policies of this cluster. No bundle builder is deployed then.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_bundle_service: Option<OpaExternalBundleServiceConfig>,
+    /// Limits how many OPA instances may be evicted at the same time, for example while draining nodes.
+    /// `maxUnavailable` defaults to `1`. No PodDisruptionBudget is created if not specified.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pod_disruption_budget: Option<PdbConfig>,
+    /// Whether to add the built-in regorule library (such as the `stackable.opa.userinfo.v1` package)
+    /// to the bundle. Disable it if all policies are provided externally and the built-in packages
+    /// conflict with them. Defaults to `true`.
+    #[serde(default = "OpaClusterConfig::default_include_builtin_rego_rules")]
+    pub include_builtin_rego_rules: bool,
+    /// Where the built-in regorule library comes from, if `includeBuiltinRegoRules` is enabled.
+    /// Defaults to `bundleBuilder`.
+    #[serde(default)]
+    pub regorule_library_source: OpaRegoruleLibrarySource,
+    /// Rolls out policy changes to some role groups first.
+    /// The other role groups only receive the new policies once they have soaked for `soakPeriod`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary_rollout: Option<OpaCanaryRolloutConfig>,
+    /// Loads the policies directly from a ConfigMap instead of building bundles, for fast
+    /// iteration on policy development clusters. Not intended for production use.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub development_mode: Option<OpaDevelopmentModeConfig>,
+    /// Runs the tests (and optionally benchmarks) of the policies in a Job for every new bundle.
+    /// Bundles are only rolled out once their tests have passed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_testing: Option<OpaPolicyTestingConfig>,
+    /// Restricts the bundles of some role groups to the packages they need, for example when every
+    /// product is served by its own role group. Role groups without a scope receive all packages.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bundle_scopes: Vec<OpaBundleScope>,
+    /// Node labels (such as `topology.kubernetes.io/zone`) to pass to OPA as runtime labels, so that
+    /// policies can make locality-aware decisions using `opa.runtime().config.labels`.
+    ///
+    /// The operator copies the labels of all nodes to a ConfigMap (see
+    /// [`OpaCluster::node_labels_config_map_name`]), which OPA reads them from when it starts.
+    /// Labels that are missing on a node are passed as empty strings.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub node_labels: Vec<String>,
+    /// Configures the Service that routes to all OPA instances of the OpaCluster.
+    #[serde(default)]
+    pub role_service: OpaRoleServiceConfig,
+    /// Additional CA certificates to trust (on top of the system CA certificates) for the TLS
+    /// connections of OPA (such as `http.send` in policies), the user-info-fetcher and the bundle
+    /// builder, for example in environments with a private CA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trust_store: Option<OpaTrustStore>,
+    /// Exposes the role service through a Gateway of the Kubernetes Gateway API, by creating an
+    /// `HTTPRoute` that is attached to the Gateway. This is an alternative to the `external-stable`
+    /// listener class on clusters that expose services through Gateways.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gateway_route: Option<OpaGatewayRouteConfig>,
+    /// Restricts which Pods may access the OPA API using a NetworkPolicy, since OPA does not
+    /// authenticate its clients.
+    #[serde(default)]
+    pub network_policy: OpaNetworkPolicyConfig,
+    /// Reports the field managers of the objects the operator manages for this OpaCluster in
+    /// `status.fieldOwnership`, and logs field managers other than the operator (such as manual
+    /// edits), whose changes the operator does not revert. Defaults to `false`.
+    #[serde(default)]
+    pub field_ownership_audit: bool,
+    /// Overrides the images of the bundle builder and user-info-fetcher sidecars, which otherwise
+    /// are the images the operator is configured with. For example, this allows air-gapped clusters
+    /// to use images from a mirror registry without reconfiguring the operator.
+    #[serde(default)]
+    pub images: OpaSidecarImages,
+}
+
+impl OpaClusterConfig {
+    const fn default_include_builtin_rego_rules() -> bool {
+        true
+    }
+
+    /// The bundle scope of the role group, if any.
+    pub fn bundle_scope(&self, role_group: &str) -> Option<&OpaBundleScope> {
+        self.bundle_scopes
+            .iter()
+            .find(|scope| scope.role_groups.iter().any(|rg| rg == role_group))
+    }
+}
+
+impl Default for OpaClusterConfig {
+    fn default() -> Self {
+        Self {
+            vector_aggregator_config_map_name: None,
+            listener_class: CurrentlySupportedListenerClasses::default(),
+            user_info: None,
+            metrics: OpaMetricsConfig::default(),
+            bundle_polling: OpaBundlePollingConfig::default(),
+            bundle_signing: None,
+            bundle_replication: None,
+            external_bundle_service: None,
+            pod_disruption_budget: None,
+            include_builtin_rego_rules: Self::default_include_builtin_rego_rules(),
+            regorule_library_source: OpaRegoruleLibrarySource::default(),
+            canary_rollout: None,
+            development_mode: None,
+            policy_testing: None,
+            bundle_scopes: Vec::new(),
+            node_labels: Vec::new(),
+            role_service: OpaRoleServiceConfig::default(),
+            trust_store: None,
+            gateway_route: None,
+            network_policy: OpaNetworkPolicyConfig::default(),
+            field_ownership_audit: false,
+            images: OpaSidecarImages::default(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaBundleScope {
+    /// The role groups that only receive the packages of this scope.
+    /// If a role group is listed in multiple scopes, only the first one is used.
+    pub role_groups: Vec<String>,
+    /// The packages (such as `trino`) to include in the bundle, along with their subpackages
+    /// (such as `trino.users`). Include `stackable.opa` to keep the built-in regorule library.
+    pub packages: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OpaTrustStore {
+    /// Name of a SecretClass, whose CA certificate is trusted.
+    SecretClass(String),
+    /// Name of a ConfigMap (in the namespace of the OpaCluster) containing the PEM encoded CA
+    /// certificates to trust under the key `ca.crt`.
+    ConfigMap(String),
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaSidecarImages {
+    /// The image of the bundle builder.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_builder: Option<OpaSidecarImage>,
+    /// The image of the user-info-fetcher, which is only deployed if `userInfo` is configured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_info_fetcher: Option<OpaSidecarImage>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaSidecarImage {
+    /// The repository of the image without tag or digest, such as
+    /// `registry.example.com/stackable/opa-bundle-builder`.
+    pub repository: String,
+    /// The tag of the image. Defaults to the tag of the image the operator is configured with, so
+    /// that mirrored images stay in sync with the operator version. If neither tag nor digest are
+    /// set, the digest of the operator image is used as well, which also covers operator images
+    /// that are referenced by digest only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// The digest of the image (such as `sha256:0123...`), which pins the image regardless of the
+    /// tag.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+}
+
+impl OpaSidecarImage {
+    /// The image reference to use instead of `default_image` (the image configured for the operator).
+    pub fn image_reference(&self, default_image: &str) -> String {
+        let mut image = self.repository.clone();
+        if let Some(tag) = self.tag.as_deref().or_else(|| image_tag(default_image)) {
+            image.push(':');
+            image.push_str(tag);
+        }
+        // Mirroring preserves digests, so the digest of the operator image identifies the same image
+        let digest = match (&self.tag, &self.digest) {
+            (None, None) => image_digest(default_image),
+            (_, digest) => digest.as_deref(),
+        };
+        if let Some(digest) = digest {
+            image.push('@');
+            image.push_str(digest);
+        }
+        image
+    }
+
+    /// Checks that the image reference can be assembled from the parts, returning a description of
+    /// the problem otherwise.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.repository.is_empty() {
+            return Err("repository must not be empty".to_string());
+        }
+        if self.repository.contains('@') || image_tag(&self.repository).is_some() {
+            return Err(format!(
+                "repository {:?} must not contain a tag or digest, use the tag and digest fields instead",
+                self.repository
+            ));
+        }
+        if let Some(digest) = &self.digest {
+            let valid = digest.split_once(':').is_some_and(|(algorithm, hex)| {
+                !algorithm.is_empty()
+                    && !hex.is_empty()
+                    && hex.chars().all(|c| c.is_ascii_hexdigit())
+            });
+            if !valid {
+                return Err(format!(
+                    "digest {digest:?} must have the form <algorithm>:<hex>, such as sha256:0123..."
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The digest of an image reference such as `registry:5000/stackable/opa@sha256:...`, if any.
+fn image_digest(image: &str) -> Option<&str> {
+    image.split_once('@').map(|(_, digest)| digest)
+}
+
+/// The tag of an image reference such as `registry:5000/stackable/opa:1.0.0@sha256:...`, if any.
+fn image_tag(image: &str) -> Option<&str> {
+    let without_digest = image.split_once('@').map_or(image, |(image, _)| image);
+    // The registry may contain a port, which is separated by a colon as well
+    let (_, name) = without_digest
+        .rsplit_once('/')
+        .unwrap_or(("", without_digest));
+    name.split_once(':').map(|(_, tag)| tag)
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OpaRegoruleLibrarySource {
+    /// The library built into the bundle builder image, which is updated along with the image.
+    #[default]
+    BundleBuilder,
+    /// The library of the operator, which it writes to the bundle ConfigMap
+    /// `<cluster-name>-regorule-library` (annotated with the version of the library). The library
+    /// is then updated along with the operator, without new bundle builder images.
+    Operator,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaDevelopmentModeConfig {
+    /// Name of the ConfigMap containing the policies (Rego files and JSON data documents).
+    /// OPA watches the mounted ConfigMap and reloads the policies as soon as it changes.
+    ///
+    /// No bundle builder is deployed, so bundle labels, OpaPolicies, bundle signing, bundle replication
+    /// and canary rollouts have no effect, and the built-in regorule library is not available.
+    pub config_map_name: String,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaPolicyTestingConfig {
+    /// Queries (such as `data.test.allow`) to benchmark with `opa bench` once the tests have passed.
+    /// The results are shown in the status of the OpaCluster.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub benchmark_queries: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaCanaryRolloutConfig {
+    /// The role groups that receive policy changes right away (the canary).
+    pub role_groups: Vec<String>,
+    /// How long the canary runs a bundle before it is promoted to the other role groups.
+    /// Defaults to 1 hour.
+    #[serde(default = "OpaCanaryRolloutConfig::default_soak_period")]
+    pub soak_period: Duration,
+}
+
+impl OpaCanaryRolloutConfig {
+    const fn default_soak_period() -> Duration {
+        Duration::from_minutes_unchecked(60)
+    }
+
+    /// Whether the role group receives policy changes right away.
+    pub fn is_canary(&self, role_group: &str) -> bool {
+        self.role_groups.iter().any(|canary| canary == role_group)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaBundlePollingConfig {
+    /// The minimum time to wait between two polls. Defaults to 10 seconds.
+    #[serde(default = "OpaBundlePollingConfig::default_min_delay")]
+    pub min_delay: Duration,
+    /// The maximum time to wait between two polls. Defaults to 20 seconds.
+    #[serde(default = "OpaBundlePollingConfig::default_max_delay")]
+    pub max_delay: Duration,
+    /// Enables long polling, so that policy changes are propagated to OPA almost immediately.
+    ///
+    /// Each poll waits for up to this duration for a new bundle, after which OPA polls again
+    /// right away. `minDelay` and `maxDelay` then only apply after failed polls.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_polling_timeout: Option<Duration>,
+    /// Delays the answers to long polls by a random duration of up to this after a new bundle has
+    /// been built, so that the OPA instances don't all activate it at the same time.
+    /// Only applies if `longPollingTimeout` is set, and never delays answers past it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub long_polling_jitter: Option<Duration>,
+    /// The maximum number of bundle downloads the bundle builder of each OPA Pod serves at the same time.
+    ///
+    /// Further downloads are rejected with `429 Too Many Requests`, which OPA retries after waiting
+    /// between `minDelay` and `maxDelay`. Unlimited if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_concurrent_downloads: Option<u32>,
+}
+
+impl OpaBundlePollingConfig {
+    const fn default_min_delay() -> Duration {
+        Duration::from_secs(10)
+    }
+
+    const fn default_max_delay() -> Duration {
+        Duration::from_secs(20)
+    }
+}
+
+impl Default for OpaBundlePollingConfig {
+    fn default() -> Self {
+        Self {
+            min_delay: Self::default_min_delay(),
+            max_delay: Self::default_max_delay(),
+            long_polling_timeout: None,
+            long_polling_jitter: None,
+            max_concurrent_downloads: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaMetricsConfig {
+    /// Labels identifying the OPA instances of this OpaCluster.
+    ///
+    /// They are passed to OPA as `labels`, which are attached to status reports and decision logs.
+    /// Additionally, they are added as annotations prefixed with `metrics.opa.stackable.tech/` to the
+    /// metrics Services, so that Prometheus can turn them into target labels when relabeling. This
+    /// prevents metrics of multiple OpaClusters scraped into the same Prometheus from colliding.
+    /// The label `name-prefix` is reserved for the `namePrefix`.
+    #[serde(default)]
+    pub labels: BTreeMap<String, String>,
+
+    /// Prefix for the names of the metrics of this OpaCluster, e.g. `production_`.
+    ///
+    /// OPA can not rename its metrics itself. Instead, the prefix is added as the
+    /// `metrics.opa.stackable.tech/name-prefix` annotation to the metrics Services, so that
+    /// Prometheus can prepend it to the metric names when relabeling.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name_prefix: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaBundleSigningConfig {
+    /// Name of the Secret (in the namespace of the OpaCluster) containing the keys.
+    ///
+    /// It must contain the key `signingKey`, which is the PEM encoded private key for the
+    /// asymmetric algorithms or the shared secret for the HMAC algorithms. For the asymmetric
+    /// algorithms it must also contain the key `verificationKey`, which is the PEM encoded public key.
+    pub secret_name: String,
+    /// The algorithm used to sign the bundles. Defaults to `RS256`.
+    #[serde(default)]
+    pub algorithm: OpaBundleSigningAlgorithm,
+    /// The ID of the key, which OPA uses to look up the key to verify the signatures with.
+    /// Defaults to `stackable`.
+    #[serde(default = "OpaBundleSigningConfig::default_key_id")]
+    pub key_id: String,
+    /// The scope of the signatures. If set, OPA rejects bundles signed for other scopes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+}
+
+impl OpaBundleSigningConfig {
+    pub const SIGNING_KEY_SECRET_KEY: &'static str = "signingKey";
+    pub const VERIFICATION_KEY_SECRET_KEY: &'static str = "verificationKey";
+
+    fn default_key_id() -> String {
+        "stackable".to_string()
+    }
+
+    /// The key of the Secret OPA reads the key to verify the signatures with from.
+    /// The HMAC algorithms use the same shared secret for signing and verification.
+    pub fn verification_key_secret_key(&self) -> &'static str {
+        if self.algorithm.is_hmac() {
+            Self::SIGNING_KEY_SECRET_KEY
+        } else {
+            Self::VERIFICATION_KEY_SECRET_KEY
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaBundleReplicationConfig {
+    /// URL every published bundle is pushed to (using HTTP `PUT`), such as a raw repository of an
+    /// artifact repository manager. Object storages that require signed requests (such as S3) are
+    /// not supported.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub push_url: Option<String>,
+    /// URL bundles are pulled from (using HTTP `GET`). If set, the bundle is replicated from there
+    /// instead of being built from the policies of this cluster.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_url: Option<String>,
+    /// How often bundles are pulled from `pullUrl`. Defaults to `30s`.
+    #[serde(default = "OpaBundleReplicationConfig::default_pull_interval")]
+    pub pull_interval: Duration,
+    /// Name of a Secret (in the namespace of the OpaCluster) with the key `authorization`, which is
+    /// sent as the `Authorization` header when pushing or pulling bundles.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub authorization_secret: Option<String>,
+}
+
+impl OpaBundleReplicationConfig {
+    pub const AUTHORIZATION_SECRET_KEY: &'static str = "authorization";
+
+    const fn default_pull_interval() -> Duration {
+        Duration::from_secs(30)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaExternalBundleServiceConfig {
+    /// Base URL of the bundle service, such as `https://nexus.example.com/repository/opa`.
+    pub url: String,
+    /// Path of the bundle, relative to `url`, such as `bundles/authz.tar.gz`.
+    pub resource: String,
+    /// The credentials OPA authenticates to the bundle service with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials: Option<OpaExternalBundleServiceCredentials>,
+    /// Additional headers sent with every request to the bundle service.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub headers: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OpaExternalBundleServiceCredentials {
+    /// Name of a Secret (in the namespace of the OpaCluster) with the key `token`, which is sent
+    /// as bearer token. The token is read for every request, so it can be rotated.
+    BearerTokenSecret(String),
+    /// Name of a Secret (in the namespace of the OpaCluster) with the keys `username` and
+    /// `password`, which are sent using HTTP basic authentication. OPA only reads them on startup.
+    BasicAuthSecret(String),
+}
+
+impl OpaExternalBundleServiceCredentials {
+    pub const TOKEN_SECRET_KEY: &'static str = "token";
+    pub const USERNAME_SECRET_KEY: &'static str = "username";
+    pub const PASSWORD_SECRET_KEY: &'static str = "password";
+
+    pub fn secret_name(&self) -> &str {
+        match self {
+            Self::BearerTokenSecret(secret_name) | Self::BasicAuthSecret(secret_name) => {
+                secret_name
+            }
+        }
+    }
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Display, Eq, JsonSchema, PartialEq, Serialize,
+)]
+#[serde(rename_all = "UPPERCASE")]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum OpaBundleSigningAlgorithm {
+    Hs256,
+    Hs384,
+    Hs512,
+    #[default]
+    Rs256,
+    Rs384,
+    Rs512,
+    Ps256,
+    Ps384,
+    Ps512,
+    Es256,
+    Es384,
+}
+
+impl OpaBundleSigningAlgorithm {
+    pub fn is_hmac(&self) -> bool {
+        matches!(self, Self::Hs256 | Self::Hs384 | Self::Hs512)
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaRoleServiceConfig {
+    /// Whether clients are only routed to the OPA instance on their own node (`Local`), or to any
+    /// OPA instance (`Cluster`). Defaults to `Local`.
+    ///
+    /// Use `Cluster` if the OPA Pods don't run on all nodes with clients, for example because their
+    /// affinity restricts them to some nodes. Otherwise, clients on the other nodes can't reach OPA.
+    #[serde(default)]
+    pub internal_traffic_policy: OpaInternalTrafficPolicy,
+    /// Whether requests of the same client are routed to the same OPA instance (`ClientIP`).
+    /// Defaults to `None`.
+    #[serde(default)]
+    pub session_affinity: OpaSessionAffinity,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaGatewayRouteConfig {
+    /// The Gateway to attach the `HTTPRoute` to, which must allow routes from the namespace of the
+    /// OpaCluster.
+    pub gateway: OpaGatewayReference,
+    /// The hostnames (such as `opa.example.com`) that are routed to OPA.
+    /// Defaults to all hostnames of the Gateway listener.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub hostnames: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaGatewayReference {
+    /// The name of the Gateway.
+    pub name: String,
+    /// The namespace of the Gateway. Defaults to the namespace of the OpaCluster.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+    /// The listener of the Gateway to attach to (`sectionName`), for example an HTTPS listener that
+    /// terminates TLS. Defaults to all listeners of the Gateway.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section_name: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaNetworkPolicyConfig {
+    /// Whether to create a NetworkPolicy that only allows the `allowedClients` to access the OPA API.
+    /// Requires a network plugin that enforces NetworkPolicies. Defaults to `false`.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The Pods that may access the OPA API. Products querying OPA (and Prometheus, if it scrapes
+    /// the metrics of OPA) must be listed here. Other ports, such as the one of the bundle builder,
+    /// are not restricted.
+    #[serde(default)]
+    pub allowed_clients: Vec<OpaNetworkPolicyPeer>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaNetworkPolicyPeer {
+    /// The labels of the namespaces the clients run in, `{}` selects all namespaces.
+    /// Defaults to the namespace of the OpaCluster.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace_labels: Option<BTreeMap<String, String>>,
+    /// The labels of the client Pods. Defaults to all Pods in the selected namespaces.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pod_labels: Option<BTreeMap<String, String>>,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Display, Eq, JsonSchema, PartialEq, Serialize,
+)]
+pub enum OpaInternalTrafficPolicy {
+    #[default]
+    Local,
+    Cluster,
+}
+
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Display, Eq, JsonSchema, PartialEq, Serialize,
+)]
+pub enum OpaSessionAffinity {
+    #[default]
+    None,
+    #[serde(rename = "ClientIP")]
+    #[strum(serialize = "ClientIP")]
+    ClientIp,
 }
 
 // TODO: Temporary solution until listener-operator is finished
@@ -150,7 +780,40 @@ impl CurrentlySupportedListenerClasses {
     ),
     serde(rename_all = "camelCase")
 )]
-pub struct OpaStorageConfig {}
+pub struct OpaStorageConfig {
+    /// Persist the bundles and data of OPA on the Kubernetes node, so that the last successfully
+    /// activated bundle survives Pod restarts and OPA can answer queries before the bundle builder is reachable.
+    #[fragment_attrs(serde(default))]
+    pub persistent_storage: OpaPersistentStorageConfig,
+}
+
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    allow(clippy::derive_partial_eq_without_eq),
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct OpaPersistentStorageConfig {
+    /// Whether OPA should persist its bundles and data on disk. Defaults to `false`.
+    pub enabled: bool,
+
+    /// Directory on the Kubernetes node that is used as `hostPath` volume.
+    /// The operator appends `<namespace>/<role group object name>` to it, so that multiple OpaClusters
+    /// can share the same directory. Defaults to `/var/lib/stackable/opa`.
+    ///
+    /// A DaemonSet can not request a PersistentVolumeClaim per Pod, which is why a `hostPath` volume is used.
+    pub host_path: String,
+}
 
 #[derive(
     Clone,
@@ -198,11 +861,266 @@ pub struct OpaConfig {
     #[fragment_attrs(serde(default))]
     pub affinity: StackableAffinity,
 
-    /// Time period Pods have to gracefully shut down, e.g. `30m`, `1h` or `2d`. Consult the operator documentation for details.
+    /// Time period Pods have to gracefully shut down, e.g. `30s`, `5m` or `1h`. Must be between `5s` and `1h`,
+    /// other values are clamped. Consult the operator documentation for details.
     #[fragment_attrs(serde(default))]
     pub graceful_shutdown_timeout: Option<Duration>,
+
+    /// The network interface OPA binds to. Defaults to `all-interfaces`.
+    #[fragment_attrs(serde(default))]
+    pub bind_address: OpaBindAddress,
+
+    /// Runtime tuning options of the OPA server.
+    #[fragment_attrs(serde(default))]
+    pub server_config: OpaServerConfig,
+
+    /// Topology spread constraints of the OPA Pods, for example to spread them across zones when
+    /// role groups only run on a subset of the nodes.
+    /// See the [Kubernetes documentation](https://kubernetes.io/docs/concepts/scheduling-eviction/topology-spread-constraints/).
+    #[fragment_attrs(serde(default))]
+    pub topology_spread_constraints: OpaTopologySpreadConstraints,
+
+    /// Node labels the nodes running OPA Pods must have, in addition to the `affinity`.
+    /// See the [Kubernetes documentation](https://kubernetes.io/docs/concepts/scheduling-eviction/assign-pod-node/#nodeselector).
+    #[fragment_attrs(serde(default))]
+    pub node_selector: OpaNodeSelector,
+
+    /// Tolerations of the OPA Pods, for example to run OPA on tainted (such as dedicated) nodes.
+    /// See the [Kubernetes documentation](https://kubernetes.io/docs/concepts/scheduling-eviction/taint-and-toleration/).
+    #[fragment_attrs(serde(default))]
+    pub tolerations: OpaTolerations,
+
+    /// Configures the `prepare` init container, which runs before OPA starts.
+    #[fragment_attrs(serde(default))]
+    pub prepare: OpaPrepareConfig,
+
+    /// Configures the `bundle-builder` sidecar, which builds the bundles served to OPA.
+    #[fragment_attrs(serde(default))]
+    pub bundle_builder: OpaBundleBuilderConfig,
+
+    /// Configures the `user-info-fetcher` sidecar, see `clusterConfig.userInfo`.
+    #[fragment_attrs(serde(default))]
+    pub user_info_fetcher: OpaUserInfoFetcherConfig,
+
+    /// Configures the probes of the OPA container.
+    #[fragment_attrs(serde(default))]
+    pub probes: OpaProbesConfig,
 }
 
+#[derive(Clone, Debug, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct OpaProbesConfig {
+    /// The HTTP path (including the query) the readiness probe of OPA requests.
+    /// Defaults to `/health?bundles=true`, so that OPA only becomes ready once its bundles have
+    /// been activated, instead of answering queries with an empty set of policies.
+    /// Use `/health` to report OPA as ready as soon as it has started.
+    pub readiness_path: String,
+
+    /// How long OPA may take to start, e.g. `5m`. The container is restarted if the startup probe
+    /// (`/health`) does not succeed within this time, and the readiness and liveness probes only run
+    /// once it has succeeded. Activating the bundles is not part of the startup, so OPA is not
+    /// restarted while they are unavailable. Defaults to `5m`.
+    pub startup_timeout: Duration,
+}
+
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct OpaBundleBuilderConfig {
+    /// Whether to run the `bundle-builder` sidecar. Defaults to `true`.
+    /// Disable it for role groups that only load external bundles (configured using
+    /// `configOverrides` of `config.json`), OPA does not load the policies of the OpaCluster then.
+    pub enabled: bool,
+
+    /// The resources of the `bundle-builder` container. Defaults to 100m-200m CPU and 128Mi memory,
+    /// increase them for large sets of policies or data.
+    #[fragment_attrs(serde(default))]
+    pub resources: Resources<OpaSidecarStorageConfig, NoRuntimeLimits>,
+}
+
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct OpaPrepareConfig {
+    /// Whether to run the `prepare` init container. Defaults to `true`.
+    /// The container is only run if an `initScript` is configured (or in development mode), since
+    /// the bundle builder creates the bundle directories itself.
+    pub enabled: bool,
+
+    /// Shell commands run at the end of the `prepare` init container, for example to download data
+    /// used by the policies (such as GeoIP databases) into a volume added with `podOverrides`.
+    /// The output is captured in the logs of the `prepare` container.
+    #[fragment_attrs(serde(default))]
+    pub init_script: Option<String>,
+
+    /// The resources of the `prepare` container. Defaults to 250m-500m CPU and 256Mi memory.
+    #[fragment_attrs(serde(default))]
+    pub resources: Resources<OpaSidecarStorageConfig, NoRuntimeLimits>,
+}
+
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct OpaUserInfoFetcherConfig {
+    /// The resources of the `user-info-fetcher` container, which is only run if `userInfo` is
+    /// configured. Defaults to 100m-200m CPU and 128Mi memory.
+    #[fragment_attrs(serde(default))]
+    pub resources: Resources<OpaSidecarStorageConfig, NoRuntimeLimits>,
+}
+
+/// The sidecars of OPA do not use any storage.
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct OpaSidecarStorageConfig {}
+
+/// List of topology spread constraints, role group constraints replace role constraints as a whole.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct OpaTopologySpreadConstraints(pub Vec<TopologySpreadConstraint>);
+
+impl Atomic for OpaTopologySpreadConstraints {}
+
+/// Node labels, a role group node selector replaces the role node selector as a whole.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct OpaNodeSelector(pub BTreeMap<String, String>);
+
+impl Atomic for OpaNodeSelector {}
+
+/// List of tolerations, role group tolerations replace role tolerations as a whole.
+#[derive(Clone, Debug, Default, Deserialize, JsonSchema, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct OpaTolerations(pub Vec<Toleration>);
+
+impl Atomic for OpaTolerations {}
+
+#[derive(Clone, Debug, Default, Fragment, JsonSchema, PartialEq)]
+#[fragment_attrs(
+    derive(
+        Clone,
+        Debug,
+        Default,
+        Deserialize,
+        Merge,
+        JsonSchema,
+        PartialEq,
+        Serialize
+    ),
+    serde(rename_all = "camelCase")
+)]
+pub struct OpaServerConfig {
+    /// The number of errors to allow before the compilation of policies fails early
+    /// (`--max-errors`). Use `-1` for no limit. Defaults to OPA's default of `10`.
+    #[fragment_attrs(serde(default))]
+    pub max_errors: Option<i32>,
+
+    /// How long OPA waits for its plugins (such as the bundle download) to become ready before
+    /// starting the server (`--ready-timeout`), e.g. `30s`. By default OPA does not wait.
+    #[fragment_attrs(serde(default))]
+    pub ready_timeout: Option<Duration>,
+
+    /// The maximum size of the cache of `http.send` responses in bytes
+    /// (`caching.inter_query_builtin_cache.max_size_bytes`). By default the cache is unlimited.
+    #[fragment_attrs(serde(default))]
+    pub http_send_cache_max_size_bytes: Option<u64>,
+
+    /// The percentage of `httpSendCacheMaxSizeBytes` above which entries are evicted from the
+    /// cache of `http.send` responses (`caching.inter_query_builtin_cache.forced_eviction_threshold_percentage`).
+    #[fragment_attrs(serde(default))]
+    pub http_send_cache_forced_eviction_threshold_percentage: Option<u8>,
+
+    /// How often expired entries are evicted from the cache of `http.send` responses
+    /// (`caching.inter_query_builtin_cache.stale_entry_eviction_period_seconds`), e.g. `1m`.
+    #[fragment_attrs(serde(default))]
+    pub http_send_cache_stale_entry_eviction_period: Option<Duration>,
+
+    /// The maximum number of entries in the cache of compiled values, such as regular expressions
+    /// and glob patterns (`caching.inter_query_builtin_value_cache.max_num_entries`).
+    #[fragment_attrs(serde(default))]
+    pub builtin_value_cache_max_entries: Option<u32>,
+
+    /// Whether to cache the results of non-deterministic builtins (such as `http.send`) within a
+    /// query, so that they are included in decision logs (`nd_builtin_cache`).
+    #[fragment_attrs(serde(default))]
+    pub non_deterministic_builtin_cache: Option<bool>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Display, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+#[strum(serialize_all = "kebab-case")]
+pub enum OpaBindAddress {
+    /// Bind to all interfaces (`0.0.0.0`).
+    #[default]
+    AllInterfaces,
+
+    /// Bind to the IP of the Pod only. This prevents OPA from being reachable via other interfaces,
+    /// e.g. of nodes using host networking.
+    PodIp,
+
+    /// Bind to localhost only. This is useful when an authenticating proxy sidecar is forwarding requests to OPA.
+    /// Please note that the OPA Service is not reachable unless such a sidecar is added, e.g. via `podOverrides`.
+    Localhost,
+}
+
+impl Atomic for OpaBindAddress {}
+
 impl OpaConfig {
     fn default_config() -> OpaConfigFragment {
         OpaConfigFragment {
@@ -216,16 +1134,60 @@ impl OpaConfig {
                     limit: Some(Quantity("256Mi".to_owned())),
                     runtime_limits: NoRuntimeLimitsFragment {},
                 },
-                storage: OpaStorageConfigFragment {},
+                storage: OpaStorageConfigFragment {
+                    persistent_storage: OpaPersistentStorageConfigFragment {
+                        enabled: Some(false),
+                        host_path: Some(DEFAULT_PERSISTENT_STORAGE_HOST_PATH.to_owned()),
+                    },
+                },
             },
             // There is no point in having a default affinity, as exactly one OPA Pods should run on every node.
             // We only have the affinity configurable to let users limit the nodes the OPA Pods run on.
             affinity: Default::default(),
             graceful_shutdown_timeout: Some(DEFAULT_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT),
+            bind_address: Some(OpaBindAddress::AllInterfaces),
+            server_config: OpaServerConfigFragment::default(),
+            topology_spread_constraints: Some(OpaTopologySpreadConstraints::default()),
+            node_selector: Some(OpaNodeSelector::default()),
+            tolerations: Some(OpaTolerations::default()),
+            prepare: OpaPrepareConfigFragment {
+                enabled: Some(true),
+                init_script: None,
+                resources: default_sidecar_resources("250m", "500m", "256Mi"),
+            },
+            bundle_builder: OpaBundleBuilderConfigFragment {
+                enabled: Some(true),
+                resources: default_sidecar_resources("100m", "200m", "128Mi"),
+            },
+            user_info_fetcher: OpaUserInfoFetcherConfigFragment {
+                resources: default_sidecar_resources("100m", "200m", "128Mi"),
+            },
+            probes: OpaProbesConfigFragment {
+                readiness_path: Some(DEFAULT_READINESS_PROBE_PATH.to_owned()),
+                startup_timeout: Some(DEFAULT_STARTUP_PROBE_TIMEOUT),
+            },
         }
     }
 }
 
+fn default_sidecar_resources(
+    cpu_min: &str,
+    cpu_max: &str,
+    memory_limit: &str,
+) -> ResourcesFragment<OpaSidecarStorageConfig, NoRuntimeLimits> {
+    ResourcesFragment {
+        cpu: CpuLimitsFragment {
+            min: Some(Quantity(cpu_min.to_owned())),
+            max: Some(Quantity(cpu_max.to_owned())),
+        },
+        memory: MemoryLimitsFragment {
+            limit: Some(Quantity(memory_limit.to_owned())),
+            runtime_limits: NoRuntimeLimitsFragment {},
+        },
+        storage: OpaSidecarStorageConfigFragment {},
+    }
+}
+
 impl Configuration for OpaConfigFragment {
     type Configurable = OpaCluster;
 
@@ -302,6 +1264,83 @@ impl OpaCluster {
             })
     }
 
+    /// The name of the ConfigMap the bundle builders store a snapshot of the last served bundle in.
+    pub fn bundle_snapshot_config_map_name(&self) -> String {
+        format!("{}-bundle-snapshot", self.name_any())
+    }
+
+    /// The name of the ConfigMap the bundle builders report the bundles delivered to OPA in, see
+    /// [`policy::BundleDelivery`].
+    pub fn bundle_delivery_config_map_name(&self) -> String {
+        policy::bundle_delivery_config_map_name(&self.name_any())
+    }
+
+    /// The name of the ConfigMap the merged configuration of the role groups is written to, if
+    /// [`DEBUG_MERGED_CONFIG_ANNOTATION`] is set.
+    pub fn merged_config_config_map_name(&self) -> String {
+        format!("{}-merged-config", self.name_any())
+    }
+
+    /// Whether the merged configuration of the role groups is written to a ConfigMap.
+    pub fn debug_merged_config(&self) -> bool {
+        self.annotations()
+            .get(DEBUG_MERGED_CONFIG_ANNOTATION)
+            .is_some_and(|value| value == "true")
+    }
+
+    /// The name of the ConfigMap the operator promotes bundles to once they have soaked on the canary
+    /// and passed their tests.
+    pub fn stable_bundle_config_map_name(&self) -> String {
+        format!("{}-bundle-stable", self.name_any())
+    }
+
+    /// The name of the ConfigMap the operator copies the labels of the nodes to, see
+    /// [`OpaClusterConfig::node_labels`].
+    pub fn node_labels_config_map_name(&self) -> String {
+        format!("{}-node-labels", self.name_any())
+    }
+
+    /// The name of the bundle ConfigMap the operator writes the regorule library to, see
+    /// [`OpaRegoruleLibrarySource::Operator`].
+    pub fn regorule_library_config_map_name(&self) -> String {
+        format!("{}-regorule-library", self.name_any())
+    }
+
+    /// Whether the operator provides the regorule library (instead of the bundle builder).
+    /// No bundles are built in development mode.
+    pub fn manages_regorule_library(&self) -> bool {
+        let cluster_config = &self.spec.cluster_config;
+        cluster_config.development_mode.is_none()
+            && cluster_config.include_builtin_rego_rules
+            && cluster_config.regorule_library_source == OpaRegoruleLibrarySource::Operator
+    }
+
+    /// Whether bundles are only rolled out (to some role groups) once they have been promoted to
+    /// the stable bundle ConfigMap. Bundles are never promoted in development mode.
+    pub fn has_stable_bundle(&self) -> bool {
+        let cluster_config = &self.spec.cluster_config;
+        cluster_config.development_mode.is_none()
+            && (cluster_config.canary_rollout.is_some() || cluster_config.policy_testing.is_some())
+    }
+
+    /// Whether the role group is served the stable bundle rather than the latest one.
+    /// Role groups outside of the canary only receive bundles once they have been promoted.
+    pub fn serves_stable_bundle(&self, role_group: &str) -> bool {
+        let is_canary = self
+            .spec
+            .cluster_config
+            .canary_rollout
+            .as_ref()
+            .is_some_and(|canary_rollout| canary_rollout.is_canary(role_group));
+        self.has_stable_bundle() && !is_canary
+    }
+
+    /// The name of the Job testing the bundle `revision`.
+    pub fn policy_tests_job_name(&self, revision: &str) -> String {
+        let short_revision = revision.get(..12).unwrap_or(revision);
+        format!("{}-policy-tests-{short_revision}", self.name_any())
+    }
+
     /// The name of the role-level load-balanced Kubernetes `Service`
     pub fn server_role_service_name(&self) -> Option<String> {
         self.metadata.name.clone()
@@ -323,6 +1362,17 @@ impl OpaCluster {
         role: &OpaRole,
         rolegroup_ref: &RoleGroupRef<OpaCluster>,
     ) -> Result<OpaConfig, Error> {
+        fragment::validate(self.merged_config_fragment(role, rolegroup_ref)?)
+            .context(FragmentValidationFailureSnafu)
+    }
+
+    /// The configuration of the role group merged with the role configuration and the defaults,
+    /// before it is validated.
+    pub fn merged_config_fragment(
+        &self,
+        role: &OpaRole,
+        rolegroup_ref: &RoleGroupRef<OpaCluster>,
+    ) -> Result<OpaConfigFragment, Error> {
         // Initialize the result with all default values as baseline
         let conf_defaults = OpaConfig::default_config();
 
@@ -352,7 +1402,7 @@ impl OpaCluster {
         conf_rolegroup.merge(&conf_role);
 
         tracing::debug!("Merged config: {:?}", conf_rolegroup);
-        fragment::validate(conf_rolegroup).context(FragmentValidationFailureSnafu)
+        Ok(conf_rolegroup)
     }
 }
 
@@ -361,6 +1411,99 @@ impl OpaCluster {
 pub struct OpaClusterStatus {
     #[serde(default)]
     pub conditions: Vec<ClusterCondition>,
+
+    /// The time the operator last successfully applied a change to the OpaCluster, that is a
+    /// change of `specHash` or `deployedProductVersion`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_update_time: Option<Time>,
+
+    /// The SHA-256 hash of the `spec` the operator last successfully reconciled.
+    /// If it does not match the current `spec`, the operator has not (yet) acted on the latest change.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spec_hash: Option<String>,
+
+    /// The product version of OPA that was last deployed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deployed_product_version: Option<String>,
+
+    /// The number of role groups whose Pods are all ready and up to date, out of all role groups,
+    /// for example `2/3`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ready_role_groups: Option<String>,
+
+    /// The revision of the latest bundle built by the bundle builders, as reported by OPA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_revision: Option<String>,
+
+    /// The products that use this OpaCluster, as registered by annotations on the discovery ConfigMap.
+    /// Changing the policies or upgrading OPA can affect all of them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub consumers: Vec<String>,
+
+    /// The bundle that is currently soaking on the canary role groups, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canary: Option<OpaCanaryStatus>,
+
+    /// The results of testing the latest bundle, if `policyTesting` is enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub policy_tests: Option<OpaPolicyTestsStatus>,
+
+    /// Discovery ConfigMaps that carry the labels of this OpaCluster but are not owned by it,
+    /// for example because they were orphaned when a previous OpaCluster of the same name was deleted.
+    /// Consumers might still use them, so they have to be checked and deleted manually.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub stale_discovery_config_maps: Vec<String>,
+
+    /// Problems with the configuration that the operator worked around, for example by clamping
+    /// out of range values.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+
+    /// The field managers of the objects managed for this OpaCluster, if
+    /// `clusterConfig.fieldOwnershipAudit` is enabled.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub field_ownership: Vec<OpaFieldOwnershipStatus>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaFieldOwnershipStatus {
+    /// The kind of the object, for example `DaemonSet`.
+    pub kind: String,
+    pub name: String,
+    /// All field managers of the object (apart from those only managing its status).
+    pub managers: Vec<String>,
+    /// The field managers that are neither the operator nor its bundle builders.
+    /// Their changes are neither reverted nor removed by the operator.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub foreign_managers: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaCanaryStatus {
+    /// The revision of the bundle.
+    pub revision: String,
+    /// When the operator first saw the bundle, it is promoted once `soakPeriod` has passed.
+    pub since: Time,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaPolicyTestsStatus {
+    /// The revision of the tested bundle.
+    pub revision: String,
+    pub result: OpaPolicyTestsResult,
+    /// The benchmark results if the tests passed, or the end of the test output if they failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+pub enum OpaPolicyTestsResult {
+    Running,
+    Passed,
+    Failed,
 }
 
 impl HasStatusCondition for OpaCluster {
@@ -371,3 +1514,173 @@ impl HasStatusCondition for OpaCluster {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use stackable_operator::kube::runtime::reflector::ObjectRef;
+
+    use super::*;
+
+    /// Merges the config of the role group `default` with the role config of the `servers`.
+    fn merged_config(servers: serde_json::Value) -> OpaConfig {
+        let opa = serde_json::from_value::<OpaCluster>(json!({
+            "apiVersion": "opa.stackable.tech/v1alpha1",
+            "kind": "OpaCluster",
+            "metadata": {
+                "name": "opa",
+                "namespace": "default",
+            },
+            "spec": {
+                "image": {
+                    "productVersion": "1.0.0",
+                },
+                "servers": servers,
+            },
+        }))
+        .expect("OpaCluster must be valid");
+        let rolegroup_ref = RoleGroupRef {
+            cluster: ObjectRef::from_obj(&opa),
+            role: OpaRole::Server.to_string(),
+            role_group: "default".to_string(),
+        };
+        opa.merged_config(&OpaRole::Server, &rolegroup_ref)
+            .expect("config must be mergeable")
+    }
+
+    fn tolerations(keys: &[&str]) -> serde_json::Value {
+        keys.iter()
+            .map(|key| json!({"key": key, "operator": "Exists"}))
+            .collect()
+    }
+
+    #[test]
+    fn role_group_node_selector_replaces_role_node_selector() {
+        let config = merged_config(json!({
+            "config": {
+                "nodeSelector": {"node-pool": "opa", "zone": "a"},
+            },
+            "roleGroups": {
+                "default": {
+                    "config": {
+                        "nodeSelector": {"node-pool": "gpu"},
+                    },
+                },
+            },
+        }));
+        assert_eq!(
+            config.node_selector.0,
+            BTreeMap::from([("node-pool".to_string(), "gpu".to_string())])
+        );
+    }
+
+    #[test]
+    fn role_node_selector_applies_to_role_groups_without_node_selector() {
+        let config = merged_config(json!({
+            "config": {
+                "nodeSelector": {"node-pool": "opa"},
+            },
+            "roleGroups": {
+                "default": {},
+            },
+        }));
+        assert_eq!(
+            config.node_selector.0,
+            BTreeMap::from([("node-pool".to_string(), "opa".to_string())])
+        );
+    }
+
+    #[test]
+    fn empty_role_group_node_selector_clears_role_node_selector() {
+        let config = merged_config(json!({
+            "config": {
+                "nodeSelector": {"node-pool": "opa"},
+            },
+            "roleGroups": {
+                "default": {
+                    "config": {
+                        "nodeSelector": {},
+                    },
+                },
+            },
+        }));
+        assert!(config.node_selector.0.is_empty());
+    }
+
+    #[test]
+    fn role_group_tolerations_replace_role_tolerations() {
+        let config = merged_config(json!({
+            "config": {
+                "tolerations": tolerations(&["dedicated", "gpu"]),
+            },
+            "roleGroups": {
+                "default": {
+                    "config": {
+                        "tolerations": tolerations(&["spot"]),
+                    },
+                },
+            },
+        }));
+        let keys = config
+            .tolerations
+            .0
+            .iter()
+            .map(|toleration| toleration.key.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, [Some("spot")]);
+    }
+
+    #[test]
+    fn role_tolerations_apply_to_role_groups_without_tolerations() {
+        let config = merged_config(json!({
+            "config": {
+                "tolerations": tolerations(&["dedicated"]),
+            },
+            "roleGroups": {
+                "default": {},
+            },
+        }));
+        let keys = config
+            .tolerations
+            .0
+            .iter()
+            .map(|toleration| toleration.key.as_deref())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, [Some("dedicated")]);
+    }
+
+    fn sidecar_image(tag: Option<&str>, digest: Option<&str>) -> OpaSidecarImage {
+        OpaSidecarImage {
+            repository: "mirror.example.com/stackable/opa-bundle-builder".to_string(),
+            tag: tag.map(str::to_string),
+            digest: digest.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn sidecar_image_inherits_tag_of_operator_image() {
+        assert_eq!(
+            sidecar_image(None, None)
+                .image_reference("registry:5000/stackable/opa-bundle-builder:1.0.0"),
+            "mirror.example.com/stackable/opa-bundle-builder:1.0.0"
+        );
+    }
+
+    #[test]
+    fn sidecar_image_inherits_digest_of_digest_only_operator_image() {
+        assert_eq!(
+            sidecar_image(None, None)
+                .image_reference("registry:5000/stackable/opa-bundle-builder@sha256:abc123"),
+            "mirror.example.com/stackable/opa-bundle-builder@sha256:abc123"
+        );
+    }
+
+    #[test]
+    fn sidecar_image_tag_does_not_inherit_digest_of_operator_image() {
+        assert_eq!(
+            sidecar_image(Some("1.1.0"), None)
+                .image_reference("registry:5000/stackable/opa-bundle-builder:1.0.0@sha256:abc123"),
+            "mirror.example.com/stackable/opa-bundle-builder:1.1.0"
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeSet;
+
+use serde::{Deserialize, Serialize};
+use stackable_operator::{
+    k8s_openapi::{api::core::v1::ConfigMap, apimachinery::pkg::apis::meta::v1::LabelSelector},
+    kube::{CustomResource, ResourceExt},
+    schemars::{self, JsonSchema},
+};
+
+/// Label that marks a ConfigMap as source of the OPA bundle.
+pub const BUNDLE_LABEL: &str = "opa.stackable.tech/bundle";
+/// Label that restricts a bundle ConfigMap to the OpaCluster with the given name.
+pub const BUNDLE_CLUSTER_LABEL: &str = "opa.stackable.tech/cluster";
+/// Label that references the [`OpaPolicy`] a bundle ConfigMap was generated from.
+pub const BUNDLE_POLICY_LABEL: &str = "opa.stackable.tech/policy";
+/// Annotation of a bundle ConfigMap that sets the package its JSON data documents are placed in.
+/// Defaults to the package of the Rego files in the same ConfigMap.
+pub const BUNDLE_DATA_PACKAGE_ANNOTATION: &str = "opa.stackable.tech/data-package";
+/// Annotation of a bundle ConfigMap that adds the Wasm module in its `policy.wasm` binary data key to
+/// the bundle, with the given comma separated entrypoints (such as `example/allow`).
+pub const BUNDLE_WASM_ENTRYPOINTS_ANNOTATION: &str = "opa.stackable.tech/wasm-entrypoints";
+
+#[derive(Clone, CustomResource, Debug, Deserialize, JsonSchema, Serialize)]
+#[kube(
+    group = "opa.stackable.tech",
+    version = "v1alpha1",
+    kind = "OpaPolicy",
+    status = "OpaPolicyStatus",
+    namespaced,
+    crates(
+        kube_core = "stackable_operator::kube::core",
+        k8s_openapi = "stackable_operator::k8s_openapi",
+        schemars = "stackable_operator::schemars"
+    )
+)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaPolicySpec {
+    /// The Rego package the policy defines, e.g. `kafka.authz`.
+    /// The package declared in the Rego source must match.
+    pub package: String,
+
+    /// Where to read the Rego source of the policy from.
+    pub source: OpaPolicySource,
+
+    /// If multiple policies define the same package for the same OpaCluster, only the one with the
+    /// highest priority is added to the bundle. Ties are broken by the name of the policies. Defaults to `0`.
+    #[serde(default)]
+    pub priority: i32,
+
+    /// Selects the OpaClusters (in the namespace of the policy) the policy is added to by their labels.
+    /// If not specified, the policy is added to all OpaClusters in the namespace.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target_cluster: Option<LabelSelector>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OpaPolicySource {
+    /// The Rego source of the policy.
+    Rego(String),
+
+    /// Reference to a key of a ConfigMap (in the namespace of the policy) containing the Rego source of the policy.
+    ConfigMapRef(OpaPolicyConfigMapRef),
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaPolicyConfigMapRef {
+    /// Name of the ConfigMap.
+    pub name: String,
+
+    /// Key of the ConfigMap containing the Rego source.
+    pub key: String,
+}
+
+#[derive(Clone, Default, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OpaPolicyStatus {
+    /// Whether the policy passed validation.
+    pub valid: bool,
+
+    /// The OpaClusters the policy is currently part of the bundle of.
+    #[serde(default)]
+    pub active_clusters: Vec<String>,
+
+    /// Explains why the policy is invalid or not active in some of the selected OpaClusters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+/// The bundle that has been delivered to all OPA instances polling a bundle builder, as reported by
+/// the bundle builder in the bundle delivery ConfigMap of its OpaCluster (see
+/// [`bundle_delivery_config_map_name`]) under the key [`bundle_delivery_key`].
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleDelivery {
+    /// The Pod of the bundle builder. The key is reused by the Pods replacing it, whose reports are
+    /// only valid once they have delivered a bundle themselves.
+    pub pod: String,
+
+    /// The revision of the delivered bundle.
+    pub revision: String,
+
+    /// The OpaPolicies (see [`BUNDLE_POLICY_LABEL`]) whose Rego is part of the delivered bundle.
+    pub policies: BTreeSet<String>,
+}
+
+/// The name of the ConfigMap the bundle builders of the OpaCluster `cluster_name` report the bundles
+/// delivered to OPA in.
+pub fn bundle_delivery_config_map_name(cluster_name: &str) -> String {
+    format!("{cluster_name}-bundle-delivery")
+}
+
+/// The key of the [`BundleDelivery`] of the bundle builder of a role group on a node.
+///
+/// The key doesn't depend on the Pod, so that the ConfigMap does not accumulate the reports of
+/// deleted Pods.
+pub fn bundle_delivery_key(node_name: &str, role_group: &str) -> String {
+    format!("{node_name}_{role_group}")
+}
+
+/// Extracts the name of the package declared by a Rego source.
+///
+/// Rego requires the package declaration to be the first statement, so only leading comments
+/// and empty lines are skipped.
+pub fn rego_package_name(rego: &str) -> Option<&str> {
+    rego.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))?
+        .strip_prefix("package ")?
+        .split(|c: char| c.is_whitespace() || c == '#')
+        .find(|name| !name.is_empty())
+}
+
+/// The path (relative to `data`) at which the bundle builder places the file `file_name` of the
+/// bundle ConfigMap `cm` as a data document, or `None` if it is not placed in a package.
+///
+/// Keys ending in `.json` are placed in the package set by [`BUNDLE_DATA_PACKAGE_ANNOTATION`], or
+/// otherwise in the package of the first Rego file of the ConfigMap. `data.json` has always been loaded
+/// from the directory of the ConfigMap, so it is only placed in the package if it is set explicitly.
+pub fn data_document_path(cm: &ConfigMap, file_name: &str) -> Option<String> {
+    let document = file_name.strip_suffix(".json")?;
+    let explicit_data_package = cm
+        .annotations()
+        .get(BUNDLE_DATA_PACKAGE_ANNOTATION)
+        .map(String::as_str);
+    if document == "data" && explicit_data_package.is_none() {
+        return None;
+    }
+    let data_package = explicit_data_package.or_else(|| {
+        cm.data
+            .iter()
+            .flatten()
+            .filter(|(file_name, _)| file_name.ends_with(".rego"))
+            .find_map(|(_, rego)| rego_package_name(rego))
+    })?;
+    let data_path = data_package.replace('.', "/");
+    Some(match document {
+        "data" => data_path,
+        _ => format!("{data_path}/{document}"),
+    })
+}
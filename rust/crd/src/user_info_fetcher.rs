@@ -1,3 +1,5 @@
+pub mod api;
+
 use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
@@ -17,6 +19,185 @@ pub struct Config {
     /// Caching configuration.
     #[serde(default)]
     pub cache: Cache,
+
+    /// Refuses to return user information for some users, independently of the policies.
+    #[serde(default)]
+    pub access_control: AccessControl,
+
+    /// Normalizes the names of the groups returned by the backend, so that policies can rely on a
+    /// consistent naming scheme regardless of the backend.
+    #[serde(default)]
+    pub group_mapping: GroupMapping,
+
+    /// Lookups of groups (`POST /group`), for example to enumerate the members of a group.
+    #[serde(default)]
+    pub group_info: GroupInfo,
+
+    /// Serve the user-info-fetcher API over HTTPS.
+    ///
+    /// The user-info-fetcher then listens on all interfaces instead of only localhost, so that it can
+    /// also be used as a central service by clients other than the OPA instance on the same node.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_tls: Option<ServerTls>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerTls {
+    /// Name of the SecretClass that issues the server certificate of the user-info-fetcher.
+    pub secret_class: String,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupInfo {
+    /// Whether groups can be looked up. Only supported by the `keycloak` and
+    /// `experimentalActiveDirectory` backends. Defaults to `false`.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// How long information about each group is cached for. Defaults to 5 minutes.
+    #[serde(default = "GroupInfo::default_entry_time_to_live")]
+    pub entry_time_to_live: Duration,
+
+    /// The maximum number of cached groups. Defaults to `1000`.
+    #[serde(default = "GroupInfo::default_max_entries")]
+    pub max_entries: u64,
+
+    /// The maximum number of members returned for a group, further members are omitted (and
+    /// `membersTruncated` is set). Defaults to `1000`.
+    #[serde(default = "GroupInfo::default_max_members")]
+    pub max_members: usize,
+}
+
+impl GroupInfo {
+    const fn default_entry_time_to_live() -> Duration {
+        Duration::from_minutes_unchecked(5)
+    }
+
+    const fn default_max_entries() -> u64 {
+        1000
+    }
+
+    const fn default_max_members() -> usize {
+        1000
+    }
+}
+
+impl Default for GroupInfo {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            entry_time_to_live: Self::default_entry_time_to_live(),
+            max_entries: Self::default_max_entries(),
+            max_members: Self::default_max_members(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupMapping {
+    /// A prefix to strip from the group names, such as `/` for the group paths returned by Keycloak.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strip_prefix: Option<String>,
+
+    /// A regular expression the (whole) group names are matched against after stripping the prefix,
+    /// such as `CN=([^,]+),.*` for the distinguished names returned by Active Directory.
+    /// Group names that don't match are kept unchanged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub regex: Option<String>,
+
+    /// The group name to use for group names matching `regex`, which can refer to its capture groups
+    /// (`$1` or `${name}`). Defaults to `$1`.
+    #[serde(default = "GroupMapping::default_replacement")]
+    pub replacement: String,
+
+    /// Whether to convert the group names to lowercase, after all other mappings. Defaults to `false`.
+    #[serde(default)]
+    pub lowercase: bool,
+}
+
+impl GroupMapping {
+    fn default_replacement() -> String {
+        "$1".to_string()
+    }
+}
+
+impl Default for GroupMapping {
+    fn default() -> Self {
+        Self {
+            strip_prefix: None,
+            regex: None,
+            replacement: Self::default_replacement(),
+            lowercase: false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessControl {
+    /// Users (and members of groups) for which requests are always refused with `403 Forbidden`,
+    /// for example compromised accounts or service accounts that must never be enriched.
+    /// Takes precedence over `allow`.
+    #[serde(default)]
+    pub deny: Principals,
+
+    /// If specified, requests are refused with `403 Forbidden` for all users except for these users
+    /// (and members of these groups).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub allow: Option<Principals>,
+}
+
+impl AccessControl {
+    /// Whether requests for the user are refused, given the ids and usernames known for the user and
+    /// the groups it is a member of.
+    pub fn denies<'a>(
+        &self,
+        users: impl IntoIterator<Item = &'a str> + Clone,
+        groups: &[String],
+    ) -> bool {
+        self.deny.matches(users.clone(), groups)
+            || self
+                .allow
+                .as_ref()
+                .is_some_and(|allow| !allow.matches(users, groups))
+    }
+
+    /// Whether lookups of the group are refused, given the ids and names known for the group.
+    ///
+    /// If `allow` is specified, only the groups listed in it can be looked up.
+    pub fn denies_group<'a>(&self, group: impl IntoIterator<Item = &'a str> + Clone) -> bool {
+        let is_listed = |principals: &Principals| {
+            group
+                .clone()
+                .into_iter()
+                .any(|name| principals.groups.iter().any(|listed| listed == name))
+        };
+        is_listed(&self.deny) || self.allow.as_ref().is_some_and(|allow| !is_listed(allow))
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Principals {
+    /// User ids or usernames.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub users: Vec<String>,
+
+    /// Names of groups, as returned by the backend.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<String>,
+}
+
+impl Principals {
+    pub fn matches<'a>(&self, users: impl IntoIterator<Item = &'a str>, groups: &[String]) -> bool {
+        users
+            .into_iter()
+            .any(|user| self.users.iter().any(|principal| principal == user))
+            || groups.iter().any(|group| self.groups.contains(group))
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
@@ -30,11 +211,21 @@ pub enum Backend {
 
     /// Backend that fetches user information from the Gaia-X
     /// Cross Federation Services Components (XFSC) Authentication & Authorization Service.
+    XfscAas(AasBackend),
+
+    /// Deprecated alias of `xfscAas`, which will be removed in a future release.
     ExperimentalXfscAas(AasBackend),
 
     /// Backend that fetches user information from Active Directory
     #[serde(rename = "experimentalActiveDirectory")]
     ActiveDirectory(ActiveDirectoryBackend),
+
+    /// Backend that fetches user information from a SCIM 2.0 service provider, such as Okta or
+    /// OneLogin.
+    Scim(ScimBackend),
+
+    /// Backend that fetches user information from the Okta Management API.
+    Okta(OktaBackend),
 }
 
 impl Default for Backend {
@@ -63,7 +254,17 @@ pub struct KeycloakBackend {
     /// Name of a Secret that contains client credentials of a Keycloak account with permission to read user metadata.
     ///
     /// Must contain the fields `clientId` and `clientSecret`.
-    pub client_credentials_secret: String,
+    /// Exactly one of `clientCredentialsSecret` and `clientCredentialsSecretClass` must be set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_credentials_secret: Option<String>,
+
+    /// Name of a SecretClass providing the client credentials, as an alternative to
+    /// `clientCredentialsSecret`, so that they can be provisioned (and rotated) by a backend of the
+    /// secret-operator, such as Vault.
+    ///
+    /// The SecretClass must provide the files `clientId` and `clientSecret`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_credentials_secret_class: Option<String>,
 
     /// The Keycloak realm that OPA's Keycloak account (as specified by `credentialsSecretName` exists in).
     ///
@@ -87,18 +288,161 @@ pub struct AasBackend {
     /// Port of the identity provider. Defaults to port 5000.
     #[serde(default = "aas_default_port")]
     pub port: u16,
+
+    /// Use a TLS connection. If not specified no TLS will be used.
+    #[serde(flatten)]
+    pub tls: TlsClientDetails,
+
+    /// How long to wait for the AAS to respond before the request is considered failed (and retried).
+    /// Defaults to 10 seconds.
+    #[serde(default = "aas_default_request_timeout")]
+    pub request_timeout: Duration,
 }
 
 fn aas_default_port() -> u16 {
     5000
 }
 
+const fn aas_default_request_timeout() -> Duration {
+    Duration::from_secs(10)
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimBackend {
+    /// Hostname of the SCIM service provider, e.g. `my-org.okta.com`.
+    pub hostname: HostName,
+
+    /// Port of the SCIM service provider. If TLS is used defaults to `443`, otherwise to `80`.
+    pub port: Option<u16>,
+
+    /// Path of the SCIM API, which contains the `/Users` and `/Groups` endpoints.
+    /// Defaults to `/scim/v2`.
+    #[serde(default = "ScimBackend::default_base_path")]
+    pub base_path: String,
+
+    /// Use a TLS connection. If not specified no TLS will be used.
+    #[serde(flatten)]
+    pub tls: TlsClientDetails,
+
+    /// Name of a Secret that contains the bearer token the user-info-fetcher authenticates with.
+    ///
+    /// Must contain the field `token`.
+    pub bearer_token_secret: String,
+
+    /// How long to wait for the service provider to respond before the request is considered failed
+    /// (and retried). Defaults to 10 seconds.
+    #[serde(default = "ScimBackend::default_request_timeout")]
+    pub request_timeout: Duration,
+}
+
+impl ScimBackend {
+    pub const BEARER_TOKEN_SECRET_KEY: &'static str = "token";
+
+    fn default_base_path() -> String {
+        "/scim/v2".to_string()
+    }
+
+    const fn default_request_timeout() -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OktaBackend {
+    /// Hostname of the Okta organization, e.g. `my-org.okta.com`.
+    pub hostname: HostName,
+
+    /// Port of the Okta organization. If TLS is used defaults to `443`, otherwise to `80`.
+    pub port: Option<u16>,
+
+    /// Use a TLS connection. If not specified no TLS will be used.
+    #[serde(flatten)]
+    pub tls: TlsClientDetails,
+
+    /// How the user-info-fetcher authenticates to the Okta Management API.
+    pub credentials: OktaCredentials,
+
+    /// How long to wait for Okta to respond before the request is considered failed (and retried).
+    /// Defaults to 10 seconds.
+    #[serde(default = "OktaBackend::default_request_timeout")]
+    pub request_timeout: Duration,
+}
+
+impl OktaBackend {
+    const fn default_request_timeout() -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum OktaCredentials {
+    /// Name of a Secret that contains an API token in the field `apiToken`.
+    /// API tokens act on behalf of the admin that created them.
+    ApiTokenSecret(String),
+
+    /// An API service app, which requests access tokens using a private key.
+    ServiceApp(OktaServiceApp),
+}
+
+impl OktaCredentials {
+    pub const API_TOKEN_SECRET_KEY: &'static str = "apiToken";
+    pub const PRIVATE_KEY_SECRET_KEY: &'static str = "privateKey";
+
+    pub fn secret_name(&self) -> &str {
+        match self {
+            Self::ApiTokenSecret(secret_name) => secret_name,
+            Self::ServiceApp(service_app) => &service_app.private_key_secret,
+        }
+    }
+
+    /// The key of the Secret the user-info-fetcher reads.
+    pub fn secret_key(&self) -> &'static str {
+        match self {
+            Self::ApiTokenSecret(_) => Self::API_TOKEN_SECRET_KEY,
+            Self::ServiceApp(_) => Self::PRIVATE_KEY_SECRET_KEY,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OktaServiceApp {
+    /// The client ID of the service app, which must be granted the `okta.users.read` and
+    /// `okta.groups.read` scopes.
+    pub client_id: String,
+
+    /// Name of a Secret that contains the PEM encoded RSA private key of the service app in the
+    /// field `privateKey`.
+    pub private_key_secret: String,
+
+    /// The ID of the key, if multiple keys are registered for the service app.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+}
+
 #[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ActiveDirectoryBackend {
     /// Hostname of the domain controller, e.g. `ad-ds-1.contoso.com`.
     pub ldap_server: String,
 
+    /// Hostnames of further domain controllers of the same domain, which are failed over to (in
+    /// order) while `ldapServer` is unavailable.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub failover_ldap_servers: Vec<String>,
+
+    /// Connections to the domain controllers are kept open and reused for further lookups.
+    #[serde(default)]
+    pub connection_pool: LdapConnectionPool,
+
+    /// Searches for the groups of users and the members of groups are split into pages, so that
+    /// they are not rejected or truncated by the size limits of the domain controllers.
+    #[serde(default)]
+    pub search: LdapSearch,
+
     /// The root Distinguished Name (DN) where users and groups are located.
     pub base_distinguished_name: String,
 
@@ -112,6 +456,81 @@ pub struct ActiveDirectoryBackend {
     /// Custom attributes, and their LDAP attribute names.
     #[serde(default)]
     pub custom_attribute_mappings: BTreeMap<String, String>,
+
+    /// Custom attributes that are read from the user's groups (including nested groups), and
+    /// their LDAP attribute names on the group objects.
+    ///
+    /// The values of all groups are aggregated (without duplicates). If a custom attribute is also
+    /// mapped by `customAttributeMappings`, the values of the user and the groups are combined.
+    #[serde(default)]
+    pub group_attribute_mappings: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LdapConnectionPool {
+    /// The maximum number of idle connections kept open per domain controller.
+    /// `0` disables pooling, so that a new connection is opened for every lookup. Defaults to `4`.
+    #[serde(default = "LdapConnectionPool::default_max_idle_connections")]
+    pub max_idle_connections: usize,
+
+    /// Idle connections are closed after this duration. Defaults to 5 minutes.
+    #[serde(default = "LdapConnectionPool::default_idle_timeout")]
+    pub idle_timeout: Duration,
+}
+
+impl LdapConnectionPool {
+    const fn default_max_idle_connections() -> usize {
+        4
+    }
+
+    const fn default_idle_timeout() -> Duration {
+        Duration::from_minutes_unchecked(5)
+    }
+}
+
+impl Default for LdapConnectionPool {
+    fn default() -> Self {
+        Self {
+            max_idle_connections: Self::default_max_idle_connections(),
+            idle_timeout: Self::default_idle_timeout(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LdapSearch {
+    /// The number of entries requested per page, using the simple paged results control.
+    /// Must not exceed the `MaxPageSize` of the domain controllers (1000 by default). Defaults to `500`.
+    #[serde(default = "LdapSearch::default_page_size")]
+    pub page_size: u32,
+
+    /// The maximum number of groups of a user, which only limits the search for the groups of users.
+    /// Looking up users that are members of more groups fails, rather than returning an incomplete
+    /// list of groups. The members of groups are limited by `groupInfo.maxMembers` instead.
+    /// Defaults to `10000`.
+    #[serde(default = "LdapSearch::default_max_results")]
+    pub max_results: usize,
+}
+
+impl LdapSearch {
+    const fn default_page_size() -> u32 {
+        500
+    }
+
+    const fn default_max_results() -> usize {
+        10000
+    }
+}
+
+impl Default for LdapSearch {
+    fn default() -> Self {
+        Self {
+            page_size: Self::default_page_size(),
+            max_results: Self::default_max_results(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
@@ -120,18 +539,59 @@ pub struct Cache {
     /// How long metadata about each user should be cached for.
     #[serde(default = "Cache::default_entry_time_to_live")]
     pub entry_time_to_live: Duration,
+
+    /// How long the fact that a user does not exist should be cached for.
+    /// If not specified, lookups of unknown users are not cached and always hit the backend.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub not_found_time_to_live: Option<Duration>,
+
+    /// How long an expired entry may still be served while it is refreshed in the background.
+    /// If not specified, expired entries are refreshed before responding.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_while_revalidate: Option<Duration>,
+
+    /// The maximum number of cached users (users looked up with different selections of custom
+    /// attributes are cached separately). The least recently used users are evicted once the limit
+    /// is reached, so that looking up many distinct users does not grow the memory usage without
+    /// bounds. Also limits the number of cached unknown users. Defaults to `10000`.
+    #[serde(default = "Cache::default_max_entries")]
+    pub max_entries: u64,
+
+    /// How much of `maxEntries` each cached user takes up. Defaults to `uniform`.
+    #[serde(default)]
+    pub entry_weight: CacheEntryWeight,
+}
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, JsonSchema, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheEntryWeight {
+    /// Every user counts as 1.
+    #[default]
+    Uniform,
+
+    /// Every user counts as 1 plus its number of groups and custom attributes, so that users taking
+    /// up more memory are evicted earlier. `maxEntries` then limits the total weight.
+    GroupsAndAttributes,
 }
 
 impl Cache {
     const fn default_entry_time_to_live() -> Duration {
         Duration::from_minutes_unchecked(1)
     }
+
+    const fn default_max_entries() -> u64 {
+        10_000
+    }
 }
 
 impl Default for Cache {
     fn default() -> Self {
         Self {
             entry_time_to_live: Self::default_entry_time_to_live(),
+            not_found_time_to_live: None,
+            stale_while_revalidate: None,
+            max_entries: Self::default_max_entries(),
+            entry_weight: CacheEntryWeight::default(),
         }
     }
 }
@@ -0,0 +1,121 @@
+//! The types of the user-info-fetcher HTTP API, shared with clients (such as other operators or
+//! tests) so that they can build requests and deserialize the responses.
+//!
+//! - `POST /user` takes a [`UserInfoRequest`] and responds with a [`UserInfo`] (or an
+//!   [`ErrorResponse`]).
+//! - `POST /users:batch` takes a list of [`UserInfoRequest`]s and responds with a list of
+//!   [`BatchUserInfoResult`]s (in the same order).
+//! - `POST /group` takes a [`GroupInfoRequest`] and responds with a [`GroupInfo`] (or an
+//!   [`ErrorResponse`]).
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+/// Identifies the user to look up, either by id or by username.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase", untagged)]
+pub enum UserInfoRequest {
+    UserInfoRequestById(UserInfoRequestById),
+    UserInfoRequestByName(UserInfoRequestByName),
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfoRequestById {
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfoRequestByName {
+    pub username: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfo {
+    /// This might be null in case the id is not known (e.g. the backend does not have this info).
+    pub id: Option<String>,
+    /// This might be null in case the username is not known (e.g. the backend does not have this info).
+    pub username: Option<String>,
+    pub groups: Vec<String>,
+    pub custom_attributes: HashMap<String, serde_json::Value>,
+}
+
+/// Identifies the group to look up, either by id or by name.
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase", untagged)]
+pub enum GroupInfoRequest {
+    GroupInfoRequestById(GroupInfoRequestById),
+    GroupInfoRequestByName(GroupInfoRequestByName),
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupInfoRequestById {
+    pub id: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq, Hash, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupInfoRequestByName {
+    /// The name of the group, as returned by the backend in the groups of users (before applying
+    /// the group mapping).
+    pub name: String,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct GroupInfo {
+    /// This might be null in case the id is not known (e.g. the backend does not have this info).
+    pub id: Option<String>,
+    /// The name of the group, in the same format as the groups of users (before applying the group
+    /// mapping).
+    pub name: String,
+    /// The usernames of the members of the group.
+    pub members: Vec<String>,
+    /// Whether further members were omitted, since the group has more than `groupInfo.maxMembers`
+    /// members.
+    pub members_truncated: bool,
+    pub custom_attributes: HashMap<String, serde_json::Value>,
+}
+
+/// The body of unsuccessful responses.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorResponse {
+    pub error: UserInfoError,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct UserInfoError {
+    pub message: String,
+    /// The messages of the underlying errors, from the outermost to the innermost cause.
+    pub causes: Vec<String>,
+}
+
+impl UserInfoError {
+    pub fn new(error: &dyn std::error::Error) -> Self {
+        Self {
+            message: error.to_string(),
+            causes: std::iter::successors(error.source(), |err| err.source())
+                .map(|err| err.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// The result of looking up a single user of a batch request.
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum BatchUserInfoResult {
+    UserInfo(UserInfo),
+    Error {
+        /// The status code `POST /user` would have responded with.
+        status: u16,
+        #[serde(flatten)]
+        error: UserInfoError,
+    },
+}
@@ -0,0 +1,140 @@
+//! Grants the bundle builders of an OpaCluster access to its bundle snapshot ConfigMap (see
+//! [`OpaCluster::bundle_snapshot_config_map_name`]) and its bundle delivery ConfigMap (see
+//! [`OpaCluster::bundle_delivery_config_map_name`]).
+//!
+//! The product ClusterRole only grants read access, so that the bundle builders cannot modify the
+//! ConfigMaps of other OpaClusters (or of anything else in the namespace).
+//! Like the NetworkPolicy (see [`crate::network_policy`]), the Role and RoleBinding are applied here
+//! rather than through [`ClusterResources`](stackable_operator::cluster_resources::ClusterResources).
+
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::{OpaCluster, OpaRole};
+use stackable_operator::{
+    builder::meta::ObjectMetaBuilder,
+    client::Client,
+    commons::product_image_selection::ResolvedProductImage,
+    k8s_openapi::{
+        api::{
+            core::v1::ServiceAccount,
+            rbac::v1::{PolicyRule, Role, RoleBinding, RoleRef, Subject},
+        },
+        apimachinery::pkg::apis::meta::v1::ObjectMeta,
+    },
+    kube::{Resource, ResourceExt},
+};
+
+use crate::{controller::build_recommended_labels, field_ownership::FIELD_MANAGER};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to build object metadata"))]
+    BuildMetadata {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to apply Role"))]
+    ApplyRole {
+        source: stackable_operator::client::Error,
+    },
+
+    #[snafu(display("failed to apply RoleBinding"))]
+    ApplyRoleBinding {
+        source: stackable_operator::client::Error,
+    },
+}
+
+/// Applies the Role that allows the bundle builders to store the bundle snapshot and report the
+/// delivered bundles, and binds it to the `service_account` of the OpaCluster.
+pub async fn reconcile_bundle_builder_rbac(
+    client: &Client,
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    service_account: &ServiceAccount,
+) -> Result<(Role, RoleBinding), Error> {
+    let role = build_role(opa, resolved_product_image)?;
+    let role = client
+        .apply_patch(FIELD_MANAGER, &role, &role)
+        .await
+        .context(ApplyRoleSnafu)?;
+    let role_binding = build_role_binding(opa, resolved_product_image, &role, service_account)?;
+    let role_binding = client
+        .apply_patch(FIELD_MANAGER, &role_binding, &role_binding)
+        .await
+        .context(ApplyRoleBindingSnafu)?;
+    Ok((role, role_binding))
+}
+
+fn build_metadata(
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+) -> Result<ObjectMeta, Error> {
+    Ok(ObjectMetaBuilder::new()
+        .name_and_namespace(opa)
+        .name(bundle_builder_role_name(opa))
+        .ownerreference_from_resource(opa, None, Some(true))
+        .context(BuildMetadataSnafu)?
+        .with_recommended_labels(build_recommended_labels(
+            opa,
+            &resolved_product_image.app_version_label,
+            &OpaRole::Server.to_string(),
+            "global",
+        ))
+        .context(BuildMetadataSnafu)?
+        .build())
+}
+
+fn build_role(
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+) -> Result<Role, Error> {
+    Ok(Role {
+        metadata: build_metadata(opa, resolved_product_image)?,
+        rules: Some(vec![
+            PolicyRule {
+                api_groups: Some(vec!["".to_string()]),
+                resources: Some(vec!["configmaps".to_string()]),
+                resource_names: Some(vec![
+                    opa.bundle_snapshot_config_map_name(),
+                    opa.bundle_delivery_config_map_name(),
+                ]),
+                verbs: vec!["patch".to_string()],
+                ..PolicyRule::default()
+            },
+            // Snapshots are only taken on shutdown if the OpaCluster has been stopped
+            PolicyRule {
+                api_groups: Some(vec![OpaCluster::group(&()).to_string()]),
+                resources: Some(vec![OpaCluster::plural(&()).to_string()]),
+                resource_names: Some(vec![opa.name_any()]),
+                verbs: vec!["get".to_string()],
+                ..PolicyRule::default()
+            },
+        ]),
+    })
+}
+
+fn build_role_binding(
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    role: &Role,
+    service_account: &ServiceAccount,
+) -> Result<RoleBinding, Error> {
+    Ok(RoleBinding {
+        metadata: build_metadata(opa, resolved_product_image)?,
+        role_ref: RoleRef {
+            api_group: "rbac.authorization.k8s.io".to_string(),
+            kind: "Role".to_string(),
+            name: role.name_any(),
+        },
+        subjects: Some(vec![Subject {
+            kind: "ServiceAccount".to_string(),
+            name: service_account.name_any(),
+            namespace: service_account.namespace(),
+            ..Subject::default()
+        }]),
+    })
+}
+
+/// The name of the Role (and RoleBinding) of the bundle builders of the OpaCluster.
+fn bundle_builder_role_name(opa: &OpaCluster) -> String {
+    format!("{}-bundle-builder", opa.name_any())
+}
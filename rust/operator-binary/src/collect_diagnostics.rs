@@ -0,0 +1,327 @@
+//! The `collect-diagnostics` subcommand, which gathers the state of an OpaCluster into a `tar.gz`
+//! archive that can be attached to support tickets.
+//!
+//! Secrets are never read. The bundle ConfigMaps are left out too, since the archive should describe
+//! how OPA is deployed rather than contain the policies.
+
+use std::{
+    fmt::Display,
+    fs::File,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use flate2::write::GzEncoder;
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::{OpaCluster, APP_NAME};
+use stackable_operator::{
+    k8s_openapi::{
+        api::{
+            apps::v1::DaemonSet,
+            core::v1::{ConfigMap, Event, Pod},
+        },
+        http,
+    },
+    kube::{
+        api::{ListParams, LogParams},
+        Api, Client, Resource, ResourceExt,
+    },
+};
+
+use crate::controller::BUNDLE_BUILDER_PORT;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to create Kubernetes client"))]
+    CreateClient {
+        source: stackable_operator::kube::Error,
+    },
+
+    #[snafu(display("failed to get OpaCluster {cluster_name:?}"))]
+    GetOpaCluster {
+        source: stackable_operator::kube::Error,
+        cluster_name: String,
+    },
+
+    #[snafu(display("failed to create archive {path:?}"))]
+    CreateArchive {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to add {file_path:?} to the archive"))]
+    AddFile {
+        source: std::io::Error,
+        file_path: String,
+    },
+
+    #[snafu(display("failed to finish archive {path:?}"))]
+    FinishArchive {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+}
+
+#[derive(clap::Args)]
+pub struct CollectDiagnosticsArgs {
+    /// The name of the OpaCluster to collect diagnostics for.
+    #[clap(long = "cluster")]
+    cluster_name: String,
+
+    /// The namespace of the OpaCluster. Defaults to the namespace of the current kubeconfig context.
+    #[clap(long)]
+    namespace: Option<String>,
+
+    /// The archive to write. Defaults to `opa-diagnostics-<cluster>.tar.gz`.
+    #[clap(long)]
+    output: Option<PathBuf>,
+
+    /// The number of log lines to collect per container.
+    #[clap(long, default_value_t = 1000)]
+    log_lines: i64,
+}
+
+/// Logs are trimmed to this size per container, regardless of `--log-lines`.
+const MAX_LOG_BYTES: i64 = 5 * 1024 * 1024;
+/// The endpoints of the bundle builder that are collected, along with the file they are stored in.
+const BUNDLE_BUILDER_ENDPOINTS: &[(&str, &str)] = &[
+    ("/status", "bundle-builder-status.json"),
+    ("/packages", "bundle-builder-packages.json"),
+];
+
+struct Archive {
+    tar: tar::Builder<GzEncoder<File>>,
+    /// The resources that could not be collected, which are listed in `errors.txt` rather than
+    /// aborting the collection.
+    errors: Vec<String>,
+}
+
+impl Archive {
+    fn add_file(&mut self, file_path: &str, data: &[u8]) -> Result<(), Error> {
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size(data.len() as u64);
+        header.set_mtime(
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |now| now.as_secs()),
+        );
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_cksum();
+        self.tar
+            .append_data(&mut header, file_path, data)
+            .context(AddFileSnafu { file_path })
+    }
+
+    fn add_yaml(&mut self, file_path: &str, object: &impl Serialize) -> Result<(), Error> {
+        match serde_yaml::to_string(object) {
+            Ok(yaml) => self.add_file(file_path, yaml.as_bytes()),
+            Err(error) => {
+                self.record_error(format!("failed to serialize {file_path}"), error);
+                Ok(())
+            }
+        }
+    }
+
+    fn record_error(&mut self, message: String, error: impl Display) {
+        eprintln!("warning: {message}: {error}");
+        self.errors.push(format!("{message}: {error}"));
+    }
+}
+
+pub async fn run(args: CollectDiagnosticsArgs) -> Result<(), Error> {
+    let client = Client::try_default().await.context(CreateClientSnafu)?;
+    let namespace = args
+        .namespace
+        .clone()
+        .unwrap_or_else(|| client.default_namespace().to_string());
+    let cluster_name = &args.cluster_name;
+    let path = args
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("opa-diagnostics-{cluster_name}.tar.gz")));
+
+    let mut opa = Api::<OpaCluster>::namespaced(client.clone(), &namespace)
+        .get(cluster_name)
+        .await
+        .context(GetOpaClusterSnafu { cluster_name })?;
+    let file = File::create(&path).context(CreateArchiveSnafu { path: &path })?;
+    let mut archive = Archive {
+        tar: tar::Builder::new(GzEncoder::new(file, flate2::Compression::default())),
+        errors: Vec::new(),
+    };
+
+    // The status (including the conditions) is part of the OpaCluster
+    strip_metadata(&mut opa);
+    archive.add_yaml("opacluster.yaml", &opa)?;
+
+    let instance_selector =
+        format!("app.kubernetes.io/name={APP_NAME},app.kubernetes.io/instance={cluster_name}");
+    let list_params = ListParams::default().labels(&instance_selector);
+
+    // The rendered configs of the role groups (and the discovery ConfigMap)
+    match Api::<ConfigMap>::namespaced(client.clone(), &namespace)
+        .list(&list_params)
+        .await
+    {
+        Ok(config_maps) => {
+            for mut cm in config_maps {
+                strip_metadata(&mut cm);
+                // Such as the bundle snapshot, which contains the policies
+                if let Some(binary_data) = &mut cm.binary_data {
+                    binary_data.clear();
+                }
+                archive.add_yaml(&format!("configmaps/{}.yaml", cm.name_any()), &cm)?;
+            }
+        }
+        Err(error) => archive.record_error("failed to list ConfigMaps".to_string(), error),
+    }
+
+    match Api::<DaemonSet>::namespaced(client.clone(), &namespace)
+        .list(&list_params)
+        .await
+    {
+        Ok(daemon_sets) => {
+            for mut ds in daemon_sets {
+                strip_metadata(&mut ds);
+                archive.add_yaml(&format!("daemonsets/{}.yaml", ds.name_any()), &ds)?;
+            }
+        }
+        Err(error) => archive.record_error("failed to list DaemonSets".to_string(), error),
+    }
+
+    match Api::<Event>::namespaced(client.clone(), &namespace)
+        .list(&ListParams::default())
+        .await
+    {
+        Ok(events) => {
+            // The Events of the OpaCluster and of all objects named after it (such as its Pods)
+            let mut events = events
+                .into_iter()
+                .filter(|event| {
+                    event.involved_object.name.as_ref().is_some_and(|name| {
+                        name == cluster_name || name.starts_with(&format!("{cluster_name}-"))
+                    })
+                })
+                .map(|mut event| {
+                    strip_metadata(&mut event);
+                    event
+                })
+                .collect::<Vec<_>>();
+            events.sort_by_key(|event| event.last_timestamp.as_ref().map(|time| time.0));
+            archive.add_yaml("events.yaml", &events)?;
+        }
+        Err(error) => archive.record_error("failed to list Events".to_string(), error),
+    }
+
+    let pods_api = Api::<Pod>::namespaced(client.clone(), &namespace);
+    match pods_api.list(&list_params).await {
+        Ok(pods) => {
+            for mut pod in pods {
+                let pod_name = pod.name_any();
+                collect_pod(&mut archive, &client, &pods_api, &pod, &args).await?;
+                strip_metadata(&mut pod);
+                archive.add_yaml(&format!("pods/{pod_name}/pod.yaml"), &pod)?;
+            }
+        }
+        Err(error) => archive.record_error("failed to list Pods".to_string(), error),
+    }
+
+    if !archive.errors.is_empty() {
+        let errors = archive.errors.join("\n");
+        archive.add_file("errors.txt", errors.as_bytes())?;
+    }
+    archive
+        .tar
+        .into_inner()
+        .and_then(GzEncoder::finish)
+        .context(FinishArchiveSnafu { path: &path })?;
+    println!("wrote diagnostics of OpaCluster {namespace}/{cluster_name} to {path:?}");
+    Ok(())
+}
+
+/// Collects the logs of all containers of the Pod, and the status of its bundle builder.
+async fn collect_pod(
+    archive: &mut Archive,
+    client: &Client,
+    pods_api: &Api<Pod>,
+    pod: &Pod,
+    args: &CollectDiagnosticsArgs,
+) -> Result<(), Error> {
+    let pod_name = pod.name_any();
+    // Native sidecars are init containers
+    let containers = pod
+        .spec
+        .iter()
+        .flat_map(|spec| {
+            spec.init_containers
+                .iter()
+                .flatten()
+                .chain(&spec.containers)
+        })
+        .map(|container| container.name.clone())
+        .collect::<Vec<_>>();
+    for container in containers {
+        let logs = pods_api
+            .logs(
+                &pod_name,
+                &LogParams {
+                    container: Some(container.clone()),
+                    tail_lines: Some(args.log_lines),
+                    limit_bytes: Some(MAX_LOG_BYTES),
+                    timestamps: true,
+                    ..LogParams::default()
+                },
+            )
+            .await;
+        match logs {
+            Ok(logs) => {
+                archive.add_file(&format!("pods/{pod_name}/{container}.log"), logs.as_bytes())?
+            }
+            Err(error) => archive.record_error(
+                format!("failed to get logs of container {container} of Pod {pod_name}"),
+                error,
+            ),
+        }
+    }
+
+    // The bundle builder listens on all interfaces, so it can be reached through the API server
+    // proxy. The user info fetcher only listens on localhost.
+    let Some(namespace) = pod.namespace() else {
+        return Ok(());
+    };
+    for (endpoint, file_name) in BUNDLE_BUILDER_ENDPOINTS {
+        let request = http::Request::get(format!(
+            "/api/v1/namespaces/{namespace}/pods/{pod_name}:{BUNDLE_BUILDER_PORT}/proxy{endpoint}"
+        ))
+        .body(Vec::new());
+        let response = match request {
+            Ok(request) => client.request_text(request).await,
+            Err(error) => {
+                archive.record_error(format!("failed to build request to {endpoint}"), error);
+                continue;
+            }
+        };
+        match response {
+            Ok(response) => {
+                archive.add_file(&format!("pods/{pod_name}/{file_name}"), response.as_bytes())?
+            }
+            Err(error) => archive.record_error(
+                format!("failed to query bundle builder endpoint {endpoint} of Pod {pod_name}"),
+                error,
+            ),
+        }
+    }
+    Ok(())
+}
+
+/// Removes metadata that is irrelevant for diagnostics, and might contain the full previous
+/// manifest (`kubectl.kubernetes.io/last-applied-configuration`).
+fn strip_metadata(object: &mut impl Resource) {
+    let meta = object.meta_mut();
+    meta.managed_fields = None;
+    if let Some(annotations) = &mut meta.annotations {
+        annotations.remove("kubectl.kubernetes.io/last-applied-configuration");
+    }
+}
@@ -1,7 +1,7 @@
 use std::{
     borrow::Cow,
-    collections::{BTreeMap, HashMap},
-    sync::Arc,
+    collections::{BTreeMap, BTreeSet, HashMap},
+    sync::{Arc, Mutex},
 };
 
 use const_format::concatcp;
@@ -9,10 +9,18 @@ use indoc::formatdoc;
 use product_config::{types::PropertyNameKind, ProductConfigManager};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use sha2::{Digest, Sha256};
 use snafu::{OptionExt, ResultExt, Snafu};
 use stackable_opa_crd::{
-    user_info_fetcher, Container, OpaCluster, OpaClusterStatus, OpaConfig, OpaRole, APP_NAME,
-    DEFAULT_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT, OPERATOR_NAME,
+    fault_injection::{fault_injection_annotations, FAULT_INJECTION_FILE_NAME},
+    policy::{BUNDLE_CLUSTER_LABEL, BUNDLE_LABEL},
+    user_info_fetcher, Container, OpaBindAddress, OpaBundlePollingConfig,
+    OpaBundleReplicationConfig, OpaBundleSigningConfig, OpaCluster, OpaClusterStatus, OpaConfig,
+    OpaConfigFragment, OpaExternalBundleServiceConfig, OpaExternalBundleServiceCredentials,
+    OpaInternalTrafficPolicy, OpaRole, OpaServerConfig, OpaSidecarImage, OpaTrustStore, APP_NAME,
+    BUNDLE_CLIENT_NODE_HEADER, BUNDLE_CLIENT_POD_HEADER, BUNDLE_LATEST_REVISION_ANNOTATION,
+    BUNDLE_REVISION_ANNOTATION, BUNDLE_SNAPSHOT_ERROR_ANNOTATION, BUNDLE_SNAPSHOT_HASH_ANNOTATION,
+    DEFAULT_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT, OPERATOR_NAME, REGORULE_LIBRARY_VERSION_ANNOTATION,
 };
 use stackable_operator::{
     builder::{
@@ -27,30 +35,41 @@ use stackable_operator::{
             PodBuilder,
         },
     },
-    cluster_resources::{ClusterResourceApplyStrategy, ClusterResources},
+    cluster_resources::{ClusterResource, ClusterResourceApplyStrategy, ClusterResources},
     commons::{
         product_image_selection::ResolvedProductImage,
         rbac::build_rbac_resources,
-        secret_class::{SecretClassVolume, SecretClassVolumeScope},
+        secret_class::{SecretClassVolume, SecretClassVolumeError, SecretClassVolumeScope},
         tls_verification::TlsClientDetailsError,
     },
     k8s_openapi::{
         api::{
             apps::v1::{DaemonSet, DaemonSetSpec},
             core::v1::{
-                ConfigMap, EmptyDirVolumeSource, EnvVar, HTTPGetAction, Probe, SecretVolumeSource,
-                Service, ServiceAccount, ServicePort, ServiceSpec,
+                ConfigMap, EmptyDirVolumeSource, EnvVar, EnvVarSource, ExecAction, HTTPGetAction,
+                HostPathVolumeSource, KeyToPath, Node, ObjectFieldSelector, PodSpec, Probe,
+                SecretKeySelector, SecretVolumeSource, SecurityContext, Service, ServiceAccount,
+                ServicePort, ServiceSpec, Volume,
             },
+            rbac::v1::RoleBinding,
         },
-        apimachinery::pkg::{apis::meta::v1::LabelSelector, util::intstr::IntOrString},
+        apimachinery::pkg::{
+            apis::meta::v1::{LabelSelector, Time},
+            util::intstr::IntOrString,
+        },
+        chrono::Utc,
         DeepMerge,
     },
     kube::{
+        api::{ListParams, PartialObjectMeta},
         core::{error_boundary, DeserializeGuard},
-        runtime::{controller::Action, reflector::ObjectRef},
-        Resource as KubeResource, ResourceExt,
+        runtime::{
+            controller::Action,
+            reflector::{ObjectRef, Store},
+        },
+        Api, Resource as KubeResource, ResourceExt,
     },
-    kvp::{Label, LabelError, Labels, ObjectLabels},
+    kvp::{Annotation, AnnotationError, Label, LabelError, Labels, ObjectLabels},
     logging::controller::ReconcilerError,
     memory::{BinaryMultiple, MemoryQuantity},
     product_config_utils::{transform_all_roles_to_config, validate_all_roles_and_groups_config},
@@ -70,40 +89,103 @@ use stackable_operator::{
         operations::ClusterOperationsConditionBuilder,
     },
     time::Duration,
-    utils::COMMON_BASH_TRAP_FUNCTIONS,
+    utils::{cluster_info::KubernetesClusterInfo, COMMON_BASH_TRAP_FUNCTIONS},
 };
 use strum::{EnumDiscriminants, IntoStaticStr};
 
 use crate::{
+    bundle_builder_rbac::reconcile_bundle_builder_rbac,
     discovery::{self, build_discovery_configmaps},
-    operations::graceful_shutdown::add_graceful_shutdown_config,
+    field_ownership::{release_legacy_field_managers, FieldOwnershipAudit},
+    gateway::reconcile_http_route,
+    metrics::PrometheusMetrics,
+    network_policy::reconcile_network_policy,
+    opa_version::{self, OpaVersion},
+    operations::{
+        canary_rollout::{reconcile_canary_rollout, CanaryRollout},
+        graceful_shutdown::{add_graceful_shutdown_config, clamp_graceful_shutdown_timeout},
+        pdb::add_pdbs,
+        stop::stop_daemonset,
+    },
     product_logging::{
         extend_role_group_config_map, resolve_vector_aggregator_address, BundleBuilderLogLevel,
+        DECISION_LOG_FILE_NAME,
     },
 };
 
 pub const OPA_CONTROLLER_NAME: &str = "opacluster";
+/// The longest delay before a failed reconciliation is retried, see [`error_policy`].
+const MAX_ERROR_BACKOFF: Duration = Duration::from_minutes_unchecked(5);
 pub const OPA_FULL_CONTROLLER_NAME: &str = concatcp!(OPA_CONTROLLER_NAME, '.', OPERATOR_NAME);
 
 pub const CONFIG_FILE: &str = "config.json";
 pub const APP_PORT: u16 = 8081;
 pub const APP_PORT_NAME: &str = "http";
 pub const METRICS_PORT_NAME: &str = "metrics";
+pub const METRICS_LABEL_ANNOTATION_PREFIX: &str = "metrics.opa.stackable.tech/";
+pub const METRICS_NAME_PREFIX_ANNOTATION: &str =
+    concatcp!(METRICS_LABEL_ANNOTATION_PREFIX, "name-prefix");
 pub const BUNDLES_ACTIVE_DIR: &str = "/bundles/active";
 pub const BUNDLES_INCOMING_DIR: &str = "/bundles/incoming";
 pub const BUNDLES_TMP_DIR: &str = "/bundles/tmp";
 pub const BUNDLE_BUILDER_PORT: i32 = 3030;
+/// The port of the bundle builder that is only reachable from within the Pod (bound to 127.0.0.1),
+/// for the endpoints that only OPA may call.
+pub const BUNDLE_BUILDER_LOCAL_PORT: u16 = 3031;
+/// The OPA service the decision logs are uploaded to.
+const DECISION_LOG_SERVICE_NAME: &str = "stackable-decision-logs";
+/// The OPA service the status reports are uploaded to.
+const STATUS_SERVICE_NAME: &str = "stackable-status";
+/// The latest bundle, pulled by all role groups unless a canary rollout is configured.
+const BUNDLE_RESOURCE: &str = "opa/bundle.tar.gz";
+/// The promoted bundle, pulled by the role groups that are not part of the canary.
+const STABLE_BUNDLE_RESOURCE: &str = "opa/stable/bundle.tar.gz";
 
 const CONFIG_VOLUME_NAME: &str = "config";
 const CONFIG_DIR: &str = "/stackable/config";
 const LOG_VOLUME_NAME: &str = "log";
-const STACKABLE_LOG_DIR: &str = "/stackable/log";
+pub const STACKABLE_LOG_DIR: &str = "/stackable/log";
 const BUNDLES_VOLUME_NAME: &str = "bundles";
 const BUNDLES_DIR: &str = "/bundles";
 const USER_INFO_FETCHER_CREDENTIALS_VOLUME_NAME: &str = "credentials";
 const USER_INFO_FETCHER_CREDENTIALS_DIR: &str = "/stackable/credentials";
 const USER_INFO_FETCHER_KERBEROS_VOLUME_NAME: &str = "kerberos";
 const USER_INFO_FETCHER_KERBEROS_DIR: &str = "/stackable/kerberos";
+const USER_INFO_FETCHER_SERVER_TLS_VOLUME_NAME: &str = "user-info-fetcher-tls";
+const USER_INFO_FETCHER_SERVER_TLS_DIR: &str = "/stackable/user-info-fetcher-tls";
+pub const USER_INFO_FETCHER_PORT: u16 = 9476;
+const USER_INFO_FETCHER_LIVENESS_PROBE_PATH: &str = "/healthz";
+/// The path of the OPA liveness probe, see [`OpaProbesConfig`](stackable_opa_crd::OpaProbesConfig)
+/// for the readiness probe.
+const OPA_LIVENESS_PROBE_PATH: &str = "/health";
+const OPA_STARTUP_PROBE_PERIOD_SECONDS: i32 = 5;
+const PERSISTENT_STORAGE_VOLUME_NAME: &str = "persistent-storage";
+const PERSISTENT_STORAGE_DIR: &str = "/stackable/persistent-storage";
+const PERSISTENT_STORAGE_PERMISSIONS_CONTAINER_NAME: &str = "persistent-storage-permissions";
+const BUNDLE_SIGNING_VOLUME_NAME: &str = "bundle-signing";
+const BUNDLE_SIGNING_DIR: &str = "/stackable/bundle-signing";
+const BUNDLE_VERIFICATION_VOLUME_NAME: &str = "bundle-verification";
+const BUNDLE_VERIFICATION_DIR: &str = "/stackable/bundle-verification";
+const NODE_LABELS_VOLUME_NAME: &str = "node-labels";
+const NODE_LABELS_DIR: &str = "/stackable/node-labels";
+/// How long the start command of OPA waits for the labels of a node that joined the cluster after
+/// the node labels ConfigMap was last updated, which includes the delay of the kubelet updating the
+/// mounted ConfigMap.
+const NODE_LABELS_WAIT_TIMEOUT_SECONDS: u32 = 120;
+const DEVELOPMENT_POLICIES_VOLUME_NAME: &str = "development-policies";
+const DEVELOPMENT_POLICIES_DIR: &str = "/stackable/development-policies";
+const TRUST_STORE_VOLUME_NAME: &str = "trust-store";
+const TRUST_STORE_DIR: &str = "/stackable/trust-store";
+const EXTERNAL_BUNDLE_SERVICE_CREDENTIALS_VOLUME_NAME: &str = "external-bundle-service-credentials";
+const EXTERNAL_BUNDLE_SERVICE_CREDENTIALS_DIR: &str =
+    "/stackable/external-bundle-service-credentials";
+/// Set by the OPA start command, and substituted into the config file by OPA.
+const EXTERNAL_BUNDLE_SERVICE_BASIC_AUTH_ENV: &str = "EXTERNAL_BUNDLE_SERVICE_BASIC_AUTH";
+/// How much longer than the long polling timeout OPA waits for the bundle builder to respond.
+const LONG_POLLING_RESPONSE_HEADER_TIMEOUT_OVERHEAD_SECONDS: u64 = 10;
+/// How often OPA uploads the decision logs to the bundle builder.
+const DECISION_LOG_UPLOAD_MIN_DELAY_SECONDS: u64 = 1;
+const DECISION_LOG_UPLOAD_MAX_DELAY_SECONDS: u64 = 5;
 
 const DOCKER_IMAGE_BASE_NAME: &str = "opa";
 
@@ -147,6 +229,13 @@ pub struct Ctx {
     pub product_config: ProductConfigManager,
     pub opa_bundle_builder_image: String,
     pub user_info_fetcher_image: String,
+    /// Whether the sidecars are deployed as native sidecars, see [`move_to_native_sidecars`].
+    pub native_sidecars: bool,
+    /// Namespaces whose ConfigMaps the bundle builders never add to bundles.
+    pub bundle_excluded_namespaces: Vec<String>,
+    /// The number of consecutive failed reconciliations of each OpaCluster, see [`error_policy`].
+    pub failed_reconciles: Mutex<HashMap<ObjectRef<OpaCluster>, u32>>,
+    pub metrics: Arc<PrometheusMetrics>,
 }
 
 #[derive(Snafu, Debug, EnumDiscriminants)]
@@ -235,6 +324,68 @@ pub enum Error {
         source: stackable_operator::cluster_resources::Error,
     },
 
+    #[snafu(display("failed to clean up stale discovery ConfigMaps"))]
+    CleanUpStaleDiscoveryConfig { source: discovery::Error },
+
+    #[snafu(display("failed to apply bundle snapshot ConfigMap"))]
+    ApplyBundleSnapshotConfig {
+        source: stackable_operator::cluster_resources::Error,
+    },
+
+    #[snafu(display("failed to apply bundle delivery ConfigMap"))]
+    ApplyBundleDeliveryConfig {
+        source: stackable_operator::cluster_resources::Error,
+    },
+
+    #[snafu(display("failed to build regorule library ConfigMap"))]
+    BuildRegoruleLibraryConfig {
+        source: stackable_operator::builder::configmap::Error,
+    },
+
+    #[snafu(display("failed to build regorule library version annotation"))]
+    BuildRegoruleLibraryAnnotation { source: AnnotationError },
+
+    #[snafu(display("failed to apply regorule library ConfigMap"))]
+    ApplyRegoruleLibraryConfig {
+        source: stackable_operator::cluster_resources::Error,
+    },
+
+    #[snafu(display("failed to list the nodes to copy their labels"))]
+    ListNodes {
+        source: stackable_operator::kube::Error,
+    },
+
+    #[snafu(display("failed to build node labels ConfigMap"))]
+    BuildNodeLabelsConfig {
+        source: stackable_operator::builder::configmap::Error,
+    },
+
+    #[snafu(display("failed to apply node labels ConfigMap"))]
+    ApplyNodeLabelsConfig {
+        source: stackable_operator::cluster_resources::Error,
+    },
+
+    #[snafu(display("failed to apply merged config ConfigMap"))]
+    ApplyMergedConfig {
+        source: stackable_operator::cluster_resources::Error,
+    },
+
+    #[snafu(display("failed to serialize merged config of role group {role_group:?}"))]
+    SerializeMergedConfig {
+        source: serde_yaml::Error,
+        role_group: String,
+    },
+
+    #[snafu(display("failed to apply stable bundle ConfigMap"))]
+    ApplyStableBundleConfig {
+        source: stackable_operator::cluster_resources::Error,
+    },
+
+    #[snafu(display("failed to roll out canary bundle"))]
+    CanaryRollout {
+        source: crate::operations::canary_rollout::Error,
+    },
+
     #[snafu(display("failed to transform configs"))]
     ProductConfigTransform {
         source: stackable_operator::product_config_utils::Error,
@@ -243,6 +394,12 @@ pub enum Error {
     #[snafu(display("failed to resolve and merge config for role and role group"))]
     FailedToResolveConfig { source: stackable_opa_crd::Error },
 
+    #[snafu(display("the config of {rolegroup} is not supported by the OPA version"))]
+    UnsupportedOpaVersion {
+        source: crate::opa_version::Error,
+        rolegroup: RoleGroupRef<OpaCluster>,
+    },
+
     #[snafu(display("illegal container name"))]
     IllegalContainerName {
         source: stackable_operator::builder::pod::container::Error,
@@ -279,12 +436,42 @@ pub enum Error {
         source: crate::operations::graceful_shutdown::Error,
     },
 
+    #[snafu(display("failed to expose the role service through the Gateway"))]
+    GatewayRoute { source: crate::gateway::Error },
+
+    #[snafu(display("failed to restrict access to OPA with a NetworkPolicy"))]
+    NetworkPolicy {
+        source: crate::network_policy::Error,
+    },
+
+    #[snafu(display("failed to grant the bundle builders access to the bundle snapshot"))]
+    BundleBuilderRbac {
+        source: crate::bundle_builder_rbac::Error,
+    },
+
+    #[snafu(display("failed to create PodDisruptionBudget"))]
+    FailedToCreatePdb {
+        source: crate::operations::pdb::Error,
+    },
+
     #[snafu(display("failed to serialize user info fetcher configuration"))]
     SerializeUserInfoFetcherConfig { source: serde_json::Error },
 
+    #[snafu(display("failed to serialize fault injection configuration"))]
+    SerializeFaultInjectionConfig { source: serde_json::Error },
+
     #[snafu(display("failed to build label"))]
     BuildLabel { source: LabelError },
 
+    #[snafu(display("failed to build annotation for metrics label {key:?}"))]
+    BuildMetricsLabelAnnotation {
+        source: AnnotationError,
+        key: String,
+    },
+
+    #[snafu(display("failed to build annotation for metrics name prefix"))]
+    BuildMetricsNamePrefixAnnotation { source: AnnotationError },
+
     #[snafu(display("failed to build object meta data"))]
     ObjectMeta {
         source: stackable_operator::builder::meta::Error,
@@ -311,10 +498,35 @@ pub enum Error {
     #[snafu(display("failed to add needed volume"))]
     AddVolume { source: builder::pod::Error },
 
+    #[snafu(display(
+        "failed to build volume spec for the User Info Fetcher server TLS certificate"
+    ))]
+    UserInfoFetcherServerTlsVolume { source: SecretClassVolumeError },
+
+    #[snafu(display("failed to build volume spec for the User Info Fetcher credentials"))]
+    UserInfoFetcherCredentialsVolume { source: SecretClassVolumeError },
+
+    #[snafu(display(
+        "exactly one of clientCredentialsSecret and clientCredentialsSecretClass must be set for the Keycloak backend"
+    ))]
+    AmbiguousUserInfoFetcherCredentials,
+
+    #[snafu(display("invalid image override clusterConfig.images.{field}: {message}"))]
+    InvalidSidecarImage { field: String, message: String },
+
+    #[snafu(display("failed to build volume spec for the trust store"))]
+    TrustStoreVolume { source: SecretClassVolumeError },
+
     #[snafu(display("failed to add needed volumeMount"))]
     AddVolumeMount {
         source: builder::pod::container::Error,
     },
+
+    #[snafu(display("failed to serialize spec of {opa} to compute its hash"))]
+    SerializeSpec {
+        source: serde_json::Error,
+        opa: ObjectRef<OpaCluster>,
+    },
 }
 type Result<T, E = Error> = std::result::Result<T, E>;
 
@@ -324,33 +536,262 @@ impl ReconcilerError for Error {
     }
 }
 
+/// How a failed reconciliation is retried, see [`error_policy`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ErrorClass {
+    /// Failures talking to the API server (such as conflicting writes), which usually succeed when
+    /// retried right away.
+    Transient,
+    /// Objects or services the OpaCluster depends on are not available (yet), which usually takes a
+    /// while to resolve.
+    Dependency,
+    /// The OpaCluster cannot be reconciled as it is, so retrying only makes sense once it has been
+    /// changed.
+    Terminal,
+}
+
+impl Error {
+    fn class(&self) -> ErrorClass {
+        match self {
+            Error::ApplyRoleService { .. }
+            | Error::ApplyRoleGroupService { .. }
+            | Error::ApplyRoleGroupConfig { .. }
+            | Error::ApplyRoleGroupDaemonSet { .. }
+            | Error::ApplyPatchRoleGroupDaemonSet { .. }
+            | Error::ApplyServiceAccount { .. }
+            | Error::ApplyRoleBinding { .. }
+            | Error::ApplyStatus { .. }
+            | Error::ApplyDiscoveryConfig { .. }
+            | Error::CleanUpStaleDiscoveryConfig { .. }
+            | Error::ApplyBundleSnapshotConfig { .. }
+            | Error::ApplyBundleDeliveryConfig { .. }
+            | Error::ApplyRegoruleLibraryConfig { .. }
+            | Error::ListNodes { .. }
+            | Error::ApplyNodeLabelsConfig { .. }
+            | Error::ApplyMergedConfig { .. }
+            | Error::ApplyStableBundleConfig { .. }
+            | Error::FailedToCreateClusterResources { .. }
+            | Error::DeleteOrphans { .. } => ErrorClass::Transient,
+
+            Error::GatewayRoute { source } => match source {
+                crate::gateway::Error::ApplyHttpRoute { .. }
+                | crate::gateway::Error::GetHttpRoute { .. }
+                | crate::gateway::Error::DeleteHttpRoute { .. } => ErrorClass::Transient,
+                crate::gateway::Error::NoNamespace
+                | crate::gateway::Error::BuildMetadata { .. } => ErrorClass::Terminal,
+            },
+
+            Error::NetworkPolicy { source } => match source {
+                crate::network_policy::Error::ApplyNetworkPolicy { .. }
+                | crate::network_policy::Error::GetNetworkPolicy { .. }
+                | crate::network_policy::Error::DeleteNetworkPolicy { .. } => ErrorClass::Transient,
+                crate::network_policy::Error::NoNamespace
+                | crate::network_policy::Error::BuildMetadata { .. }
+                | crate::network_policy::Error::BuildLabel { .. } => ErrorClass::Terminal,
+            },
+
+            Error::BundleBuilderRbac { source } => match source {
+                crate::bundle_builder_rbac::Error::ApplyRole { .. }
+                | crate::bundle_builder_rbac::Error::ApplyRoleBinding { .. } => {
+                    ErrorClass::Transient
+                }
+                crate::bundle_builder_rbac::Error::BuildMetadata { .. } => ErrorClass::Terminal,
+            },
+
+            Error::FailedToCreatePdb { source } => match source {
+                crate::operations::pdb::Error::ApplyPdb { .. } => ErrorClass::Transient,
+                crate::operations::pdb::Error::CreatePdb { .. } => ErrorClass::Terminal,
+            },
+
+            // The Vector aggregator discovery ConfigMap and the bundle snapshot are created by
+            // others, and policy tests take a while to run
+            Error::ResolveVectorAggregatorAddress { .. } | Error::CanaryRollout { .. } => {
+                ErrorClass::Dependency
+            }
+
+            Error::InvalidOpaCluster { .. }
+            | Error::NoName
+            | Error::InternalOperatorFailure { .. }
+            | Error::RoleServiceNameNotFound
+            | Error::BuildRoleGroupConfig { .. }
+            | Error::BuildRegoruleLibraryConfig { .. }
+            | Error::BuildRegoruleLibraryAnnotation { .. }
+            | Error::BuildNodeLabelsConfig { .. }
+            | Error::InvalidProductConfig { .. }
+            | Error::ObjectMissingMetadataForOwnerRef { .. }
+            | Error::BuildDiscoveryConfig { .. }
+            | Error::ProductConfigTransform { .. }
+            | Error::FailedToResolveConfig { .. }
+            | Error::UnsupportedOpaVersion { .. }
+            | Error::IllegalContainerName { .. }
+            | Error::InvalidLoggingConfig { .. }
+            | Error::BuildRbacResources { .. }
+            | Error::GracefulShutdown { .. }
+            | Error::SerializeUserInfoFetcherConfig { .. }
+            | Error::SerializeFaultInjectionConfig { .. }
+            | Error::SerializeMergedConfig { .. }
+            | Error::BuildLabel { .. }
+            | Error::BuildMetricsLabelAnnotation { .. }
+            | Error::BuildMetricsNamePrefixAnnotation { .. }
+            | Error::ObjectMeta { .. }
+            | Error::UserInfoFetcherKerberosVolume { .. }
+            | Error::UserInfoFetcherKerberosVolumeMount { .. }
+            | Error::UserInfoFetcherTlsVolumeAndMounts { .. }
+            | Error::ConfigureLogging { .. }
+            | Error::AddVolume { .. }
+            | Error::UserInfoFetcherServerTlsVolume { .. }
+            | Error::UserInfoFetcherCredentialsVolume { .. }
+            | Error::AmbiguousUserInfoFetcherCredentials
+            | Error::InvalidSidecarImage { .. }
+            | Error::TrustStoreVolume { .. }
+            | Error::AddVolumeMount { .. }
+            | Error::SerializeSpec { .. } => ErrorClass::Terminal,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct OpaClusterConfigFile {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     services: Vec<OpaClusterConfigService>,
-    bundles: OpaClusterBundle,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundles: Option<OpaClusterBundle>,
     #[serde(skip_serializing_if = "Option::is_none")]
     decision_logs: Option<OpaClusterConfigDecisionLog>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    persistence_directory: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage: Option<OpaClusterConfigStorage>,
+    status: OpaClusterConfigStatus,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    caching: Option<OpaClusterConfigCaching>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nd_builtin_cache: Option<bool>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    keys: BTreeMap<String, OpaClusterConfigKey>,
 }
 
 impl OpaClusterConfigFile {
-    pub fn new(decision_logging: Option<OpaClusterConfigDecisionLog>) -> Self {
+    pub fn new(
+        decision_logging: Option<OpaClusterConfigDecisionLog>,
+        persistent_storage_enabled: bool,
+        labels: BTreeMap<String, String>,
+        bundle_signing: Option<&OpaBundleSigningConfig>,
+        bundle_source: Option<&BundleSource>,
+        bundle_polling: &OpaBundlePollingConfig,
+        server_config: &OpaServerConfig,
+        opa_version: &OpaVersion,
+    ) -> Self {
+        let long_polling_timeout_seconds = bundle_polling
+            .long_polling_timeout
+            .map(|timeout| timeout.as_secs());
+        // Without a bundle source (in development mode) there is no service to talk to
+        let services = bundle_source
+            .map(|bundle_source| match bundle_source {
+                BundleSource::BundleBuilder { .. } => OpaClusterConfigService {
+                    name: String::from("stackable"),
+                    url: String::from("http://localhost:3030/opa/v1"),
+                    // Long polls only respond once a new bundle is available or the timeout is reached
+                    response_header_timeout_seconds: long_polling_timeout_seconds.map(|timeout| {
+                        timeout + LONG_POLLING_RESPONSE_HEADER_TIMEOUT_OVERHEAD_SECONDS
+                    }),
+                    // Lets the bundle builder track which revision each OPA instance last downloaded
+                    headers: BTreeMap::from([
+                        (
+                            BUNDLE_CLIENT_POD_HEADER.to_string(),
+                            "${POD_NAME}".to_string(),
+                        ),
+                        (
+                            BUNDLE_CLIENT_NODE_HEADER.to_string(),
+                            "${NODE_NAME}".to_string(),
+                        ),
+                    ]),
+                    credentials: None,
+                },
+                BundleSource::External(external) => OpaClusterConfigService {
+                    name: String::from("stackable"),
+                    url: external.url.clone(),
+                    response_header_timeout_seconds: None,
+                    headers: external.headers.clone(),
+                    credentials: external
+                        .credentials
+                        .as_ref()
+                        .map(OpaClusterConfigServiceCredentials::new),
+                },
+            })
+            .into_iter()
+            .chain(
+                matches!(bundle_source, Some(BundleSource::BundleBuilder { .. })).then(|| {
+                    OpaClusterConfigService {
+                        name: String::from(STATUS_SERVICE_NAME),
+                        url: format!("http://127.0.0.1:{BUNDLE_BUILDER_LOCAL_PORT}/opa/v1"),
+                        response_header_timeout_seconds: None,
+                        headers: BTreeMap::new(),
+                        credentials: None,
+                    }
+                }),
+            )
+            .chain(
+                decision_logging
+                    .as_ref()
+                    .and_then(|decision_logging| decision_logging.service.as_ref())
+                    .map(|service| OpaClusterConfigService {
+                        name: service.clone(),
+                        url: format!("http://127.0.0.1:{BUNDLE_BUILDER_LOCAL_PORT}/opa/v1"),
+                        response_header_timeout_seconds: None,
+                        headers: BTreeMap::new(),
+                        credentials: None,
+                    }),
+            )
+            .collect();
         Self {
-            services: vec![OpaClusterConfigService {
-                name: String::from("stackable"),
-                url: String::from("http://localhost:3030/opa/v1"),
-            }],
-            bundles: OpaClusterBundle {
+            services,
+            bundles: bundle_source.map(|bundle_source| OpaClusterBundle {
                 stackable: OpaClusterBundleConfig {
                     service: String::from("stackable"),
-                    resource: String::from("opa/bundle.tar.gz"),
+                    resource: bundle_source.resource().to_string(),
                     persist: true,
                     polling: OpaClusterBundleConfigPolling {
-                        min_delay_seconds: 10,
-                        max_delay_seconds: 20,
+                        min_delay_seconds: bundle_polling.min_delay.as_secs(),
+                        max_delay_seconds: bundle_polling.max_delay.as_secs(),
+                        long_polling_timeout_seconds,
                     },
+                    signing: bundle_signing.map(|signing| OpaClusterBundleConfigSigning {
+                        keyid: signing.key_id.clone(),
+                        scope: signing.scope.clone(),
+                    }),
                 },
-            },
+            }),
             decision_logs: decision_logging,
+            persistence_directory: persistent_storage_enabled
+                .then(|| format!("{PERSISTENT_STORAGE_DIR}/bundles")),
+            storage: persistent_storage_enabled.then(|| OpaClusterConfigStorage {
+                disk: OpaClusterConfigStorageDisk {
+                    directory: format!("{PERSISTENT_STORAGE_DIR}/disk"),
+                    auto_create: true,
+                },
+            }),
+            status: OpaClusterConfigStatus {
+                prometheus: opa_version.supports(&opa_version::STATUS_PROMETHEUS),
+                service: matches!(bundle_source, Some(BundleSource::BundleBuilder { .. }))
+                    .then(|| String::from(STATUS_SERVICE_NAME)),
+            },
+            labels,
+            keys: bundle_signing
+                .map(|signing| {
+                    (
+                        signing.key_id.clone(),
+                        OpaClusterConfigKey {
+                            algorithm: signing.algorithm.to_string(),
+                        },
+                    )
+                })
+                .into_iter()
+                .collect(),
+            caching: OpaClusterConfigCaching::new(server_config),
+            nd_builtin_cache: server_config.non_deterministic_builtin_cache,
         }
     }
 }
@@ -359,6 +800,71 @@ impl OpaClusterConfigFile {
 struct OpaClusterConfigService {
     name: String,
     url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_header_timeout_seconds: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    headers: BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    credentials: Option<OpaClusterConfigServiceCredentials>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpaClusterConfigServiceCredentials {
+    bearer: OpaClusterConfigServiceBearerCredentials,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpaClusterConfigServiceBearerCredentials {
+    scheme: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_path: Option<String>,
+}
+
+impl OpaClusterConfigServiceCredentials {
+    /// OPA supports basic authentication as bearer credentials with the scheme `Basic`, the token
+    /// is the base64 encoded `username:password` computed by the OPA start command.
+    fn new(credentials: &OpaExternalBundleServiceCredentials) -> Self {
+        let bearer = match credentials {
+            OpaExternalBundleServiceCredentials::BearerTokenSecret(_) => {
+                OpaClusterConfigServiceBearerCredentials {
+                    scheme: String::from("Bearer"),
+                    token: None,
+                    token_path: Some(format!(
+                        "{EXTERNAL_BUNDLE_SERVICE_CREDENTIALS_DIR}/{key}",
+                        key = OpaExternalBundleServiceCredentials::TOKEN_SECRET_KEY
+                    )),
+                }
+            }
+            OpaExternalBundleServiceCredentials::BasicAuthSecret(_) => {
+                OpaClusterConfigServiceBearerCredentials {
+                    scheme: String::from("Basic"),
+                    token: Some(format!("${{{EXTERNAL_BUNDLE_SERVICE_BASIC_AUTH_ENV}}}")),
+                    token_path: None,
+                }
+            }
+        };
+        Self { bearer }
+    }
+}
+
+/// Where OPA loads its bundle from.
+enum BundleSource<'a> {
+    /// The bundle builder running next to OPA, serving `resource`.
+    BundleBuilder {
+        resource: String,
+    },
+    External(&'a OpaExternalBundleServiceConfig),
+}
+
+impl BundleSource<'_> {
+    fn resource(&self) -> &str {
+        match self {
+            BundleSource::BundleBuilder { resource } => resource,
+            BundleSource::External(external) => &external.resource,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
@@ -372,46 +878,177 @@ struct OpaClusterBundleConfig {
     resource: String,
     persist: bool,
     polling: OpaClusterBundleConfigPolling,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signing: Option<OpaClusterBundleConfigSigning>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpaClusterBundleConfigSigning {
+    keyid: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
 struct OpaClusterBundleConfigPolling {
-    min_delay_seconds: i32,
-    max_delay_seconds: i32,
+    min_delay_seconds: u64,
+    max_delay_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    long_polling_timeout_seconds: Option<u64>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct OpaClusterConfigDecisionLog {
     console: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    service: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reporting: Option<OpaClusterConfigDecisionLogReporting>,
 }
 
-pub async fn reconcile_opa(
-    opa: Arc<DeserializeGuard<OpaCluster>>,
-    ctx: Arc<Ctx>,
-) -> Result<Action> {
-    tracing::info!("Starting reconcile");
-    let opa = opa
-        .0
-        .as_ref()
-        .map_err(error_boundary::InvalidObject::clone)
-        .context(InvalidOpaClusterSnafu)?;
-    let opa_ref = ObjectRef::from_obj(opa);
+#[derive(Serialize, Deserialize)]
+struct OpaClusterConfigDecisionLogReporting {
+    min_delay_seconds: u64,
+    max_delay_seconds: u64,
+}
 
-    let client = &ctx.client;
+#[derive(Serialize, Deserialize)]
+/// The key itself is passed to OPA using `--set-file`, so that it does not end up in the
+/// ConfigMap (it is a shared secret for the HMAC algorithms).
+struct OpaClusterConfigKey {
+    algorithm: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpaClusterConfigStatus {
+    /// Only set for OPA versions that support it.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    prometheus: bool,
+    /// The bundle builder publishes bundle activation failures as Events on the OpaCluster.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    service: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpaClusterConfigCaching {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inter_query_builtin_cache: Option<OpaClusterConfigInterQueryBuiltinCache>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inter_query_builtin_value_cache: Option<OpaClusterConfigInterQueryBuiltinValueCache>,
+}
+
+impl OpaClusterConfigCaching {
+    /// Returns `None` if no caching option is configured, so that OPA's defaults apply.
+    fn new(server_config: &OpaServerConfig) -> Option<Self> {
+        let inter_query_builtin_cache = OpaClusterConfigInterQueryBuiltinCache {
+            max_size_bytes: server_config.http_send_cache_max_size_bytes,
+            forced_eviction_threshold_percentage: server_config
+                .http_send_cache_forced_eviction_threshold_percentage,
+            stale_entry_eviction_period_seconds: server_config
+                .http_send_cache_stale_entry_eviction_period
+                .map(|period| period.as_secs()),
+        };
+        let inter_query_builtin_value_cache = server_config
+            .builtin_value_cache_max_entries
+            .map(|max_num_entries| OpaClusterConfigInterQueryBuiltinValueCache { max_num_entries });
+        let inter_query_builtin_cache = (inter_query_builtin_cache.max_size_bytes.is_some()
+            || inter_query_builtin_cache
+                .forced_eviction_threshold_percentage
+                .is_some()
+            || inter_query_builtin_cache
+                .stale_entry_eviction_period_seconds
+                .is_some())
+        .then_some(inter_query_builtin_cache);
+        (inter_query_builtin_cache.is_some() || inter_query_builtin_value_cache.is_some())
+            .then_some(Self {
+                inter_query_builtin_cache,
+                inter_query_builtin_value_cache,
+            })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpaClusterConfigInterQueryBuiltinCache {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_size_bytes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    forced_eviction_threshold_percentage: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stale_entry_eviction_period_seconds: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpaClusterConfigInterQueryBuiltinValueCache {
+    max_num_entries: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpaClusterConfigStorage {
+    disk: OpaClusterConfigStorageDisk,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OpaClusterConfigStorageDisk {
+    directory: String,
+    auto_create: bool,
+}
+
+/// Everything [`build_resources`] needs that is not part of the OpaCluster, so that the resources can
+/// also be built without contacting Kubernetes (see [`crate::render`]).
+pub struct BuildContext<'a> {
+    pub product_config: &'a ProductConfigManager,
+    pub cluster_info: &'a KubernetesClusterInfo,
+    /// The address of the Vector aggregator, `None` if no logs are shipped.
+    pub vector_aggregator_address: Option<&'a str>,
+    pub opa_bundle_builder_image: &'a str,
+    pub user_info_fetcher_image: &'a str,
+    pub native_sidecars: bool,
+    pub bundle_excluded_namespaces: &'a [String],
+}
+
+/// The resources of an OpaCluster that only depend on the OpaCluster itself, see [`build_resources`].
+///
+/// Resources that can only be built after looking up other objects in the cluster (such as the
+/// NetworkPolicy, the HTTPRoute and the PodDisruptionBudgets) are built while reconciling instead.
+pub struct OpaClusterResources {
+    pub resolved_product_image: ResolvedProductImage,
+    pub server_role_service: Service,
+    /// The bundle snapshot and bundle delivery ConfigMaps, which are not needed in development mode.
+    pub bundle_config_maps: Option<(ConfigMap, ConfigMap)>,
+    pub regorule_library_config_map: Option<ConfigMap>,
+    pub stable_bundle_config_map: Option<ConfigMap>,
+    pub service_account: ServiceAccount,
+    pub role_binding: RoleBinding,
+    pub role_groups: Vec<RoleGroupResources>,
+    pub merged_config_config_map: Option<ConfigMap>,
+    pub discovery_config_maps: Vec<ConfigMap>,
+    /// Problems with the OpaCluster that don't prevent its resources from being built.
+    pub warnings: Vec<String>,
+}
+
+pub struct RoleGroupResources {
+    pub rolegroup: RoleGroupRef<OpaCluster>,
+    pub config_map: ConfigMap,
+    pub service: Service,
+    /// Already stopped if the OpaCluster is stopped, see [`stop_daemonset`].
+    pub daemonset: DaemonSet,
+}
+
+/// Builds the resources of the OpaCluster, in the order they are applied by [`reconcile_opa`].
+///
+/// This does not contact Kubernetes, the `cluster_resources` are only used for their labels.
+pub fn build_resources(
+    opa: &OpaCluster,
+    cluster_resources: &ClusterResources,
+    ctx: &BuildContext,
+) -> Result<OpaClusterResources> {
+    let opa_ref = ObjectRef::from_obj(opa);
     let resolved_product_image = opa
         .spec
         .image
         .resolve(DOCKER_IMAGE_BASE_NAME, crate::built_info::PKG_VERSION);
     let opa_role = OpaRole::Server;
-
-    let mut cluster_resources = ClusterResources::new(
-        APP_NAME,
-        OPERATOR_NAME,
-        OPA_CONTROLLER_NAME,
-        &opa.object_ref(&()),
-        ClusterResourceApplyStrategy::from(&opa.spec.cluster_operation),
-    )
-    .context(FailedToCreateClusterResourcesSnafu)?;
+    let opa_version = OpaVersion::new(&resolved_product_image.product_version);
 
     let validated_config = validate_all_roles_and_groups_config(
         &resolved_product_image.product_version,
@@ -430,7 +1067,7 @@ pub async fn reconcile_opa(
             .into(),
         )
         .context(ProductConfigTransformSnafu)?,
-        &ctx.product_config,
+        ctx.product_config,
         false,
         false,
     )
@@ -440,129 +1077,497 @@ pub async fn reconcile_opa(
         .map(Cow::Borrowed)
         .unwrap_or_default();
 
-    let vector_aggregator_address = resolve_vector_aggregator_address(opa, client)
-        .await
-        .context(ResolveVectorAggregatorAddressSnafu)?;
+    let mut warnings = Vec::new();
+    if let Some(warning) = check_deprecated_fields(opa) {
+        warnings.push(warning);
+    }
 
     let server_role_service = build_server_role_service(opa, &resolved_product_image)?;
-    // required for discovery config map later
-    let server_role_service = cluster_resources
-        .add(client, server_role_service)
-        .await
-        .context(ApplyRoleServiceSnafu)?;
+
+    // The bundle builders store the snapshot in it, the operator only manages its lifecycle.
+    // No bundles are built in development mode.
+    let bundle_config_maps = if opa.spec.cluster_config.development_mode.is_none() {
+        Some((
+            build_bundle_config_map(
+                opa,
+                &resolved_product_image,
+                opa.bundle_snapshot_config_map_name(),
+            )?,
+            // The bundle builders report the bundles delivered to OPA in it, see `BundleDelivery`
+            build_bundle_config_map(
+                opa,
+                &resolved_product_image,
+                opa.bundle_delivery_config_map_name(),
+            )?,
+        ))
+    } else {
+        None
+    };
+    // Removed as an orphan once the library is no longer managed by the operator
+    let regorule_library_config_map = if opa.manages_regorule_library() {
+        Some(build_regorule_library_config_map(
+            opa,
+            &resolved_product_image,
+        )?)
+    } else {
+        None
+    };
+    let stable_bundle_config_map = if opa.has_stable_bundle() {
+        Some(build_bundle_config_map(
+            opa,
+            &resolved_product_image,
+            opa.stable_bundle_config_map_name(),
+        )?)
+    } else {
+        None
+    };
 
     let required_labels = cluster_resources
         .get_required_labels()
         .context(BuildLabelSnafu)?;
-
-    let (rbac_sa, rbac_rolebinding) =
+    let (service_account, role_binding) =
         build_rbac_resources(opa, APP_NAME, required_labels).context(BuildRbacResourcesSnafu)?;
 
-    let rbac_sa = cluster_resources
-        .add(client, rbac_sa.clone())
-        .await
-        .context(ApplyServiceAccountSnafu)?;
-    cluster_resources
-        .add(client, rbac_rolebinding)
-        .await
-        .context(ApplyRoleBindingSnafu)?;
-
-    let mut ds_cond_builder = DaemonSetConditionBuilder::default();
-
-    for (rolegroup_name, rolegroup_config) in role_server_config.iter() {
+    let mut role_groups = Vec::new();
+    let mut merged_config_fragments = BTreeMap::new();
+    // Sorted, so that the resources are always built (and rendered) in the same order
+    for (rolegroup_name, rolegroup_config) in role_server_config.iter().collect::<BTreeMap<_, _>>()
+    {
         let rolegroup = RoleGroupRef {
             cluster: opa_ref.clone(),
             role: opa_role.to_string(),
             role_group: rolegroup_name.to_string(),
         };
 
-        let merged_config = opa
+        let mut merged_config = opa
             .merged_config(&opa_role, &rolegroup)
             .context(FailedToResolveConfigSnafu)?;
+        if opa.debug_merged_config() {
+            merged_config_fragments.insert(
+                rolegroup_name.clone(),
+                opa.merged_config_fragment(&opa_role, &rolegroup)
+                    .context(FailedToResolveConfigSnafu)?,
+            );
+        }
+        opa_version
+            .validate(
+                &merged_config,
+                opa.spec
+                    .cluster_config
+                    .bundle_polling
+                    .long_polling_timeout
+                    .is_some(),
+            )
+            .context(UnsupportedOpaVersionSnafu {
+                rolegroup: rolegroup.clone(),
+            })?;
+        if let Some(warning) = clamp_graceful_shutdown_timeout(&mut merged_config) {
+            tracing::warn!(%rolegroup, warning, "invalid graceful shutdown timeout");
+            warnings.push(format!("role group {rolegroup_name}: {warning}"));
+        }
+        if let Some(warning) = check_internal_traffic_policy(opa, &merged_config) {
+            tracing::warn!(%rolegroup, warning, "role service might be unreachable");
+            warnings.push(format!("role group {rolegroup_name}: {warning}"));
+        }
 
-        let rg_configmap = build_server_rolegroup_config_map(
+        let config_map = build_server_rolegroup_config_map(
             opa,
             &resolved_product_image,
             &rolegroup,
+            rolegroup_config,
             &merged_config,
-            vector_aggregator_address.as_deref(),
+            &opa_version,
+            ctx.vector_aggregator_address,
         )?;
-        let rg_service = build_rolegroup_service(opa, &resolved_product_image, &rolegroup)?;
-        let rg_daemonset = build_server_rolegroup_daemonset(
+        let service = build_rolegroup_service(opa, &resolved_product_image, &rolegroup)?;
+        let mut daemonset = build_server_rolegroup_daemonset(
             opa,
             &resolved_product_image,
             &opa_role,
             &rolegroup,
             rolegroup_config,
             &merged_config,
-            &ctx.opa_bundle_builder_image,
-            &ctx.user_info_fetcher_image,
-            &rbac_sa,
+            &opa_version,
+            ctx.opa_bundle_builder_image,
+            ctx.user_info_fetcher_image,
+            ctx.native_sidecars,
+            ctx.bundle_excluded_namespaces,
+            &service_account,
         )?;
-
-        cluster_resources
-            .add(client, rg_configmap)
-            .await
-            .with_context(|_| ApplyRoleGroupConfigSnafu {
-                rolegroup: rolegroup.clone(),
-            })?;
-        cluster_resources
-            .add(client, rg_service)
-            .await
-            .with_context(|_| ApplyRoleGroupServiceSnafu {
-                rolegroup: rolegroup.clone(),
-            })?;
-        ds_cond_builder.add(
-            cluster_resources
-                .add(client, rg_daemonset.clone())
-                .await
-                .with_context(|_| ApplyRoleGroupDaemonSetSnafu {
-                    rolegroup: rolegroup.clone(),
-                })?,
-        );
-
-        // Previous version of opa-operator used the field manager scope "opacluster" to write out a DaemonSet with the bundle-builder container called "opa-bundle-builder".
-        // During https://github.com/stackabletech/opa-operator/pull/420 it was renamed to "bundle-builder".
-        // As we are now using the field manager scope "opa.stackable.tech_opacluster", our old changes (with the old container) will stay valid.
-        // We have to use the old field manager scope and post an empty path to get rid of it
-        // https://github.com/stackabletech/issues/issues/390 will implement a proper fix, e.g. also fixing Services and ConfigMaps
-        // For details see https://github.com/stackabletech/opa-operator/issues/444
-        tracing::trace!(
-            "Removing old field manager scope \"opacluster\" of DaemonSet {daemonset_name} to remove the \"opa-bundle-builder\" container. \
-            See https://github.com/stackabletech/opa-operator/issues/444 and https://github.com/stackabletech/issues/issues/390 for details.",
-            daemonset_name = rg_daemonset.name_any()
-        );
-        client
-            .apply_patch(
-                "opacluster",
-                &rg_daemonset,
-                // We can hardcode this here, as https://github.com/stackabletech/issues/issues/390 will solve the general problem and we always have created DaemonSets using the "apps/v1" version
-                json!({"apiVersion": "apps/v1", "kind": "DaemonSet"}),
-            )
-            .await
-            .context(ApplyPatchRoleGroupDaemonSetSnafu { rolegroup })?;
+        if opa.spec.cluster_operation.stopped {
+            stop_daemonset(&mut daemonset);
+        }
+        role_groups.push(RoleGroupResources {
+            rolegroup,
+            config_map,
+            service,
+            daemonset,
+        });
     }
 
-    for discovery_cm in build_discovery_configmaps(
+    // Not building the ConfigMap deletes it once the annotation has been removed
+    let merged_config_config_map = if opa.debug_merged_config() {
+        Some(build_merged_config_config_map(
+            opa,
+            &resolved_product_image,
+            &merged_config_fragments,
+        )?)
+    } else {
+        None
+    };
+
+    let discovery_config_maps = build_discovery_configmaps(
         opa,
         opa,
         &resolved_product_image,
         &server_role_service,
-        &client.kubernetes_cluster_info,
+        ctx.cluster_info,
     )
-    .context(BuildDiscoveryConfigSnafu)?
-    {
-        cluster_resources
-            .add(client, discovery_cm)
-            .await
-            .context(ApplyDiscoveryConfigSnafu)?;
-    }
-
+    .context(BuildDiscoveryConfigSnafu)?;
+
+    Ok(OpaClusterResources {
+        resolved_product_image,
+        server_role_service,
+        bundle_config_maps,
+        regorule_library_config_map,
+        stable_bundle_config_map,
+        service_account,
+        role_binding,
+        role_groups,
+        merged_config_config_map,
+        discovery_config_maps,
+        warnings,
+    })
+}
+
+/// Records every object applied for an OpaCluster for the field ownership audit and the metrics.
+struct ResourceApplier<'a> {
+    client: &'a stackable_operator::client::Client,
+    metrics: &'a PrometheusMetrics,
+    field_ownership_audit: FieldOwnershipAudit,
+}
+
+impl ResourceApplier<'_> {
+    /// Applies `resource` as part of the `cluster_resources`, see [`ClusterResources::add`].
+    async fn add<T: ClusterResource + Sync>(
+        &mut self,
+        cluster_resources: &mut ClusterResources,
+        resource: T,
+    ) -> Result<T, stackable_operator::cluster_resources::Error> {
+        let applied = cluster_resources.add(self.client, resource).await?;
+        self.record(&applied);
+        Ok(applied)
+    }
+
+    /// Records an object that was applied without the [`ClusterResources`].
+    fn record<T: KubeResource<DynamicType = ()>>(&mut self, applied: &T) {
+        self.field_ownership_audit.record(applied);
+        self.metrics
+            .record_applied_resource(OPA_FULL_CONTROLLER_NAME, applied);
+    }
+}
+
+pub async fn reconcile_opa(
+    opa: Arc<DeserializeGuard<OpaCluster>>,
+    ctx: Arc<Ctx>,
+) -> Result<Action> {
+    tracing::info!("Starting reconcile");
+    let opa = opa
+        .0
+        .as_ref()
+        .map_err(error_boundary::InvalidObject::clone)
+        .context(InvalidOpaClusterSnafu)?;
+    let opa_ref = ObjectRef::from_obj(opa);
+
+    let client = &ctx.client;
+    let opa_role = OpaRole::Server;
+
+    let mut cluster_resources = ClusterResources::new(
+        APP_NAME,
+        OPERATOR_NAME,
+        OPA_CONTROLLER_NAME,
+        &opa.object_ref(&()),
+        ClusterResourceApplyStrategy::from(&opa.spec.cluster_operation),
+    )
+    .context(FailedToCreateClusterResourcesSnafu)?;
+
+    let vector_aggregator_address = resolve_vector_aggregator_address(opa, client)
+        .await
+        .context(ResolveVectorAggregatorAddressSnafu)?;
+
+    let OpaClusterResources {
+        resolved_product_image,
+        server_role_service,
+        bundle_config_maps,
+        regorule_library_config_map,
+        stable_bundle_config_map,
+        service_account,
+        role_binding,
+        role_groups,
+        merged_config_config_map,
+        discovery_config_maps,
+        mut warnings,
+    } = build_resources(
+        opa,
+        &cluster_resources,
+        &BuildContext {
+            product_config: &ctx.product_config,
+            cluster_info: &client.kubernetes_cluster_info,
+            vector_aggregator_address: vector_aggregator_address.as_deref(),
+            opa_bundle_builder_image: &ctx.opa_bundle_builder_image,
+            user_info_fetcher_image: &ctx.user_info_fetcher_image,
+            native_sidecars: ctx.native_sidecars,
+            bundle_excluded_namespaces: &ctx.bundle_excluded_namespaces,
+        },
+    )?;
+
+    let mut applier = ResourceApplier {
+        client,
+        metrics: &ctx.metrics,
+        field_ownership_audit: FieldOwnershipAudit::new(
+            opa.spec.cluster_config.field_ownership_audit,
+        ),
+    };
+
+    let server_role_service = applier
+        .add(&mut cluster_resources, server_role_service)
+        .await
+        .context(ApplyRoleServiceSnafu)?;
+    if let Some(http_route) = reconcile_http_route(
+        client,
+        opa,
+        &resolved_product_image,
+        &server_role_service.name_any(),
+    )
+    .await
+    .context(GatewayRouteSnafu)?
+    {
+        applier.record(&http_route);
+    }
+    if let Some(network_policy) = reconcile_network_policy(client, opa, &resolved_product_image)
+        .await
+        .context(NetworkPolicySnafu)?
+    {
+        applier.record(&network_policy);
+    }
+
+    let mut bundle_snapshot_config_map = None;
+    if let Some((snapshot_config_map, delivery_config_map)) = bundle_config_maps {
+        let snapshot_config_map = applier
+            .add(&mut cluster_resources, snapshot_config_map)
+            .await
+            .context(ApplyBundleSnapshotConfigSnafu)?;
+        applier
+            .add(&mut cluster_resources, delivery_config_map)
+            .await
+            .context(ApplyBundleDeliveryConfigSnafu)?;
+        bundle_snapshot_config_map = Some(snapshot_config_map);
+    }
+    if let Some(regorule_library_config_map) = regorule_library_config_map {
+        applier
+            .add(&mut cluster_resources, regorule_library_config_map)
+            .await
+            .context(ApplyRegoruleLibraryConfigSnafu)?;
+    }
+    // Removed as an orphan once no node labels are configured
+    if !opa.spec.cluster_config.node_labels.is_empty() {
+        let nodes = Api::<Node>::all(client.as_kube_client())
+            .list_metadata(&ListParams::default())
+            .await
+            .context(ListNodesSnafu)?;
+        applier
+            .add(
+                &mut cluster_resources,
+                build_node_labels_config_map(opa, &resolved_product_image, &nodes.items)?,
+            )
+            .await
+            .context(ApplyNodeLabelsConfigSnafu)?;
+    }
+    let stable_bundle_config_map = match stable_bundle_config_map {
+        Some(stable_bundle_config_map) => Some(
+            applier
+                .add(&mut cluster_resources, stable_bundle_config_map)
+                .await
+                .context(ApplyStableBundleConfigSnafu)?,
+        ),
+        None => None,
+    };
+
+    let rbac_sa = applier
+        .add(&mut cluster_resources, service_account)
+        .await
+        .context(ApplyServiceAccountSnafu)?;
+    applier
+        .add(&mut cluster_resources, role_binding)
+        .await
+        .context(ApplyRoleBindingSnafu)?;
+    if bundle_snapshot_config_map.is_some() {
+        let (bundle_builder_role, bundle_builder_role_binding) =
+            reconcile_bundle_builder_rbac(client, opa, &resolved_product_image, &rbac_sa)
+                .await
+                .context(BundleBuilderRbacSnafu)?;
+        applier.record(&bundle_builder_role);
+        applier.record(&bundle_builder_role_binding);
+    }
+
+    let mut ds_cond_builder = DaemonSetConditionBuilder::default();
+    let mut ready_role_groups = 0;
+    if let Some(namespace) = opa
+        .namespace()
+        .filter(|namespace| ctx.bundle_excluded_namespaces.contains(namespace))
+    {
+        warnings.push(format!(
+            "namespace {namespace:?} is excluded from bundles by the operator, so the bundle \
+            builders ignore all bundle ConfigMaps of this OpaCluster"
+        ));
+    }
+    if let Some(snapshot_error) = bundle_snapshot_config_map
+        .as_ref()
+        .and_then(|cm| cm.annotations().get(BUNDLE_SNAPSHOT_ERROR_ANNOTATION))
+    {
+        warnings.push(format!(
+            "the bundle has not been snapshotted: {snapshot_error}"
+        ));
+    }
+
+    let total_role_groups = role_groups.len();
+    for RoleGroupResources {
+        rolegroup,
+        config_map: rg_configmap,
+        service: rg_service,
+        daemonset: mut rg_daemonset,
+    } in role_groups
+    {
+        // Only the DaemonSet itself is annotated, so that policy changes don't restart the Pods
+        let served_bundle_config_map = if opa.serves_stable_bundle(&rolegroup.role_group) {
+            stable_bundle_config_map.as_ref()
+        } else {
+            bundle_snapshot_config_map.as_ref()
+        };
+        if let Some(revision) = served_bundle_config_map.and_then(bundle_revision) {
+            rg_daemonset
+                .annotations_mut()
+                .insert(BUNDLE_REVISION_ANNOTATION.to_string(), revision.clone());
+        }
+
+        applier
+            .add(&mut cluster_resources, rg_configmap)
+            .await
+            .with_context(|_| ApplyRoleGroupConfigSnafu {
+                rolegroup: rolegroup.clone(),
+            })?;
+        applier
+            .add(&mut cluster_resources, rg_service)
+            .await
+            .with_context(|_| ApplyRoleGroupServiceSnafu {
+                rolegroup: rolegroup.clone(),
+            })?;
+        let applied_daemonset = applier
+            .add(&mut cluster_resources, rg_daemonset)
+            .await
+            .with_context(|_| ApplyRoleGroupDaemonSetSnafu {
+                rolegroup: rolegroup.clone(),
+            })?;
+        // Previous versions of the operator applied the DaemonSet with the field manager
+        // "opacluster", whose fields (such as the renamed "opa-bundle-builder" container) would
+        // otherwise never be removed, see https://github.com/stackabletech/opa-operator/issues/444
+        release_legacy_field_managers(client, &applied_daemonset)
+            .await
+            .context(ApplyPatchRoleGroupDaemonSetSnafu { rolegroup })?;
+        // A stopped DaemonSet has no Pods, which would otherwise count as ready (and available)
+        if !opa.spec.cluster_operation.stopped {
+            if daemonset_is_ready(&applied_daemonset) {
+                ready_role_groups += 1;
+            }
+            ds_cond_builder.add(applied_daemonset);
+        }
+    }
+
+    if let Some(merged_config_config_map) = merged_config_config_map {
+        applier
+            .add(&mut cluster_resources, merged_config_config_map)
+            .await
+            .context(ApplyMergedConfigSnafu)?;
+    }
+
+    let mut consumers = BTreeSet::new();
+    if let Some(pdb) = &opa.spec.cluster_config.pod_disruption_budget {
+        add_pdbs(pdb, opa, &opa_role, client, &mut cluster_resources)
+            .await
+            .context(FailedToCreatePdbSnafu)?;
+    }
+
+    for discovery_cm in discovery_config_maps {
+        let discovery_cm = applier
+            .add(&mut cluster_resources, discovery_cm)
+            .await
+            .context(ApplyDiscoveryConfigSnafu)?;
+        consumers.extend(discovery::consumers(&discovery_cm));
+    }
+
+    let canary_rollout = match &stable_bundle_config_map {
+        Some(stable_bundle_config_map) => reconcile_canary_rollout(
+            opa,
+            &resolved_product_image,
+            stable_bundle_config_map,
+            client,
+            &mut cluster_resources,
+        )
+        .await
+        .context(CanaryRolloutSnafu)?,
+        None => CanaryRollout::default(),
+    };
+
+    let stale_discovery_config_maps = discovery::clean_up_stale_discovery_configmaps(opa, client)
+        .await
+        .context(CleanUpStaleDiscoveryConfigSnafu)?;
+
     let cluster_operation_cond_builder =
         ClusterOperationsConditionBuilder::new(&opa.spec.cluster_operation);
 
+    // The spec contains HashMaps, which serialize in random order. Going through a Value sorts the
+    // keys of all objects, so that the hash only changes if the spec does.
+    let canonical_spec = serde_json::to_value(&opa.spec).context(SerializeSpecSnafu {
+        opa: opa_ref.clone(),
+    })?;
+    let spec_hash = format!(
+        "{:x}",
+        Sha256::digest(canonical_spec.to_string().as_bytes())
+    );
+    let deployed_product_version = resolved_product_image.product_version.clone();
+    let previous_status = opa.status.as_ref();
+    // Only bump the update time when something changed, otherwise every status patch would
+    // trigger another reconciliation
+    let last_update_time = match previous_status {
+        Some(OpaClusterStatus {
+            last_update_time: Some(last_update_time),
+            spec_hash: Some(previous_spec_hash),
+            deployed_product_version: Some(previous_product_version),
+            ..
+        }) if *previous_spec_hash == spec_hash
+            && *previous_product_version == deployed_product_version =>
+        {
+            last_update_time.clone()
+        }
+        _ => Time(Utc::now()),
+    };
+
     let status = OpaClusterStatus {
         conditions: compute_conditions(opa, &[&ds_cond_builder, &cluster_operation_cond_builder]),
+        last_update_time: Some(last_update_time),
+        spec_hash: Some(spec_hash),
+        deployed_product_version: Some(deployed_product_version),
+        ready_role_groups: Some(format!("{ready_role_groups}/{total_role_groups}")),
+        bundle_revision: bundle_snapshot_config_map
+            .as_ref()
+            .and_then(bundle_revision)
+            .cloned(),
+        consumers: consumers.into_iter().collect(),
+        canary: canary_rollout.status,
+        policy_tests: canary_rollout.policy_tests,
+        stale_discovery_config_maps,
+        warnings,
+        field_ownership: applier.field_ownership_audit.into_status(),
     };
 
     client
@@ -575,7 +1580,20 @@ pub async fn reconcile_opa(
         .await
         .context(DeleteOrphansSnafu)?;
 
-    Ok(Action::await_change())
+    ctx.failed_reconciles.lock().unwrap().remove(&opa_ref);
+
+    // Soaking bundles are promoted once the soak period has passed
+    Ok(canary_rollout
+        .requeue_after
+        .map_or_else(Action::await_change, Action::requeue))
+}
+
+/// Whether all Pods of the DaemonSet are ready and run its current revision.
+fn daemonset_is_ready(daemonset: &DaemonSet) -> bool {
+    daemonset.status.as_ref().is_some_and(|status| {
+        status.number_ready == status.desired_number_scheduled
+            && status.updated_number_scheduled == Some(status.desired_number_scheduled)
+    })
 }
 
 /// The server-role service is the primary endpoint that should be used by clients that do not perform internal load balancing,
@@ -615,7 +1633,20 @@ pub fn build_server_role_service(
             ..ServicePort::default()
         }]),
         selector: Some(service_selector_labels.into()),
-        internal_traffic_policy: Some("Local".to_string()),
+        internal_traffic_policy: Some(
+            opa.spec
+                .cluster_config
+                .role_service
+                .internal_traffic_policy
+                .to_string(),
+        ),
+        session_affinity: Some(
+            opa.spec
+                .cluster_config
+                .role_service
+                .session_affinity
+                .to_string(),
+        ),
         ..ServiceSpec::default()
     };
 
@@ -626,6 +1657,23 @@ pub fn build_server_role_service(
     })
 }
 
+/// Warns if the role service only routes to OPA instances on the same node, while the affinity of the
+/// role group might keep OPA off some nodes.
+fn check_internal_traffic_policy(opa: &OpaCluster, merged_config: &OpaConfig) -> Option<String> {
+    let affinity = &merged_config.affinity;
+    let restricts_nodes = affinity.node_affinity.is_some()
+        || affinity.node_selector.is_some()
+        || !merged_config.node_selector.0.is_empty();
+    (opa.spec.cluster_config.role_service.internal_traffic_policy
+        == OpaInternalTrafficPolicy::Local
+        && restricts_nodes)
+        .then(|| {
+            "the affinity restricts the nodes OPA runs on, but the internalTrafficPolicy of the role \
+            service is Local, so clients on other nodes can't reach OPA"
+                .to_string()
+        })
+}
+
 /// The rolegroup [`Service`] is a headless service that allows direct access to the instances of a certain rolegroup
 ///
 /// This is mostly useful for internal communication between peers, or for clients that perform client-side load balancing.
@@ -637,7 +1685,8 @@ fn build_rolegroup_service(
     let prometheus_label =
         Label::try_from(("prometheus.io/scrape", "true")).context(BuildLabelSnafu)?;
 
-    let metadata = ObjectMetaBuilder::new()
+    let mut metadata = ObjectMetaBuilder::new();
+    metadata
         .name_and_namespace(opa)
         .name(rolegroup.object_name())
         .ownerreference_from_resource(opa, None, Some(true))
@@ -649,8 +1698,24 @@ fn build_rolegroup_service(
             &rolegroup.role_group,
         ))
         .context(ObjectMetaSnafu)?
-        .with_label(prometheus_label)
-        .build();
+        .with_label(prometheus_label);
+
+    // Expose the metrics labels as annotations, so that Prometheus can use them as target labels when relabeling
+    for (key, value) in &opa.spec.cluster_config.metrics.labels {
+        let annotation = Annotation::try_from((
+            format!("{METRICS_LABEL_ANNOTATION_PREFIX}{key}").as_str(),
+            value.as_str(),
+        ))
+        .with_context(|_| BuildMetricsLabelAnnotationSnafu { key })?;
+        metadata.with_annotation(annotation);
+    }
+    if let Some(name_prefix) = &opa.spec.cluster_config.metrics.name_prefix {
+        let annotation =
+            Annotation::try_from((METRICS_NAME_PREFIX_ANNOTATION, name_prefix.as_str()))
+                .context(BuildMetricsNamePrefixAnnotationSnafu)?;
+        metadata.with_annotation(annotation);
+    }
+    let metadata = metadata.build();
 
     let service_selector_labels =
         Labels::role_group_selector(opa, APP_NAME, &rolegroup.role, &rolegroup.role_group)
@@ -673,12 +1738,127 @@ fn build_rolegroup_service(
     })
 }
 
+/// A [`ConfigMap`] that a bundle is stored in, either the snapshot of the last served bundle
+/// (which is served after the OpaCluster was stopped and resumed until the first bundle has been
+/// built) or the stable bundle promoted by a canary rollout. The bundle builders also report the
+/// bundles delivered to OPA in such a ConfigMap.
+///
+/// The bundle itself is not managed here, so that applying the ConfigMap keeps it.
+fn build_bundle_config_map(
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    name: String,
+) -> Result<ConfigMap> {
+    let metadata = ObjectMetaBuilder::new()
+        .name_and_namespace(opa)
+        .name(name)
+        .ownerreference_from_resource(opa, None, Some(true))
+        .context(ObjectMissingMetadataForOwnerRefSnafu)?
+        .with_recommended_labels(build_recommended_labels(
+            opa,
+            &resolved_product_image.app_version_label,
+            &OpaRole::Server.to_string(),
+            "global",
+        ))
+        .context(ObjectMetaSnafu)?
+        .build();
+
+    Ok(ConfigMap {
+        metadata,
+        ..ConfigMap::default()
+    })
+}
+
+/// The bundle ConfigMap containing the regorule library of the operator, which is restricted to the
+/// OpaCluster.
+fn build_regorule_library_config_map(
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+) -> Result<ConfigMap> {
+    let metadata = ObjectMetaBuilder::new()
+        .name_and_namespace(opa)
+        .name(opa.regorule_library_config_map_name())
+        .ownerreference_from_resource(opa, None, Some(true))
+        .context(ObjectMissingMetadataForOwnerRefSnafu)?
+        .with_recommended_labels(build_recommended_labels(
+            opa,
+            &resolved_product_image.app_version_label,
+            &OpaRole::Server.to_string(),
+            "global",
+        ))
+        .context(ObjectMetaSnafu)?
+        .with_label(Label::try_from((BUNDLE_LABEL, "true")).context(BuildLabelSnafu)?)
+        .with_label(
+            Label::try_from((BUNDLE_CLUSTER_LABEL, opa.name_any().as_str()))
+                .context(BuildLabelSnafu)?,
+        )
+        .with_annotation(
+            Annotation::try_from((
+                REGORULE_LIBRARY_VERSION_ANNOTATION,
+                stackable_opa_regorule_library::VERSION,
+            ))
+            .context(BuildRegoruleLibraryAnnotationSnafu)?,
+        )
+        .build();
+
+    let mut cm_builder = ConfigMapBuilder::new();
+    cm_builder.metadata(metadata);
+    for (file_path, rego) in stackable_opa_regorule_library::REGORULES {
+        // ConfigMap keys can not contain slashes, the bundle builder only relies on the package
+        // declared in the file anyway
+        cm_builder.add_data(file_path.replace('/', "."), *rego);
+    }
+    cm_builder.build().context(BuildRegoruleLibraryConfigSnafu)
+}
+
+/// Lists the merged configuration of every role group (as `<role group>.yaml`), so that users can see
+/// which values won the merge of the defaults, role and role group configuration.
+///
+/// Corrections made by the operator afterwards (such as clamping the graceful shutdown timeout) are
+/// reported as warnings in the status instead.
+fn build_merged_config_config_map(
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    merged_config_fragments: &BTreeMap<String, OpaConfigFragment>,
+) -> Result<ConfigMap> {
+    let data = merged_config_fragments
+        .iter()
+        .map(|(role_group, merged_config)| {
+            Ok((
+                format!("{role_group}.yaml"),
+                serde_yaml::to_string(merged_config)
+                    .context(SerializeMergedConfigSnafu { role_group })?,
+            ))
+        })
+        .collect::<Result<_>>()?;
+    let metadata = ObjectMetaBuilder::new()
+        .name_and_namespace(opa)
+        .name(opa.merged_config_config_map_name())
+        .ownerreference_from_resource(opa, None, Some(true))
+        .context(ObjectMissingMetadataForOwnerRefSnafu)?
+        .with_recommended_labels(build_recommended_labels(
+            opa,
+            &resolved_product_image.app_version_label,
+            &OpaRole::Server.to_string(),
+            "global",
+        ))
+        .context(ObjectMetaSnafu)?
+        .build();
+    Ok(ConfigMap {
+        metadata,
+        data: Some(data),
+        ..ConfigMap::default()
+    })
+}
+
 /// The rolegroup [`ConfigMap`] configures the rolegroup based on the configuration given by the administrator
 fn build_server_rolegroup_config_map(
     opa: &OpaCluster,
     resolved_product_image: &ResolvedProductImage,
     rolegroup: &RoleGroupRef<OpaCluster>,
+    rolegroup_config: &HashMap<PropertyNameKind, BTreeMap<String, String>>,
     merged_config: &OpaConfig,
+    opa_version: &OpaVersion,
     vector_aggregator_address: Option<&str>,
 ) -> Result<ConfigMap> {
     let mut cm_builder = ConfigMapBuilder::new();
@@ -697,9 +1877,16 @@ fn build_server_rolegroup_config_map(
         .context(ObjectMetaSnafu)?
         .build();
 
-    cm_builder
-        .metadata(metadata)
-        .add_data(CONFIG_FILE, build_config_file(merged_config));
+    cm_builder.metadata(metadata).add_data(
+        CONFIG_FILE,
+        build_config_file(
+            opa,
+            &rolegroup.role_group,
+            merged_config,
+            opa_version,
+            rolegroup_config.get(&PropertyNameKind::File(CONFIG_FILE.to_string())),
+        ),
+    );
 
     if let Some(user_info) = &opa.spec.cluster_config.user_info {
         cm_builder.add_data(
@@ -708,6 +1895,16 @@ fn build_server_rolegroup_config_map(
         );
     }
 
+    // Only takes effect if the bundle builder and user-info-fetcher are built with fault injection
+    let fault_injection_annotations = fault_injection_annotations(opa.annotations());
+    if !fault_injection_annotations.is_empty() {
+        cm_builder.add_data(
+            FAULT_INJECTION_FILE_NAME,
+            serde_json::to_string_pretty(&fault_injection_annotations)
+                .context(SerializeFaultInjectionConfigSnafu)?,
+        );
+    }
+
     extend_role_group_config_map(
         rolegroup,
         vector_aggregator_address,
@@ -740,8 +1937,11 @@ fn build_server_rolegroup_daemonset(
     rolegroup_ref: &RoleGroupRef<OpaCluster>,
     server_config: &HashMap<PropertyNameKind, BTreeMap<String, String>>,
     merged_config: &OpaConfig,
+    opa_version: &OpaVersion,
     opa_bundle_builder_image: &str,
     user_info_fetcher_image: &str,
+    native_sidecars: bool,
+    bundle_excluded_namespaces: &[String],
     service_account: &ServiceAccount,
 ) -> Result<DaemonSet> {
     let role = opa.role(opa_role);
@@ -792,11 +1992,15 @@ fn build_server_rolegroup_daemonset(
         .context(AddVolumeMountSnafu)?
         .add_volume_mount(LOG_VOLUME_NAME, STACKABLE_LOG_DIR)
         .context(AddVolumeMountSnafu)?
-        .resources(merged_config.resources.to_owned().into());
+        .resources(merged_config.prepare.resources.to_owned().into());
 
     cb_bundle_builder
         .image_from_product_image(resolved_product_image) // inherit the pull policy and pull secrets, and then...
-        .image(opa_bundle_builder_image) // ...override the image
+        .image(sidecar_image(
+            opa.spec.cluster_config.images.bundle_builder.as_ref(),
+            "bundleBuilder",
+            opa_bundle_builder_image,
+        )?) // ...override the image
         .command(vec![
             "/bin/bash".to_string(),
             "-x".to_string(),
@@ -809,6 +2013,29 @@ fn build_server_rolegroup_daemonset(
             &bundle_builder_container_name,
         )])
         .add_env_var_from_field_path("WATCH_NAMESPACE", FieldPathEnvVar::Namespace)
+        .add_env_var("CLUSTER_NAME", opa.name_any())
+        // The library of the operator is added to the bundle as a ConfigMap instead
+        .add_env_var(
+            "INCLUDE_BUILTIN_REGO_RULES",
+            (opa.spec.cluster_config.include_builtin_rego_rules && !opa.manages_regorule_library())
+                .to_string(),
+        )
+        .add_env_var(
+            "BUNDLE_SNAPSHOT_CONFIG_MAP",
+            opa.bundle_snapshot_config_map_name(),
+        )
+        // Together with the node name, the role group identifies the report of the delivered bundle
+        .add_env_var(
+            "BUNDLE_DELIVERY_CONFIG_MAP",
+            opa.bundle_delivery_config_map_name(),
+        )
+        .add_env_var("ROLE_GROUP", &rolegroup_ref.role_group)
+        .add_env_vars(pod_and_node_name_env_vars())
+        .add_env_var(
+            "BUNDLE_CACHE_FILE",
+            format!("{BUNDLES_DIR}/bundle-builder-cache.tar.gz"),
+        )
+        .add_env_var("PREPARE_BUNDLES_DIR", BUNDLES_DIR)
         .add_env_var(
             "OPA_BUNDLE_BUILDER_LOG",
             bundle_builder_log_level(merged_config).to_string(),
@@ -817,20 +2044,33 @@ fn build_server_rolegroup_daemonset(
             "OPA_BUNDLE_BUILDER_LOG_DIRECTORY",
             format!("{STACKABLE_LOG_DIR}/{bundle_builder_container_name}"),
         )
+        .add_env_var(
+            "FAULT_INJECTION_FILE",
+            format!("{CONFIG_DIR}/{FAULT_INJECTION_FILE_NAME}"),
+        )
+        .add_env_var(
+            "DECISION_LOG_FILE",
+            format!("{STACKABLE_LOG_DIR}/{opa_container_name}/{DECISION_LOG_FILE_NAME}"),
+        )
         .add_volume_mount(BUNDLES_VOLUME_NAME, BUNDLES_DIR)
         .context(AddVolumeMountSnafu)?
+        .add_volume_mount(CONFIG_VOLUME_NAME, CONFIG_DIR)
+        .context(AddVolumeMountSnafu)?
         .add_volume_mount(LOG_VOLUME_NAME, STACKABLE_LOG_DIR)
         .context(AddVolumeMountSnafu)?
-        .resources(
-            ResourceRequirementsBuilder::new()
-                .with_cpu_request("100m")
-                .with_cpu_limit("200m")
-                .with_memory_request("128Mi")
-                .with_memory_limit("128Mi")
-                .build(),
-        )
+        .resources(merged_config.bundle_builder.resources.to_owned().into())
+        // As a native sidecar, OPA is only started once the bundle builder has started up
+        .startup_probe(Probe {
+            period_seconds: Some(2),
+            failure_threshold: Some(30),
+            http_get: Some(HTTPGetAction {
+                port: IntOrString::Int(BUNDLE_BUILDER_PORT),
+                path: Some("/status".to_string()),
+                ..HTTPGetAction::default()
+            }),
+            ..Probe::default()
+        })
         .readiness_probe(Probe {
-            initial_delay_seconds: Some(5),
             period_seconds: Some(10),
             failure_threshold: Some(5),
             http_get: Some(HTTPGetAction {
@@ -841,7 +2081,6 @@ fn build_server_rolegroup_daemonset(
             ..Probe::default()
         })
         .liveness_probe(Probe {
-            initial_delay_seconds: Some(30),
             period_seconds: Some(10),
             http_get: Some(HTTPGetAction {
                 port: IntOrString::Int(BUNDLE_BUILDER_PORT),
@@ -850,6 +2089,19 @@ fn build_server_rolegroup_daemonset(
             }),
             ..Probe::default()
         });
+    let bundle_polling = &opa.spec.cluster_config.bundle_polling;
+    if let Some(max_concurrent_downloads) = bundle_polling.max_concurrent_downloads {
+        cb_bundle_builder.add_env_var(
+            "MAX_CONCURRENT_BUNDLE_DOWNLOADS",
+            max_concurrent_downloads.to_string(),
+        );
+    }
+    if let Some(long_polling_jitter) = bundle_polling.long_polling_jitter {
+        cb_bundle_builder.add_env_var("LONG_POLLING_JITTER", long_polling_jitter.to_string());
+    }
+    if !bundle_excluded_namespaces.is_empty() {
+        cb_bundle_builder.add_env_var("EXCLUDED_NAMESPACES", bundle_excluded_namespaces.join(","));
+    }
 
     cb_opa
         .image_from_product_image(resolved_product_image)
@@ -861,7 +2113,9 @@ fn build_server_rolegroup_daemonset(
             "-c".to_string(),
         ])
         .args(vec![build_opa_start_command(
+            opa,
             merged_config,
+            opa_version,
             &opa_container_name,
         )])
         .add_env_vars(env)
@@ -875,25 +2129,264 @@ fn build_server_rolegroup_daemonset(
         .add_volume_mount(LOG_VOLUME_NAME, STACKABLE_LOG_DIR)
         .context(AddVolumeMountSnafu)?
         .resources(merged_config.resources.to_owned().into())
+        // Like the liveness probe, the startup probe does not wait for the bundles, so that OPA is
+        // not restarted in a loop while they can't be activated
+        .startup_probe(Probe {
+            period_seconds: Some(OPA_STARTUP_PROBE_PERIOD_SECONDS),
+            failure_threshold: Some(opa_startup_probe_failure_threshold(
+                merged_config.probes.startup_timeout,
+            )),
+            ..opa_probe_action(&merged_config.bind_address, OPA_LIVENESS_PROBE_PATH)
+        })
         .readiness_probe(Probe {
-            initial_delay_seconds: Some(5),
             period_seconds: Some(10),
             failure_threshold: Some(5),
-            http_get: Some(HTTPGetAction {
-                port: IntOrString::String(APP_PORT_NAME.to_string()),
-                ..HTTPGetAction::default()
-            }),
-            ..Probe::default()
+            ..opa_probe_action(
+                &merged_config.bind_address,
+                &merged_config.probes.readiness_path,
+            )
         })
+        // Only checks that OPA is running, so that OPA is not restarted while the bundle builder
+        // is unavailable
         .liveness_probe(Probe {
-            initial_delay_seconds: Some(30),
             period_seconds: Some(10),
-            http_get: Some(HTTPGetAction {
-                port: IntOrString::String(APP_PORT_NAME.to_string()),
-                ..HTTPGetAction::default()
+            ..opa_probe_action(&merged_config.bind_address, OPA_LIVENESS_PROBE_PATH)
+        });
+
+    let user_info_server_tls = opa
+        .spec
+        .cluster_config
+        .user_info
+        .as_ref()
+        .and_then(|user_info| user_info.server_tls.as_ref());
+    if merged_config.bind_address == OpaBindAddress::PodIp || user_info_server_tls.is_some() {
+        cb_opa.add_env_vars(vec![EnvVar {
+            name: "POD_IP".to_string(),
+            value_from: Some(EnvVarSource {
+                field_ref: Some(ObjectFieldSelector {
+                    field_path: "status.podIP".to_string(),
+                    ..ObjectFieldSelector::default()
+                }),
+                ..EnvVarSource::default()
             }),
-            ..Probe::default()
+            ..EnvVar::default()
+        }]);
+    }
+
+    // Substituted into the headers OPA identifies itself to the bundle builder with
+    cb_opa.add_env_vars(pod_and_node_name_env_vars());
+
+    if user_info_server_tls.is_some() {
+        // The regorule library reads these to reach the user-info-fetcher over HTTPS.
+        // The certificate is issued for the Pod IP, as 127.0.0.1 is not part of it.
+        cb_opa
+            .add_volume_mount(
+                USER_INFO_FETCHER_SERVER_TLS_VOLUME_NAME,
+                USER_INFO_FETCHER_SERVER_TLS_DIR,
+            )
+            .context(AddVolumeMountSnafu)?
+            .add_env_var(
+                "USER_INFO_FETCHER_URL",
+                format!("https://[$(POD_IP)]:{USER_INFO_FETCHER_PORT}"),
+            )
+            .add_env_var(
+                "USER_INFO_FETCHER_CA_CERT_FILE",
+                format!("{USER_INFO_FETCHER_SERVER_TLS_DIR}/ca.crt"),
+            );
+    } else if opa.spec.cluster_config.user_info.is_some() {
+        cb_opa.add_env_var(
+            "USER_INFO_FETCHER_URL",
+            format!("http://127.0.0.1:{USER_INFO_FETCHER_PORT}"),
+        );
+    }
+
+    if let Some(trust_store) = &opa.spec.cluster_config.trust_store {
+        pb.add_volume(build_trust_store_volume(trust_store)?)
+            .context(AddVolumeSnafu)?;
+        // Go reads the CA certificates from all files in these directories, in addition to the
+        // system CA bundle
+        cb_opa
+            .add_volume_mount(TRUST_STORE_VOLUME_NAME, TRUST_STORE_DIR)
+            .context(AddVolumeMountSnafu)?
+            .add_env_var(
+                "SSL_CERT_DIR",
+                format!("{TRUST_STORE_DIR}:/etc/ssl/certs:/etc/pki/tls/certs"),
+            );
+        cb_bundle_builder
+            .add_volume_mount(TRUST_STORE_VOLUME_NAME, TRUST_STORE_DIR)
+            .context(AddVolumeMountSnafu)?
+            .add_env_var("TRUST_STORE_FILE", format!("{TRUST_STORE_DIR}/ca.crt"));
+    }
+
+    let persistent_storage = &merged_config.resources.storage.persistent_storage;
+    if persistent_storage.enabled {
+        cb_opa
+            .add_volume_mount(PERSISTENT_STORAGE_VOLUME_NAME, PERSISTENT_STORAGE_DIR)
+            .context(AddVolumeMountSnafu)?;
+        pb.add_volume(Volume {
+            name: PERSISTENT_STORAGE_VOLUME_NAME.to_string(),
+            host_path: Some(HostPathVolumeSource {
+                path: format!(
+                    "{host_path}/{namespace}/{rolegroup}",
+                    host_path = persistent_storage.host_path.trim_end_matches('/'),
+                    namespace = opa.namespace().unwrap_or_default(),
+                    rolegroup = rolegroup_ref.object_name(),
+                ),
+                type_: Some("DirectoryOrCreate".to_string()),
+            }),
+            ..Volume::default()
+        })
+        .context(AddVolumeSnafu)?;
+
+        // The kubelet creates missing hostPath directories owned by root, and fsGroup is not applied
+        // to hostPath volumes, so the directory has to be handed over to the OPA user explicitly
+        let mut cb_permissions =
+            ContainerBuilder::new(PERSISTENT_STORAGE_PERMISSIONS_CONTAINER_NAME)
+                .context(IllegalContainerNameSnafu)?;
+        cb_permissions
+            .image_from_product_image(resolved_product_image)
+            .command(vec![
+                "/bin/bash".to_string(),
+                "-x".to_string(),
+                "-euo".to_string(),
+                "pipefail".to_string(),
+                "-c".to_string(),
+            ])
+            .args(vec![format!(
+                "chown -R 1000:0 {PERSISTENT_STORAGE_DIR} && chmod -R u+rwX,g+rwX {PERSISTENT_STORAGE_DIR}"
+            )])
+            .add_volume_mount(PERSISTENT_STORAGE_VOLUME_NAME, PERSISTENT_STORAGE_DIR)
+            .context(AddVolumeMountSnafu)?
+            .resources(
+                ResourceRequirementsBuilder::new()
+                    .with_cpu_request("100m")
+                    .with_cpu_limit("200m")
+                    .with_memory_request("64Mi")
+                    .with_memory_limit("64Mi")
+                    .build(),
+            );
+        let mut permissions_container = cb_permissions.build();
+        permissions_container.security_context = Some(SecurityContext {
+            run_as_user: Some(0),
+            run_as_group: Some(0),
+            run_as_non_root: Some(false),
+            ..SecurityContext::default()
         });
+        pb.add_init_container(permissions_container);
+    }
+
+    if let Some(bundle_signing) = &opa.spec.cluster_config.bundle_signing {
+        pb.add_volume(
+            VolumeBuilder::new(BUNDLE_SIGNING_VOLUME_NAME)
+                .secret(SecretVolumeSource {
+                    secret_name: Some(bundle_signing.secret_name.clone()),
+                    items: Some(vec![KeyToPath {
+                        key: OpaBundleSigningConfig::SIGNING_KEY_SECRET_KEY.to_string(),
+                        path: OpaBundleSigningConfig::SIGNING_KEY_SECRET_KEY.to_string(),
+                        ..KeyToPath::default()
+                    }]),
+                    ..Default::default()
+                })
+                .build(),
+        )
+        .context(AddVolumeSnafu)?;
+        // OPA only gets to see the key it verifies the signatures with, which (unless a HMAC
+        // algorithm is used) is not the private signing key
+        let verification_key = bundle_signing.verification_key_secret_key();
+        pb.add_volume(
+            VolumeBuilder::new(BUNDLE_VERIFICATION_VOLUME_NAME)
+                .secret(SecretVolumeSource {
+                    secret_name: Some(bundle_signing.secret_name.clone()),
+                    items: Some(vec![KeyToPath {
+                        key: verification_key.to_string(),
+                        path: verification_key.to_string(),
+                        ..KeyToPath::default()
+                    }]),
+                    ..Default::default()
+                })
+                .build(),
+        )
+        .context(AddVolumeSnafu)?;
+        cb_bundle_builder
+            .add_volume_mount(BUNDLE_SIGNING_VOLUME_NAME, BUNDLE_SIGNING_DIR)
+            .context(AddVolumeMountSnafu)?
+            .add_env_var(
+                "BUNDLE_SIGNING_KEY_FILE",
+                format!(
+                    "{BUNDLE_SIGNING_DIR}/{key}",
+                    key = OpaBundleSigningConfig::SIGNING_KEY_SECRET_KEY
+                ),
+            )
+            .add_env_var(
+                "BUNDLE_SIGNING_ALGORITHM",
+                bundle_signing.algorithm.to_string(),
+            )
+            .add_env_var("BUNDLE_SIGNING_KEY_ID", &bundle_signing.key_id);
+        if let Some(scope) = &bundle_signing.scope {
+            cb_bundle_builder.add_env_var("BUNDLE_SIGNING_SCOPE", scope);
+        }
+        cb_opa
+            .add_volume_mount(BUNDLE_VERIFICATION_VOLUME_NAME, BUNDLE_VERIFICATION_DIR)
+            .context(AddVolumeMountSnafu)?;
+    }
+
+    if let Some(credentials) = opa
+        .spec
+        .cluster_config
+        .external_bundle_service
+        .as_ref()
+        .and_then(|external| external.credentials.as_ref())
+    {
+        pb.add_volume(
+            VolumeBuilder::new(EXTERNAL_BUNDLE_SERVICE_CREDENTIALS_VOLUME_NAME)
+                .secret(SecretVolumeSource {
+                    secret_name: Some(credentials.secret_name().to_string()),
+                    ..Default::default()
+                })
+                .build(),
+        )
+        .context(AddVolumeSnafu)?;
+        cb_opa
+            .add_volume_mount(
+                EXTERNAL_BUNDLE_SERVICE_CREDENTIALS_VOLUME_NAME,
+                EXTERNAL_BUNDLE_SERVICE_CREDENTIALS_DIR,
+            )
+            .context(AddVolumeMountSnafu)?;
+    }
+
+    if opa.has_stable_bundle() {
+        cb_bundle_builder.add_env_var(
+            "STABLE_BUNDLE_CONFIG_MAP",
+            opa.stable_bundle_config_map_name(),
+        );
+    }
+    if let Some(bundle_replication) = &opa.spec.cluster_config.bundle_replication {
+        if let Some(push_url) = &bundle_replication.push_url {
+            cb_bundle_builder.add_env_var("REPLICATION_PUSH_URL", push_url);
+        }
+        if let Some(pull_url) = &bundle_replication.pull_url {
+            cb_bundle_builder
+                .add_env_var("REPLICATION_PULL_URL", pull_url)
+                .add_env_var(
+                    "REPLICATION_PULL_INTERVAL",
+                    bundle_replication.pull_interval.to_string(),
+                );
+        }
+        if let Some(authorization_secret) = &bundle_replication.authorization_secret {
+            cb_bundle_builder.add_env_vars(vec![EnvVar {
+                name: "REPLICATION_AUTHORIZATION".to_string(),
+                value_from: Some(EnvVarSource {
+                    secret_key_ref: Some(SecretKeySelector {
+                        name: authorization_secret.clone(),
+                        key: OpaBundleReplicationConfig::AUTHORIZATION_SECRET_KEY.to_string(),
+                        ..SecretKeySelector::default()
+                    }),
+                    ..EnvVarSource::default()
+                }),
+                ..EnvVar::default()
+            }]);
+        }
+    }
 
     let pb_metadata = ObjectMetaBuilder::new()
         .with_recommended_labels(build_recommended_labels(
@@ -905,10 +2398,42 @@ fn build_server_rolegroup_daemonset(
         .context(ObjectMetaSnafu)?
         .build();
 
+    if !opa.spec.cluster_config.node_labels.is_empty() {
+        // The start command reads the labels of its node, see build_node_labels_config_map
+        pb.add_volume(
+            VolumeBuilder::new(NODE_LABELS_VOLUME_NAME)
+                .with_config_map(opa.node_labels_config_map_name())
+                .build(),
+        )
+        .context(AddVolumeSnafu)?;
+        cb_opa
+            .add_volume_mount(NODE_LABELS_VOLUME_NAME, NODE_LABELS_DIR)
+            .context(AddVolumeMountSnafu)?;
+    }
+    if let Some(development_mode) = &opa.spec.cluster_config.development_mode {
+        // OPA watches the policies itself, so the bundle builder is not needed
+        pb.add_volume(
+            VolumeBuilder::new(DEVELOPMENT_POLICIES_VOLUME_NAME)
+                .with_config_map(&development_mode.config_map_name)
+                .build(),
+        )
+        .context(AddVolumeSnafu)?;
+        cb_opa
+            .add_volume_mount(DEVELOPMENT_POLICIES_VOLUME_NAME, DEVELOPMENT_POLICIES_DIR)
+            .context(AddVolumeMountSnafu)?;
+    }
+    let bundle_builder_enabled = has_bundle_builder(opa, merged_config);
+    if bundle_builder_enabled {
+        pb.add_container(cb_bundle_builder.build());
+    }
+
+    // The bundle builder also creates the bundle directories itself, but the prepare container is
+    // kept so that its logs (and resources) stay where existing deployments expect them
+    if merged_config.prepare.enabled {
+        pb.add_init_container(cb_prepare.build());
+    }
     pb.metadata(pb_metadata)
-        .add_init_container(cb_prepare.build())
         .add_container(cb_opa.build())
-        .add_container(cb_bundle_builder.build())
         .image_pull_secrets_from_product_image(resolved_product_image)
         .affinity(&merged_config.affinity)
         .add_volume(
@@ -953,24 +2478,40 @@ fn build_server_rolegroup_daemonset(
 
         cb_user_info_fetcher
             .image_from_product_image(resolved_product_image) // inherit the pull policy and pull secrets, and then...
-            .image(user_info_fetcher_image) // ...override the image
+            .image(sidecar_image(
+                opa.spec.cluster_config.images.user_info_fetcher.as_ref(),
+                "userInfoFetcher",
+                user_info_fetcher_image,
+            )?) // ...override the image
             .command(vec!["stackable-opa-user-info-fetcher".to_string()])
             .add_env_var("CONFIG", format!("{CONFIG_DIR}/user-info-fetcher.json"))
             .add_env_var("CREDENTIALS_DIR", USER_INFO_FETCHER_CREDENTIALS_DIR)
+            .add_env_var(
+                "FAULT_INJECTION_FILE",
+                format!("{CONFIG_DIR}/{FAULT_INJECTION_FILE_NAME}"),
+            )
             .add_volume_mount(CONFIG_VOLUME_NAME, CONFIG_DIR)
             .context(AddVolumeMountSnafu)?
-            .resources(
-                ResourceRequirementsBuilder::new()
-                    .with_cpu_request("100m")
-                    .with_cpu_limit("200m")
-                    .with_memory_request("128Mi")
-                    .with_memory_limit("128Mi")
-                    .build(),
-            );
+            .resources(merged_config.user_info_fetcher.resources.to_owned().into())
+            // There is deliberately no readiness probe (checking the backend), since an unavailable
+            // backend would otherwise take all OPA Pods out of the Service
+            .liveness_probe(Probe {
+                initial_delay_seconds: Some(10),
+                period_seconds: Some(10),
+                ..user_info_fetcher_probe_action(
+                    user_info.server_tls.is_some(),
+                    USER_INFO_FETCHER_LIVENESS_PROBE_PATH,
+                )
+            });
 
         match &user_info.backend {
             user_info_fetcher::Backend::None {} => {}
-            user_info_fetcher::Backend::ExperimentalXfscAas(_) => {}
+            user_info_fetcher::Backend::XfscAas(aas)
+            | user_info_fetcher::Backend::ExperimentalXfscAas(aas) => {
+                aas.tls
+                    .add_volumes_and_mounts(&mut pb, vec![&mut cb_user_info_fetcher])
+                    .context(UserInfoFetcherTlsVolumeAndMountsSnafu)?;
+            }
             user_info_fetcher::Backend::ActiveDirectory(ad) => {
                 pb.add_volume(
                     SecretClassVolume::new(
@@ -1005,16 +2546,68 @@ fn build_server_rolegroup_daemonset(
                     .add_volumes_and_mounts(&mut pb, vec![&mut cb_user_info_fetcher])
                     .context(UserInfoFetcherTlsVolumeAndMountsSnafu)?;
             }
-            user_info_fetcher::Backend::Keycloak(keycloak) => {
+            user_info_fetcher::Backend::Scim(scim) => {
+                pb.add_volume(
+                    VolumeBuilder::new(USER_INFO_FETCHER_CREDENTIALS_VOLUME_NAME)
+                        .secret(SecretVolumeSource {
+                            secret_name: Some(scim.bearer_token_secret.clone()),
+                            ..Default::default()
+                        })
+                        .build(),
+                )
+                .context(AddVolumeSnafu)?;
+                cb_user_info_fetcher
+                    .add_volume_mount(
+                        USER_INFO_FETCHER_CREDENTIALS_VOLUME_NAME,
+                        USER_INFO_FETCHER_CREDENTIALS_DIR,
+                    )
+                    .context(AddVolumeMountSnafu)?;
+                scim.tls
+                    .add_volumes_and_mounts(&mut pb, vec![&mut cb_user_info_fetcher])
+                    .context(UserInfoFetcherTlsVolumeAndMountsSnafu)?;
+            }
+            user_info_fetcher::Backend::Okta(okta) => {
                 pb.add_volume(
                     VolumeBuilder::new(USER_INFO_FETCHER_CREDENTIALS_VOLUME_NAME)
                         .secret(SecretVolumeSource {
-                            secret_name: Some(keycloak.client_credentials_secret.clone()),
+                            secret_name: Some(okta.credentials.secret_name().to_string()),
                             ..Default::default()
                         })
                         .build(),
                 )
                 .context(AddVolumeSnafu)?;
+                cb_user_info_fetcher
+                    .add_volume_mount(
+                        USER_INFO_FETCHER_CREDENTIALS_VOLUME_NAME,
+                        USER_INFO_FETCHER_CREDENTIALS_DIR,
+                    )
+                    .context(AddVolumeMountSnafu)?;
+                okta.tls
+                    .add_volumes_and_mounts(&mut pb, vec![&mut cb_user_info_fetcher])
+                    .context(UserInfoFetcherTlsVolumeAndMountsSnafu)?;
+            }
+            user_info_fetcher::Backend::Keycloak(keycloak) => {
+                // Either way, the fetcher reads the files clientId and clientSecret
+                let credentials_volume = match (
+                    &keycloak.client_credentials_secret,
+                    &keycloak.client_credentials_secret_class,
+                ) {
+                    (Some(secret_name), None) => {
+                        VolumeBuilder::new(USER_INFO_FETCHER_CREDENTIALS_VOLUME_NAME)
+                            .secret(SecretVolumeSource {
+                                secret_name: Some(secret_name.clone()),
+                                ..Default::default()
+                            })
+                            .build()
+                    }
+                    (None, Some(secret_class)) => {
+                        SecretClassVolume::new(secret_class.clone(), None)
+                            .to_volume(USER_INFO_FETCHER_CREDENTIALS_VOLUME_NAME)
+                            .context(UserInfoFetcherCredentialsVolumeSnafu)?
+                    }
+                    _ => return AmbiguousUserInfoFetcherCredentialsSnafu.fail(),
+                };
+                pb.add_volume(credentials_volume).context(AddVolumeSnafu)?;
                 cb_user_info_fetcher
                     .add_volume_mount(
                         USER_INFO_FETCHER_CREDENTIALS_VOLUME_NAME,
@@ -1028,6 +2621,48 @@ fn build_server_rolegroup_daemonset(
             }
         }
 
+        if let Some(server_tls) = &user_info.server_tls {
+            pb.add_volume(
+                SecretClassVolume::new(
+                    server_tls.secret_class.clone(),
+                    Some(SecretClassVolumeScope {
+                        pod: true,
+                        node: true,
+                        services: Vec::new(),
+                        listener_volumes: Vec::new(),
+                    }),
+                )
+                .to_volume(USER_INFO_FETCHER_SERVER_TLS_VOLUME_NAME)
+                .context(UserInfoFetcherServerTlsVolumeSnafu)?,
+            )
+            .context(AddVolumeSnafu)?;
+            cb_user_info_fetcher
+                .add_volume_mount(
+                    USER_INFO_FETCHER_SERVER_TLS_VOLUME_NAME,
+                    USER_INFO_FETCHER_SERVER_TLS_DIR,
+                )
+                .context(AddVolumeMountSnafu)?
+                .add_env_var(
+                    "TLS_CERT_FILE",
+                    format!("{USER_INFO_FETCHER_SERVER_TLS_DIR}/tls.crt"),
+                )
+                .add_env_var(
+                    "TLS_KEY_FILE",
+                    format!("{USER_INFO_FETCHER_SERVER_TLS_DIR}/tls.key"),
+                )
+                .add_env_var(
+                    "LISTEN_ADDRESS",
+                    format!("0.0.0.0:{USER_INFO_FETCHER_PORT}"),
+                );
+        }
+
+        if opa.spec.cluster_config.trust_store.is_some() {
+            cb_user_info_fetcher
+                .add_volume_mount(TRUST_STORE_VOLUME_NAME, TRUST_STORE_DIR)
+                .context(AddVolumeMountSnafu)?
+                .add_env_var("TRUST_STORE_FILE", format!("{TRUST_STORE_DIR}/ca.crt"));
+        }
+
         pb.add_container(cb_user_info_fetcher.build());
     }
 
@@ -1052,8 +2687,35 @@ fn build_server_rolegroup_daemonset(
     add_graceful_shutdown_config(merged_config, &mut pb).context(GracefulShutdownSnafu)?;
 
     let mut pod_template = pb.build_template();
+    if !merged_config.topology_spread_constraints.0.is_empty() {
+        if let Some(pod_spec) = &mut pod_template.spec {
+            pod_spec.topology_spread_constraints =
+                Some(merged_config.topology_spread_constraints.0.clone());
+        }
+    }
+    if let Some(pod_spec) = &mut pod_template.spec {
+        // Added to the node selector of the affinity (if any)
+        if !merged_config.node_selector.0.is_empty() {
+            pod_spec
+                .node_selector
+                .get_or_insert_with(BTreeMap::new)
+                .extend(merged_config.node_selector.0.clone());
+        }
+        if !merged_config.tolerations.0.is_empty() {
+            pod_spec
+                .tolerations
+                .get_or_insert_with(Vec::new)
+                .extend(merged_config.tolerations.0.iter().cloned());
+        }
+    }
     pod_template.merge_from(role.config.pod_overrides.clone());
     pod_template.merge_from(role_group.config.pod_overrides.clone());
+    // After merging the podOverrides, which refer to the sidecars as (regular) containers
+    if native_sidecars {
+        if let Some(pod_spec) = &mut pod_template.spec {
+            move_to_native_sidecars(pod_spec);
+        }
+    }
 
     let metadata = ObjectMetaBuilder::new()
         .name_and_namespace(opa)
@@ -1094,19 +2756,132 @@ fn build_server_rolegroup_daemonset(
 }
 
 pub fn error_policy(
-    _obj: Arc<DeserializeGuard<OpaCluster>>,
+    obj: Arc<DeserializeGuard<OpaCluster>>,
     error: &Error,
-    _ctx: Arc<Ctx>,
+    ctx: Arc<Ctx>,
 ) -> Action {
-    match error {
-        // root object is invalid, will be requeued when modified anyway
-        Error::InvalidOpaCluster { .. } => Action::await_change(),
+    let base_delay = match error.class() {
+        // Someone else modified the object in the meantime, retrying with its new version succeeds
+        ErrorClass::Transient if is_conflict(error) => {
+            return Action::requeue(*Duration::from_secs(1));
+        }
+        ErrorClass::Transient => *Duration::from_secs(1),
+        ErrorClass::Dependency => *Duration::from_secs(60),
+        // Will be requeued when the OpaCluster (or any of the objects it owns) is modified anyway
+        ErrorClass::Terminal => {
+            tracing::warn!(
+                opa = %ObjectRef::from_obj(&*obj),
+                error = error as &dyn std::error::Error,
+                "failed to reconcile OpaCluster, will not retry until it has been changed"
+            );
+            return Action::await_change();
+        }
+    };
+    // Back off exponentially, so that persistent failures don't keep hammering the API server
+    let failed_reconciles = obj.0.as_ref().ok().map_or(0, |opa| {
+        let mut failed_reconciles = ctx.failed_reconciles.lock().unwrap();
+        let count = failed_reconciles
+            .entry(ObjectRef::from_obj(opa))
+            .or_default();
+        *count = count.saturating_add(1);
+        *count
+    });
+    let delay = base_delay
+        .saturating_mul(2u32.saturating_pow(failed_reconciles.saturating_sub(1)))
+        .min(*MAX_ERROR_BACKOFF);
+    Action::requeue(delay)
+}
+
+/// Whether the `error` was caused by a conflicting write (`409 Conflict`).
+fn is_conflict(error: &Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(error) = source {
+        if let Some(stackable_operator::kube::Error::Api(response)) =
+            error.downcast_ref::<stackable_operator::kube::Error>()
+        {
+            return response.code == 409;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// The OpaClusters that pass node labels to OPA, which need to be reconciled when nodes change.
+pub fn opa_clusters_with_node_labels(
+    store: &Store<DeserializeGuard<OpaCluster>>,
+) -> Vec<ObjectRef<DeserializeGuard<OpaCluster>>> {
+    store
+        .state()
+        .into_iter()
+        .filter(|opa| {
+            opa.0
+                .as_ref()
+                .is_ok_and(|opa| !opa.spec.cluster_config.node_labels.is_empty())
+        })
+        .map(|opa| ObjectRef::from_obj(&*opa))
+        .collect()
+}
+
+/// The environment variable of the OPA container containing the value of the `i`th node label.
+fn node_label_env_var(i: usize) -> String {
+    format!("NODE_LABEL_{i}")
+}
+
+/// Copies the configured labels of every node to a ConfigMap, with one file per node (named like
+/// the node) setting the environment variables of the labels (see [`node_label_env_var`]).
+///
+/// The downward API can't expose the labels of nodes, so the start command of OPA reads the file of
+/// its node instead, and OPA substitutes the variables into the runtime labels of its config file.
+pub fn build_node_labels_config_map(
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    nodes: &[PartialObjectMeta<Node>],
+) -> Result<ConfigMap> {
+    let metadata = ObjectMetaBuilder::new()
+        .name_and_namespace(opa)
+        .name(opa.node_labels_config_map_name())
+        .ownerreference_from_resource(opa, None, Some(true))
+        .context(ObjectMissingMetadataForOwnerRefSnafu)?
+        .with_recommended_labels(build_recommended_labels(
+            opa,
+            &resolved_product_image.app_version_label,
+            &OpaRole::Server.to_string(),
+            "global",
+        ))
+        .context(ObjectMetaSnafu)?
+        .build();
 
-        _ => Action::requeue(*Duration::from_secs(10)),
+    let mut cm_builder = ConfigMapBuilder::new();
+    cm_builder.metadata(metadata);
+    for node in nodes {
+        let node_labels = node.labels();
+        let env_file = opa
+            .spec
+            .cluster_config
+            .node_labels
+            .iter()
+            .enumerate()
+            .map(|(i, label)| {
+                let value = node_labels.get(label).map_or("", String::as_str);
+                format!(
+                    "export {name}={value}\n",
+                    name = node_label_env_var(i),
+                    value = shell_single_quote(value)
+                )
+            })
+            .collect::<String>();
+        cm_builder.add_data(node.name_any(), env_file);
     }
+    cm_builder.build().context(BuildNodeLabelsConfigSnafu)
 }
 
-fn build_config_file(merged_config: &OpaConfig) -> String {
+fn build_config_file(
+    opa: &OpaCluster,
+    role_group: &str,
+    merged_config: &OpaConfig,
+    opa_version: &OpaVersion,
+    config_overrides: Option<&BTreeMap<String, String>>,
+) -> String {
     let mut decision_logging_enabled = DEFAULT_DECISION_LOGGING_ENABLED;
 
     if let Some(ContainerLogConfig {
@@ -1118,20 +2893,203 @@ fn build_config_file(merged_config: &OpaConfig) -> String {
         }
     }
 
-    let decision_logging = if decision_logging_enabled {
-        Some(OpaClusterConfigDecisionLog { console: true })
+    let bundle_builder_enabled = has_bundle_builder(opa, merged_config);
+    let bundle_source = if opa.spec.cluster_config.development_mode.is_some() {
+        None
+    } else if let Some(external) = &opa.spec.cluster_config.external_bundle_service {
+        Some(BundleSource::External(external))
+    } else if bundle_builder_enabled {
+        let bundle_resource = if opa.serves_stable_bundle(role_group) {
+            STABLE_BUNDLE_RESOURCE
+        } else {
+            BUNDLE_RESOURCE
+        };
+        // The bundle builder only serves the packages in the scope of the role group
+        Some(BundleSource::BundleBuilder {
+            resource: match opa.spec.cluster_config.bundle_scope(role_group) {
+                Some(scope) => format!("{bundle_resource}?packages={}", scope.packages.join(",")),
+                None => bundle_resource.to_string(),
+            },
+        })
     } else {
         None
     };
 
-    let config = OpaClusterConfigFile::new(decision_logging);
+    // The bundle builder writes the decision logs to a dedicated file, so that they are not
+    // intermixed with the server logs. Without a bundle builder they are logged to the console.
+    let decision_logging = decision_logging_enabled.then(|| match bundle_builder_enabled {
+        true => OpaClusterConfigDecisionLog {
+            console: false,
+            service: Some(String::from(DECISION_LOG_SERVICE_NAME)),
+            reporting: Some(OpaClusterConfigDecisionLogReporting {
+                min_delay_seconds: DECISION_LOG_UPLOAD_MIN_DELAY_SECONDS,
+                max_delay_seconds: DECISION_LOG_UPLOAD_MAX_DELAY_SECONDS,
+            }),
+        },
+        false => OpaClusterConfigDecisionLog {
+            console: true,
+            service: None,
+            reporting: None,
+        },
+    });
+
+    let mut labels = opa.spec.cluster_config.metrics.labels.clone();
+    for (i, label) in opa.spec.cluster_config.node_labels.iter().enumerate() {
+        labels.insert(label.clone(), format!("${{{}}}", node_label_env_var(i)));
+    }
+
+    let config = OpaClusterConfigFile::new(
+        decision_logging,
+        merged_config.resources.storage.persistent_storage.enabled,
+        labels,
+        opa.spec.cluster_config.bundle_signing.as_ref(),
+        bundle_source.as_ref(),
+        &opa.spec.cluster_config.bundle_polling,
+        &merged_config.server_config,
+        opa_version,
+    );
+
+    let mut config = json!(config);
+    for (path, value) in config_overrides.into_iter().flatten() {
+        apply_config_override(&mut config, path, value);
+    }
 
     // The unwrap() shouldn't panic under any circumstances because Rusts type checker takes care of the OpaClusterConfigFile
     // and serde + serde_json therefore serialize/deserialize a valid struct
-    serde_json::to_string_pretty(&json!(config)).unwrap()
+    serde_json::to_string_pretty(&config).unwrap()
+}
+
+/// Whether the role group runs the bundle builder, which is not the case in development mode (where
+/// OPA loads the policies itself), with an external bundle service or if it is disabled for the role
+/// group.
+fn has_bundle_builder(opa: &OpaCluster, merged_config: &OpaConfig) -> bool {
+    opa.spec.cluster_config.development_mode.is_none()
+        && opa.spec.cluster_config.external_bundle_service.is_none()
+        && merged_config.bundle_builder.enabled
+}
+
+/// Sets the value at `path` in the OPA config, where `path` is a list of object keys and array indices
+/// separated by dots (for example `services.0.url`).
+///
+/// The value is parsed as JSON if possible (so that numbers, booleans and objects can be set), and
+/// used as a string otherwise. Missing objects along the path are created. Objects are deep-merged
+/// into the existing objects, see [`merge_config_value`], and `null` removes the key at `path`.
+fn apply_config_override(config: &mut serde_json::Value, path: &str, value: &str) {
+    let value = serde_json::from_str(value)
+        .unwrap_or_else(|_| serde_json::Value::String(value.to_string()));
+    if value.is_null() {
+        remove_config_value(config, path);
+        return;
+    }
+    let mut target = config;
+    for segment in path.split('.') {
+        if !target.is_array() && !target.is_object() {
+            *target = serde_json::Value::Object(serde_json::Map::new());
+        }
+        target = match target {
+            serde_json::Value::Array(items) => match segment.parse::<usize>() {
+                Ok(index) if index < items.len() => &mut items[index],
+                _ => {
+                    tracing::warn!(path, "ignoring config override of invalid array element");
+                    return;
+                }
+            },
+            serde_json::Value::Object(fields) => {
+                fields.entry(segment).or_insert(serde_json::Value::Null)
+            }
+            _ => unreachable!("target was just turned into an object"),
+        };
+    }
+    merge_config_value(target, value);
+}
+
+/// Removes the key at `path` (see [`apply_config_override`]) from its object, if it exists.
+///
+/// Array elements can't be removed, as that would shift the indices of the following overrides.
+fn remove_config_value(config: &mut serde_json::Value, path: &str) {
+    let (parent_path, key) = match path.rsplit_once('.') {
+        Some((parent_path, key)) => (parent_path.split('.').collect::<Vec<_>>(), key),
+        None => (Vec::new(), path),
+    };
+    let parent_pointer = parent_path
+        .into_iter()
+        .map(|segment| format!("/{}", segment.replace('~', "~0").replace('/', "~1")))
+        .collect::<String>();
+    match config.pointer_mut(&parent_pointer) {
+        Some(serde_json::Value::Object(fields)) => {
+            fields.remove(key);
+        }
+        Some(serde_json::Value::Array(_)) => {
+            tracing::warn!(path, "ignoring config override removing an array element");
+        }
+        _ => {}
+    }
+}
+
+/// Deep-merges `value` into `target` following the semantics of a JSON merge patch
+/// ([RFC 7386](https://datatracker.ietf.org/doc/html/rfc7386)): objects are merged key by key, `null`
+/// removes a key of an object, and all other values (including arrays) replace the target.
+fn merge_config_value(target: &mut serde_json::Value, value: serde_json::Value) {
+    match (target, value) {
+        (serde_json::Value::Object(target_fields), serde_json::Value::Object(fields)) => {
+            for (key, value) in fields {
+                if value.is_null() {
+                    target_fields.remove(&key);
+                } else {
+                    merge_config_value(
+                        target_fields.entry(key).or_insert(serde_json::Value::Null),
+                        value,
+                    );
+                }
+            }
+        }
+        (target, value) => *target = value,
+    }
+}
+
+/// Builds the volume containing the CA certificates of the trust store as `ca.crt`.
+fn build_trust_store_volume(trust_store: &OpaTrustStore) -> Result<Volume> {
+    Ok(match trust_store {
+        OpaTrustStore::SecretClass(secret_class) => {
+            SecretClassVolume::new(secret_class.clone(), None)
+                .to_volume(TRUST_STORE_VOLUME_NAME)
+                .context(TrustStoreVolumeSnafu)?
+        }
+        OpaTrustStore::ConfigMap(config_map) => VolumeBuilder::new(TRUST_STORE_VOLUME_NAME)
+            .with_config_map(config_map)
+            .build(),
+    })
+}
+
+/// Turns the sidecars of OPA into native sidecars, that is init containers with the restart policy
+/// `Always`. Kubernetes starts them (after the prepare container) before OPA and stops them after
+/// OPA, so OPA never starts without a bundle builder to fetch the bundle from, and the last logs of
+/// OPA are still shipped by Vector.
+fn move_to_native_sidecars(pod_spec: &mut PodSpec) {
+    let sidecar_names = [
+        Container::BundleBuilder.to_string(),
+        "user-info-fetcher".to_string(),
+        Container::Vector.to_string(),
+    ];
+    let (sidecars, containers) = std::mem::take(&mut pod_spec.containers)
+        .into_iter()
+        .partition::<Vec<_>, _>(|container| sidecar_names.contains(&container.name));
+    pod_spec.containers = containers;
+    pod_spec
+        .init_containers
+        .get_or_insert_with(Vec::new)
+        .extend(sidecars.into_iter().map(|mut sidecar| {
+            sidecar.restart_policy = Some("Always".to_string());
+            sidecar
+        }));
 }
 
-fn build_opa_start_command(merged_config: &OpaConfig, container_name: &str) -> String {
+fn build_opa_start_command(
+    opa: &OpaCluster,
+    merged_config: &OpaConfig,
+    opa_version: &OpaVersion,
+    container_name: &str,
+) -> String {
     let mut file_log_level = DEFAULT_FILE_LOG_LEVEL;
     let mut console_log_level = DEFAULT_CONSOLE_LOG_LEVEL;
     let mut server_log_level = DEFAULT_SERVER_LOG_LEVEL;
@@ -1182,25 +3140,245 @@ fn build_opa_start_command(merged_config: &OpaConfig, container_name: &str) -> S
         server = server_log_level
     );
 
+    let bundle_signing_args = opa
+        .spec
+        .cluster_config
+        .bundle_signing
+        .as_ref()
+        .map(|bundle_signing| {
+            let key_id = escape_opa_set_path_segment(&bundle_signing.key_id);
+            format!(
+                " {arg}",
+                arg = shell_single_quote(&format!(
+                    "--set-file=keys.{key_id}.key={BUNDLE_VERIFICATION_DIR}/{key}",
+                    key = bundle_signing.verification_key_secret_key()
+                ))
+            )
+        })
+        .unwrap_or_default();
+
+    let shutdown_grace_period_s = merged_config
+        .graceful_shutdown_timeout
+        .unwrap_or(DEFAULT_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT)
+        .as_secs();
+
+    // Tracing is disabled while reading the credentials, so that they don't end up in the logs
+    let external_bundle_service_credentials_command = match opa
+        .spec
+        .cluster_config
+        .external_bundle_service
+        .as_ref()
+        .and_then(|external| external.credentials.as_ref())
+    {
+        Some(OpaExternalBundleServiceCredentials::BasicAuthSecret(_)) => formatdoc! {"
+            {{ set +x; }} 2>/dev/null
+            export {EXTERNAL_BUNDLE_SERVICE_BASIC_AUTH_ENV}=\"$(printf '%s:%s' \"$(cat {EXTERNAL_BUNDLE_SERVICE_CREDENTIALS_DIR}/{username})\" \"$(cat {EXTERNAL_BUNDLE_SERVICE_CREDENTIALS_DIR}/{password})\" | base64 -w 0)\"
+            set -x",
+            username = OpaExternalBundleServiceCredentials::USERNAME_SECRET_KEY,
+            password = OpaExternalBundleServiceCredentials::PASSWORD_SECRET_KEY,
+        },
+        Some(OpaExternalBundleServiceCredentials::BearerTokenSecret(_)) | None => String::new(),
+    };
+
+    // Sets the environment variables OPA substitutes into the runtime labels, see build_node_labels_config_map
+    let node_labels_command = if opa.spec.cluster_config.node_labels.is_empty() {
+        String::new()
+    } else {
+        formatdoc! {"
+            node_labels_file=\"{NODE_LABELS_DIR}/$NODE_NAME\"
+            for _ in $(seq {NODE_LABELS_WAIT_TIMEOUT_SECONDS}); do
+              [ -f \"$node_labels_file\" ] && break
+              sleep 1
+            done
+            if [ -f \"$node_labels_file\" ]; then
+              source \"$node_labels_file\"
+            else
+              echo \"The labels of node $NODE_NAME were not found after {NODE_LABELS_WAIT_TIMEOUT_SECONDS}s, starting without them\"
+            fi"
+        }
+    };
+
+    // The hidden files and directories are the internals Kubernetes uses to update the mounted ConfigMap atomically,
+    // they would otherwise be loaded as duplicates of the policies
+    let development_mode_args = if opa.spec.cluster_config.development_mode.is_some() {
+        format!(" --watch --ignore '.*' {DEVELOPMENT_POLICIES_DIR}")
+    } else {
+        String::new()
+    };
+
+    let mut server_args = String::new();
+    if let Some(max_errors) = merged_config.server_config.max_errors {
+        server_args.push_str(&format!(" --max-errors {max_errors}"));
+    }
+    if let Some(ready_timeout) = merged_config.server_config.ready_timeout {
+        server_args.push_str(&format!(" --ready-timeout {}", ready_timeout.as_secs()));
+    }
+
+    let telemetry_args = if opa_version.supports(&opa_version::DISABLE_TELEMETRY) {
+        " --disable-telemetry"
+    } else {
+        ""
+    };
+
     // TODO: Think about adding --shutdown-wait-period, as suggested by https://github.com/open-policy-agent/opa/issues/2764
     formatdoc! {"
         {COMMON_BASH_TRAP_FUNCTIONS}
+        {shutdown_progress_functions}
         {remove_vector_shutdown_file_command}
         prepare_signal_handlers
+        trap 'shutdown_started=$SECONDS; report_shutdown_progress & handle_term_signal' TERM
         containerdebug --output={STACKABLE_LOG_DIR}/containerdebug-state.json --loop &
-        opa run -s -a 0.0.0.0:{APP_PORT} -c {CONFIG_DIR}/{CONFIG_FILE} -l {opa_log_level} --shutdown-grace-period {shutdown_grace_period_s} --disable-telemetry {logging_redirects} &
+        {external_bundle_service_credentials_command}
+        {node_labels_command}
+        opa run -s -a {bind_host}:{APP_PORT} -c {CONFIG_DIR}/{CONFIG_FILE}{bundle_signing_args}{server_args} -l {opa_log_level} --shutdown-grace-period {shutdown_grace_period_s}{telemetry_args}{development_mode_args} {logging_redirects} &
         wait_for_termination $!
+        report_shutdown_duration
         {create_vector_shutdown_file_command}
         ",
+        shutdown_progress_functions = shutdown_progress_functions(shutdown_grace_period_s),
         remove_vector_shutdown_file_command =
             remove_vector_shutdown_file_command(STACKABLE_LOG_DIR),
         create_vector_shutdown_file_command =
             create_vector_shutdown_file_command(STACKABLE_LOG_DIR),
-        shutdown_grace_period_s = merged_config.graceful_shutdown_timeout.unwrap_or(DEFAULT_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT).as_secs(),
+        bind_host = opa_bind_host(&merged_config.bind_address),
         opa_log_level = [console_log_level, file_log_level].iter().min().unwrap_or(&LogLevel::INFO).to_opa_literal()
     }
 }
 
+/// Shell functions reporting the progress of the graceful shutdown of OPA (the open connections to
+/// OPA every second, and how long the shutdown took in the end), so that the graceful shutdown
+/// timeout can be tuned based on how long OPA actually takes to drain.
+///
+/// `report_shutdown_progress` runs in the background once `SIGTERM` was received, tracing is
+/// disabled there to keep the log readable.
+fn shutdown_progress_functions(shutdown_grace_period_s: u64) -> String {
+    formatdoc! {r#"
+        count_opa_connections()
+        {{
+            # Established (state 01) connections to the OPA port, which is hex encoded in /proc/net/tcp
+            local count=0 local_address state
+            while read -r _ local_address _ state _; do
+                if [[ "$local_address" == *:{app_port_hex} && "$state" == "01" ]]; then
+                    count=$((count + 1))
+                fi
+            done < <(cat /proc/net/tcp /proc/net/tcp6 2>/dev/null)
+            echo "$count"
+        }}
+
+        report_shutdown_progress()
+        {{
+            {{ set +x; }} 2>/dev/null
+            while [ -n "${{term_child_pid:-}}" ] && kill -0 "$term_child_pid" 2>/dev/null; do
+                echo "Draining OPA: $(count_opa_connections) open connections $((SECONDS - shutdown_started))s after SIGTERM (graceful shutdown timeout: {shutdown_grace_period_s}s)"
+                sleep 1
+            done
+        }}
+
+        report_shutdown_duration()
+        {{
+            if [ -n "${{shutdown_started:-}}" ]; then
+                echo "OPA shut down $((SECONDS - shutdown_started))s after SIGTERM (graceful shutdown timeout: {shutdown_grace_period_s}s)"
+            fi
+        }}
+        "#,
+        app_port_hex = format!("{APP_PORT:04X}"),
+    }
+}
+
+/// The host OPA binds to. `POD_IP` is expanded by the shell starting OPA and bracketed, so that
+/// IPv6 addresses can be followed by the port.
+fn opa_bind_host(bind_address: &OpaBindAddress) -> &'static str {
+    match bind_address {
+        OpaBindAddress::AllInterfaces => "0.0.0.0",
+        OpaBindAddress::PodIp => "[${POD_IP}]",
+        OpaBindAddress::Localhost => "127.0.0.1",
+    }
+}
+
+/// The action of the OPA startup, readiness and liveness probes, requesting `path`.
+///
+/// The kubelet can not reach OPA if it is bound to localhost, so the probe has to be executed inside the container in that case.
+fn opa_probe_action(bind_address: &OpaBindAddress, path: &str) -> Probe {
+    match bind_address {
+        OpaBindAddress::AllInterfaces | OpaBindAddress::PodIp => Probe {
+            http_get: Some(HTTPGetAction {
+                port: IntOrString::String(APP_PORT_NAME.to_string()),
+                path: Some(path.to_string()),
+                ..HTTPGetAction::default()
+            }),
+            ..Probe::default()
+        },
+        OpaBindAddress::Localhost => Probe {
+            exec: Some(ExecAction {
+                command: Some(vec![
+                    "curl".to_string(),
+                    "--fail".to_string(),
+                    "--silent".to_string(),
+                    format!("http://127.0.0.1:{APP_PORT}{path}"),
+                ]),
+            }),
+            ..Probe::default()
+        },
+    }
+}
+
+/// The user-info-fetcher only listens on the Pod IP if it serves its API over HTTPS, otherwise it is
+/// only reachable from within the Pod.
+fn user_info_fetcher_probe_action(server_tls: bool, path: &str) -> Probe {
+    if server_tls {
+        Probe {
+            http_get: Some(HTTPGetAction {
+                port: IntOrString::Int(USER_INFO_FETCHER_PORT.into()),
+                path: Some(path.to_string()),
+                scheme: Some("HTTPS".to_string()),
+                ..HTTPGetAction::default()
+            }),
+            ..Probe::default()
+        }
+    } else {
+        Probe {
+            exec: Some(ExecAction {
+                command: Some(vec![
+                    "curl".to_string(),
+                    "--fail".to_string(),
+                    "--silent".to_string(),
+                    format!("http://127.0.0.1:{USER_INFO_FETCHER_PORT}{path}"),
+                ]),
+            }),
+            ..Probe::default()
+        }
+    }
+}
+
+/// The `POD_NAME` and `NODE_NAME` environment variables.
+fn pod_and_node_name_env_vars() -> Vec<EnvVar> {
+    [
+        ("POD_NAME", "metadata.name"),
+        ("NODE_NAME", "spec.nodeName"),
+    ]
+    .into_iter()
+    .map(|(name, field_path)| EnvVar {
+        name: name.to_string(),
+        value_from: Some(EnvVarSource {
+            field_ref: Some(ObjectFieldSelector {
+                field_path: field_path.to_string(),
+                ..ObjectFieldSelector::default()
+            }),
+            ..EnvVarSource::default()
+        }),
+        ..EnvVar::default()
+    })
+    .collect()
+}
+
+/// How many failed startup probes it takes to exhaust the `startupTimeout`.
+fn opa_startup_probe_failure_threshold(startup_timeout: Duration) -> i32 {
+    let period_seconds = OPA_STARTUP_PROBE_PERIOD_SECONDS.unsigned_abs().into();
+    i32::try_from(startup_timeout.as_secs().div_ceil(period_seconds))
+        .unwrap_or(i32::MAX)
+        .max(1)
+}
+
 fn build_bundle_builder_start_command(merged_config: &OpaConfig, container_name: &str) -> String {
     let mut console_logging_off = false;
 
@@ -1255,6 +3433,62 @@ fn bundle_builder_log_level(merged_config: &OpaConfig) -> BundleBuilderLogLevel
     BundleBuilderLogLevel::Info
 }
 
+/// The image of a sidecar container, taking the override of the OpaCluster into account.
+///
+/// The override is validated here as well, since the admission webhook is optional.
+fn sidecar_image(
+    image_override: Option<&OpaSidecarImage>,
+    field: &str,
+    operator_image: &str,
+) -> Result<String> {
+    let Some(image_override) = image_override else {
+        return Ok(operator_image.to_string());
+    };
+    image_override
+        .validate()
+        .map_err(|message| InvalidSidecarImageSnafu { field, message }.build())?;
+    Ok(image_override.image_reference(operator_image))
+}
+
+/// Returns a warning if the OpaCluster uses fields that are deprecated.
+///
+/// The warning is reported in the status (rather than logged), so that it is not repeated for every
+/// reconciliation.
+fn check_deprecated_fields(opa: &OpaCluster) -> Option<String> {
+    let user_info = opa.spec.cluster_config.user_info.as_ref()?;
+    matches!(
+        user_info.backend,
+        user_info_fetcher::Backend::ExperimentalXfscAas(_)
+    )
+    .then(|| {
+        "clusterConfig.userInfo.backend.experimentalXfscAas is deprecated and will be removed in \
+         the next version of the OpaCluster API, use xfscAas instead"
+            .to_string()
+    })
+}
+
+/// Returns the revision of the latest bundle recorded in the bundle snapshot (or stable bundle)
+/// ConfigMap `cm`.
+///
+/// Unless the bundles are snapshotted continuously, the bundle builders only record the revision of
+/// the latest bundle, which can be newer than the snapshot.
+fn bundle_revision(cm: &ConfigMap) -> Option<&String> {
+    let annotations = cm.annotations();
+    annotations
+        .get(BUNDLE_LATEST_REVISION_ANNOTATION)
+        .or_else(|| annotations.get(BUNDLE_SNAPSHOT_HASH_ANNOTATION))
+}
+
+/// Quotes `value` as a single word for bash, so that it is passed on verbatim.
+fn shell_single_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Escapes `segment` for the path of OPA's `--set` and `--set-file` flags, which is split at dots.
+fn escape_opa_set_path_segment(segment: &str) -> String {
+    segment.replace('\\', "\\\\").replace('.', "\\.")
+}
+
 fn build_prepare_start_command(merged_config: &OpaConfig, container_name: &str) -> Vec<String> {
     let mut prepare_container_args = vec![];
     if let Some(ContainerLogConfig {
@@ -1275,6 +3509,12 @@ fn build_prepare_start_command(merged_config: &OpaConfig, container_name: &str)
     prepare_container_args.push(format!("echo \"Create dir [{BUNDLES_TMP_DIR}]\""));
     prepare_container_args.push(format!("mkdir -p {BUNDLES_TMP_DIR}"));
 
+    if let Some(init_script) = &merged_config.prepare.init_script {
+        prepare_container_args.push("echo \"Run init script\"".to_string());
+        // Grouped, so that multi-line scripts are chained as a whole
+        prepare_container_args.push(format!("{{\n{init_script}\n}}"));
+    }
+
     prepare_container_args
 }
 
@@ -1313,3 +3553,65 @@ pub fn build_recommended_labels<'a, T>(
         role_group,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merge_config_value_merges_objects_key_by_key() {
+        let mut config =
+            json!({"decision_logs": {"console": true, "reporting": {"min_delay_seconds": 5}}});
+        merge_config_value(
+            &mut config,
+            json!({"decision_logs": {"reporting": {"max_delay_seconds": 10}}}),
+        );
+        assert_eq!(
+            config,
+            json!({"decision_logs": {"console": true, "reporting": {"min_delay_seconds": 5, "max_delay_seconds": 10}}})
+        );
+    }
+
+    #[test]
+    fn merge_config_value_removes_keys_set_to_null() {
+        let mut config = json!({"decision_logs": {"console": true}, "status": {"console": true}});
+        merge_config_value(&mut config, json!({"decision_logs": null, "missing": null}));
+        assert_eq!(config, json!({"status": {"console": true}}));
+    }
+
+    #[test]
+    fn merge_config_value_replaces_arrays_and_scalars() {
+        let mut config = json!({"labels": ["a", "b"], "default_decision": "/http/example/allow"});
+        merge_config_value(
+            &mut config,
+            json!({"labels": ["c"], "default_decision": {"nested": true}}),
+        );
+        assert_eq!(
+            config,
+            json!({"labels": ["c"], "default_decision": {"nested": true}})
+        );
+    }
+
+    #[test]
+    fn shell_single_quote_passes_values_on_verbatim() {
+        assert_eq!(shell_single_quote(""), "''");
+        assert_eq!(shell_single_quote("eu-west-1a"), "'eu-west-1a'");
+        assert_eq!(
+            shell_single_quote("$(reboot) `id` \"x\""),
+            "'$(reboot) `id` \"x\"'"
+        );
+        assert_eq!(shell_single_quote("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn escape_opa_set_path_segment_escapes_dots_and_backslashes() {
+        assert_eq!(escape_opa_set_path_segment("global_key"), "global_key");
+        assert_eq!(
+            escape_opa_set_path_segment("keys.example.com"),
+            r"keys\.example\.com"
+        );
+        assert_eq!(escape_opa_set_path_segment(r"a\.b"), r"a\\\.b");
+    }
+}
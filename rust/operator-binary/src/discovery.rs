@@ -1,10 +1,19 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::Serialize;
 use snafu::{OptionExt, ResultExt, Snafu};
-use stackable_opa_crd::{OpaCluster, OpaRole};
+use stackable_opa_crd::{OpaCluster, OpaRole, APP_NAME, CONSUMER_ANNOTATION_PREFIX};
 use stackable_operator::{
     builder::{configmap::ConfigMapBuilder, meta::ObjectMetaBuilder},
+    client::Client,
     commons::product_image_selection::ResolvedProductImage,
     k8s_openapi::api::core::v1::{ConfigMap, Service},
-    kube::{runtime::reflector::ObjectRef, Resource, ResourceExt},
+    kube::{
+        api::{DeleteParams, ListParams},
+        runtime::reflector::ObjectRef,
+        Api, Resource, ResourceExt,
+    },
+    role_utils::RoleGroupRef,
     utils::cluster_info::KubernetesClusterInfo,
 };
 
@@ -24,6 +33,9 @@ pub enum Error {
     #[snafu(display("object has no namespace associated"))]
     NoNamespace,
 
+    #[snafu(display("object has no uid associated"))]
+    NoUid,
+
     #[snafu(display("failed to build ConfigMap"))]
     BuildConfigMap {
         source: stackable_operator::builder::configmap::Error,
@@ -33,6 +45,39 @@ pub enum Error {
     ObjectMeta {
         source: stackable_operator::builder::meta::Error,
     },
+
+    #[snafu(display("failed to serialize endpoints document"))]
+    SerializeEndpoints { source: serde_json::Error },
+
+    #[snafu(display("failed to list discovery ConfigMaps"))]
+    ListDiscoveryConfigMaps {
+        source: stackable_operator::kube::Error,
+    },
+
+    #[snafu(display("failed to delete stale discovery ConfigMap {name:?}"))]
+    DeleteStaleDiscoveryConfigMap {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+}
+
+/// The role group label of the discovery [`ConfigMap`]s.
+const DISCOVERY_ROLE_GROUP: &str = "discovery";
+
+/// Describes how to connect to the OPA instances, published as JSON under the `OPA_ENDPOINTS` key.
+///
+/// OPA runs on every node, so clients should prefer the instance on their own node to avoid network roundtrips.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OpaEndpoints {
+    protocol: &'static str,
+    port: u16,
+    /// The role Service, which always routes to the OPA instance on the node of the client
+    /// (`internalTrafficPolicy: Local`).
+    node_local_url: String,
+    /// The Services of the individual role groups, by role group name.
+    /// These also route to the OPA instance on the node of the client.
+    role_group_urls: BTreeMap<String, String>,
 }
 
 /// Builds discovery [`ConfigMap`]s for connecting to a [`OpaCluster`] for all expected scenarios
@@ -63,17 +108,43 @@ fn build_discovery_configmap(
     svc: &Service,
     cluster_info: &KubernetesClusterInfo,
 ) -> Result<ConfigMap, Error> {
-    let url = format!(
-        "http://{name}.{namespace}.svc.{cluster_domain}:{port}/",
-        name = svc.metadata.name.as_deref().context(NoNameSnafu)?,
-        namespace = svc
-            .metadata
-            .namespace
-            .as_deref()
-            .context(NoNamespaceSnafu)?,
-        cluster_domain = cluster_info.cluster_domain,
-        port = APP_PORT
-    );
+    let namespace = svc
+        .metadata
+        .namespace
+        .as_deref()
+        .context(NoNamespaceSnafu)?;
+    let service_url = |service_name: &str| {
+        format!(
+            "http://{service_name}.{namespace}.svc.{cluster_domain}:{port}/",
+            cluster_domain = cluster_info.cluster_domain,
+            port = APP_PORT
+        )
+    };
+    let url = service_url(svc.metadata.name.as_deref().context(NoNameSnafu)?);
+
+    let opa_ref = ObjectRef::from_obj(opa);
+    let endpoints = OpaEndpoints {
+        protocol: "http",
+        port: APP_PORT,
+        node_local_url: url.clone(),
+        role_group_urls: opa
+            .spec
+            .servers
+            .role_groups
+            .keys()
+            .map(|role_group| {
+                let rolegroup_ref = RoleGroupRef {
+                    cluster: opa_ref.clone(),
+                    role: OpaRole::Server.to_string(),
+                    role_group: role_group.clone(),
+                };
+                (
+                    role_group.clone(),
+                    service_url(&rolegroup_ref.object_name()),
+                )
+            })
+            .collect(),
+    };
 
     let metadata = ObjectMetaBuilder::new()
         .name_and_namespace(opa)
@@ -86,7 +157,7 @@ fn build_discovery_configmap(
             opa,
             &resolved_product_image.app_version_label,
             &OpaRole::Server.to_string(),
-            "discovery",
+            DISCOVERY_ROLE_GROUP,
         ))
         .context(ObjectMetaSnafu)?
         .build();
@@ -94,6 +165,81 @@ fn build_discovery_configmap(
     ConfigMapBuilder::new()
         .metadata(metadata)
         .add_data("OPA", url)
+        .add_data(
+            "OPA_ENDPOINTS",
+            serde_json::to_string_pretty(&endpoints).context(SerializeEndpointsSnafu)?,
+        )
         .build()
         .context(BuildConfigMapSnafu)
 }
+
+/// Lists the consumers that registered themselves on an (applied) discovery [`ConfigMap`],
+/// see [`CONSUMER_ANNOTATION_PREFIX`].
+///
+/// The annotations are owned by the consumers, so they are retained when the operator applies the ConfigMap.
+pub fn consumers(discovery_cm: &ConfigMap) -> BTreeSet<String> {
+    discovery_cm
+        .annotations()
+        .iter()
+        .filter(|(key, _)| key.starts_with(CONSUMER_ANNOTATION_PREFIX))
+        .map(|(key, consumer)| {
+            if consumer.is_empty() {
+                key.trim_start_matches(CONSUMER_ANNOTATION_PREFIX)
+                    .to_string()
+            } else {
+                consumer.clone()
+            }
+        })
+        .collect()
+}
+
+/// Cleans up discovery [`ConfigMap`]s that carry the labels of the [`OpaCluster`], but are not owned by it.
+///
+/// This happens when an OpaCluster is deleted and recreated (for example while renaming it in a
+/// GitOps tool), since consumers refer to the discovery ConfigMap by name. ConfigMaps owned by a
+/// previous OpaCluster are deleted right away instead of waiting for the garbage collector.
+/// The names of the remaining ConfigMaps (which are not owned by any OpaCluster, for example
+/// because they were orphaned on deletion) are returned, so that they can be reported in the status.
+pub async fn clean_up_stale_discovery_configmaps(
+    opa: &OpaCluster,
+    client: &Client,
+) -> Result<Vec<String>, Error> {
+    let namespace = opa.namespace().context(NoNamespaceSnafu)?;
+    let uid = opa.uid().context(NoUidSnafu)?;
+    let cm_api = Api::<ConfigMap>::namespaced(client.as_kube_client(), &namespace);
+    let cms = cm_api
+        .list(&ListParams::default().labels(&format!(
+            "app.kubernetes.io/name={APP_NAME},app.kubernetes.io/instance={instance},app.kubernetes.io/role-group={DISCOVERY_ROLE_GROUP}",
+            instance = opa.name_any(),
+        )))
+        .await
+        .context(ListDiscoveryConfigMapsSnafu)?;
+    let mut stale_cms = Vec::new();
+    for cm in cms {
+        let name = cm.name_any();
+        let owners = cm.owner_references();
+        if owners.iter().any(|owner| owner.uid == uid) {
+            continue;
+        }
+        if owners
+            .iter()
+            .any(|owner| owner.kind == OpaCluster::kind(&()))
+        {
+            tracing::info!(
+                config_map = name,
+                "deleting discovery ConfigMap of previous OpaCluster"
+            );
+            cm_api
+                .delete(&name, &DeleteParams::default())
+                .await
+                .context(DeleteStaleDiscoveryConfigMapSnafu { name })?;
+        } else {
+            tracing::warn!(
+                config_map = name,
+                "found discovery ConfigMap that is not owned by the OpaCluster, it must be deleted manually"
+            );
+            stale_cms.push(name);
+        }
+    }
+    Ok(stale_cms)
+}
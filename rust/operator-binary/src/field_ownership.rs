@@ -0,0 +1,141 @@
+//! Field ownership of the objects managed for an OpaCluster.
+//!
+//! All objects are applied using server-side apply with a single field manager ([`FIELD_MANAGER`],
+//! the one [`ClusterResources`](stackable_operator::cluster_resources::ClusterResources) uses), so
+//! that fields the operator stops setting are removed by the API server. Fields set by other field
+//! managers are never removed this way, which is what previously left the renamed
+//! `opa-bundle-builder` container behind in the DaemonSets (see
+//! <https://github.com/stackabletech/opa-operator/issues/444>).
+//!
+//! If `clusterConfig.fieldOwnershipAudit` is enabled, the field managers of the applied objects are
+//! reported in the status of the OpaCluster, and foreign field managers (such as `kubectl edit`) are
+//! logged.
+
+use std::{collections::BTreeSet, fmt::Debug};
+
+use const_format::concatcp;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use stackable_opa_crd::{OpaFieldOwnershipStatus, OPERATOR_NAME};
+use stackable_operator::{
+    client::{Client, GetApi},
+    kube::{Resource, ResourceExt},
+};
+
+use crate::{
+    controller::OPA_CONTROLLER_NAME, operations::canary_rollout::CANARY_ROLLOUT_FIELD_MANAGER,
+};
+
+/// The field manager all objects of an OpaCluster are applied with.
+pub const FIELD_MANAGER: &str = concatcp!(OPERATOR_NAME, '_', OPA_CONTROLLER_NAME);
+
+/// Field managers used by previous versions of the operator, whose fields are released.
+const LEGACY_FIELD_MANAGERS: &[&str] = &[OPA_CONTROLLER_NAME];
+
+/// The field manager the bundle builders store the bundle snapshots with.
+const BUNDLE_BUILDER_FIELD_MANAGER: &str = "opa-bundle-builder";
+
+/// Field managers that deliberately share objects with [`FIELD_MANAGER`], and are therefore not
+/// reported as foreign.
+const KNOWN_FIELD_MANAGERS: &[&str] = &[
+    FIELD_MANAGER,
+    // The status of the OpaCluster
+    OPERATOR_NAME,
+    // Promotes bundles into the stable bundle ConfigMap
+    CANARY_ROLLOUT_FIELD_MANAGER,
+    // Stores the bundle snapshots
+    BUNDLE_BUILDER_FIELD_MANAGER,
+];
+
+/// Releases the fields owned by [`LEGACY_FIELD_MANAGERS`] by applying an empty object with them.
+///
+/// Only sends requests if the (applied) `object` actually has fields owned by a legacy field manager.
+pub async fn release_legacy_field_managers<T>(
+    client: &Client,
+    object: &T,
+) -> Result<(), stackable_operator::client::Error>
+where
+    T: Clone + Debug + DeserializeOwned + Resource<DynamicType = ()> + GetApi,
+{
+    for legacy_manager in field_managers(object)
+        .into_iter()
+        .filter(|manager| LEGACY_FIELD_MANAGERS.contains(&manager.as_str()))
+    {
+        tracing::info!(
+            object = %object.name_any(),
+            kind = %T::kind(&()),
+            field_manager = %legacy_manager,
+            "releasing fields owned by legacy field manager"
+        );
+        client
+            .apply_patch(
+                &legacy_manager,
+                object,
+                json!({"apiVersion": T::api_version(&()), "kind": T::kind(&())}),
+            )
+            .await?;
+    }
+    Ok(())
+}
+
+/// Collects the field managers of the applied objects, if the audit is enabled.
+pub struct FieldOwnershipAudit {
+    enabled: bool,
+    objects: Vec<OpaFieldOwnershipStatus>,
+}
+
+impl FieldOwnershipAudit {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled,
+            objects: Vec::new(),
+        }
+    }
+
+    /// Records an applied object, whose foreign field managers are logged if the audit is enabled.
+    pub fn record<T: Resource<DynamicType = ()>>(&mut self, object: &T) {
+        if !self.enabled {
+            return;
+        }
+        let managers = field_managers(object);
+        let foreign_managers = managers
+            .iter()
+            .filter(|manager| !KNOWN_FIELD_MANAGERS.contains(&manager.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        let kind = T::kind(&()).into_owned();
+        for manager in &foreign_managers {
+            tracing::warn!(
+                object = %object.name_any(),
+                %kind,
+                field_manager = %manager,
+                "object managed by the operator has fields owned by a foreign field manager, \
+                 which are not removed or reset by the operator"
+            );
+        }
+        self.objects.push(OpaFieldOwnershipStatus {
+            kind,
+            name: object.name_any(),
+            managers: managers.into_iter().collect(),
+            foreign_managers,
+        });
+    }
+
+    /// The ownership report for the status of the OpaCluster, empty if the audit is disabled.
+    pub fn into_status(self) -> Vec<OpaFieldOwnershipStatus> {
+        self.objects
+    }
+}
+
+/// The field managers of the object, excluding those that only manage its status (such as the
+/// DaemonSet controller).
+fn field_managers<T: Resource>(object: &T) -> BTreeSet<String> {
+    object
+        .meta()
+        .managed_fields
+        .iter()
+        .flatten()
+        .filter(|entry| entry.subresource.as_deref() != Some("status"))
+        .filter_map(|entry| entry.manager.clone())
+        .collect()
+}
@@ -0,0 +1,197 @@
+//! Exposes the role service through a Gateway of the Kubernetes Gateway API.
+//!
+//! The `HTTPRoute` is not managed by [`ClusterResources`](stackable_operator::cluster_resources::ClusterResources),
+//! which only supports built-in resources, so it is applied (and deleted once it is no longer
+//! configured) here.
+
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_opa_crd::{OpaCluster, OpaGatewayRouteConfig, OpaRole};
+use stackable_operator::{
+    builder::meta::ObjectMetaBuilder,
+    client::Client,
+    commons::product_image_selection::ResolvedProductImage,
+    kube::{api::DeleteParams, Api, CustomResource, Resource, ResourceExt},
+};
+
+use crate::{
+    controller::{build_recommended_labels, APP_PORT},
+    field_ownership::{release_legacy_field_managers, FIELD_MANAGER},
+};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("object has no namespace"))]
+    NoNamespace,
+
+    #[snafu(display("failed to build object metadata"))]
+    BuildMetadata {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to apply HTTPRoute"))]
+    ApplyHttpRoute {
+        source: stackable_operator::client::Error,
+    },
+
+    #[snafu(display("failed to get HTTPRoute {name:?}"))]
+    GetHttpRoute {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to delete HTTPRoute {name:?}"))]
+    DeleteHttpRoute {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+}
+
+/// The subset of the `HTTPRoute` of the Gateway API that the operator creates.
+#[derive(Clone, CustomResource, Debug, Deserialize, Serialize)]
+#[kube(
+    group = "gateway.networking.k8s.io",
+    version = "v1",
+    kind = "HTTPRoute",
+    plural = "httproutes",
+    namespaced,
+    schema = "disabled",
+    crates(
+        kube_core = "stackable_operator::kube::core",
+        k8s_openapi = "stackable_operator::k8s_openapi",
+        schemars = "stackable_operator::schemars"
+    )
+)]
+#[serde(rename_all = "camelCase")]
+pub struct HttpRouteSpec {
+    parent_refs: Vec<ParentReference>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    hostnames: Vec<String>,
+    rules: Vec<HttpRouteRule>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ParentReference {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    section_name: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct HttpRouteRule {
+    backend_refs: Vec<BackendReference>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BackendReference {
+    name: String,
+    port: u16,
+}
+
+/// Applies the `HTTPRoute` if it is configured (returning the applied `HTTPRoute`), and deletes the
+/// `HTTPRoute` created for a previous configuration otherwise.
+pub async fn reconcile_http_route(
+    client: &Client,
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    role_service_name: &str,
+) -> Result<Option<HTTPRoute>, Error> {
+    match &opa.spec.cluster_config.gateway_route {
+        Some(gateway_route) => {
+            let http_route = build_http_route(
+                opa,
+                resolved_product_image,
+                role_service_name,
+                gateway_route,
+            )?;
+            let http_route = client
+                .apply_patch(FIELD_MANAGER, &http_route, &http_route)
+                .await
+                .context(ApplyHttpRouteSnafu)?;
+            // Previous versions applied the HTTPRoute with the legacy field manager
+            release_legacy_field_managers(client, &http_route)
+                .await
+                .context(ApplyHttpRouteSnafu)?;
+            Ok(Some(http_route))
+        }
+        None => {
+            delete_http_route(client, opa).await?;
+            Ok(None)
+        }
+    }
+}
+
+fn build_http_route(
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    role_service_name: &str,
+    gateway_route: &OpaGatewayRouteConfig,
+) -> Result<HTTPRoute, Error> {
+    let metadata = ObjectMetaBuilder::new()
+        .name_and_namespace(opa)
+        .ownerreference_from_resource(opa, None, Some(true))
+        .context(BuildMetadataSnafu)?
+        .with_recommended_labels(build_recommended_labels(
+            opa,
+            &resolved_product_image.app_version_label,
+            &OpaRole::Server.to_string(),
+            "global",
+        ))
+        .context(BuildMetadataSnafu)?
+        .build();
+    let gateway = &gateway_route.gateway;
+    Ok(HTTPRoute {
+        metadata,
+        spec: HttpRouteSpec {
+            parent_refs: vec![ParentReference {
+                name: gateway.name.clone(),
+                namespace: gateway.namespace.clone(),
+                section_name: gateway.section_name.clone(),
+            }],
+            hostnames: gateway_route.hostnames.clone(),
+            rules: vec![HttpRouteRule {
+                backend_refs: vec![BackendReference {
+                    name: role_service_name.to_string(),
+                    port: APP_PORT,
+                }],
+            }],
+        },
+    })
+}
+
+/// Deletes the `HTTPRoute` of the OpaCluster, if there is one.
+///
+/// This also succeeds if the Gateway API is not installed in the cluster.
+async fn delete_http_route(client: &Client, opa: &OpaCluster) -> Result<(), Error> {
+    let namespace = opa.namespace().context(NoNamespaceSnafu)?;
+    let name = opa.name_any();
+    let api = Api::<HTTPRoute>::namespaced(client.as_kube_client(), &namespace);
+    let Some(http_route) = api
+        .get_opt(&name)
+        .await
+        .context(GetHttpRouteSnafu { name: &name })?
+    else {
+        return Ok(());
+    };
+    // Routes that were created by someone else are left alone
+    if !http_route
+        .owner_references()
+        .iter()
+        .any(|owner| Some(&owner.uid) == opa.meta().uid.as_ref())
+    {
+        return Ok(());
+    }
+    tracing::info!(
+        http_route = name,
+        "deleting HTTPRoute that is no longer configured"
+    );
+    api.delete(&name, &DeleteParams::default())
+        .await
+        .context(DeleteHttpRouteSnafu { name })?;
+    Ok(())
+}
@@ -0,0 +1,200 @@
+//! HTTP endpoints reporting the health of the operator itself, so that it can be wired into monitoring.
+//!
+//! * `GET /healthz` reports whether the controllers are running, along with their last reconciliation.
+//!   It responds with `503 Service Unavailable` if any controller has stopped.
+//! * `GET /clusters` summarizes the conditions of all managed OpaClusters.
+//! * `GET /metrics` serves the metrics about the reconciliations in the OpenMetrics text format, see
+//!   [`crate::metrics`].
+
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Json, Router};
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::OpaCluster;
+use stackable_operator::{
+    client::Client,
+    k8s_openapi::{apimachinery::pkg::apis::meta::v1::Time, chrono::Utc},
+    kube::{
+        api::ListParams,
+        core::DeserializeGuard,
+        runtime::{controller, reflector::ObjectRef, watcher},
+        Resource, ResourceExt,
+    },
+    namespace::WatchNamespace,
+    status::condition::ClusterCondition,
+};
+use tokio::net::TcpListener;
+
+use crate::metrics::PrometheusMetrics;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to run health server"))]
+    RunServer { source: std::io::Error },
+}
+
+/// Tracks the liveness of a single controller.
+#[derive(Default)]
+pub struct ControllerHealth {
+    running: AtomicBool,
+    last_reconcile: Mutex<Option<LastReconcile>>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LastReconcile {
+    time: Time,
+    /// The reconciled object, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    object: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl ControllerHealth {
+    pub fn set_running(&self, running: bool) {
+        self.running.store(running, Ordering::Relaxed);
+    }
+
+    pub fn record_reconcile<K, E>(
+        &self,
+        result: &Result<(ObjectRef<K>, controller::Action), controller::Error<E, watcher::Error>>,
+    ) where
+        K: Resource,
+        K::DynamicType: Clone,
+        E: std::error::Error + 'static,
+    {
+        let (object, error) = match result {
+            Ok((object, _)) => (Some(object.to_string()), None),
+            Err(controller::Error::ReconcilerFailed(error, object)) => {
+                (Some(object.to_string()), Some(error.to_string()))
+            }
+            Err(error) => (None, Some(error.to_string())),
+        };
+        *self.last_reconcile.lock().unwrap() = Some(LastReconcile {
+            time: Time(Utc::now()),
+            object,
+            error,
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct HealthState {
+    /// The controllers by their full name.
+    pub controllers: BTreeMap<&'static str, Arc<ControllerHealth>>,
+    pub client: Client,
+    pub watch_namespace: WatchNamespace,
+    pub metrics: Arc<PrometheusMetrics>,
+}
+
+pub async fn run_server(listener: TcpListener, state: HealthState) -> Result<(), Error> {
+    let app = Router::new()
+        .route("/healthz", get(get_health))
+        .route("/clusters", get(get_clusters))
+        .route("/metrics", get(get_metrics))
+        .with_state(state);
+    axum::serve(listener, app.into_make_service())
+        .await
+        .context(RunServerSnafu)
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ControllerHealthReport {
+    running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_reconcile: Option<LastReconcile>,
+}
+
+async fn get_health(State(state): State<HealthState>) -> impl IntoResponse {
+    let controllers = state
+        .controllers
+        .iter()
+        .map(|(name, health)| {
+            (
+                *name,
+                ControllerHealthReport {
+                    running: health.running.load(Ordering::Relaxed),
+                    last_reconcile: health.last_reconcile.lock().unwrap().clone(),
+                },
+            )
+        })
+        .collect::<BTreeMap<_, _>>();
+    let status = if controllers.values().all(|controller| controller.running) {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(controllers))
+}
+
+async fn get_metrics(State(state): State<HealthState>) -> impl IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "application/openmetrics-text; version=1.0.0; charset=utf-8",
+        )],
+        state.metrics.render(),
+    )
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct OpaClusterSummary {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    /// Set if the OpaCluster could not be parsed, in which case it is not reconciled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    conditions: Vec<ClusterCondition>,
+}
+
+async fn get_clusters(State(state): State<HealthState>) -> impl IntoResponse {
+    let opa_api = state
+        .watch_namespace
+        .get_api::<DeserializeGuard<OpaCluster>>(&state.client);
+    let opas = match opa_api.list(&ListParams::default()).await {
+        Ok(opas) => opas,
+        Err(error) => {
+            tracing::error!(
+                error = &error as &dyn std::error::Error,
+                "failed to list OpaClusters"
+            );
+            return Err((
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "failed to list OpaClusters",
+            ));
+        }
+    };
+    Ok(Json(
+        opas.into_iter()
+            .map(|opa| {
+                let (error, conditions) = match &opa.0 {
+                    Ok(opa) => (
+                        None,
+                        opa.status
+                            .as_ref()
+                            .map(|status| status.conditions.clone())
+                            .unwrap_or_default(),
+                    ),
+                    Err(invalid) => (Some(invalid.error.clone()), Vec::new()),
+                };
+                OpaClusterSummary {
+                    name: opa.name_any(),
+                    namespace: opa.namespace(),
+                    error,
+                    conditions,
+                }
+            })
+            .collect::<Vec<_>>(),
+    ))
+}
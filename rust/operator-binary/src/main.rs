@@ -1,35 +1,57 @@
-use std::sync::Arc;
+use std::{collections::BTreeMap, net::SocketAddr, sync::Arc};
 
 use clap::{crate_description, crate_version, Parser};
-use futures::StreamExt;
+use futures::{future, Stream, StreamExt};
 use product_config::ProductConfigManager;
-use stackable_opa_crd::{OpaCluster, APP_NAME, OPERATOR_NAME};
+use stackable_opa_crd::{policy::OpaPolicy, OpaCluster, APP_NAME, OPERATOR_NAME};
 use stackable_operator::{
     cli::{Command, ProductOperatorRun},
     client::{self, Client},
     k8s_openapi::api::{
         apps::v1::DaemonSet,
-        core::v1::{ConfigMap, Service},
+        batch::v1::Job,
+        core::v1::{ConfigMap, Node, Service},
     },
     kube::{
         core::DeserializeGuard,
         runtime::{
             events::{Recorder, Reporter},
-            watcher, Controller,
+            metadata_watcher, predicates,
+            reflector::Store,
+            watcher, Config as ControllerConfig, Controller, WatchStreamExt,
         },
-        Api,
+        Api, ResourceExt,
     },
     logging::controller::report_controller_reconciled,
     namespace::WatchNamespace,
     CustomResourceExt,
 };
+use tokio::net::TcpListener;
 
-use crate::controller::OPA_FULL_CONTROLLER_NAME;
+use crate::{
+    controller::{opa_clusters_with_node_labels, OPA_FULL_CONTROLLER_NAME},
+    health::{ControllerHealth, HealthState},
+    metrics::{PrometheusMetrics, ReconcileMetrics},
+    policy_controller::{policies_in_namespace, OPA_POLICY_FULL_CONTROLLER_NAME},
+};
 
+mod bundle_builder_rbac;
+mod collect_diagnostics;
 mod controller;
 mod discovery;
+mod field_ownership;
+mod gateway;
+mod health;
+mod metrics;
+mod migrate;
+mod network_policy;
+mod opa_version;
 mod operations;
+mod policy_controller;
 mod product_logging;
+mod render;
+mod validate_policies;
+mod webhook;
 
 pub mod built_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
@@ -39,7 +61,29 @@ pub mod built_info {
 #[clap(about, author)]
 struct Opts {
     #[clap(subcommand)]
-    cmd: Command<OpaRun>,
+    cmd: OpaCommand,
+}
+
+#[derive(clap::Subcommand)]
+enum OpaCommand {
+    #[clap(flatten)]
+    Operator(Command<OpaRun>),
+
+    /// Validate a set of bundle ConfigMaps (from a cluster or local manifests) with the checks of
+    /// the bundle builder, and print a report.
+    ValidatePolicies(validate_policies::ValidatePoliciesArgs),
+
+    /// Collect the configuration, status, Events and logs of an OpaCluster into an archive that can
+    /// be attached to support tickets. Secrets are not collected.
+    CollectDiagnostics(collect_diagnostics::CollectDiagnosticsArgs),
+
+    /// Translate the legacy OpenPolicyAgent objects (authz.stackable.tech/v1) into OpaClusters,
+    /// print them as YAML manifests and report everything that cannot be migrated.
+    Migrate(migrate::MigrateArgs),
+
+    /// Print the resources the operator would create for an OpaCluster manifest (read from stdin)
+    /// without contacting a Kubernetes cluster.
+    Render(render::RenderArgs),
 }
 
 #[derive(clap::Parser)]
@@ -48,19 +92,66 @@ struct OpaRun {
     #[clap(long, env)]
     operator_image: String,
 
+    /// The address to serve the health endpoints of the operator on (`/healthz` and `/clusters`).
+    #[clap(long, env, default_value = "0.0.0.0:8080")]
+    health_listen_address: SocketAddr,
+
+    /// Export metrics about the reconciliations via OTLP.
+    /// The exporter is configured by the standard `OTEL_EXPORTER_OTLP_*` environment variables.
+    #[clap(long, env)]
+    otlp_metrics: bool,
+
+    /// Whether to run the sidecars of OPA as native sidecars (init containers that keep running),
+    /// which start before and stop after OPA. `auto` uses them if the Kubernetes version supports
+    /// them (1.29 and later).
+    #[clap(long, env, value_enum, default_value_t = NativeSidecars::Auto)]
+    native_sidecars: NativeSidecars,
+
+    /// Namespaces whose ConfigMaps are never added to bundles, even if they are labeled as bundle
+    /// ConfigMaps (such as `kube-system`, or namespaces of untrusted tenants).
+    /// Enforced by the bundle builders of all OpaClusters, regardless of their configuration.
+    #[clap(long, env, value_delimiter = ',')]
+    bundle_excluded_namespaces: Vec<String>,
+
+    #[clap(flatten)]
+    webhook: webhook::WebhookArgs,
+
     #[clap(flatten)]
     common: ProductOperatorRun,
 }
 
+#[derive(Clone, Copy, clap::ValueEnum)]
+enum NativeSidecars {
+    Auto,
+    Enabled,
+    Disabled,
+}
+
+/// The number of reconciliations each controller runs at once.
+const MAX_CONCURRENT_RECONCILES: u16 = 16;
+
+/// The first Kubernetes version that enables native sidecars by default.
+const NATIVE_SIDECARS_MIN_KUBERNETES_MINOR_VERSION: u32 = 29;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let opts = Opts::parse();
     match opts.cmd {
-        Command::Crd => {
+        OpaCommand::ValidatePolicies(args) => validate_policies::run(args).await?,
+        OpaCommand::CollectDiagnostics(args) => collect_diagnostics::run(args).await?,
+        OpaCommand::Migrate(args) => migrate::run(args).await?,
+        OpaCommand::Render(args) => render::run(args)?,
+        OpaCommand::Operator(Command::Crd) => {
             OpaCluster::print_yaml_schema(built_info::PKG_VERSION)?;
+            OpaPolicy::print_yaml_schema(built_info::PKG_VERSION)?;
         }
-        Command::Run(OpaRun {
+        OpaCommand::Operator(Command::Run(OpaRun {
             operator_image,
+            health_listen_address,
+            otlp_metrics,
+            native_sidecars,
+            bundle_excluded_namespaces,
+            webhook,
             common:
                 ProductOperatorRun {
                     product_config,
@@ -68,7 +159,7 @@ async fn main() -> anyhow::Result<()> {
                     tracing_target,
                     cluster_info_opts,
                 },
-        }) => {
+        })) => {
             stackable_operator::logging::initialize_logging(
                 "OPA_OPERATOR_LOG",
                 APP_NAME,
@@ -83,6 +174,8 @@ async fn main() -> anyhow::Result<()> {
                 built_info::BUILT_TIME_UTC,
                 built_info::RUSTC_VERSION,
             );
+            let meter_provider = otlp_metrics.then(metrics::init).transpose()?;
+
             let product_config = product_config.load(&[
                 "deploy/config-spec/properties.yaml",
                 "/etc/stackable/opa-operator/config-spec/properties.yaml",
@@ -91,14 +184,72 @@ async fn main() -> anyhow::Result<()> {
             let client =
                 client::initialize_operator(Some(OPERATOR_NAME.to_string()), &cluster_info_opts)
                     .await?;
-            create_controller(
-                client,
-                product_config,
-                watch_namespace,
-                operator_image.clone(),
-                operator_image,
+            let native_sidecars = match native_sidecars {
+                NativeSidecars::Auto => supports_native_sidecars(&client).await,
+                NativeSidecars::Enabled => true,
+                NativeSidecars::Disabled => false,
+            };
+            tracing::info!(native_sidecars, "deploying sidecars of OPA");
+            let prometheus_metrics = Arc::new(PrometheusMetrics::default());
+            let opa_controller_health = Arc::new(ControllerHealth::default());
+            let policy_controller_health = Arc::new(ControllerHealth::default());
+            let health_listener = TcpListener::bind(health_listen_address).await?;
+            let health_state = HealthState {
+                controllers: BTreeMap::from([
+                    (OPA_FULL_CONTROLLER_NAME, opa_controller_health.clone()),
+                    (
+                        OPA_POLICY_FULL_CONTROLLER_NAME,
+                        policy_controller_health.clone(),
+                    ),
+                ]),
+                client: client.clone(),
+                watch_namespace: watch_namespace.clone(),
+                metrics: prometheus_metrics.clone(),
+            };
+
+            futures::future::join4(
+                create_controller(
+                    client.clone(),
+                    product_config,
+                    watch_namespace.clone(),
+                    operator_image.clone(),
+                    operator_image,
+                    native_sidecars,
+                    bundle_excluded_namespaces.clone(),
+                    opa_controller_health,
+                    prometheus_metrics.clone(),
+                ),
+                create_policy_controller(
+                    client.clone(),
+                    watch_namespace,
+                    bundle_excluded_namespaces,
+                    policy_controller_health,
+                    prometheus_metrics,
+                ),
+                async {
+                    // The controllers keep running even if the health endpoints fail
+                    if let Err(error) = health::run_server(health_listener, health_state).await {
+                        tracing::error!(
+                            error = &error as &dyn std::error::Error,
+                            "health server failed"
+                        );
+                    }
+                },
+                async {
+                    // Without the webhook OpaClusters are still validated during the reconciliation
+                    if let Err(error) = webhook::run_server(webhook, client).await {
+                        tracing::error!(
+                            error = &error as &dyn std::error::Error,
+                            "webhook server failed"
+                        );
+                    }
+                },
             )
             .await;
+
+            if let Some(meter_provider) = meter_provider {
+                meter_provider.shutdown()?;
+            }
         }
     };
 
@@ -114,16 +265,30 @@ async fn create_controller(
     watch_namespace: WatchNamespace,
     opa_bundle_builder_image: String,
     user_info_fetcher_image: String,
+    native_sidecars: bool,
+    bundle_excluded_namespaces: Vec<String>,
+    health: Arc<ControllerHealth>,
+    metrics: Arc<PrometheusMetrics>,
 ) {
     let opa_api: Api<DeserializeGuard<OpaCluster>> = watch_namespace.get_api(&client);
     let daemonsets_api: Api<DeserializeGuard<DaemonSet>> = watch_namespace.get_api(&client);
     let configmaps_api: Api<DeserializeGuard<ConfigMap>> = watch_namespace.get_api(&client);
     let services_api: Api<DeserializeGuard<Service>> = watch_namespace.get_api(&client);
+    let jobs_api: Api<DeserializeGuard<Job>> = watch_namespace.get_api(&client);
 
-    let controller = Controller::new(opa_api, watcher::Config::default())
+    let controller = Controller::new(opa_api, watcher::Config::default());
+    let opa_store = controller.store();
+    metrics.observe_opa_clusters(opa_store.clone());
+    metrics::observe_opa_clusters(opa_store.clone());
+    let controller = controller
+        .with_config(ControllerConfig::default().concurrency(MAX_CONCURRENT_RECONCILES))
         .owns(daemonsets_api, watcher::Config::default())
         .owns(configmaps_api, watcher::Config::default())
-        .owns(services_api, watcher::Config::default());
+        .owns(services_api, watcher::Config::default())
+        // The policy tests Jobs, whose results decide whether bundles are promoted
+        .owns(jobs_api, watcher::Config::default())
+        // The labels of the nodes are copied to the OpaClusters that pass them to OPA
+        .reconcile_all_on(node_label_changes(&client, opa_store));
 
     let event_recorder = Arc::new(Recorder::new(
         client.as_kube_client(),
@@ -132,15 +297,25 @@ async fn create_controller(
             instance: None,
         },
     ));
+    let reconcile_metrics = ReconcileMetrics::new(OPA_FULL_CONTROLLER_NAME, metrics.clone());
+    health.set_running(true);
     controller
         .run(
-            controller::reconcile_opa,
+            move |opa, ctx| {
+                reconcile_metrics
+                    .clone()
+                    .measure(controller::reconcile_opa(opa, ctx))
+            },
             controller::error_policy,
             Arc::new(controller::Ctx {
                 client: client.clone(),
                 product_config,
                 opa_bundle_builder_image,
                 user_info_fetcher_image,
+                native_sidecars,
+                bundle_excluded_namespaces,
+                failed_reconciles: Default::default(),
+                metrics,
             }),
         )
         // We can let the reporting happen in the background
@@ -150,6 +325,7 @@ async fn create_controller(
                 // The event_recorder needs to be shared across all invocations, so that
                 // events are correctly aggregated
                 let event_recorder = event_recorder.clone();
+                health.record_reconcile(&result);
                 async move {
                     report_controller_reconciled(
                         &event_recorder,
@@ -161,4 +337,147 @@ async fn create_controller(
             },
         )
         .await;
+    health.set_running(false);
+}
+
+/// Emits an item whenever the labels of a node change while an OpaCluster passes node labels to OPA.
+///
+/// Only the metadata of the nodes is watched, so that the frequent updates of their status are not
+/// transferred at all.
+fn node_label_changes(
+    client: &Client,
+    opa_store: Store<DeserializeGuard<OpaCluster>>,
+) -> impl Stream<Item = ()> + Send + Sync + 'static {
+    let node_changes = metadata_watcher(
+        Api::<Node>::all(client.as_kube_client()),
+        watcher::Config::default(),
+    )
+    .default_backoff()
+    .touched_objects()
+    .predicate_filter(predicates::labels)
+    .filter(move |node| {
+        future::ready(node.is_err() || !opa_clusters_with_node_labels(&opa_store).is_empty())
+    });
+    // Controller::reconcile_all_on requires a Sync stream, which the watcher is not, so it is
+    // polled in the background instead
+    let (mut tx, rx) = futures::channel::mpsc::channel(0);
+    tokio::spawn(async move {
+        let mut node_changes = std::pin::pin!(node_changes);
+        while let Some(node) = node_changes.next().await {
+            match node {
+                Ok(_) => {
+                    // If the channel is full, a reconciliation of all OpaClusters is pending anyway
+                    if tx.try_send(()).is_err_and(|error| error.is_disconnected()) {
+                        break;
+                    }
+                }
+                Err(error) => tracing::warn!(
+                    error = &error as &dyn std::error::Error,
+                    "failed to watch the labels of the nodes"
+                ),
+            }
+        }
+    });
+    rx
+}
+
+/// This creates an instance of a [`Controller`] reconciling [`OpaPolicy`] objects into bundle ConfigMaps.
+///
+/// This is an async method and the returned future needs to be consumed to make progress.
+async fn create_policy_controller(
+    client: Client,
+    watch_namespace: WatchNamespace,
+    bundle_excluded_namespaces: Vec<String>,
+    health: Arc<ControllerHealth>,
+    metrics: Arc<PrometheusMetrics>,
+) {
+    let policy_api: Api<DeserializeGuard<OpaPolicy>> = watch_namespace.get_api(&client);
+    let opa_api: Api<DeserializeGuard<OpaCluster>> = watch_namespace.get_api(&client);
+    let configmaps_api: Api<DeserializeGuard<ConfigMap>> = watch_namespace.get_api(&client);
+
+    let controller = Controller::new(policy_api.clone(), watcher::Config::default());
+    let policy_store = controller.store();
+    let controller = controller
+        .with_config(ControllerConfig::default().concurrency(MAX_CONCURRENT_RECONCILES))
+        .owns(configmaps_api, watcher::Config::default())
+        // Changes of OpaClusters (e.g. their labels) and competing policies can change which
+        // policies are active in which OpaCluster
+        .watches(opa_api, watcher::Config::default(), {
+            let policy_store = policy_store.clone();
+            move |opa| policies_in_namespace(&policy_store, opa.namespace())
+        })
+        .watches(policy_api, watcher::Config::default(), move |policy| {
+            policies_in_namespace(&policy_store, policy.namespace())
+        });
+
+    let event_recorder = Arc::new(Recorder::new(
+        client.as_kube_client(),
+        Reporter {
+            controller: OPA_POLICY_FULL_CONTROLLER_NAME.to_string(),
+            instance: None,
+        },
+    ));
+    let reconcile_metrics = ReconcileMetrics::new(OPA_POLICY_FULL_CONTROLLER_NAME, metrics.clone());
+    health.set_running(true);
+    controller
+        .run(
+            move |policy, ctx| {
+                reconcile_metrics
+                    .clone()
+                    .measure(policy_controller::reconcile_policy(policy, ctx))
+            },
+            policy_controller::error_policy,
+            Arc::new(policy_controller::Ctx {
+                client: client.clone(),
+                bundle_excluded_namespaces,
+                metrics,
+            }),
+        )
+        .for_each_concurrent(16, |result| {
+            let event_recorder = event_recorder.clone();
+            health.record_reconcile(&result);
+            async move {
+                report_controller_reconciled(
+                    &event_recorder,
+                    OPA_POLICY_FULL_CONTROLLER_NAME,
+                    &result,
+                )
+                .await;
+            }
+        })
+        .await;
+    health.set_running(false);
+}
+
+/// Checks whether the API server is recent enough to support native sidecars.
+///
+/// Falls back to regular sidecar containers if the version cannot be determined.
+async fn supports_native_sidecars(client: &Client) -> bool {
+    let version = match client.as_kube_client().apiserver_version().await {
+        Ok(version) => version,
+        Err(error) => {
+            tracing::warn!(
+                error = &error as &dyn std::error::Error,
+                "failed to get Kubernetes version, not using native sidecars"
+            );
+            return false;
+        }
+    };
+    // Some distributions append suffixes to the minor version, such as "29+"
+    let minor = version
+        .minor
+        .trim_end_matches(|c: char| !c.is_ascii_digit())
+        .parse::<u32>();
+    match (version.major.as_str(), minor) {
+        ("1", Ok(minor)) => minor >= NATIVE_SIDECARS_MIN_KUBERNETES_MINOR_VERSION,
+        ("1", Err(_)) => {
+            tracing::warn!(
+                version.minor,
+                "failed to parse Kubernetes minor version, not using native sidecars"
+            );
+            false
+        }
+        // Kubernetes 2 and later
+        _ => true,
+    }
 }
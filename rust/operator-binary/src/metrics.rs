@@ -0,0 +1,296 @@
+//! Metrics about the reconciliations of the operator.
+//!
+//! They are exported as OpenTelemetry metrics via OTLP, and served in the OpenMetrics text format at
+//! `GET /metrics` of the health server (see [`PrometheusMetrics`]).
+//!
+//! The exporter is configured by the standard `OTEL_EXPORTER_OTLP_*` environment variables.
+//! If metrics are not enabled, the instruments are backed by the no-op global meter provider, while
+//! the Prometheus metrics are always collected.
+
+use std::{
+    future::Future,
+    sync::{Arc, OnceLock},
+    time::Instant,
+};
+
+use opentelemetry::{
+    global,
+    metrics::{Counter, Histogram, Meter, MetricsError, Unit},
+    KeyValue,
+};
+use opentelemetry_sdk::{metrics::SdkMeterProvider, runtime, Resource};
+use prometheus_client::{
+    encoding::{text::encode, EncodeLabelSet},
+    metrics::{
+        counter::Counter as PrometheusCounter, family::Family, gauge::Gauge,
+        histogram::Histogram as PrometheusHistogram,
+    },
+    registry::Registry,
+};
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::{OpaCluster, APP_NAME};
+use stackable_operator::{
+    kube::{core::DeserializeGuard, runtime::reflector::Store, Resource as KubeResource},
+    logging::controller::ReconcilerError,
+};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to build OTLP metric exporter"))]
+    BuildExporter { source: MetricsError },
+}
+
+fn meter() -> Meter {
+    global::meter("stackable-opa-operator")
+}
+
+/// Installs the global meter provider exporting the metrics via OTLP.
+///
+/// The returned provider must be shut down before exiting, to flush the remaining metrics.
+///
+/// This does not use the `stackable-telemetry` builder, as the version matching stackable-operator
+/// 0.85 only sets up tracing (console and OTLP logs and traces), not metrics, and is not a
+/// dependency of stackable-operator yet.
+pub fn init() -> Result<SdkMeterProvider, Error> {
+    let provider = opentelemetry_otlp::new_pipeline()
+        .metrics(runtime::Tokio)
+        .with_exporter(opentelemetry_otlp::new_exporter().tonic())
+        .with_resource(Resource::new([KeyValue::new("service.name", APP_NAME)]))
+        .build()
+        .context(BuildExporterSnafu)?;
+    global::set_meter_provider(provider.clone());
+    Ok(provider)
+}
+
+/// Reports the number of OpaClusters watched by the operator via OTLP.
+pub fn observe_opa_clusters(store: Store<DeserializeGuard<OpaCluster>>) {
+    meter()
+        .u64_observable_gauge("opa_operator.opa_clusters")
+        .with_description("The number of OpaClusters watched by the operator")
+        .with_callback(move |observer| observer.observe(store.state().len() as u64, &[]))
+        .init();
+}
+
+/// The upper bounds of the buckets of the reconciliation duration histogram, in seconds.
+const DURATION_BUCKETS: [f64; 10] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ControllerLabels {
+    controller: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ReconcileLabels {
+    controller: String,
+    result: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct ErrorLabels {
+    controller: String,
+    error: String,
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+struct AppliedResourceLabels {
+    controller: String,
+    kind: String,
+}
+
+/// The metrics served in the OpenMetrics text format, shared by all controllers.
+pub struct PrometheusMetrics {
+    registry: Registry,
+    opa_cluster_store: OnceLock<Store<DeserializeGuard<OpaCluster>>>,
+    opa_clusters: Gauge,
+    reconciles: Family<ReconcileLabels, PrometheusCounter>,
+    errors: Family<ErrorLabels, PrometheusCounter>,
+    durations: Family<ControllerLabels, PrometheusHistogram, fn() -> PrometheusHistogram>,
+    in_progress: Family<ControllerLabels, Gauge>,
+    applied_resources: Family<AppliedResourceLabels, PrometheusCounter>,
+}
+
+impl Default for PrometheusMetrics {
+    fn default() -> Self {
+        let mut metrics = Self {
+            registry: Registry::default(),
+            opa_cluster_store: OnceLock::new(),
+            opa_clusters: Gauge::default(),
+            reconciles: Family::default(),
+            errors: Family::default(),
+            durations: Family::new_with_constructor(|| {
+                PrometheusHistogram::new(DURATION_BUCKETS.into_iter())
+            }),
+            in_progress: Family::default(),
+            applied_resources: Family::default(),
+        };
+        let registry = &mut metrics.registry;
+        registry.register(
+            "opa_operator_opa_clusters",
+            "The number of OpaClusters watched by the operator",
+            metrics.opa_clusters.clone(),
+        );
+        registry.register(
+            "opa_operator_reconciles",
+            "The number of reconciliations, by result",
+            metrics.reconciles.clone(),
+        );
+        registry.register(
+            "opa_operator_reconcile_errors",
+            "The number of failed reconciliations, by error",
+            metrics.errors.clone(),
+        );
+        registry.register(
+            "opa_operator_reconcile_duration_seconds",
+            "How long reconciliations took",
+            metrics.durations.clone(),
+        );
+        registry.register(
+            "opa_operator_reconciles_in_progress",
+            "The number of reconciliations currently running",
+            metrics.in_progress.clone(),
+        );
+        registry.register(
+            "opa_operator_applied_resources",
+            "The number of objects applied by the controller, by kind",
+            metrics.applied_resources.clone(),
+        );
+        metrics
+    }
+}
+
+impl PrometheusMetrics {
+    /// Reports the number of OpaClusters watched by the operator.
+    pub fn observe_opa_clusters(&self, store: Store<DeserializeGuard<OpaCluster>>) {
+        let _ = self.opa_cluster_store.set(store);
+    }
+
+    /// Counts an object applied by the `controller`, by its kind.
+    pub fn record_applied_resource<T: KubeResource<DynamicType = ()>>(
+        &self,
+        controller: &str,
+        _object: &T,
+    ) {
+        self.applied_resources
+            .get_or_create(&AppliedResourceLabels {
+                controller: controller.to_string(),
+                kind: T::kind(&()).into_owned(),
+            })
+            .inc();
+    }
+
+    /// Renders the metrics in the OpenMetrics text format.
+    pub fn render(&self) -> String {
+        if let Some(store) = self.opa_cluster_store.get() {
+            self.opa_clusters.set(store.state().len() as i64);
+        }
+        let mut rendered = String::new();
+        encode(&mut rendered, &self.registry).expect("writing to a String cannot fail");
+        rendered
+    }
+}
+
+/// The metrics about the reconciliations of a single controller.
+#[derive(Clone)]
+pub struct ReconcileMetrics {
+    controller_name: &'static str,
+    controller: KeyValue,
+    reconciles: Counter<u64>,
+    duration: Histogram<f64>,
+    errors: Counter<u64>,
+    prometheus: Arc<PrometheusMetrics>,
+}
+
+impl ReconcileMetrics {
+    pub fn new(controller: &'static str, prometheus: Arc<PrometheusMetrics>) -> Self {
+        let meter = meter();
+        Self {
+            controller_name: controller,
+            controller: KeyValue::new("controller", controller),
+            reconciles: meter
+                .u64_counter("opa_operator.reconciles")
+                .with_description("The number of reconciliations, by result")
+                .init(),
+            duration: meter
+                .f64_histogram("opa_operator.reconcile.duration")
+                .with_description("How long reconciliations took")
+                .with_unit(Unit::new("s"))
+                .init(),
+            errors: meter
+                .u64_counter("opa_operator.reconcile.errors")
+                .with_description("The number of failed reconciliations, by error category")
+                .init(),
+            prometheus,
+        }
+    }
+
+    /// Records the outcome and duration of the reconciliation `reconcile`.
+    pub async fn measure<T, E: ReconcilerError>(
+        self,
+        reconcile: impl Future<Output = Result<T, E>>,
+    ) -> Result<T, E> {
+        let labels = ControllerLabels {
+            controller: self.controller_name.to_string(),
+        };
+        let in_progress =
+            GaugeGuard::inc(self.prometheus.in_progress.get_or_create(&labels).clone());
+        let start = Instant::now();
+        let result = reconcile.await;
+        let duration = start.elapsed().as_secs_f64();
+        drop(in_progress);
+
+        self.duration.record(duration, &[self.controller.clone()]);
+        self.prometheus
+            .durations
+            .get_or_create(&labels)
+            .observe(duration);
+        let outcome = match &result {
+            Ok(_) => "success",
+            Err(error) => {
+                self.errors.add(
+                    1,
+                    &[
+                        self.controller.clone(),
+                        KeyValue::new("category", error.category()),
+                    ],
+                );
+                // The category is the name of the error variant
+                self.prometheus
+                    .errors
+                    .get_or_create(&ErrorLabels {
+                        controller: self.controller_name.to_string(),
+                        error: error.category().to_string(),
+                    })
+                    .inc();
+                "error"
+            }
+        };
+        self.reconciles.add(
+            1,
+            &[self.controller.clone(), KeyValue::new("result", outcome)],
+        );
+        self.prometheus
+            .reconciles
+            .get_or_create(&ReconcileLabels {
+                controller: self.controller_name.to_string(),
+                result: outcome.to_string(),
+            })
+            .inc();
+        result
+    }
+}
+
+/// Increments the gauge until it is dropped, which also covers cancelled reconciliations.
+struct GaugeGuard(Gauge);
+
+impl GaugeGuard {
+    fn inc(gauge: Gauge) -> Self {
+        gauge.inc();
+        Self(gauge)
+    }
+}
+
+impl Drop for GaugeGuard {
+    fn drop(&mut self) {
+        self.0.dec();
+    }
+}
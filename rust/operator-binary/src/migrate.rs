@@ -0,0 +1,451 @@
+//! The `migrate` subcommand, which translates the legacy `OpenPolicyAgent` objects
+//! (`authz.stackable.tech/v1`) of the old operator into OpaClusters.
+//!
+//! The OpaClusters are printed as YAML manifests to stdout, so that they can be reviewed before
+//! applying them. Everything that cannot be mapped is reported on stderr. A conversion webhook is
+//! not possible, since Kubernetes only converts between versions of the same API group and kind.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use serde_json::json;
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::OpaCluster;
+use stackable_operator::{
+    k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector,
+    kube::{
+        api::{ApiResource, DynamicObject, GroupVersionKind, ListParams},
+        Api, ResourceExt,
+    },
+};
+
+use crate::controller::APP_PORT;
+
+/// The API group of the legacy resource.
+const LEGACY_GROUP: &str = "authz.stackable.tech";
+/// The API version of the legacy resource.
+const LEGACY_VERSION: &str = "v1";
+/// The kind of the legacy resource.
+const LEGACY_KIND: &str = "OpenPolicyAgent";
+/// The role group of the OpaCluster that legacy objects without role groups are migrated to.
+const DEFAULT_ROLE_GROUP: &str = "default";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to create Kubernetes client"))]
+    CreateClient {
+        source: stackable_operator::kube::Error,
+    },
+
+    #[snafu(display("failed to list the legacy {LEGACY_KIND} objects"))]
+    ListLegacyObjects {
+        source: stackable_operator::kube::Error,
+    },
+
+    #[snafu(display("failed to read manifest {path:?}"))]
+    ReadManifest {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to parse manifest {path:?}"))]
+    ParseManifest {
+        source: serde_yaml::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to serialize the OpaCluster {name:?}"))]
+    SerializeOpaCluster {
+        source: serde_yaml::Error,
+        name: String,
+    },
+
+    #[snafu(display("{objects} {LEGACY_KIND} objects could not be migrated"))]
+    MigrationIncomplete { objects: usize },
+}
+
+#[derive(clap::Args)]
+pub struct MigrateArgs {
+    /// The namespace to read the legacy objects from. Defaults to all namespaces.
+    #[clap(long)]
+    namespace: Option<String>,
+
+    /// Read the legacy objects from these (multi-document) YAML manifests instead of a live
+    /// cluster.
+    #[clap(long = "file", conflicts_with = "namespace")]
+    files: Vec<PathBuf>,
+
+    /// The OPA version to use for legacy objects that do not specify one.
+    #[clap(long)]
+    product_version: Option<String>,
+}
+
+/// The spec of the legacy `OpenPolicyAgent` resource. Unknown fields are collected, so that they
+/// can be reported instead of being dropped silently.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacySpec {
+    version: Option<String>,
+    nodes: Option<LegacyRole>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyRole {
+    config: Option<LegacyConfig>,
+    #[serde(default)]
+    role_groups: BTreeMap<String, LegacyRoleGroup>,
+    #[serde(default)]
+    env_overrides: BTreeMap<String, String>,
+    config_overrides: Option<serde_json::Value>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyRoleGroup {
+    replicas: Option<u16>,
+    selector: Option<LabelSelector>,
+    config: Option<LegacyConfig>,
+    #[serde(default)]
+    env_overrides: BTreeMap<String, String>,
+    config_overrides: Option<serde_json::Value>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LegacyConfig {
+    repo_rule_reference: Option<String>,
+    port: Option<u16>,
+    #[serde(flatten)]
+    unknown: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+struct Finding {
+    severity: Severity,
+    /// The legacy object (and field) the finding applies to.
+    location: String,
+    message: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{severity}: {}: {}", self.location, self.message)
+    }
+}
+
+/// Collects the findings of the migration of a single legacy object.
+struct Report<'a> {
+    object: String,
+    findings: &'a mut Vec<Finding>,
+}
+
+impl Report<'_> {
+    fn add(&mut self, severity: Severity, field: &str, message: impl Into<String>) {
+        self.findings.push(Finding {
+            severity,
+            location: format!("{object}: {field}", object = self.object),
+            message: message.into(),
+        });
+    }
+
+    fn unknown_fields(&mut self, path: &str, unknown: &BTreeMap<String, serde_json::Value>) {
+        for field in unknown.keys() {
+            self.add(
+                Severity::Warning,
+                &format!("{path}.{field}"),
+                "unknown field, not migrated",
+            );
+        }
+    }
+}
+
+pub async fn run(args: MigrateArgs) -> Result<(), Error> {
+    let legacy_objects = if args.files.is_empty() {
+        list_legacy_objects(&args).await?
+    } else {
+        read_legacy_objects(&args)?
+    };
+
+    let mut findings = Vec::new();
+    let mut failed_objects = 0;
+    for legacy in &legacy_objects {
+        match migrate(legacy, args.product_version.as_deref(), &mut findings) {
+            Some(opa) => {
+                let manifest = serde_yaml::to_string(&opa).context(SerializeOpaClusterSnafu {
+                    name: opa.name_any(),
+                })?;
+                println!("---\n{manifest}");
+            }
+            None => failed_objects += 1,
+        }
+    }
+
+    findings.sort_by_key(|finding| finding.severity);
+    for finding in &findings {
+        eprintln!("{finding}");
+    }
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    eprintln!(
+        "migrated {migrated} of {total} {LEGACY_KIND} objects: {errors} errors, {warnings} warnings",
+        migrated = legacy_objects.len() - failed_objects,
+        total = legacy_objects.len(),
+        warnings = findings.len() - errors,
+    );
+    if failed_objects > 0 {
+        return MigrationIncompleteSnafu {
+            objects: failed_objects,
+        }
+        .fail();
+    }
+    Ok(())
+}
+
+fn legacy_api_resource() -> ApiResource {
+    ApiResource::from_gvk(&GroupVersionKind::gvk(
+        LEGACY_GROUP,
+        LEGACY_VERSION,
+        LEGACY_KIND,
+    ))
+}
+
+async fn list_legacy_objects(args: &MigrateArgs) -> Result<Vec<DynamicObject>, Error> {
+    let client = stackable_operator::kube::Client::try_default()
+        .await
+        .context(CreateClientSnafu)?;
+    let api_resource = legacy_api_resource();
+    let api = match &args.namespace {
+        Some(namespace) => Api::<DynamicObject>::namespaced_with(client, namespace, &api_resource),
+        None => Api::<DynamicObject>::all_with(client, &api_resource),
+    };
+    Ok(api
+        .list(&ListParams::default())
+        .await
+        .context(ListLegacyObjectsSnafu)?
+        .items)
+}
+
+fn read_legacy_objects(args: &MigrateArgs) -> Result<Vec<DynamicObject>, Error> {
+    let mut legacy_objects = Vec::new();
+    for path in &args.files {
+        legacy_objects.extend(read_manifest(path)?);
+    }
+    Ok(legacy_objects)
+}
+
+fn read_manifest(path: &Path) -> Result<Vec<DynamicObject>, Error> {
+    let manifest = std::fs::read_to_string(path).context(ReadManifestSnafu { path })?;
+    let mut legacy_objects = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(&manifest) {
+        let object =
+            serde_yaml::Value::deserialize(document).context(ParseManifestSnafu { path })?;
+        let is_legacy = object.get("kind").and_then(|kind| kind.as_str()) == Some(LEGACY_KIND)
+            && object
+                .get("apiVersion")
+                .and_then(|api_version| api_version.as_str())
+                == Some(format!("{LEGACY_GROUP}/{LEGACY_VERSION}").as_str());
+        if is_legacy {
+            legacy_objects.push(
+                serde_yaml::from_value::<DynamicObject>(object)
+                    .context(ParseManifestSnafu { path })?,
+            );
+        }
+    }
+    Ok(legacy_objects)
+}
+
+/// Translates a legacy object into an OpaCluster, or returns `None` (with an error finding) if it
+/// cannot be migrated at all.
+fn migrate(
+    legacy: &DynamicObject,
+    default_product_version: Option<&str>,
+    findings: &mut Vec<Finding>,
+) -> Option<OpaCluster> {
+    let mut report = Report {
+        object: format!(
+            "{LEGACY_KIND}/{name}.{namespace}",
+            name = legacy.name_any(),
+            namespace = legacy.namespace().unwrap_or_default()
+        ),
+        findings,
+    };
+    let spec = match legacy.data.get("spec") {
+        Some(spec) => match LegacySpec::deserialize(spec) {
+            Ok(spec) => spec,
+            Err(error) => {
+                report.add(
+                    Severity::Error,
+                    "spec",
+                    format!("failed to parse the legacy spec: {error}"),
+                );
+                return None;
+            }
+        },
+        None => LegacySpec::default(),
+    };
+    report.unknown_fields("spec", &spec.unknown);
+
+    let Some(product_version) = spec.version.as_deref().or(default_product_version) else {
+        report.add(
+            Severity::Error,
+            "spec.version",
+            "no OPA version is set, pass one with --product-version",
+        );
+        return None;
+    };
+
+    let nodes = spec.nodes.unwrap_or_default();
+    report.unknown_fields("spec.nodes", &nodes.unknown);
+    if nodes.config_overrides.is_some() {
+        report.add(
+            Severity::Warning,
+            "spec.nodes.configOverrides",
+            "the configuration files of OPA have changed, set the overrides of config.json again",
+        );
+    }
+    if let Some(config) = &nodes.config {
+        migrate_config(config, "spec.nodes.config", &mut report);
+    }
+
+    let mut role_groups = serde_json::Map::new();
+    for (role_group_name, role_group) in &nodes.role_groups {
+        let path = format!("spec.nodes.roleGroups.{role_group_name}");
+        role_groups.insert(
+            role_group_name.clone(),
+            migrate_role_group(role_group, &path, &mut report),
+        );
+    }
+    if role_groups.is_empty() {
+        report.add(
+            Severity::Warning,
+            "spec.nodes.roleGroups",
+            format!("no role groups are defined, adding the role group {DEFAULT_ROLE_GROUP:?}"),
+        );
+        role_groups.insert(DEFAULT_ROLE_GROUP.to_string(), json!({}));
+    }
+
+    let opa = json!({
+        "apiVersion": "opa.stackable.tech/v1alpha1",
+        "kind": "OpaCluster",
+        "metadata": {
+            "name": legacy.name_any(),
+            "namespace": legacy.namespace(),
+            // The annotations (such as kubectl.kubernetes.io/last-applied-configuration) refer to
+            // the legacy object
+            "labels": legacy.labels(),
+        },
+        "spec": {
+            "image": {
+                "productVersion": product_version,
+            },
+            "servers": {
+                "envOverrides": nodes.env_overrides,
+                "roleGroups": role_groups,
+            },
+        },
+    });
+    match serde_json::from_value::<OpaCluster>(opa) {
+        Ok(opa) => Some(opa),
+        Err(error) => {
+            report.add(
+                Severity::Error,
+                "spec",
+                format!("failed to build a valid OpaCluster: {error}"),
+            );
+            None
+        }
+    }
+}
+
+fn migrate_role_group(
+    role_group: &LegacyRoleGroup,
+    path: &str,
+    report: &mut Report,
+) -> serde_json::Value {
+    report.unknown_fields(path, &role_group.unknown);
+    if let Some(replicas) = role_group.replicas {
+        report.add(
+            Severity::Warning,
+            &format!("{path}.replicas"),
+            format!(
+                "OPA runs on every matching node (as a DaemonSet), the {replicas} replicas are ignored"
+            ),
+        );
+    }
+    if role_group.config_overrides.is_some() {
+        report.add(
+            Severity::Warning,
+            &format!("{path}.configOverrides"),
+            "the configuration files of OPA have changed, set the overrides of config.json again",
+        );
+    }
+    if let Some(config) = &role_group.config {
+        migrate_config(config, &format!("{path}.config"), report);
+    }
+
+    let mut config = serde_json::Map::new();
+    if let Some(selector) = &role_group.selector {
+        if selector
+            .match_expressions
+            .as_ref()
+            .is_some_and(|expressions| !expressions.is_empty())
+        {
+            report.add(
+                Severity::Warning,
+                &format!("{path}.selector.matchExpressions"),
+                "only matchLabels can be migrated to a node selector, use config.affinity.nodeAffinity instead",
+            );
+        }
+        if let Some(match_labels) = &selector.match_labels {
+            config.insert("nodeSelector".to_string(), json!(match_labels));
+        }
+    }
+    json!({
+        "config": config,
+        "envOverrides": role_group.env_overrides,
+    })
+}
+
+/// The legacy config has no equivalent in OpaClusters, so it is only reported.
+fn migrate_config(config: &LegacyConfig, path: &str, report: &mut Report) {
+    report.unknown_fields(path, &config.unknown);
+    if let Some(repo_rule_reference) = &config.repo_rule_reference {
+        report.add(
+            Severity::Warning,
+            &format!("{path}.repoRuleReference"),
+            format!(
+                "policies are no longer fetched from a rule repository, move the rules from \
+                 {repo_rule_reference} into bundle ConfigMaps (labeled opa.stackable.tech/bundle)"
+            ),
+        );
+    }
+    if let Some(port) = config.port.filter(|port| *port != APP_PORT) {
+        report.add(
+            Severity::Warning,
+            &format!("{path}.port"),
+            format!("OPA always listens on port {APP_PORT}, update the clients using port {port}"),
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Restricts the access to the OPA API (and its sidecars) using a NetworkPolicy, since OPA does not
+//! authenticate its clients.
+//!
+//! Like the `HTTPRoute` (see [`crate::gateway`]), the NetworkPolicy is applied (and deleted once it
+//! is disabled) here rather than through [`ClusterResources`](stackable_operator::cluster_resources::ClusterResources).
+
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_opa_crd::{
+    OpaCluster, OpaNetworkPolicyConfig, OpaNetworkPolicyPeer, OpaRole, APP_NAME,
+};
+use stackable_operator::{
+    builder::meta::ObjectMetaBuilder,
+    client::Client,
+    commons::product_image_selection::ResolvedProductImage,
+    k8s_openapi::{
+        api::networking::v1::{
+            NetworkPolicy, NetworkPolicyIngressRule, NetworkPolicyPeer, NetworkPolicyPort,
+            NetworkPolicySpec,
+        },
+        apimachinery::pkg::{apis::meta::v1::LabelSelector, util::intstr::IntOrString},
+    },
+    kube::{api::DeleteParams, Api, Resource, ResourceExt},
+    kvp::Labels,
+};
+
+use crate::{
+    controller::{build_recommended_labels, APP_PORT, BUNDLE_BUILDER_PORT, USER_INFO_FETCHER_PORT},
+    field_ownership::{release_legacy_field_managers, FIELD_MANAGER},
+};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("object has no namespace"))]
+    NoNamespace,
+
+    #[snafu(display("failed to build object metadata"))]
+    BuildMetadata {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to build label"))]
+    BuildLabel {
+        source: stackable_operator::kvp::LabelError,
+    },
+
+    #[snafu(display("failed to apply NetworkPolicy"))]
+    ApplyNetworkPolicy {
+        source: stackable_operator::client::Error,
+    },
+
+    #[snafu(display("failed to get NetworkPolicy {name:?}"))]
+    GetNetworkPolicy {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to delete NetworkPolicy {name:?}"))]
+    DeleteNetworkPolicy {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+}
+
+/// Applies the NetworkPolicy if it is enabled (returning the applied NetworkPolicy), and deletes the
+/// NetworkPolicy created while it was enabled otherwise.
+pub async fn reconcile_network_policy(
+    client: &Client,
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+) -> Result<Option<NetworkPolicy>, Error> {
+    let config = &opa.spec.cluster_config.network_policy;
+    if config.enabled {
+        let network_policy = build_network_policy(opa, resolved_product_image, config)?;
+        let network_policy = client
+            .apply_patch(FIELD_MANAGER, &network_policy, &network_policy)
+            .await
+            .context(ApplyNetworkPolicySnafu)?;
+        // Previous versions applied the NetworkPolicy with the legacy field manager
+        release_legacy_field_managers(client, &network_policy)
+            .await
+            .context(ApplyNetworkPolicySnafu)?;
+        Ok(Some(network_policy))
+    } else {
+        delete_network_policy(client, opa).await?;
+        Ok(None)
+    }
+}
+
+fn build_network_policy(
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    config: &OpaNetworkPolicyConfig,
+) -> Result<NetworkPolicy, Error> {
+    let role_name = OpaRole::Server.to_string();
+    let metadata = ObjectMetaBuilder::new()
+        .name_and_namespace(opa)
+        .ownerreference_from_resource(opa, None, Some(true))
+        .context(BuildMetadataSnafu)?
+        .with_recommended_labels(build_recommended_labels(
+            opa,
+            &resolved_product_image.app_version_label,
+            &role_name,
+            "global",
+        ))
+        .context(BuildMetadataSnafu)?
+        .build();
+    let pod_selector_labels =
+        Labels::role_selector(opa, APP_NAME, &role_name).context(BuildLabelSnafu)?;
+
+    // OPA reaches the bundle builder and the user-info-fetcher within its own Pod, which is always
+    // allowed, so only the allowed clients can reach them from outside (e.g. to scrape their metrics).
+    // A rule without peers would allow all clients, so no rule is added if none are allowed.
+    let mut ingress = vec![];
+    if !config.allowed_clients.is_empty() {
+        ingress.push(NetworkPolicyIngressRule {
+            ports: Some(
+                [
+                    APP_PORT.into(),
+                    BUNDLE_BUILDER_PORT,
+                    USER_INFO_FETCHER_PORT.into(),
+                ]
+                .into_iter()
+                .map(tcp_port)
+                .collect(),
+            ),
+            from: Some(config.allowed_clients.iter().map(build_peer).collect()),
+        });
+    }
+
+    Ok(NetworkPolicy {
+        metadata,
+        spec: Some(NetworkPolicySpec {
+            pod_selector: LabelSelector {
+                match_labels: Some(pod_selector_labels.into()),
+                ..LabelSelector::default()
+            },
+            policy_types: Some(vec!["Ingress".to_string()]),
+            ingress: Some(ingress),
+            ..NetworkPolicySpec::default()
+        }),
+    })
+}
+
+fn build_peer(peer: &OpaNetworkPolicyPeer) -> NetworkPolicyPeer {
+    let selector = |labels: &Option<_>| {
+        labels.as_ref().map(|labels| LabelSelector {
+            match_labels: Some(labels.clone()),
+            ..LabelSelector::default()
+        })
+    };
+    NetworkPolicyPeer {
+        namespace_selector: selector(&peer.namespace_labels),
+        // A peer without selectors is invalid, so select all Pods of the namespace of the OpaCluster
+        pod_selector: selector(&peer.pod_labels)
+            .or_else(|| peer.namespace_labels.is_none().then(LabelSelector::default)),
+        ..NetworkPolicyPeer::default()
+    }
+}
+
+fn tcp_port(port: i32) -> NetworkPolicyPort {
+    NetworkPolicyPort {
+        port: Some(IntOrString::Int(port)),
+        protocol: Some("TCP".to_string()),
+        ..NetworkPolicyPort::default()
+    }
+}
+
+/// Deletes the NetworkPolicy of the OpaCluster, if there is one.
+async fn delete_network_policy(client: &Client, opa: &OpaCluster) -> Result<(), Error> {
+    let namespace = opa.namespace().context(NoNamespaceSnafu)?;
+    let name = opa.name_any();
+    let api = Api::<NetworkPolicy>::namespaced(client.as_kube_client(), &namespace);
+    let Some(network_policy) = api
+        .get_opt(&name)
+        .await
+        .context(GetNetworkPolicySnafu { name: &name })?
+    else {
+        return Ok(());
+    };
+    // NetworkPolicies that were created by someone else are left alone
+    if !network_policy
+        .owner_references()
+        .iter()
+        .any(|owner| Some(&owner.uid) == opa.meta().uid.as_ref())
+    {
+        return Ok(());
+    }
+    tracing::info!(
+        network_policy = name,
+        "deleting NetworkPolicy that is no longer enabled"
+    );
+    api.delete(&name, &DeleteParams::default())
+        .await
+        .context(DeleteNetworkPolicySnafu { name })?;
+    Ok(())
+}
@@ -0,0 +1,107 @@
+//! The features of OPA that are only available as of some version, so that the config file and the
+//! command line flags of OPA are only rendered with what the deployed OPA version supports.
+
+use semver::Version;
+use snafu::Snafu;
+use stackable_opa_crd::OpaConfig;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display(
+        "{feature} requires OPA {min_version} or later, but OPA {product_version} is deployed"
+    ))]
+    UnsupportedFeature {
+        feature: &'static str,
+        min_version: Version,
+        product_version: String,
+    },
+}
+
+/// A feature of OPA and the first version supporting it.
+pub struct Feature {
+    name: &'static str,
+    min_version: (u64, u64, u64),
+}
+
+pub const DISABLE_TELEMETRY: Feature = Feature {
+    name: "--disable-telemetry",
+    min_version: (0, 34, 0),
+};
+pub const STATUS_PROMETHEUS: Feature = Feature {
+    name: "status.prometheus",
+    min_version: (0, 25, 0),
+};
+pub const BUNDLE_LONG_POLLING: Feature = Feature {
+    name: "bundlePolling.longPollingTimeout",
+    min_version: (0, 26, 0),
+};
+pub const NON_DETERMINISTIC_BUILTIN_CACHE: Feature = Feature {
+    name: "serverConfig.nonDeterministicBuiltinCache",
+    min_version: (0, 44, 0),
+};
+pub const BUILTIN_VALUE_CACHE: Feature = Feature {
+    name: "serverConfig.builtinValueCacheMaxEntries",
+    min_version: (0, 61, 0),
+};
+pub const READY_TIMEOUT: Feature = Feature {
+    name: "serverConfig.readyTimeout",
+    min_version: (0, 62, 0),
+};
+
+/// The version of the deployed OPA.
+pub struct OpaVersion<'a> {
+    product_version: &'a str,
+    /// `None` if the product version is not a semantic version, for example for custom images.
+    version: Option<Version>,
+}
+
+impl<'a> OpaVersion<'a> {
+    pub fn new(product_version: &'a str) -> Self {
+        Self {
+            product_version,
+            version: Version::parse(product_version).ok(),
+        }
+    }
+
+    /// Whether the OPA version supports `feature`.
+    ///
+    /// Versions that can not be parsed are assumed to be recent enough to support all features.
+    pub fn supports(&self, feature: &Feature) -> bool {
+        self.version.as_ref().is_none_or(|version| {
+            (version.major, version.minor, version.patch) >= feature.min_version
+        })
+    }
+
+    /// Checks that the OPA version supports all features configured in the role group.
+    pub fn validate(
+        &self,
+        merged_config: &OpaConfig,
+        bundle_long_polling: bool,
+    ) -> Result<(), Error> {
+        let server_config = &merged_config.server_config;
+        let configured_features = [
+            (&BUNDLE_LONG_POLLING, bundle_long_polling),
+            (
+                &NON_DETERMINISTIC_BUILTIN_CACHE,
+                server_config.non_deterministic_builtin_cache.is_some(),
+            ),
+            (
+                &BUILTIN_VALUE_CACHE,
+                server_config.builtin_value_cache_max_entries.is_some(),
+            ),
+            (&READY_TIMEOUT, server_config.ready_timeout.is_some()),
+        ];
+        for (feature, configured) in configured_features {
+            if configured && !self.supports(feature) {
+                let (major, minor, patch) = feature.min_version;
+                return UnsupportedFeatureSnafu {
+                    feature: feature.name,
+                    min_version: Version::new(major, minor, patch),
+                    product_version: self.product_version,
+                }
+                .fail();
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,203 @@
+//! Promotes the latest bundle to the stable bundle once it has soaked on the canary role groups
+//! and passed its tests.
+//!
+//! The bundle builders store the latest bundle in the bundle snapshot ConfigMap, which is what the
+//! canary role groups are served. All other role groups are served the stable bundle ConfigMap,
+//! which only the operator writes to.
+
+use std::time::Duration;
+
+use const_format::concatcp;
+use serde_json::json;
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_opa_crd::{
+    OpaCanaryStatus, OpaCluster, OpaPolicyTestsResult, OpaPolicyTestsStatus,
+    BUNDLE_SNAPSHOT_HASH_ANNOTATION, BUNDLE_SNAPSHOT_KEY, OPERATOR_NAME,
+};
+use stackable_operator::{
+    client::Client,
+    cluster_resources::ClusterResources,
+    commons::product_image_selection::ResolvedProductImage,
+    k8s_openapi::{
+        api::core::v1::ConfigMap, apimachinery::pkg::apis::meta::v1::Time, chrono::Utc, ByteString,
+    },
+    kube::{
+        api::{Patch, PatchParams},
+        Api, ResourceExt,
+    },
+};
+
+use crate::operations::policy_testing::{self, reconcile_policy_tests};
+
+/// The promoted bundle is written with a separate field manager, so that the operator applying the
+/// (empty) stable bundle ConfigMap does not remove it again.
+pub const CANARY_ROLLOUT_FIELD_MANAGER: &str = concatcp!(OPERATOR_NAME, "_canaryrollout");
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("object defines no namespace"))]
+    ObjectHasNoNamespace,
+
+    #[snafu(display("failed to get bundle snapshot ConfigMap {name:?}"))]
+    GetBundleSnapshot {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to promote bundle to stable bundle ConfigMap {name:?}"))]
+    PromoteBundle {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to test policies"))]
+    PolicyTests { source: policy_testing::Error },
+}
+
+#[derive(Default)]
+pub struct CanaryRollout {
+    /// The bundle that is still soaking on the canary, if any.
+    pub status: Option<OpaCanaryStatus>,
+    /// How long until the soaking bundle is due for promotion.
+    pub requeue_after: Option<Duration>,
+    /// The results of testing the latest bundle, if policy testing is enabled.
+    pub policy_tests: Option<OpaPolicyTestsStatus>,
+}
+
+/// Promotes the latest bundle to `stable_bundle_config_map` once it has soaked for the configured period
+/// (if canary rollouts are enabled) and its tests have passed (if policy testing is enabled).
+///
+/// Without failing tests, the first bundle is promoted right away, since the other role groups have no
+/// policies before that.
+pub async fn reconcile_canary_rollout(
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    stable_bundle_config_map: &ConfigMap,
+    client: &Client,
+    cluster_resources: &mut ClusterResources,
+) -> Result<CanaryRollout, Error> {
+    let namespace = opa.namespace().context(ObjectHasNoNamespaceSnafu)?;
+    let api = Api::<ConfigMap>::namespaced(client.as_kube_client(), &namespace);
+    let snapshot_name = opa.bundle_snapshot_config_map_name();
+    let snapshot = api
+        .get_opt(&snapshot_name)
+        .await
+        .with_context(|_| GetBundleSnapshotSnafu {
+            name: snapshot_name.clone(),
+        })?;
+    let Some((revision, tarball)) = snapshot.as_ref().and_then(bundle_in_config_map) else {
+        tracing::debug!("no bundle has been snapshotted yet, nothing to promote");
+        return Ok(CanaryRollout::default());
+    };
+    // The latest bundle is tested even if it has been promoted already, to keep the results around
+    let policy_tests = match &opa.spec.cluster_config.policy_testing {
+        Some(policy_testing) => Some(
+            reconcile_policy_tests(
+                policy_testing,
+                opa,
+                resolved_product_image,
+                revision,
+                client,
+                cluster_resources,
+            )
+            .await
+            .context(PolicyTestsSnafu)?,
+        ),
+        None => None,
+    };
+    let stable_revision =
+        bundle_in_config_map(stable_bundle_config_map).map(|(revision, _)| revision);
+    if stable_revision == Some(revision) {
+        return Ok(CanaryRollout {
+            policy_tests,
+            ..CanaryRollout::default()
+        });
+    }
+
+    let (status, remaining) = match &opa.spec.cluster_config.canary_rollout {
+        Some(canary_rollout) => {
+            let since = match opa
+                .status
+                .as_ref()
+                .and_then(|status| status.canary.as_ref())
+            {
+                Some(canary) if canary.revision == revision => canary.since.clone(),
+                _ => Time(Utc::now()),
+            };
+            let soaked_for = (Utc::now() - since.0).to_std().unwrap_or_default();
+            let remaining = canary_rollout.soak_period.saturating_sub(soaked_for);
+            (
+                Some(OpaCanaryStatus {
+                    revision: revision.to_string(),
+                    since,
+                }),
+                remaining,
+            )
+        }
+        None => (None, Duration::ZERO),
+    };
+    let requeue_after = (!remaining.is_zero()).then_some(remaining);
+    // The Job testing the bundle triggers another reconciliation once it has finished
+    let tests_result = policy_tests
+        .as_ref()
+        .map(|policy_tests| policy_tests.result);
+    if tests_result.is_some_and(|result| result != OpaPolicyTestsResult::Passed) {
+        tracing::info!(
+            revision,
+            result = ?tests_result,
+            "bundle has not passed its tests (yet), not promoting it"
+        );
+        return Ok(CanaryRollout {
+            status,
+            requeue_after,
+            policy_tests,
+        });
+    }
+    if stable_revision.is_some() && requeue_after.is_some() {
+        tracing::info!(revision, ?remaining, "canary bundle is soaking");
+        return Ok(CanaryRollout {
+            status,
+            requeue_after,
+            policy_tests,
+        });
+    }
+
+    let stable_name = stable_bundle_config_map.name_any();
+    tracing::info!(
+        revision,
+        config_map = stable_name,
+        "promoting canary bundle to stable bundle"
+    );
+    api.patch(
+        &stable_name,
+        &PatchParams::apply(CANARY_ROLLOUT_FIELD_MANAGER).force(),
+        &Patch::Apply(json!({
+            "apiVersion": "v1",
+            "kind": "ConfigMap",
+            "metadata": {
+                "annotations": {
+                    BUNDLE_SNAPSHOT_HASH_ANNOTATION: revision,
+                },
+            },
+            "binaryData": {
+                BUNDLE_SNAPSHOT_KEY: tarball,
+            },
+        })),
+    )
+    .await
+    .context(PromoteBundleSnafu { name: stable_name })?;
+    Ok(CanaryRollout {
+        policy_tests,
+        ..CanaryRollout::default()
+    })
+}
+
+/// Returns the hash and the tarball of the bundle stored in the ConfigMap, if any.
+fn bundle_in_config_map(cm: &ConfigMap) -> Option<(&str, &ByteString)> {
+    Some((
+        cm.annotations()
+            .get(BUNDLE_SNAPSHOT_HASH_ANNOTATION)?
+            .as_str(),
+        cm.binary_data.as_ref()?.get(BUNDLE_SNAPSHOT_KEY)?,
+    ))
+}
@@ -1,5 +1,8 @@
 use snafu::{ResultExt, Snafu};
-use stackable_opa_crd::{OpaConfig, SERVER_GRACEFUL_SHUTDOWN_SAFETY_OVERHEAD};
+use stackable_opa_crd::{
+    OpaConfig, MAX_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT, MIN_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT,
+    SERVER_GRACEFUL_SHUTDOWN_SAFETY_OVERHEAD,
+};
 use stackable_operator::builder::pod::PodBuilder;
 
 #[derive(Debug, Snafu)]
@@ -10,6 +13,24 @@ pub enum Error {
     },
 }
 
+/// Clamps the graceful shutdown timeout to the range that OPA and node drains can handle.
+///
+/// Returns a warning if the configured timeout had to be changed.
+pub fn clamp_graceful_shutdown_timeout(merged_config: &mut OpaConfig) -> Option<String> {
+    let timeout = merged_config.graceful_shutdown_timeout.as_mut()?;
+    let (limit, bound) = if *timeout < MIN_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT {
+        (MIN_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT, "below the minimum")
+    } else if *timeout > MAX_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT {
+        (MAX_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT, "above the maximum")
+    } else {
+        return None;
+    };
+    let warning =
+        format!("gracefulShutdownTimeout {timeout} is {bound} of {limit}, using {limit} instead");
+    *timeout = limit;
+    Some(warning)
+}
+
 pub fn add_graceful_shutdown_config(
     merged_config: &OpaConfig,
     pod_builder: &mut PodBuilder,
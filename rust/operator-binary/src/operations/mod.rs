@@ -1 +1,5 @@
+pub mod canary_rollout;
 pub mod graceful_shutdown;
+pub mod pdb;
+pub mod policy_testing;
+pub mod stop;
@@ -0,0 +1,59 @@
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::{OpaCluster, OpaRole, APP_NAME, OPERATOR_NAME};
+use stackable_operator::{
+    builder::pdb::PodDisruptionBudgetBuilder, client::Client, cluster_resources::ClusterResources,
+    commons::pdb::PdbConfig, kube::ResourceExt,
+};
+
+use crate::controller::OPA_CONTROLLER_NAME;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("Cannot create PodDisruptionBudget for role [{role}]"))]
+    CreatePdb {
+        source: stackable_operator::builder::pdb::Error,
+        role: String,
+    },
+
+    #[snafu(display("Cannot apply PodDisruptionBudget [{name}]"))]
+    ApplyPdb {
+        source: stackable_operator::cluster_resources::Error,
+        name: String,
+    },
+}
+
+pub async fn add_pdbs(
+    pdb: &PdbConfig,
+    opa: &OpaCluster,
+    role: &OpaRole,
+    client: &Client,
+    cluster_resources: &mut ClusterResources,
+) -> Result<(), Error> {
+    if !pdb.enabled {
+        return Ok(());
+    }
+    let max_unavailable = pdb.max_unavailable.unwrap_or(match role {
+        // OPA runs on every node, and products query the instance on their own node, so taking out
+        // one instance at a time only affects the products on a single node.
+        OpaRole::Server => 1,
+    });
+    let pdb = PodDisruptionBudgetBuilder::new_with_role(
+        opa,
+        APP_NAME,
+        &role.to_string(),
+        OPERATOR_NAME,
+        OPA_CONTROLLER_NAME,
+    )
+    .with_context(|_| CreatePdbSnafu {
+        role: role.to_string(),
+    })?
+    .with_max_unavailable(max_unavailable)
+    .build();
+    let pdb_name = pdb.name_any();
+    cluster_resources
+        .add(client, pdb)
+        .await
+        .with_context(|_| ApplyPdbSnafu { name: pdb_name })?;
+
+    Ok(())
+}
@@ -0,0 +1,334 @@
+//! Tests the policies of every new bundle in a Job, so that only bundles whose tests pass are promoted
+//! to the stable bundle ConfigMap.
+//!
+//! The Job runs `opa test` (and `opa bench` for the configured queries) against the bundle snapshot,
+//! which contains the same bundle the bundle builders serve.
+
+use indoc::formatdoc;
+use sha2::{Digest, Sha256};
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::{
+    OpaCluster, OpaPolicyTestingConfig, OpaPolicyTestsResult, OpaPolicyTestsStatus,
+    BUNDLE_SNAPSHOT_KEY,
+};
+use stackable_operator::{
+    builder::{
+        self,
+        meta::ObjectMetaBuilder,
+        pod::{
+            container::ContainerBuilder, resources::ResourceRequirementsBuilder,
+            security::PodSecurityContextBuilder, volume::VolumeBuilder, PodBuilder,
+        },
+    },
+    client::Client,
+    cluster_resources::ClusterResources,
+    commons::product_image_selection::ResolvedProductImage,
+    k8s_openapi::api::{
+        batch::v1::{Job, JobSpec},
+        core::v1::{EnvVar, Pod},
+    },
+    kube::{
+        api::{DeleteParams, ListParams},
+        Api, ResourceExt,
+    },
+    kvp::{Annotation, AnnotationError},
+};
+
+use crate::controller::build_recommended_labels;
+
+const POLICY_TESTS_ROLE: &str = "policy-tests";
+const POLICY_TESTS_CONTAINER_NAME: &str = "policy-tests";
+const BUNDLE_VOLUME_NAME: &str = "bundle";
+const BUNDLE_DIR: &str = "/stackable/bundle";
+/// The hash of the Pod template of the Job, which can't be updated once the Job has been created.
+const JOB_TEMPLATE_HASH_ANNOTATION: &str = "opa.stackable.tech/job-template-hash";
+/// Retries Pods that fail for other reasons than the tests, such as evictions or node failures.
+const JOB_BACKOFF_LIMIT: i32 = 2;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("object is missing metadata to build owner reference"))]
+    ObjectMissingMetadataForOwnerRef { source: builder::meta::Error },
+
+    #[snafu(display("failed to build Metadata"))]
+    ObjectMeta { source: builder::meta::Error },
+
+    #[snafu(display("failed to build the Pod template hash annotation"))]
+    BuildAnnotation { source: AnnotationError },
+
+    #[snafu(display("illegal container name"))]
+    IllegalContainerName {
+        source: builder::pod::container::Error,
+    },
+
+    #[snafu(display("failed to add needed volume"))]
+    AddVolume { source: builder::pod::Error },
+
+    #[snafu(display("failed to add needed volumeMount"))]
+    AddVolumeMount {
+        source: builder::pod::container::Error,
+    },
+
+    #[snafu(display("failed to apply policy tests Job {name:?}"))]
+    ApplyJob {
+        source: stackable_operator::cluster_resources::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to serialize the Pod template of policy tests Job {name:?}"))]
+    SerializeJobTemplate {
+        source: serde_json::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to get policy tests Job {name:?}"))]
+    GetJob {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to delete outdated policy tests Job {name:?}"))]
+    DeleteJob {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to list the Pods of policy tests Job {name:?}"))]
+    ListJobPods {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+}
+
+/// Makes sure the bundle `revision` is (being) tested, and returns the results of the tests.
+///
+/// Every revision is tested by a separate Job, the Jobs of previous revisions are deleted as orphans.
+/// Since the Pod template of a Job can't be updated, the Job is deleted (and recreated by a later
+/// reconciliation) if its template changes, for example because the OPA image changed.
+pub async fn reconcile_policy_tests(
+    config: &OpaPolicyTestingConfig,
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    revision: &str,
+    client: &Client,
+    cluster_resources: &mut ClusterResources,
+) -> Result<OpaPolicyTestsStatus, Error> {
+    let job = build_policy_tests_job(config, opa, resolved_product_image, revision)?;
+    let job_name = job.name_any();
+    let running = || OpaPolicyTestsStatus {
+        revision: revision.to_string(),
+        result: OpaPolicyTestsResult::Running,
+        message: None,
+    };
+
+    let jobs = Api::<Job>::namespaced(
+        client.as_kube_client(),
+        &opa.namespace().unwrap_or_default(),
+    );
+    let existing_job = jobs
+        .get_opt(&job_name)
+        .await
+        .context(GetJobSnafu { name: &job_name })?;
+    if let Some(existing_job) = existing_job {
+        if existing_job.annotations().get(JOB_TEMPLATE_HASH_ANNOTATION)
+            != job.annotations().get(JOB_TEMPLATE_HASH_ANNOTATION)
+        {
+            tracing::info!(
+                job = job_name,
+                "deleting policy tests Job with an outdated Pod template"
+            );
+            jobs.delete(&job_name, &DeleteParams::background())
+                .await
+                .context(DeleteJobSnafu { name: &job_name })?;
+            return Ok(running());
+        }
+    }
+
+    let job = cluster_resources
+        .add(client, job)
+        .await
+        .with_context(|_| ApplyJobSnafu {
+            name: job_name.clone(),
+        })?;
+
+    // Failed Pods are retried, so the Job has only failed once it reports so
+    let job_finished_with = |condition_type: &str| {
+        job.status
+            .iter()
+            .flat_map(|status| status.conditions.iter().flatten())
+            .any(|condition| condition.type_ == condition_type && condition.status == "True")
+    };
+    let result = if job_finished_with("Complete") {
+        OpaPolicyTestsResult::Passed
+    } else if job_finished_with("Failed") {
+        OpaPolicyTestsResult::Failed
+    } else {
+        return Ok(running());
+    };
+    tracing::info!(revision, ?result, "policy tests finished");
+
+    Ok(OpaPolicyTestsStatus {
+        revision: revision.to_string(),
+        result,
+        message: termination_message(opa, &job_name, client).await?,
+    })
+}
+
+fn build_policy_tests_job(
+    config: &OpaPolicyTestingConfig,
+    opa: &OpaCluster,
+    resolved_product_image: &ResolvedProductImage,
+    revision: &str,
+) -> Result<Job, Error> {
+    let labels = || {
+        build_recommended_labels(
+            opa,
+            &resolved_product_image.app_version_label,
+            POLICY_TESTS_ROLE,
+            "global",
+        )
+    };
+    let job_name = opa.policy_tests_job_name(revision);
+
+    // Passed as environment variables, so that the queries don't need to be quoted for the shell
+    let benchmark_query_env_vars = config
+        .benchmark_queries
+        .iter()
+        .enumerate()
+        .map(|(i, query)| EnvVar {
+            name: format!("BENCHMARK_QUERY_{i}"),
+            value: Some(query.clone()),
+            ..EnvVar::default()
+        })
+        .collect::<Vec<_>>();
+
+    let mut cb =
+        ContainerBuilder::new(POLICY_TESTS_CONTAINER_NAME).context(IllegalContainerNameSnafu)?;
+    cb.image_from_product_image(resolved_product_image)
+        .command(vec![
+            "/bin/bash".to_string(),
+            "-euo".to_string(),
+            "pipefail".to_string(),
+            "-c".to_string(),
+        ])
+        .args(vec![build_policy_tests_command(
+            revision,
+            benchmark_query_env_vars.len(),
+        )])
+        .add_env_vars(benchmark_query_env_vars)
+        .add_volume_mount(BUNDLE_VOLUME_NAME, BUNDLE_DIR)
+        .context(AddVolumeMountSnafu)?
+        .resources(
+            ResourceRequirementsBuilder::new()
+                .with_cpu_request("250m")
+                .with_cpu_limit("1")
+                .with_memory_request("256Mi")
+                .with_memory_limit("256Mi")
+                .build(),
+        );
+    let mut container = cb.build();
+    // The end of the test output explains failed tests
+    container.termination_message_policy = Some("FallbackToLogsOnError".to_string());
+
+    let mut pb = PodBuilder::new();
+    pb.metadata(
+        ObjectMetaBuilder::new()
+            .with_recommended_labels(labels())
+            .context(ObjectMetaSnafu)?
+            .build(),
+    )
+    .add_container(container)
+    .image_pull_secrets_from_product_image(resolved_product_image)
+    .restart_policy("Never")
+    .add_volume(
+        VolumeBuilder::new(BUNDLE_VOLUME_NAME)
+            .with_config_map(opa.bundle_snapshot_config_map_name())
+            .build(),
+    )
+    .context(AddVolumeSnafu)?
+    .security_context(
+        PodSecurityContextBuilder::new()
+            .run_as_user(1000)
+            .run_as_group(0)
+            .fs_group(1000)
+            .build(),
+    );
+
+    let template = pb.build_template();
+    let template_hash = format!(
+        "{:x}",
+        Sha256::digest(serde_json::to_vec(&template).with_context(|_| {
+            SerializeJobTemplateSnafu {
+                name: job_name.clone(),
+            }
+        })?)
+    );
+    let metadata = ObjectMetaBuilder::new()
+        .name_and_namespace(opa)
+        .name(job_name)
+        .ownerreference_from_resource(opa, None, Some(true))
+        .context(ObjectMissingMetadataForOwnerRefSnafu)?
+        .with_recommended_labels(labels())
+        .context(ObjectMetaSnafu)?
+        .with_annotation(
+            Annotation::try_from((JOB_TEMPLATE_HASH_ANNOTATION, template_hash.as_str()))
+                .context(BuildAnnotationSnafu)?,
+        )
+        .build();
+
+    Ok(Job {
+        metadata,
+        spec: Some(JobSpec {
+            template,
+            backoff_limit: Some(JOB_BACKOFF_LIMIT),
+            ..JobSpec::default()
+        }),
+        status: None,
+    })
+}
+
+fn build_policy_tests_command(revision: &str, benchmark_queries: usize) -> String {
+    let benchmarks = (0..benchmark_queries)
+        .map(|i| {
+            format!("opa bench --bundle /tmp/bundle --format gobench \"$BENCHMARK_QUERY_{i}\" | tee -a /dev/termination-log")
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    // The mounted ConfigMap might already contain a newer bundle, whose results would be
+    // attributed to the wrong revision
+    formatdoc! {"
+        if ! tar -xzOf {BUNDLE_DIR}/{BUNDLE_SNAPSHOT_KEY} .manifest | grep -qF '\"revision\":\"{revision}\"'; then
+          echo \"The bundle changed before it could be tested\" > /dev/termination-log
+          exit 1
+        fi
+        mkdir /tmp/bundle
+        tar -xzf {BUNDLE_DIR}/{BUNDLE_SNAPSHOT_KEY} -C /tmp/bundle
+        opa test --bundle /tmp/bundle --verbose
+        {benchmarks}
+        "
+    }
+}
+
+/// The termination message of the finished Job, which contains the benchmark results or the
+/// end of the output of the failed tests.
+async fn termination_message(
+    opa: &OpaCluster,
+    job_name: &str,
+    client: &Client,
+) -> Result<Option<String>, Error> {
+    let pods = Api::<Pod>::namespaced(
+        client.as_kube_client(),
+        &opa.namespace().unwrap_or_default(),
+    )
+    .list(&ListParams::default().labels(&format!("job-name={job_name}")))
+    .await
+    .context(ListJobPodsSnafu { name: job_name })?;
+    Ok(pods
+        .into_iter()
+        .filter_map(|pod| pod.status?.container_statuses)
+        .flatten()
+        .filter(|status| status.name == POLICY_TESTS_CONTAINER_NAME)
+        .find_map(|status| status.state?.terminated?.message)
+        .map(|message| message.trim().to_string())
+        .filter(|message| !message.is_empty()))
+}
@@ -0,0 +1,28 @@
+//! Stopping an OpaCluster (`clusterOperation.stopped`).
+//!
+//! DaemonSets can not be scaled down, so the Pods of a stopped OpaCluster are removed by a node
+//! selector that matches no node. Everything else (the role group ConfigMaps, the bundle snapshot
+//! and the discovery ConfigMaps) is kept, so that the OpaCluster resumes with the same
+//! configuration and serves the snapshotted bundle right away.
+
+use std::collections::BTreeMap;
+
+use stackable_operator::k8s_openapi::api::apps::v1::DaemonSet;
+
+/// The node label the Pods of stopped OpaClusters are restricted to, which no node has.
+pub const STOPPED_NODE_SELECTOR_LABEL: &str = "opa.stackable.tech/cluster-stopped";
+
+/// Removes all Pods of the DaemonSet. The rest of the Pod template is kept as is, so that resuming
+/// only removes the node selector again.
+pub fn stop_daemonset(daemonset: &mut DaemonSet) {
+    if let Some(pod_spec) = daemonset
+        .spec
+        .as_mut()
+        .and_then(|spec| spec.template.spec.as_mut())
+    {
+        pod_spec.node_selector = Some(BTreeMap::from([(
+            STOPPED_NODE_SELECTOR_LABEL.to_string(),
+            "true".to_string(),
+        )]));
+    }
+}
@@ -0,0 +1,592 @@
+//! Reconciles [`OpaPolicy`] objects into bundle ConfigMaps, which are picked up by the bundle builders
+//! of the selected [`OpaCluster`]s.
+//!
+//! Deleted OpaPolicies keep their finalizer until the bundle builders report that their Rego has been
+//! removed from the bundles of all OPA instances (see [`BundleDelivery`]), so that the deletion only
+//! completes once the policy is no longer enforced.
+use std::{collections::BTreeMap, sync::Arc};
+
+use const_format::concatcp;
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_opa_crd::{
+    policy::{
+        bundle_delivery_config_map_name, bundle_delivery_key, rego_package_name, BundleDelivery,
+        OpaPolicy, OpaPolicySource, OpaPolicyStatus, BUNDLE_CLUSTER_LABEL, BUNDLE_LABEL,
+        BUNDLE_POLICY_LABEL,
+    },
+    Container, OpaCluster, APP_NAME, OPERATOR_NAME,
+};
+use stackable_operator::{
+    builder::{configmap::ConfigMapBuilder, meta::ObjectMetaBuilder},
+    k8s_openapi::{
+        api::core::v1::{ConfigMap, Pod},
+        apimachinery::pkg::apis::meta::v1::LabelSelector,
+        chrono::Utc,
+    },
+    kube::{
+        api::{DeleteParams, ListParams},
+        core::{error_boundary, DeserializeGuard},
+        runtime::{
+            controller::Action,
+            finalizer::{self, finalizer},
+            reflector::{ObjectRef, Store},
+        },
+        Api, Resource, ResourceExt,
+    },
+    kvp::{Label, LabelError},
+    logging::controller::ReconcilerError,
+    time::Duration,
+};
+use strum::{EnumDiscriminants, IntoStaticStr};
+
+use crate::metrics::PrometheusMetrics;
+
+pub const OPA_POLICY_CONTROLLER_NAME: &str = "opapolicy";
+pub const OPA_POLICY_FULL_CONTROLLER_NAME: &str =
+    concatcp!(OPA_POLICY_CONTROLLER_NAME, '.', OPERATOR_NAME);
+const OPA_POLICY_FINALIZER: &str = concatcp!(OPERATOR_NAME, "/opapolicy");
+
+/// Policies referencing a ConfigMap are re-read regularly, as changes of the ConfigMap do not trigger a reconciliation.
+const REREAD_POLICY_SOURCE_INTERVAL: Duration = Duration::from_minutes_unchecked(5);
+/// Deleted policies are released after this, even if the removal of their Rego could not be
+/// confirmed (for example because the bundle builder is disabled or OPA instances are unreachable).
+const REMOVAL_VERIFICATION_TIMEOUT: Duration = Duration::from_minutes_unchecked(5);
+
+pub struct Ctx {
+    pub client: stackable_operator::client::Client,
+    /// Namespaces whose bundle ConfigMaps are ignored by the bundle builders, so no bundle ConfigMaps
+    /// are generated there either.
+    pub bundle_excluded_namespaces: Vec<String>,
+    pub metrics: Arc<PrometheusMetrics>,
+}
+
+#[derive(Snafu, Debug, EnumDiscriminants)]
+#[strum_discriminants(derive(IntoStaticStr))]
+pub enum Error {
+    #[snafu(display("OpaPolicy object is invalid"))]
+    InvalidOpaPolicy {
+        source: error_boundary::InvalidObject,
+    },
+
+    #[snafu(display("object has no namespace"))]
+    ObjectHasNoNamespace,
+
+    #[snafu(display("failed to apply or clean up OpaPolicy"))]
+    Finalizer {
+        source: Box<finalizer::Error<Error>>,
+    },
+
+    #[snafu(display("failed to read source ConfigMap {name:?}"))]
+    GetSourceConfigMap {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("failed to list OpaClusters"))]
+    ListOpaClusters {
+        source: stackable_operator::kube::Error,
+    },
+
+    #[snafu(display("failed to list OpaPolicies"))]
+    ListOpaPolicies {
+        source: stackable_operator::kube::Error,
+    },
+
+    #[snafu(display("failed to list bundle ConfigMaps of the OpaPolicy"))]
+    ListBundleConfigMaps {
+        source: stackable_operator::kube::Error,
+    },
+
+    #[snafu(display("failed to delete bundle ConfigMap {name:?}"))]
+    DeleteBundleConfigMap {
+        source: stackable_operator::kube::Error,
+        name: String,
+    },
+
+    #[snafu(display("object is missing metadata to build owner reference"))]
+    ObjectMissingMetadataForOwnerRef {
+        source: stackable_operator::builder::meta::Error,
+    },
+
+    #[snafu(display("failed to build label"))]
+    BuildLabel { source: LabelError },
+
+    #[snafu(display("failed to build bundle ConfigMap for OpaCluster {cluster:?}"))]
+    BuildBundleConfigMap {
+        source: stackable_operator::builder::configmap::Error,
+        cluster: String,
+    },
+
+    #[snafu(display("failed to apply bundle ConfigMap for OpaCluster {cluster:?}"))]
+    ApplyBundleConfigMap {
+        source: stackable_operator::client::Error,
+        cluster: String,
+    },
+
+    #[snafu(display("failed to update status"))]
+    ApplyStatus {
+        source: stackable_operator::client::Error,
+    },
+
+    #[snafu(display("failed to list the Pods of OpaCluster {cluster:?}"))]
+    ListOpaPods {
+        source: stackable_operator::kube::Error,
+        cluster: String,
+    },
+
+    #[snafu(display("failed to read the bundle delivery ConfigMap of OpaCluster {cluster:?}"))]
+    GetBundleDeliveryConfigMap {
+        source: stackable_operator::kube::Error,
+        cluster: String,
+    },
+
+    #[snafu(display(
+        "the Rego of the deleted OpaPolicy is still served by OpaCluster {cluster:?} (Pods: {})",
+        pods.join(", ")
+    ))]
+    RemovalPending { cluster: String, pods: Vec<String> },
+}
+type Result<T, E = Error> = std::result::Result<T, E>;
+
+impl ReconcilerError for Error {
+    fn category(&self) -> &'static str {
+        ErrorDiscriminants::from(self).into()
+    }
+}
+
+pub async fn reconcile_policy(
+    policy: Arc<DeserializeGuard<OpaPolicy>>,
+    ctx: Arc<Ctx>,
+) -> Result<Action> {
+    tracing::info!("Starting reconcile");
+    let policy = policy
+        .0
+        .as_ref()
+        .map_err(error_boundary::InvalidObject::clone)
+        .context(InvalidOpaPolicySnafu)?;
+    let namespace = policy.namespace().context(ObjectHasNoNamespaceSnafu)?;
+
+    let policy_api = Api::<OpaPolicy>::namespaced(ctx.client.as_kube_client(), &namespace);
+    finalizer(
+        &policy_api,
+        OPA_POLICY_FINALIZER,
+        Arc::new(policy.clone()),
+        |event| async move {
+            match event {
+                finalizer::Event::Apply(policy) => apply_policy(&policy, &namespace, &ctx).await,
+                finalizer::Event::Cleanup(policy) => {
+                    delete_bundle_config_maps(&policy, &namespace, &ctx, &[]).await?;
+                    verify_bundle_removal(&policy, &namespace, &ctx).await?;
+                    Ok(Action::await_change())
+                }
+            }
+        },
+    )
+    .await
+    .map_err(Box::new)
+    .context(FinalizerSnafu)
+}
+
+async fn apply_policy(policy: &OpaPolicy, namespace: &str, ctx: &Ctx) -> Result<Action> {
+    let client = &ctx.client;
+
+    let rego = match read_policy_source(policy, namespace, ctx).await? {
+        Ok(rego) => rego,
+        Err(message) => {
+            tracing::info!(reason = %message, "OpaPolicy is invalid");
+            delete_bundle_config_maps(policy, namespace, ctx, &[]).await?;
+            let status = OpaPolicyStatus {
+                valid: false,
+                active_clusters: Vec::new(),
+                message: Some(message),
+            };
+            client
+                .apply_patch_status(OPERATOR_NAME, policy, &status)
+                .await
+                .context(ApplyStatusSnafu)?;
+            return Ok(Action::requeue(*REREAD_POLICY_SOURCE_INTERVAL));
+        }
+    };
+
+    let clusters =
+        Api::<DeserializeGuard<OpaCluster>>::namespaced(client.as_kube_client(), namespace)
+            .list(&ListParams::default())
+            .await
+            .context(ListOpaClustersSnafu)?;
+    let policies =
+        Api::<DeserializeGuard<OpaPolicy>>::namespaced(client.as_kube_client(), namespace)
+            .list(&ListParams::default())
+            .await
+            .context(ListOpaPoliciesSnafu)?;
+    let competing_policies = policies
+        .iter()
+        .filter_map(|other| other.0.as_ref().ok())
+        .filter(|other| {
+            other.name_any() == policy.name_any()
+                || (other.spec.package == policy.spec.package
+                    && other.meta().deletion_timestamp.is_none()
+                    // Policies that have not been validated yet are assumed to be valid
+                    && other.status.as_ref().map_or(true, |status| status.valid))
+        })
+        .collect::<Vec<_>>();
+
+    let mut active_clusters = Vec::new();
+    let mut shadowed_by = BTreeMap::<String, String>::new();
+    for cluster in &clusters {
+        if !targets_cluster(policy, cluster.labels()) {
+            continue;
+        }
+        let cluster_name = cluster.name_any();
+        let winner = competing_policies
+            .iter()
+            .filter(|other| targets_cluster(other, cluster.labels()))
+            // Highest priority wins, ties are won by the lexicographically first name
+            .max_by(|a, b| {
+                a.spec
+                    .priority
+                    .cmp(&b.spec.priority)
+                    .then_with(|| b.name_any().cmp(&a.name_any()))
+            })
+            .map(|winner| winner.name_any());
+        match winner {
+            Some(winner) if winner != policy.name_any() => {
+                shadowed_by.insert(cluster_name, winner);
+            }
+            _ => {
+                let cm = build_bundle_config_map(policy, &cluster_name, &rego)?;
+                client
+                    .apply_patch(OPA_POLICY_FULL_CONTROLLER_NAME, &cm, &cm)
+                    .await
+                    .with_context(|_| ApplyBundleConfigMapSnafu {
+                        cluster: cluster_name.clone(),
+                    })?;
+                ctx.metrics
+                    .record_applied_resource(OPA_POLICY_FULL_CONTROLLER_NAME, &cm);
+                active_clusters.push(cluster_name);
+            }
+        }
+    }
+    delete_bundle_config_maps(policy, namespace, ctx, &active_clusters).await?;
+
+    let status = OpaPolicyStatus {
+        valid: true,
+        active_clusters,
+        message: (!shadowed_by.is_empty()).then(|| {
+            shadowed_by
+                .iter()
+                .map(|(cluster, winner)| {
+                    format!("package is provided by OpaPolicy {winner:?} in OpaCluster {cluster:?}")
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        }),
+    };
+    client
+        .apply_patch_status(OPERATOR_NAME, policy, &status)
+        .await
+        .context(ApplyStatusSnafu)?;
+
+    Ok(match policy.spec.source {
+        OpaPolicySource::Rego(_) => Action::await_change(),
+        OpaPolicySource::ConfigMapRef(_) => Action::requeue(*REREAD_POLICY_SOURCE_INTERVAL),
+    })
+}
+
+/// Returns the Rego source of the policy, or a message describing why the policy is invalid.
+async fn read_policy_source(
+    policy: &OpaPolicy,
+    namespace: &str,
+    ctx: &Ctx,
+) -> Result<Result<String, String>> {
+    if ctx
+        .bundle_excluded_namespaces
+        .iter()
+        .any(|excluded| excluded == namespace)
+    {
+        return Ok(Err(format!(
+            "namespace {namespace:?} is excluded from bundles by the operator"
+        )));
+    }
+
+    let package = &policy.spec.package;
+    if package.is_empty()
+        || !package.split('.').all(|segment| {
+            !segment.is_empty()
+                && segment
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+        })
+    {
+        return Ok(Err(format!("{package:?} is not a valid package name")));
+    }
+
+    let rego = match &policy.spec.source {
+        OpaPolicySource::Rego(rego) => rego.clone(),
+        OpaPolicySource::ConfigMapRef(cm_ref) => {
+            let cm = Api::<ConfigMap>::namespaced(ctx.client.as_kube_client(), namespace)
+                .get_opt(&cm_ref.name)
+                .await
+                .with_context(|_| GetSourceConfigMapSnafu {
+                    name: cm_ref.name.clone(),
+                })?;
+            let Some(cm) = cm else {
+                return Ok(Err(format!("ConfigMap {:?} does not exist", cm_ref.name)));
+            };
+            match cm.data.and_then(|mut data| data.remove(&cm_ref.key)) {
+                Some(rego) => rego,
+                None => {
+                    return Ok(Err(format!(
+                        "ConfigMap {:?} has no key {:?}",
+                        cm_ref.name, cm_ref.key
+                    )))
+                }
+            }
+        }
+    };
+
+    Ok(match rego_package_name(&rego) {
+        Some(declared_package) if declared_package == package => Ok(rego),
+        Some(declared_package) => Err(format!(
+            "the Rego source declares package {declared_package:?} instead of {package:?}"
+        )),
+        None => Err("the Rego source does not declare a package".to_string()),
+    })
+}
+
+/// Whether the `targetCluster` selector of the policy matches an OpaCluster with the given labels.
+fn targets_cluster(policy: &OpaPolicy, cluster_labels: &BTreeMap<String, String>) -> bool {
+    let Some(LabelSelector {
+        match_labels,
+        match_expressions,
+    }) = &policy.spec.target_cluster
+    else {
+        return true;
+    };
+    match_labels
+        .iter()
+        .flatten()
+        .all(|(key, value)| cluster_labels.get(key) == Some(value))
+        && match_expressions.iter().flatten().all(|expression| {
+            let value = cluster_labels.get(&expression.key);
+            let values = expression.values.as_deref().unwrap_or_default();
+            match expression.operator.as_str() {
+                "In" => value.is_some_and(|value| values.contains(value)),
+                "NotIn" => value.map_or(true, |value| !values.contains(value)),
+                "Exists" => value.is_some(),
+                "DoesNotExist" => value.is_none(),
+                _ => false,
+            }
+        })
+}
+
+fn build_bundle_config_map(
+    policy: &OpaPolicy,
+    cluster_name: &str,
+    rego: &str,
+) -> Result<ConfigMap> {
+    let policy_name = policy.name_any();
+    let metadata = ObjectMetaBuilder::new()
+        .name_and_namespace(policy)
+        .name(bundle_config_map_name(&policy_name, cluster_name))
+        .ownerreference_from_resource(policy, None, Some(true))
+        .context(ObjectMissingMetadataForOwnerRefSnafu)?
+        .with_label(Label::try_from((BUNDLE_LABEL, "true")).context(BuildLabelSnafu)?)
+        .with_label(
+            Label::try_from((BUNDLE_POLICY_LABEL, policy_name.as_str()))
+                .context(BuildLabelSnafu)?,
+        )
+        .with_label(Label::try_from((BUNDLE_CLUSTER_LABEL, cluster_name)).context(BuildLabelSnafu)?)
+        .build();
+
+    ConfigMapBuilder::new()
+        .metadata(metadata)
+        .add_data(format!("{policy_name}.rego"), rego)
+        .build()
+        .with_context(|_| BuildBundleConfigMapSnafu {
+            cluster: cluster_name,
+        })
+}
+
+fn bundle_config_map_name(policy_name: &str, cluster_name: &str) -> String {
+    format!("{policy_name}-{cluster_name}-policy")
+}
+
+/// Deletes the bundle ConfigMaps generated for the policy, except for the ones of the `retained_clusters`.
+async fn delete_bundle_config_maps(
+    policy: &OpaPolicy,
+    namespace: &str,
+    ctx: &Ctx,
+    retained_clusters: &[String],
+) -> Result<()> {
+    let cm_api = Api::<ConfigMap>::namespaced(ctx.client.as_kube_client(), namespace);
+    let cms = cm_api
+        .list(&ListParams::default().labels(&format!(
+            "{BUNDLE_POLICY_LABEL}={policy_name}",
+            policy_name = policy.name_any()
+        )))
+        .await
+        .context(ListBundleConfigMapsSnafu)?;
+    for cm in cms {
+        if cm
+            .labels()
+            .get(BUNDLE_CLUSTER_LABEL)
+            .is_some_and(|cluster| retained_clusters.contains(cluster))
+        {
+            continue;
+        }
+        let name = cm.name_any();
+        tracing::info!(config_map = name, "deleting bundle ConfigMap of OpaPolicy");
+        cm_api
+            .delete(&name, &DeleteParams::default())
+            .await
+            .context(DeleteBundleConfigMapSnafu { name })?;
+    }
+    Ok(())
+}
+
+/// Checks that the bundle builders of all OpaClusters the policy was active in no longer serve its
+/// Rego, so that the finalizer is only removed once the policy is no longer enforced.
+///
+/// The bundle builders report the bundles delivered to OPA in the bundle delivery ConfigMap of their
+/// OpaCluster (see [`BundleDelivery`]). Pods without a bundle builder are skipped. Bundle builders
+/// that have not reported a bundle without the policy count as pending, until
+/// [`REMOVAL_VERIFICATION_TIMEOUT`] after the deletion of the policy.
+async fn verify_bundle_removal(policy: &OpaPolicy, namespace: &str, ctx: &Ctx) -> Result<()> {
+    let timed_out = policy
+        .meta()
+        .deletion_timestamp
+        .as_ref()
+        .and_then(|deleted_at| (Utc::now() - deleted_at.0).to_std().ok())
+        .is_some_and(|since_deletion| since_deletion > *REMOVAL_VERIFICATION_TIMEOUT);
+    let kube_client = ctx.client.as_kube_client();
+    let pods_api = Api::<Pod>::namespaced(kube_client.clone(), namespace);
+    let cm_api = Api::<ConfigMap>::namespaced(kube_client, namespace);
+    let bundle_builder_container = Container::BundleBuilder.to_string();
+    let policy_name = policy.name_any();
+    let active_clusters = policy
+        .status
+        .as_ref()
+        .map(|status| status.active_clusters.as_slice())
+        .unwrap_or_default();
+    for cluster_name in active_clusters {
+        let deliveries = cm_api
+            .get_opt(&bundle_delivery_config_map_name(cluster_name))
+            .await
+            .with_context(|_| GetBundleDeliveryConfigMapSnafu {
+                cluster: cluster_name.clone(),
+            })?
+            .and_then(|cm| cm.data)
+            .unwrap_or_default();
+        let pods = pods_api
+            .list(&ListParams::default().labels(&format!(
+                "app.kubernetes.io/name={APP_NAME},app.kubernetes.io/instance={cluster_name}"
+            )))
+            .await
+            .with_context(|_| ListOpaPodsSnafu {
+                cluster: cluster_name.clone(),
+            })?;
+        let mut pending_pods = Vec::new();
+        for pod in pods {
+            let Some(spec) = &pod.spec else {
+                continue;
+            };
+            let has_bundle_builder = spec
+                .containers
+                .iter()
+                .chain(spec.init_containers.iter().flatten())
+                .any(|container| container.name == bundle_builder_container);
+            let running = pod
+                .status
+                .as_ref()
+                .and_then(|status| status.phase.as_deref())
+                == Some("Running");
+            if !has_bundle_builder || !running {
+                continue;
+            }
+            let pod_name = pod.name_any();
+            let delivery = spec
+                .node_name
+                .as_deref()
+                .zip(pod.labels().get("app.kubernetes.io/role-group"))
+                .and_then(|(node_name, role_group)| {
+                    deliveries.get(&bundle_delivery_key(node_name, role_group))
+                })
+                .and_then(|delivery| serde_json::from_str::<BundleDelivery>(delivery).ok())
+                // Reports of the Pod this one replaced are outdated
+                .filter(|delivery| delivery.pod == pod_name);
+            match delivery {
+                Some(delivery) if !delivery.policies.contains(&policy_name) => {}
+                Some(delivery) => {
+                    tracing::info!(
+                        pod = pod_name,
+                        revision = delivery.revision,
+                        "bundle builder still serves the Rego of the deleted OpaPolicy"
+                    );
+                    pending_pods.push(pod_name);
+                }
+                None => {
+                    tracing::info!(
+                        pod = pod_name,
+                        "bundle builder has not reported a delivered bundle yet"
+                    );
+                    pending_pods.push(pod_name);
+                }
+            }
+        }
+        if !pending_pods.is_empty() {
+            if timed_out {
+                tracing::warn!(
+                    cluster = cluster_name,
+                    pods = ?pending_pods,
+                    "removal of the deleted OpaPolicy could not be confirmed in time, releasing it anyway"
+                );
+                continue;
+            }
+            return RemovalPendingSnafu {
+                cluster: cluster_name.clone(),
+                pods: pending_pods,
+            }
+            .fail();
+        }
+    }
+    Ok(())
+}
+
+/// Returns references to all policies in the given namespace, used to re-evaluate them when
+/// OpaClusters or competing policies change.
+pub fn policies_in_namespace(
+    store: &Store<DeserializeGuard<OpaPolicy>>,
+    namespace: Option<String>,
+) -> Vec<ObjectRef<DeserializeGuard<OpaPolicy>>> {
+    store
+        .state()
+        .into_iter()
+        .filter(|policy| policy.namespace() == namespace)
+        .map(|policy| ObjectRef::from_obj(&*policy))
+        .collect()
+}
+
+pub fn error_policy(
+    _obj: Arc<DeserializeGuard<OpaPolicy>>,
+    error: &Error,
+    _ctx: Arc<Ctx>,
+) -> Action {
+    match error {
+        // root object is invalid, will be requeued when modified anyway
+        Error::InvalidOpaPolicy { .. } => Action::await_change(),
+
+        // The bundle builders usually pick up the deletion within seconds
+        Error::Finalizer { source } if is_removal_pending(source) => {
+            Action::requeue(*Duration::from_secs(5))
+        }
+
+        _ => Action::requeue(*Duration::from_secs(10)),
+    }
+}
+
+fn is_removal_pending(error: &finalizer::Error<Error>) -> bool {
+    matches!(
+        error,
+        finalizer::Error::CleanupFailed(Error::RemovalPending { .. })
+    )
+}
@@ -12,6 +12,8 @@ use stackable_operator::{
     role_utils::RoleGroupRef,
 };
 
+use crate::controller::STACKABLE_LOG_DIR;
+
 #[derive(Snafu, Debug)]
 pub enum Error {
     #[snafu(display("object has no namespace"))]
@@ -28,12 +30,37 @@ pub enum Error {
     },
     #[snafu(display("vectorAggregatorConfigMapName must be set"))]
     MissingVectorAggregatorAddress,
+    #[snafu(display("failed to add the decision log source to the Vector configuration"))]
+    ExtendVectorConfig { source: serde_yaml::Error },
 }
 
 type Result<T, E = Error> = std::result::Result<T, E>;
 
 const VECTOR_AGGREGATOR_CM_ENTRY: &str = "ADDRESS";
 
+/// The file (in the log directory of the OPA container) the bundle builder writes the decision logs to.
+pub const DECISION_LOG_FILE_NAME: &str = "decisions.json";
+const DECISION_LOG_SOURCE: &str = "files_opa_decisions";
+/// Picked up by the transform of the framework that adds the metadata to all processed files.
+const DECISION_LOG_TRANSFORM: &str = "processed_files_opa_decisions";
+/// Turns the decisions into structured events, keeping the whole decision as the message.
+const DECISION_LOG_REMAP: &str = r#"
+parsed_event, err = parse_json(string!(.message))
+.logger = "decision"
+.level = "INFO"
+if err != null {
+  .errors = ["JSON not parsable: " + err]
+} else {
+  .timestamp = parse_timestamp(string(parsed_event.timestamp) ?? "", "%+") ?? now()
+  .decision_id = parsed_event.decision_id
+  .path = parsed_event.path
+  latency_ns, err = to_float(parsed_event.metrics.timer_server_handler_ns)
+  if err == null {
+    .latency_ms = latency_ns / 1000000
+  }
+}
+"#;
+
 #[derive(strum::Display)]
 #[strum(serialize_all = "lowercase")]
 pub enum OpaLogLevel {
@@ -125,15 +152,70 @@ pub fn extend_role_group_config_map(
     };
 
     if logging.enable_vector_agent {
+        let vector_config = product_logging::framework::create_vector_config(
+            rolegroup,
+            vector_aggregator_address.context(MissingVectorAggregatorAddressSnafu)?,
+            vector_log_config,
+        );
         cm_builder.add_data(
             product_logging::framework::VECTOR_CONFIG_FILE,
-            product_logging::framework::create_vector_config(
-                rolegroup,
-                vector_aggregator_address.context(MissingVectorAggregatorAddressSnafu)?,
-                vector_log_config,
-            ),
+            add_decision_log_source(&vector_config)?,
         );
     }
 
     Ok(())
 }
+
+/// Adds a source (and transform) for the decision log file to the Vector configuration of the
+/// framework, so that decisions are shipped as structured events with the `decision_id`, `path`
+/// and `latency_ms` fields.
+fn add_decision_log_source(vector_config: &str) -> Result<String> {
+    let mut config = serde_yaml::from_str::<serde_yaml::Value>(vector_config)
+        .context(ExtendVectorConfigSnafu)?;
+    let decision_log_file = format!(
+        "{STACKABLE_LOG_DIR}/{container}/{DECISION_LOG_FILE_NAME}",
+        container = Container::Opa
+    );
+    if let Some(sources) = config
+        .get_mut("sources")
+        .and_then(serde_yaml::Value::as_mapping_mut)
+    {
+        sources.insert(
+            DECISION_LOG_SOURCE.into(),
+            serde_yaml::from_str(&format!(
+                "{{type: file, include: [{decision_log_file}, {decision_log_file}.1]}}"
+            ))
+            .context(ExtendVectorConfigSnafu)?,
+        );
+    }
+    if let Some(transforms) = config
+        .get_mut("transforms")
+        .and_then(serde_yaml::Value::as_mapping_mut)
+    {
+        // In case the transforms consuming the processed files list them explicitly
+        for (_, transform) in transforms.iter_mut() {
+            if let Some(inputs) = transform
+                .get_mut("inputs")
+                .and_then(serde_yaml::Value::as_sequence_mut)
+            {
+                let consumes_processed_files = inputs.iter().any(|input| {
+                    input
+                        .as_str()
+                        .is_some_and(|input| input.starts_with("processed_files_"))
+                });
+                let matches_all_processed_files = inputs
+                    .iter()
+                    .any(|input| input.as_str() == Some("processed_files_*"));
+                if consumes_processed_files && !matches_all_processed_files {
+                    inputs.push(DECISION_LOG_TRANSFORM.into());
+                }
+            }
+        }
+        let mut transform = serde_yaml::Mapping::new();
+        transform.insert("type".into(), "remap".into());
+        transform.insert("inputs".into(), vec![DECISION_LOG_SOURCE].into());
+        transform.insert("source".into(), DECISION_LOG_REMAP.into());
+        transforms.insert(DECISION_LOG_TRANSFORM.into(), transform.into());
+    }
+    serde_yaml::to_string(&config).context(ExtendVectorConfigSnafu)
+}
@@ -0,0 +1,269 @@
+//! The `render` subcommand, which prints the resources the controller would create for an
+//! OpaCluster without contacting a Kubernetes cluster, for example to diff changes in GitOps
+//! pipelines.
+//!
+//! Everything that depends on the state of the cluster is left out or replaced: resources that are
+//! only created after looking up other objects (such as NetworkPolicies, HTTPRoutes and
+//! PodDisruptionBudgets) are not rendered, and the Vector aggregator address must be passed
+//! explicitly.
+
+use std::{io::Read, path::PathBuf};
+
+use serde::Serialize;
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::{OpaCluster, APP_NAME, OPERATOR_NAME};
+use stackable_operator::{
+    cli::ProductConfigPath,
+    cluster_resources::{ClusterResourceApplyStrategy, ClusterResources},
+    commons::networking::DomainName,
+    kube::Resource,
+    utils::cluster_info::KubernetesClusterInfo,
+};
+
+use crate::controller::{self, build_resources, BuildContext, OPA_CONTROLLER_NAME};
+
+/// The UID set on OpaClusters without one, so that owner references can be rendered.
+const PLACEHOLDER_UID: &str = "00000000-0000-0000-0000-000000000000";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to read OpaCluster from {path:?}"))]
+    ReadOpaCluster {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to parse OpaCluster"))]
+    ParseOpaCluster { source: serde_yaml::Error },
+
+    #[snafu(display("failed to load product config"))]
+    LoadProductConfig {
+        source: stackable_operator::product_config_utils::Error,
+    },
+
+    #[snafu(display("failed to create cluster resources"))]
+    CreateClusterResources {
+        source: stackable_operator::cluster_resources::Error,
+    },
+
+    #[snafu(display("failed to build resources"))]
+    BuildResources { source: controller::Error },
+
+    #[snafu(display("failed to serialize resource"))]
+    SerializeResource { source: serde_yaml::Error },
+}
+
+#[derive(clap::Args)]
+pub struct RenderArgs {
+    /// The OpaCluster manifest to render. Defaults to stdin.
+    #[clap(long)]
+    file: Option<PathBuf>,
+
+    /// The namespace of OpaClusters that don't specify one.
+    #[clap(long, default_value = "default")]
+    namespace: String,
+
+    /// The full image tag of the operator, used for the sidecars.
+    #[clap(long, env)]
+    operator_image: String,
+
+    /// Render the sidecars as native sidecars (init containers that keep running).
+    #[clap(long)]
+    native_sidecars: bool,
+
+    /// Namespaces whose ConfigMaps are never added to bundles, see the `run` subcommand.
+    #[clap(long, env, value_delimiter = ',')]
+    bundle_excluded_namespaces: Vec<String>,
+
+    /// The address of the Vector aggregator, which is otherwise read from the discovery ConfigMap
+    /// referenced by `vectorAggregatorConfigMapName`.
+    #[clap(long)]
+    vector_aggregator_address: Option<String>,
+
+    /// The cluster domain used in the discovery ConfigMaps.
+    #[clap(long, default_value = "cluster.local")]
+    cluster_domain: DomainName,
+
+    /// Path to the product config, see the `run` subcommand.
+    #[clap(long, short = 'p', value_name = "FILE", default_value = "", env)]
+    product_config: ProductConfigPath,
+}
+
+pub fn run(args: RenderArgs) -> Result<(), Error> {
+    let manifest = match &args.file {
+        Some(path) => std::fs::read_to_string(path).context(ReadOpaClusterSnafu { path })?,
+        None => {
+            let mut manifest = String::new();
+            std::io::stdin()
+                .read_to_string(&mut manifest)
+                .context(ReadOpaClusterSnafu { path: "-" })?;
+            manifest
+        }
+    };
+    let opa = parse_opa_cluster(&manifest, &args.namespace)?;
+
+    for resource in render(&opa, &args)? {
+        print!("---\n{resource}");
+    }
+    eprintln!(
+        "NetworkPolicies, HTTPRoutes, PodDisruptionBudgets, node label ConfigMaps and canary \
+         rollouts depend on the state of the cluster and are not rendered"
+    );
+    Ok(())
+}
+
+/// Parses the OpaCluster, filling in the metadata that is only set once it has been created.
+fn parse_opa_cluster(manifest: &str, namespace: &str) -> Result<OpaCluster, Error> {
+    let mut opa = serde_yaml::from_str::<OpaCluster>(manifest).context(ParseOpaClusterSnafu)?;
+    let metadata = opa.meta_mut();
+    metadata.namespace.get_or_insert(namespace.to_string());
+    metadata.uid.get_or_insert(PLACEHOLDER_UID.to_string());
+    Ok(opa)
+}
+
+/// Renders the resources as YAML documents, in the order they are applied by the controller.
+fn render(opa: &OpaCluster, args: &RenderArgs) -> Result<Vec<String>, Error> {
+    let product_config = args
+        .product_config
+        .load(&[
+            "deploy/config-spec/properties.yaml",
+            "/etc/stackable/opa-operator/config-spec/properties.yaml",
+        ])
+        .context(LoadProductConfigSnafu)?;
+    // Only used for the labels, nothing is applied
+    let cluster_resources = ClusterResources::new(
+        APP_NAME,
+        OPERATOR_NAME,
+        OPA_CONTROLLER_NAME,
+        &opa.object_ref(&()),
+        ClusterResourceApplyStrategy::from(&opa.spec.cluster_operation),
+    )
+    .context(CreateClusterResourcesSnafu)?;
+    let cluster_info = KubernetesClusterInfo {
+        cluster_domain: args.cluster_domain.clone(),
+    };
+    let resources = build_resources(
+        opa,
+        &cluster_resources,
+        &BuildContext {
+            product_config: &product_config,
+            cluster_info: &cluster_info,
+            vector_aggregator_address: args.vector_aggregator_address.as_deref(),
+            opa_bundle_builder_image: &args.operator_image,
+            user_info_fetcher_image: &args.operator_image,
+            native_sidecars: args.native_sidecars,
+            bundle_excluded_namespaces: &args.bundle_excluded_namespaces,
+        },
+    )
+    .context(BuildResourcesSnafu)?;
+    for warning in &resources.warnings {
+        eprintln!("warning: {warning}");
+    }
+
+    let mut rendered = vec![to_yaml(&resources.server_role_service)?];
+    if let Some((snapshot_config_map, delivery_config_map)) = &resources.bundle_config_maps {
+        rendered.push(to_yaml(snapshot_config_map)?);
+        rendered.push(to_yaml(delivery_config_map)?);
+    }
+    if let Some(regorule_library_config_map) = &resources.regorule_library_config_map {
+        rendered.push(to_yaml(regorule_library_config_map)?);
+    }
+    if let Some(stable_bundle_config_map) = &resources.stable_bundle_config_map {
+        rendered.push(to_yaml(stable_bundle_config_map)?);
+    }
+    rendered.push(to_yaml(&resources.service_account)?);
+    rendered.push(to_yaml(&resources.role_binding)?);
+    for role_group in &resources.role_groups {
+        rendered.push(to_yaml(&role_group.config_map)?);
+        rendered.push(to_yaml(&role_group.service)?);
+        rendered.push(to_yaml(&role_group.daemonset)?);
+    }
+    if let Some(merged_config_config_map) = &resources.merged_config_config_map {
+        rendered.push(to_yaml(merged_config_config_map)?);
+    }
+    for discovery_config_map in &resources.discovery_config_maps {
+        rendered.push(to_yaml(discovery_config_map)?);
+    }
+    Ok(rendered)
+}
+
+fn to_yaml<T: Serialize>(resource: &T) -> Result<String, Error> {
+    serde_yaml::to_string(resource).context(SerializeResourceSnafu)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use clap::Parser;
+
+    use super::*;
+
+    #[derive(clap::Parser)]
+    struct Cli {
+        #[clap(flatten)]
+        render: RenderArgs,
+    }
+
+    fn test_dir() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("test/render")
+    }
+
+    /// Renders the OpaCluster `test/render/<name>.yaml` and compares the rendered resources (as
+    /// printed by the `render` subcommand) with the golden file `test/render/<name>.golden.yaml`.
+    ///
+    /// If the environment variable `UPDATE_GOLDEN_FILES` is set, the golden file is overwritten with
+    /// the rendered resources instead, whose diff then needs to be reviewed.
+    fn assert_renders_golden(name: &str) {
+        let product_config =
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("../../deploy/config-spec/properties.yaml");
+        let args = Cli::parse_from([
+            "render".as_ref(),
+            "--operator-image".as_ref(),
+            "oci.stackable.tech/sdp/opa-operator:0.0.0-dev".as_ref(),
+            "--product-config".as_ref(),
+            product_config.as_os_str(),
+        ])
+        .render;
+        let manifest = std::fs::read_to_string(test_dir().join(format!("{name}.yaml")))
+            .expect("OpaCluster fixture must be readable");
+        let opa = parse_opa_cluster(&manifest, &args.namespace).expect("OpaCluster must be valid");
+
+        let rendered = render(&opa, &args)
+            .expect("OpaCluster must be renderable")
+            .iter()
+            .map(|resource| format!("---\n{resource}"))
+            .collect::<String>();
+        let golden_path = test_dir().join(format!("{name}.golden.yaml"));
+        if std::env::var_os("UPDATE_GOLDEN_FILES").is_some() {
+            std::fs::write(&golden_path, rendered).expect("golden file must be writable");
+            return;
+        }
+        let golden = std::fs::read_to_string(&golden_path).unwrap_or_else(|error| {
+            panic!(
+                "golden file {golden_path:?} must be readable ({error}), run the tests with \
+                 UPDATE_GOLDEN_FILES=1 to create it"
+            )
+        });
+        assert!(
+            rendered == golden,
+            "the rendered resources differ from {golden_path:?}, run the tests with \
+             UPDATE_GOLDEN_FILES=1 and review the diff of the golden file"
+        );
+    }
+
+    #[test]
+    fn render_simple() {
+        assert_renders_golden("simple");
+    }
+
+    #[test]
+    fn render_canary_rollout() {
+        assert_renders_golden("canary-rollout");
+    }
+
+    #[test]
+    fn render_development_mode() {
+        assert_renders_golden("development-mode");
+    }
+}
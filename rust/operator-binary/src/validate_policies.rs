@@ -0,0 +1,340 @@
+//! The `validate-policies` subcommand, which runs the checks of the bundle builder against a set of
+//! bundle ConfigMaps without deploying them, for example to validate policy changes in CI pipelines.
+//!
+//! The ConfigMaps are either read from a live cluster or from local manifests. Compiling the Rego
+//! files requires an `opa` binary, which the operator image does not contain.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::policy::{
+    data_document_path, rego_package_name, BUNDLE_CLUSTER_LABEL, BUNDLE_LABEL,
+};
+use stackable_operator::{
+    k8s_openapi::api::core::v1::ConfigMap,
+    kube::{api::ListParams, runtime::reflector::ObjectRef, Api, ResourceExt},
+};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to create Kubernetes client"))]
+    CreateClient {
+        source: stackable_operator::kube::Error,
+    },
+
+    #[snafu(display("failed to list bundle ConfigMaps"))]
+    ListConfigMaps {
+        source: stackable_operator::kube::Error,
+    },
+
+    #[snafu(display("failed to read manifest {path:?}"))]
+    ReadManifest {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to parse manifest {path:?}"))]
+    ParseManifest {
+        source: serde_yaml::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to write policies to {path:?} for compilation"))]
+    WritePolicies {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("failed to run {opa_binary:?}"))]
+    RunOpa {
+        source: std::io::Error,
+        opa_binary: PathBuf,
+    },
+
+    #[snafu(display("the policies are invalid ({errors} errors)"))]
+    PoliciesInvalid { errors: usize },
+}
+
+#[derive(clap::Args)]
+pub struct ValidatePoliciesArgs {
+    /// The label selector of the bundle ConfigMaps to validate.
+    #[clap(long, default_value = BUNDLE_LABEL)]
+    label: String,
+
+    /// The namespace to read the bundle ConfigMaps from. Defaults to all namespaces.
+    #[clap(long)]
+    namespace: Option<String>,
+
+    /// Read the ConfigMaps from these (multi-document) YAML manifests instead of a live cluster.
+    /// In this case `--label` must be a label key, and only ConfigMaps with that label are validated.
+    #[clap(long = "file", conflicts_with = "namespace")]
+    files: Vec<PathBuf>,
+
+    /// Validate the bundle of this OpaCluster, skipping ConfigMaps that are restricted to other
+    /// OpaClusters.
+    #[clap(long)]
+    cluster_name: Option<String>,
+
+    /// Skip the ConfigMaps in these namespaces, which the bundle builders ignore as well (see the
+    /// `--bundle-excluded-namespaces` parameter of the operator).
+    #[clap(long, env, value_delimiter = ',')]
+    bundle_excluded_namespaces: Vec<String>,
+
+    /// Path to an `opa` binary to compile the Rego files with (`opa check`).
+    /// Without it, the Rego files are only checked for a package declaration.
+    #[clap(long, env)]
+    opa_binary: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+struct Finding {
+    severity: Severity,
+    /// The ConfigMap (and file) the finding applies to, if any.
+    location: Option<String>,
+    message: String,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        match &self.location {
+            Some(location) => write!(f, "{severity}: {location}: {}", self.message),
+            None => write!(f, "{severity}: {}", self.message),
+        }
+    }
+}
+
+pub async fn run(args: ValidatePoliciesArgs) -> Result<(), Error> {
+    let config_maps = if args.files.is_empty() {
+        list_config_maps(&args).await?
+    } else {
+        read_config_maps(&args)?
+    };
+
+    let mut findings = Vec::new();
+    let mut validated_config_maps = Vec::new();
+    for cm in config_maps {
+        let cm_ref = ObjectRef::from_obj(&cm);
+        if cm
+            .namespace()
+            .is_some_and(|namespace| args.bundle_excluded_namespaces.contains(&namespace))
+        {
+            println!("skipping {cm_ref}, whose namespace is excluded from bundles");
+            continue;
+        }
+        if let Some(cm_cluster_name) = cm.labels().get(BUNDLE_CLUSTER_LABEL) {
+            if args
+                .cluster_name
+                .as_ref()
+                .is_some_and(|cluster_name| cluster_name != cm_cluster_name)
+            {
+                println!(
+                    "skipping {cm_ref}, which is restricted to the OpaCluster {cm_cluster_name}"
+                );
+                continue;
+            }
+        }
+        validated_config_maps.push(cm);
+    }
+    check_config_maps(&validated_config_maps, &mut findings);
+    if let Some(opa_binary) = &args.opa_binary {
+        compile_policies(opa_binary, &validated_config_maps, &mut findings).await?;
+    }
+
+    findings.sort_by_key(|finding| finding.severity);
+    for finding in &findings {
+        println!("{finding}");
+    }
+    let errors = findings
+        .iter()
+        .filter(|finding| finding.severity == Severity::Error)
+        .count();
+    println!(
+        "validated {config_maps} ConfigMaps: {errors} errors, {warnings} warnings",
+        config_maps = validated_config_maps.len(),
+        warnings = findings.len() - errors,
+    );
+    if errors > 0 {
+        return PoliciesInvalidSnafu { errors }.fail();
+    }
+    Ok(())
+}
+
+async fn list_config_maps(args: &ValidatePoliciesArgs) -> Result<Vec<ConfigMap>, Error> {
+    let client = stackable_operator::kube::Client::try_default()
+        .await
+        .context(CreateClientSnafu)?;
+    let api = match &args.namespace {
+        Some(namespace) => Api::<ConfigMap>::namespaced(client, namespace),
+        None => Api::<ConfigMap>::all(client),
+    };
+    Ok(api
+        .list(&ListParams::default().labels(&args.label))
+        .await
+        .context(ListConfigMapsSnafu)?
+        .items)
+}
+
+fn read_config_maps(args: &ValidatePoliciesArgs) -> Result<Vec<ConfigMap>, Error> {
+    let mut config_maps = Vec::new();
+    for path in &args.files {
+        let manifest = std::fs::read_to_string(path).context(ReadManifestSnafu { path })?;
+        for document in serde_yaml::Deserializer::from_str(&manifest) {
+            let object =
+                serde_yaml::Value::deserialize(document).context(ParseManifestSnafu { path })?;
+            if object.get("kind").and_then(|kind| kind.as_str()) != Some("ConfigMap") {
+                continue;
+            }
+            let cm =
+                serde_yaml::from_value::<ConfigMap>(object).context(ParseManifestSnafu { path })?;
+            if cm.labels().contains_key(&args.label) {
+                config_maps.push(cm);
+            }
+        }
+    }
+    Ok(config_maps)
+}
+
+/// Runs the checks that the bundle builder runs while building the bundle, see `build_bundle` in
+/// the bundle builder.
+fn check_config_maps(config_maps: &[ConfigMap], findings: &mut Vec<Finding>) {
+    // The data documents by the path they are placed at in the bundle
+    let mut data_documents = BTreeMap::<String, String>::new();
+    let mut packages = BTreeMap::<String, BTreeSet<String>>::new();
+    for cm in config_maps {
+        let cm_ref = ObjectRef::from_obj(cm);
+        if cm.data.as_ref().is_none_or(BTreeMap::is_empty) {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                location: Some(cm_ref.to_string()),
+                message: "the ConfigMap contains no files".to_string(),
+            });
+        }
+        for (file_name, data) in cm.data.iter().flatten() {
+            let location = Some(format!("{cm_ref}/{file_name}"));
+            if file_name.ends_with(".rego") {
+                match rego_package_name(data) {
+                    Some(package) => {
+                        packages
+                            .entry(package.to_string())
+                            .or_default()
+                            .insert(cm_ref.to_string());
+                    }
+                    None => findings.push(Finding {
+                        severity: Severity::Error,
+                        location,
+                        message: "the Rego file declares no package, so the bundle claims all \
+                                  roots and OPA refuses to activate other bundles"
+                            .to_string(),
+                    }),
+                }
+            } else if let Some(data_path) = data_document_path(cm, file_name) {
+                if let Err(error) = serde_json::from_str::<serde_json::Value>(data) {
+                    findings.push(Finding {
+                        severity: Severity::Error,
+                        location,
+                        message: format!(
+                            "the data document is not valid JSON and is skipped: {error}"
+                        ),
+                    });
+                    continue;
+                }
+                if let Some(other) =
+                    data_documents.insert(data_path.clone(), format!("{cm_ref}/{file_name}"))
+                {
+                    findings.push(Finding {
+                        severity: Severity::Error,
+                        location,
+                        message: format!(
+                            "the data document conflicts with {other} at {data_path}, only one of them is added to the bundle"
+                        ),
+                    });
+                }
+            }
+        }
+    }
+    for (package, config_maps) in packages {
+        if config_maps.len() > 1 {
+            findings.push(Finding {
+                severity: Severity::Warning,
+                location: None,
+                message: format!(
+                    "the package {package} is declared in multiple ConfigMaps ({}), their rules are merged",
+                    config_maps.into_iter().collect::<Vec<_>>().join(", ")
+                ),
+            });
+        }
+    }
+}
+
+/// Compiles the Rego files of all ConfigMaps together with `opa check`, as OPA does when activating
+/// the bundle.
+async fn compile_policies(
+    opa_binary: &Path,
+    config_maps: &[ConfigMap],
+    findings: &mut Vec<Finding>,
+) -> Result<(), Error> {
+    let rego_files = config_maps
+        .iter()
+        .flat_map(|cm| {
+            cm.data
+                .iter()
+                .flatten()
+                .filter(|(file_name, _)| file_name.ends_with(".rego"))
+                .map(move |(file_name, rego)| (cm, file_name, rego))
+        })
+        .collect::<Vec<_>>();
+    // opa check fails for directories that don't exist, and there is nothing to compile anyway
+    if rego_files.is_empty() {
+        return Ok(());
+    }
+
+    let dir = std::env::temp_dir().join(format!("opa-validate-policies-{}", std::process::id()));
+    for (cm, file_name, rego) in rego_files {
+        let cm_dir = dir
+            .join(cm.namespace().unwrap_or_default())
+            .join(cm.name_any());
+        let path = cm_dir.join(file_name);
+        tokio::fs::create_dir_all(&cm_dir)
+            .await
+            .context(WritePoliciesSnafu { path: &cm_dir })?;
+        tokio::fs::write(&path, rego)
+            .await
+            .context(WritePoliciesSnafu { path: &path })?;
+    }
+    let output = tokio::process::Command::new(opa_binary)
+        .arg("check")
+        .arg(&dir)
+        .output()
+        .await
+        .context(RunOpaSnafu { opa_binary });
+    if let Err(error) = tokio::fs::remove_dir_all(&dir).await {
+        eprintln!("failed to clean up {dir:?}: {error}");
+    }
+    let output = output?;
+    if !output.status.success() {
+        // The paths in the output are relative to the temporary directory, that is
+        // <namespace>/<ConfigMap>/<file>
+        let report =
+            String::from_utf8_lossy(&output.stderr).replace(&format!("{}/", dir.display()), "");
+        findings.push(Finding {
+            severity: Severity::Error,
+            location: None,
+            message: format!("the policies do not compile:\n{}", report.trim_end()),
+        });
+    }
+    Ok(())
+}
@@ -0,0 +1,411 @@
+//! A validating admission webhook for OpaClusters, which rejects specs that the operator would fail
+//! to reconcile (or that would break the OPA Pods) at apply time, rather than only reporting them
+//! during the reconciliation.
+//!
+//! * `POST /validate/opacluster` handles `AdmissionReview`s of OpaClusters.
+//!
+//! Settings that are valid but risky (such as TLS without verification) are returned as warnings,
+//! which `kubectl` prints without rejecting the change.
+
+use std::{net::SocketAddr, path::PathBuf, time::Duration};
+
+use axum::{extract::State, routing::post, Json, Router};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::{
+    user_info_fetcher as user_info_crd, OpaCluster, OpaRole, MAX_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT,
+    MIN_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT,
+};
+use stackable_operator::{
+    client::Client,
+    commons::tls_verification::TlsClientDetails,
+    k8s_openapi::api::core::v1::Secret,
+    kube::{runtime::reflector::ObjectRef, Api},
+    role_utils::RoleGroupRef,
+};
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to load webhook TLS certificate"))]
+    LoadTlsCertificate { source: std::io::Error },
+
+    #[snafu(display("failed to run webhook server"))]
+    RunServer { source: std::io::Error },
+}
+
+/// How often the certificate is reloaded, so that rotated certificates are picked up without a restart.
+const TLS_RELOAD_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(clap::Args)]
+pub struct WebhookArgs {
+    /// The address to serve the validating admission webhook on.
+    /// It must be reachable from the API server, which calls the webhook through a Service.
+    #[clap(long, env, default_value = "0.0.0.0:8443")]
+    webhook_listen_address: SocketAddr,
+
+    /// Path to the PEM encoded certificate to serve the webhook with.
+    /// The webhook is only served if a certificate is configured, since the API server only calls
+    /// webhooks over HTTPS.
+    #[clap(long, env, requires = "webhook_tls_key_file")]
+    webhook_tls_cert_file: Option<PathBuf>,
+
+    /// Path to the PEM encoded private key of `--webhook-tls-cert-file`.
+    #[clap(long, env, requires = "webhook_tls_cert_file")]
+    webhook_tls_key_file: Option<PathBuf>,
+}
+
+/// Serves the webhook until it fails, does nothing if no certificate is configured.
+pub async fn run_server(args: WebhookArgs, client: Client) -> Result<(), Error> {
+    let (Some(cert_file), Some(key_file)) =
+        (&args.webhook_tls_cert_file, &args.webhook_tls_key_file)
+    else {
+        return Ok(());
+    };
+    // Use the same crypto provider as the rest of the dependency tree instead of pulling in aws-lc-rs
+    // as well. Installing fails if a provider has already been installed, which is fine.
+    let _ = rustls::crypto::ring::default_provider().install_default();
+    let tls_config = axum_server::tls_rustls::RustlsConfig::from_pem_file(cert_file, key_file)
+        .await
+        .context(LoadTlsCertificateSnafu)?;
+    tokio::spawn({
+        let tls_config = tls_config.clone();
+        let (cert_file, key_file) = (cert_file.clone(), key_file.clone());
+        async move {
+            let mut interval = tokio::time::interval(TLS_RELOAD_INTERVAL);
+            // The first tick completes immediately, the certificate has just been loaded
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                if let Err(error) = tls_config.reload_from_pem_file(&cert_file, &key_file).await {
+                    tracing::error!(
+                        error = &error as &dyn std::error::Error,
+                        "failed to reload webhook TLS certificate, keeping the previous one"
+                    );
+                }
+            }
+        }
+    });
+    let app = Router::new()
+        .route("/validate/opacluster", post(validate_opa_cluster))
+        .with_state(client);
+    axum_server::bind_rustls(args.webhook_listen_address, tls_config)
+        .serve(app.into_make_service())
+        .await
+        .context(RunServerSnafu)
+}
+
+/// The subset of `admission.k8s.io/v1` `AdmissionReview`s that the webhook uses.
+#[derive(Deserialize)]
+struct AdmissionReview {
+    request: AdmissionRequest,
+}
+
+#[derive(Deserialize)]
+struct AdmissionRequest {
+    uid: String,
+    /// Unset for deletions, which are always allowed.
+    object: Option<serde_json::Value>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AdmissionReviewResponse {
+    api_version: &'static str,
+    kind: &'static str,
+    response: AdmissionResponse,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AdmissionResponse {
+    uid: String,
+    allowed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    status: Option<AdmissionStatus>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    warnings: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct AdmissionStatus {
+    code: u16,
+    message: String,
+}
+
+/// The outcome of validating an OpaCluster.
+#[derive(Default)]
+struct Validation {
+    /// Problems that the OpaCluster is rejected for.
+    errors: Vec<String>,
+    /// Problems that the operator can work around.
+    warnings: Vec<String>,
+}
+
+async fn validate_opa_cluster(
+    State(client): State<Client>,
+    Json(review): Json<AdmissionReview>,
+) -> Json<AdmissionReviewResponse> {
+    let AdmissionRequest { uid, object } = review.request;
+    let validation = match object {
+        Some(object) => match serde_json::from_value::<OpaCluster>(object) {
+            Ok(opa) => validate(&client, &opa).await,
+            Err(error) => Validation {
+                errors: vec![format!("invalid OpaCluster: {error}")],
+                ..Validation::default()
+            },
+        },
+        None => Validation::default(),
+    };
+    let status = (!validation.errors.is_empty()).then(|| AdmissionStatus {
+        code: 422,
+        message: validation.errors.join("; "),
+    });
+    Json(AdmissionReviewResponse {
+        api_version: "admission.k8s.io/v1",
+        kind: "AdmissionReview",
+        response: AdmissionResponse {
+            uid,
+            allowed: status.is_none(),
+            status,
+            warnings: validation.warnings,
+        },
+    })
+}
+
+async fn validate(client: &Client, opa: &OpaCluster) -> Validation {
+    let mut validation = Validation::default();
+    validate_role_groups(opa, &mut validation);
+    validate_sidecar_images(opa, &mut validation);
+    if let Some(user_info) = &opa.spec.cluster_config.user_info {
+        validate_user_info(client, opa, user_info, &mut validation).await;
+    }
+    validation
+}
+
+/// Checks that the configuration of every role group can be merged and validated, that the
+/// readiness probe path is absolute, and that the graceful shutdown timeout is within the range that
+/// the operator supports.
+fn validate_role_groups(opa: &OpaCluster, validation: &mut Validation) {
+    let opa_ref = ObjectRef::from_obj(opa);
+    for role_group in opa.spec.servers.role_groups.keys() {
+        let rolegroup_ref = RoleGroupRef {
+            cluster: opa_ref.clone(),
+            role: OpaRole::Server.to_string(),
+            role_group: role_group.clone(),
+        };
+        let merged_config = match opa.merged_config(&OpaRole::Server, &rolegroup_ref) {
+            Ok(merged_config) => merged_config,
+            Err(error) => {
+                validation
+                    .errors
+                    .push(format!("role group {role_group}: {}", error_chain(&error)));
+                continue;
+            }
+        };
+        if !merged_config.probes.readiness_path.starts_with('/') {
+            validation.errors.push(format!(
+                "role group {role_group}: probes.readinessPath {:?} must start with a slash",
+                merged_config.probes.readiness_path
+            ));
+        }
+        if let Some(timeout) = merged_config.graceful_shutdown_timeout {
+            if timeout < MIN_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT
+                || timeout > MAX_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT
+            {
+                validation.errors.push(format!(
+                    "role group {role_group}: gracefulShutdownTimeout {timeout} must be between \
+                     {MIN_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT} and {MAX_SERVER_GRACEFUL_SHUTDOWN_TIMEOUT}"
+                ));
+            }
+        }
+    }
+}
+
+/// Checks that the image references of the sidecar image overrides are well-formed.
+fn validate_sidecar_images(opa: &OpaCluster, validation: &mut Validation) {
+    let images = &opa.spec.cluster_config.images;
+    for (field, image) in [
+        ("bundleBuilder", &images.bundle_builder),
+        ("userInfoFetcher", &images.user_info_fetcher),
+    ] {
+        if let Some(Err(error)) = image.as_ref().map(|image| image.validate()) {
+            validation
+                .errors
+                .push(format!("clusterConfig.images.{field}: {error}"));
+        }
+    }
+}
+
+async fn validate_user_info(
+    client: &Client,
+    opa: &OpaCluster,
+    user_info: &user_info_crd::Config,
+    validation: &mut Validation,
+) {
+    let tls: Option<&TlsClientDetails> = match &user_info.backend {
+        user_info_crd::Backend::None {} => None,
+        user_info_crd::Backend::Keycloak(keycloak) => {
+            match (
+                &keycloak.client_credentials_secret,
+                &keycloak.client_credentials_secret_class,
+            ) {
+                (Some(secret_name), None) => {
+                    validate_credentials_secret(
+                        client,
+                        opa,
+                        secret_name,
+                        "userInfo.backend.keycloak.clientCredentialsSecret",
+                        &["clientId", "clientSecret"],
+                        validation,
+                    )
+                    .await
+                }
+                // The SecretClass only provides the credentials once the Pods are scheduled
+                (None, Some(_)) => {}
+                _ => validation.errors.push(
+                    "userInfo.backend.keycloak: exactly one of clientCredentialsSecret and \
+                     clientCredentialsSecretClass must be set"
+                        .to_string(),
+                ),
+            }
+            Some(&keycloak.tls)
+        }
+        user_info_crd::Backend::XfscAas(aas) | user_info_crd::Backend::ExperimentalXfscAas(aas) => {
+            Some(&aas.tls)
+        }
+        user_info_crd::Backend::ActiveDirectory(ad) => Some(&ad.tls),
+        user_info_crd::Backend::Scim(scim) => {
+            validate_credentials_secret(
+                client,
+                opa,
+                &scim.bearer_token_secret,
+                "userInfo.backend.scim.bearerTokenSecret",
+                &[user_info_crd::ScimBackend::BEARER_TOKEN_SECRET_KEY],
+                validation,
+            )
+            .await;
+            Some(&scim.tls)
+        }
+        user_info_crd::Backend::Okta(okta) => {
+            let field = match &okta.credentials {
+                user_info_crd::OktaCredentials::ApiTokenSecret(_) => {
+                    "userInfo.backend.okta.credentials.apiTokenSecret"
+                }
+                user_info_crd::OktaCredentials::ServiceApp(_) => {
+                    "userInfo.backend.okta.credentials.serviceApp.privateKeySecret"
+                }
+            };
+            validate_credentials_secret(
+                client,
+                opa,
+                okta.credentials.secret_name(),
+                field,
+                &[okta.credentials.secret_key()],
+                validation,
+            )
+            .await;
+            Some(&okta.tls)
+        }
+    };
+    if matches!(
+        user_info.backend,
+        user_info_crd::Backend::ExperimentalXfscAas(_)
+    ) {
+        validation.warnings.push(
+            "userInfo.backend.experimentalXfscAas is deprecated and will be removed in the next \
+             version of the OpaCluster API, use xfscAas instead"
+                .to_string(),
+        );
+    }
+    if tls.is_some_and(|tls| tls.uses_tls() && !tls.uses_tls_verification()) {
+        validation.warnings.push(
+            "userInfo.backend: TLS is used without verifying the server certificate, so the \
+             backend cannot be authenticated"
+                .to_string(),
+        );
+    }
+
+    if user_info.group_info.enabled
+        && !matches!(
+            user_info.backend,
+            user_info_crd::Backend::Keycloak(_) | user_info_crd::Backend::ActiveDirectory(_)
+        )
+    {
+        validation.warnings.push(
+            "userInfo.groupInfo: group lookups are only supported by the keycloak and \
+             experimentalActiveDirectory backends, and are refused otherwise"
+                .to_string(),
+        );
+    }
+
+    // The user-info-fetcher anchors the regex in the same way, see its group_mapping module
+    if let Some(regex) = &user_info.group_mapping.regex {
+        if let Err(error) = Regex::new(&format!("^(?:{regex})$")) {
+            validation
+                .errors
+                .push(format!("userInfo.groupMapping.regex is invalid: {error}"));
+        }
+    }
+}
+
+/// Checks that the Secret exists and contains the credentials expected by the user-info-fetcher,
+/// which would otherwise fail to start.
+///
+/// Problems are only reported as warnings, since the Secret might be applied after the OpaCluster
+/// (for example by GitOps tools, which apply all objects at once).
+async fn validate_credentials_secret(
+    client: &Client,
+    opa: &OpaCluster,
+    secret_name: &str,
+    field: &str,
+    keys: &[&str],
+    validation: &mut Validation,
+) {
+    let Some(namespace) = opa.metadata.namespace.as_deref() else {
+        return;
+    };
+    let secret = match Api::<Secret>::namespaced(client.as_kube_client(), namespace)
+        .get_opt(secret_name)
+        .await
+    {
+        Ok(secret) => secret,
+        Err(error) => {
+            // Don't block changes just because the API server could not be queried
+            tracing::warn!(
+                error = &error as &dyn std::error::Error,
+                secret_name,
+                "failed to get user-info-fetcher credentials Secret"
+            );
+            return;
+        }
+    };
+    let Some(secret) = secret else {
+        validation
+            .warnings
+            .push(format!("{field}: Secret {secret_name:?} does not exist"));
+        return;
+    };
+    for key in keys {
+        if !secret
+            .data
+            .as_ref()
+            .is_some_and(|data| data.contains_key(key))
+        {
+            validation.warnings.push(format!(
+                "{field}: Secret {secret_name:?} does not contain {key:?}"
+            ));
+        }
+    }
+}
+
+fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(error) = source {
+        message.push_str(": ");
+        message.push_str(&error.to_string());
+        source = error.source();
+    }
+    message
+}
@@ -1,3 +1,6 @@
+/// The version of the library, which changes whenever the library (or the operator) is released.
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
 pub const REGORULES: &[(&str, &str)] = &[(
     "stackable/opa/userinfo/v1.rego",
     include_str!("userinfo/v1.rego"),
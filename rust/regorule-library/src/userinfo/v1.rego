@@ -1,19 +1,62 @@
 package stackable.opa.userinfo.v1
 
-# Lookup by (human-readable) username
-userInfoByUsername(username) := http.send({
+# The operator sets USER_INFO_FETCHER_URL (and USER_INFO_FETCHER_CA_CERT_FILE
+# when the user-info-fetcher serves its API over HTTPS)
+default _baseUrl := "http://127.0.0.1:9476"
+
+_baseUrl := opa.runtime().env.USER_INFO_FETCHER_URL
+
+default _tlsOptions := {}
+
+_tlsOptions := {"tls_ca_cert_file": opa.runtime().env.USER_INFO_FETCHER_CA_CERT_FILE}
+
+_request(path, body, raiseError) := http.send(object.union({
   "method": "POST",
-  "url": "http://127.0.0.1:9476/user",
-  "body": {"username": username},
+  "url": concat("", [_baseUrl, path]),
+  "body": body,
   "headers": {"Content-Type": "application/json"},
-  "raise_error": true
-}).body
+  # The user-info-fetcher sets Cache-Control to how long the user information
+  # stays fresh, so that it is only requested once per query and user
+  "cache": true,
+  "raise_error": raiseError
+}, _tlsOptions))
+
+_fetchUserInfo(body) := _fetchUserInfoFrom("/user", body)
+
+_fetchUserInfoFrom(path, body) := _request(path, body, true).body
+
+_tryFetchUserInfoFrom(path, body) := response.body if {
+  response := _request(path, body, false)
+  response.status_code == 200
+}
+
+# Lookup by (human-readable) username
+userInfoByUsername(username) := _fetchUserInfo({"username": username})
 
 # Lookup by stable user identifier
-userInfoById(id) := http.send({
-  "method": "POST",
-  "url": "http://127.0.0.1:9476/user",
-  "body": {"id": id},
-  "headers": {"Content-Type": "application/json"},
-  "raise_error": true
-}).body
+userInfoById(id) := _fetchUserInfo({"id": id})
+
+# Lookups that are undefined if the user cannot be looked up (for example
+# because the user does not exist or the backend is unavailable), instead of
+# returning the error
+tryUserInfoByUsername(username) := _tryFetchUserInfoFrom("/user", {"username": username})
+
+tryUserInfoById(id) := _tryFetchUserInfoFrom("/user", {"id": id})
+
+# Lookups that only fetch the listed custom attributes, which is faster if the
+# policy only needs some (or none, if empty) of them
+_attributesPath(customAttributes) := concat("", ["/user?customAttributes=", urlquery.encode(concat(",", customAttributes))])
+
+userInfoByUsernameWithAttributes(username, customAttributes) := _fetchUserInfoFrom(_attributesPath(customAttributes), {"username": username})
+
+userInfoByIdWithAttributes(id, customAttributes) := _fetchUserInfoFrom(_attributesPath(customAttributes), {"id": id})
+
+# Lookups of groups (including their members), which must be enabled with
+# userInfo.groupInfo.enabled. Groups are named like the groups of users
+groupInfoByName(name) := _fetchUserInfoFrom("/group", {"name": name})
+
+groupInfoById(id) := _fetchUserInfoFrom("/group", {"id": id})
+
+tryGroupInfoByName(name) := _tryFetchUserInfoFrom("/group", {"name": name})
+
+tryGroupInfoById(id) := _tryFetchUserInfoFrom("/group", {"id": id})
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BTreeMap, HashMap},
+    collections::{BTreeMap, BTreeSet, HashMap},
     fmt::Display,
     io::{Cursor, Read},
     num::ParseIntError,
@@ -7,49 +7,80 @@ use std::{
 };
 
 use byteorder::{BigEndian, LittleEndian, ReadBytesExt};
+use futures::{future::BoxFuture, FutureExt};
 use hyper::StatusCode;
-use ldap3::{ldap_escape, Ldap, LdapConnAsync, LdapConnSettings, LdapError, Scope, SearchEntry};
+use ldap3::{
+    adapters::{Adapter, EntriesOnly, PagedResults},
+    ldap_escape, Ldap, LdapError, LdapResult, Scope, SearchEntry,
+};
 use snafu::{OptionExt, ResultExt, Snafu};
-use stackable_operator::commons::tls_verification::TlsClientDetails;
+use stackable_opa_crd::user_info_fetcher as crd;
 use uuid::Uuid;
 
-use crate::{http_error, utils, ErrorRenderUserInfoRequest, UserInfo, UserInfoRequest};
+use crate::{
+    backend::{ldap_pool::LdapPool, UserInfoBackend},
+    get_group_info_error, get_user_info_error, http_error, utils, AttributeSelection,
+    ErrorRenderGroupInfoRequest, ErrorRenderUserInfoRequest, GetGroupInfoError, GetUserInfoError,
+    GroupInfo, GroupInfoRequest, UserInfo, UserInfoRequest,
+};
 
 #[derive(Snafu, Debug)]
 pub enum Error {
     #[snafu(display("failed to configure TLS"))]
     ConfigureTls { source: utils::tls::Error },
 
-    #[snafu(display("failed to connect to LDAP"))]
-    ConnectLdap { source: LdapError },
+    #[snafu(display("failed to connect to any LDAP server"))]
+    ConnectLdap { source: super::ldap_pool::Error },
 
     #[snafu(display("failed to send LDAP request"))]
     RequestLdap { source: LdapError },
 
-    #[snafu(display("failed to bind LDAP credentials"))]
-    BindLdap { source: LdapError },
-
     #[snafu(display("failed to search LDAP for users"))]
     FindUserLdap { source: LdapError },
 
     #[snafu(display("failed to search LDAP for groups of user"))]
     FindUserGroupsLdap { source: LdapError },
 
+    #[snafu(display("failed to search LDAP for groups"))]
+    FindGroupLdap { source: LdapError },
+
+    #[snafu(display("failed to search LDAP for members of group"))]
+    FindGroupMembersLdap { source: LdapError },
+
     #[snafu(display("invalid user ID sent by client"))]
     ParseIdByClient { source: uuid::Error },
 
+    #[snafu(display("invalid group ID sent by client"))]
+    ParseGroupIdByClient { source: uuid::Error },
+
     #[snafu(display("invalid user ID sent by LDAP"))]
     ParseIdByLdap { source: uuid::Error },
 
     #[snafu(display("unable to find user {request}"))]
     UserNotFound { request: ErrorRenderUserInfoRequest },
 
+    #[snafu(display("unable to find group {request}"))]
+    GroupNotFound {
+        request: ErrorRenderGroupInfoRequest,
+    },
+
+    #[snafu(display("failed to parse group {group_dn:?}'s SID"))]
+    ParseGroupSid {
+        source: ParseSecurityIdError,
+        group_dn: String,
+    },
+
     #[snafu(display("unable to parse user {user_dn:?}'s primary group's RID"))]
     InvalidPrimaryGroupRelativeId {
         source: ParseIntError,
         user_dn: String,
     },
 
+    #[snafu(display(
+        "user {user_dn:?} is a member of more than {max_results} groups (search.maxResults)"
+    ))]
+    TooManyUserGroups { user_dn: String, max_results: usize },
+
     #[snafu(display("user {user_dn:?}'s SID has no subauthorities"))]
     UserSidHasNoSubauthorities { user_dn: String },
 
@@ -66,13 +97,18 @@ impl http_error::Error for Error {
             Error::ConfigureTls { .. } => StatusCode::SERVICE_UNAVAILABLE,
             Error::ConnectLdap { .. } => StatusCode::SERVICE_UNAVAILABLE,
             Error::RequestLdap { .. } => StatusCode::SERVICE_UNAVAILABLE,
-            Error::BindLdap { .. } => StatusCode::SERVICE_UNAVAILABLE,
             Error::FindUserLdap { .. } => StatusCode::SERVICE_UNAVAILABLE,
             Error::FindUserGroupsLdap { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            Error::FindGroupLdap { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            Error::FindGroupMembersLdap { .. } => StatusCode::SERVICE_UNAVAILABLE,
             Error::ParseIdByClient { .. } => StatusCode::BAD_REQUEST,
+            Error::ParseGroupIdByClient { .. } => StatusCode::BAD_REQUEST,
             Error::ParseIdByLdap { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Error::UserNotFound { .. } => StatusCode::NOT_FOUND,
+            Error::GroupNotFound { .. } => StatusCode::NOT_FOUND,
+            Error::ParseGroupSid { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Error::InvalidPrimaryGroupRelativeId { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Error::TooManyUserGroups { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Error::UserSidHasNoSubauthorities { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Error::ParseUserSid { .. } => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -89,95 +125,342 @@ const LDAP_FIELD_OBJECT_DISTINGUISHED_NAME: &str = "dn";
 const LDAP_FIELD_USER_NAME: &str = "userPrincipalName";
 const LDAP_FIELD_USER_PRIMARY_GROUP_RID: &str = "primaryGroupID";
 const LDAP_FIELD_GROUP_MEMBER: &str = "member";
+/// The distinguished name of an object, when used in filters (it is returned as `dn`)
+const LDAP_FILTER_DISTINGUISHED_NAME: &str = "distinguishedName";
+const LDAP_FIELD_MEMBER_OF: &str = "memberOf";
+
+/// The result code of searches that returned more than the requested number of entries, see
+/// [RFC 4511](https://www.rfc-editor.org/rfc/rfc4511#appendix-A.1).
+const LDAP_RESULT_SIZE_LIMIT_EXCEEDED: u32 = 4;
+
+/// The Active Directory backend, with the TLS configuration loaded once at startup
+/// instead of for every request.
+pub struct ResolvedActiveDirectoryBackend {
+    ldap_pool: LdapPool,
+    base_distinguished_name: String,
+    search: crd::LdapSearch,
+    custom_attribute_mappings: BTreeMap<String, String>,
+    group_attribute_mappings: BTreeMap<String, String>,
+}
 
-#[tracing::instrument(skip(tls, base_distinguished_name, custom_attribute_mappings))]
-pub(crate) async fn get_user_info(
-    request: &UserInfoRequest,
-    ldap_server: &str,
-    tls: &TlsClientDetails,
-    base_distinguished_name: &str,
-    custom_attribute_mappings: &BTreeMap<String, String>,
-) -> Result<UserInfo, Error> {
-    let ldap_tls = utils::tls::configure_native_tls(tls)
-        .await
-        .context(ConfigureTlsSnafu)?;
-    let (ldap_conn, mut ldap) = LdapConnAsync::with_settings(
-        LdapConnSettings::new().set_connector(ldap_tls),
-        &format!(
-            "{protocol}://{ldap_server}",
-            protocol = if tls.uses_tls() { "ldaps" } else { "ldap" }
-        ),
-    )
-    .await
-    .context(ConnectLdapSnafu)?;
-    ldap3::drive!(ldap_conn);
-    ldap.sasl_gssapi_bind(ldap_server)
-        .await
-        .context(RequestLdapSnafu)?
-        .success()
-        .context(BindLdapSnafu)?;
-    let user_filter = match request {
-        UserInfoRequest::UserInfoRequestById(id) => {
-            format!(
-                "{LDAP_FIELD_OBJECT_ID}={}",
-                ldap_escape_bytes(
-                    &Uuid::from_str(&id.id)
-                        .context(ParseIdByClientSnafu)?
-                        .to_bytes_le()
+impl ResolvedActiveDirectoryBackend {
+    pub async fn resolve(
+        config: &crd::ActiveDirectoryBackend,
+        trust_store: Option<&[u8]>,
+    ) -> Result<Self, Error> {
+        let crd::ActiveDirectoryBackend {
+            ldap_server,
+            failover_ldap_servers,
+            connection_pool,
+            search,
+            base_distinguished_name,
+            kerberos_secret_class_name: _,
+            tls,
+            custom_attribute_mappings,
+            group_attribute_mappings,
+        } = config;
+        Ok(Self {
+            ldap_pool: LdapPool::new(
+                std::iter::once(ldap_server)
+                    .chain(failover_ldap_servers)
+                    .cloned(),
+                utils::tls::configure_native_tls(tls, trust_store)
+                    .await
+                    .context(ConfigureTlsSnafu)?,
+                tls.uses_tls(),
+                connection_pool,
+            ),
+            base_distinguished_name: base_distinguished_name.clone(),
+            search: search.clone(),
+            custom_attribute_mappings: custom_attribute_mappings.clone(),
+            group_attribute_mappings: group_attribute_mappings.clone(),
+        })
+    }
+
+    #[tracing::instrument(skip(self), fields(ldap_server))]
+    async fn get_user_info(
+        &self,
+        request: &UserInfoRequest,
+        attributes: &AttributeSelection,
+    ) -> Result<UserInfo, Error> {
+        let mut ldap = self.ldap_pool.get().await.context(ConnectLdapSnafu)?;
+        tracing::Span::current().record("ldap_server", ldap.server());
+        let result = self
+            .get_user_info_from(&mut ldap.ldap, request, attributes)
+            .await;
+        // Connections that failed to send a request might be broken, the other errors are
+        // answers from the server
+        if !matches!(result, Err(Error::RequestLdap { .. })) {
+            ldap.release();
+        }
+        result
+    }
+
+    async fn get_user_info_from(
+        &self,
+        ldap: &mut Ldap,
+        request: &UserInfoRequest,
+        attributes: &AttributeSelection,
+    ) -> Result<UserInfo, Error> {
+        let Self {
+            ldap_pool: _,
+            base_distinguished_name,
+            search,
+            custom_attribute_mappings,
+            group_attribute_mappings,
+        } = self;
+        // Only the selected attributes are requested from LDAP
+        let custom_attribute_mappings =
+            select_attribute_mappings(custom_attribute_mappings, attributes);
+        let group_attribute_mappings =
+            select_attribute_mappings(group_attribute_mappings, attributes);
+        let user_filter = match request {
+            UserInfoRequest::UserInfoRequestById(id) => {
+                format!(
+                    "{LDAP_FIELD_OBJECT_ID}={}",
+                    ldap_escape_bytes(
+                        &Uuid::from_str(&id.id)
+                            .context(ParseIdByClientSnafu)?
+                            .to_bytes_le()
+                    )
                 )
+            }
+            UserInfoRequest::UserInfoRequestByName(username) => {
+                format!("{LDAP_FIELD_USER_NAME}={}", ldap_escape(&username.username))
+            }
+        };
+        let requested_user_attrs = [
+            LDAP_FIELD_OBJECT_SECURITY_ID,
+            LDAP_FIELD_OBJECT_ID,
+            LDAP_FIELD_USER_NAME,
+            LDAP_FIELD_USER_PRIMARY_GROUP_RID,
+        ]
+        .into_iter()
+        .chain(custom_attribute_mappings.values().map(String::as_str))
+        .collect::<Vec<&str>>();
+        let user_query_filter = format!("(&(objectClass=user)({user_filter}))");
+        tracing::debug!(
+            user_query_filter,
+            ?requested_user_attrs,
+            "requesting user from LDAP"
+        );
+        let user = ldap
+            .search(
+                base_distinguished_name,
+                Scope::Subtree,
+                &user_query_filter,
+                requested_user_attrs,
             )
-        }
-        UserInfoRequest::UserInfoRequestByName(username) => {
-            format!("{LDAP_FIELD_USER_NAME}={}", ldap_escape(&username.username))
-        }
-    };
-    let requested_user_attrs = [
-        LDAP_FIELD_OBJECT_SECURITY_ID,
-        LDAP_FIELD_OBJECT_ID,
-        LDAP_FIELD_USER_NAME,
-        LDAP_FIELD_USER_PRIMARY_GROUP_RID,
-    ]
-    .into_iter()
-    .chain(custom_attribute_mappings.values().map(String::as_str))
-    .collect::<Vec<&str>>();
-    let user_query_filter = format!("(&(objectClass=user)({user_filter}))");
-    tracing::debug!(
-        user_query_filter,
-        ?requested_user_attrs,
-        "requesting user from LDAP"
-    );
-    let user = ldap
-        .search(
+            .await
+            .context(RequestLdapSnafu)?
+            .success()
+            .context(FindUserLdapSnafu)?
+            .0
+            .into_iter()
+            .next()
+            .context(UserNotFoundSnafu { request })?;
+        let user = SearchEntry::construct(user);
+        tracing::debug!(?user, "got user from LDAP");
+        user_attributes(
+            ldap,
             base_distinguished_name,
-            Scope::Subtree,
-            &user_query_filter,
-            requested_user_attrs,
+            search,
+            &user,
+            &custom_attribute_mappings,
+            &group_attribute_mappings,
         )
         .await
-        .context(RequestLdapSnafu)?
-        .success()
-        .context(FindUserLdapSnafu)?
-        .0
-        .into_iter()
-        .next()
-        .context(UserNotFoundSnafu { request })?;
-    let user = SearchEntry::construct(user);
-    tracing::debug!(?user, "got user from LDAP");
-    user_attributes(
-        &mut ldap,
-        base_distinguished_name,
-        &user,
-        custom_attribute_mappings,
-    )
-    .await
+    }
+}
+
+impl ResolvedActiveDirectoryBackend {
+    #[tracing::instrument(skip(self), fields(ldap_server))]
+    async fn get_group_info(
+        &self,
+        request: &GroupInfoRequest,
+        max_members: usize,
+    ) -> Result<GroupInfo, Error> {
+        let mut ldap = self.ldap_pool.get().await.context(ConnectLdapSnafu)?;
+        tracing::Span::current().record("ldap_server", ldap.server());
+        let result = self
+            .get_group_info_from(&mut ldap.ldap, request, max_members)
+            .await;
+        // See get_user_info
+        if !matches!(result, Err(Error::RequestLdap { .. })) {
+            ldap.release();
+        }
+        result
+    }
+
+    async fn get_group_info_from(
+        &self,
+        ldap: &mut Ldap,
+        request: &GroupInfoRequest,
+        max_members: usize,
+    ) -> Result<GroupInfo, Error> {
+        let Self {
+            ldap_pool: _,
+            base_distinguished_name,
+            search,
+            custom_attribute_mappings: _,
+            group_attribute_mappings,
+        } = self;
+        let group_filter = match request {
+            GroupInfoRequest::GroupInfoRequestById(id) => {
+                format!(
+                    "{LDAP_FIELD_OBJECT_ID}={}",
+                    ldap_escape_bytes(
+                        &Uuid::from_str(&id.id)
+                            .context(ParseGroupIdByClientSnafu)?
+                            .to_bytes_le()
+                    )
+                )
+            }
+            // Groups are named by their distinguished names, like the groups of users
+            GroupInfoRequest::GroupInfoRequestByName(name) => {
+                format!(
+                    "{LDAP_FILTER_DISTINGUISHED_NAME}={}",
+                    ldap_escape(&name.name)
+                )
+            }
+        };
+        let requested_group_attrs = [LDAP_FIELD_OBJECT_ID, LDAP_FIELD_OBJECT_SECURITY_ID]
+            .into_iter()
+            .chain(group_attribute_mappings.values().map(String::as_str))
+            .collect::<Vec<&str>>();
+        let group_query_filter = format!("(&(objectClass=group)({group_filter}))");
+        tracing::debug!(
+            group_query_filter,
+            ?requested_group_attrs,
+            "requesting group from LDAP"
+        );
+        let group = ldap
+            .search(
+                base_distinguished_name,
+                Scope::Subtree,
+                &group_query_filter,
+                requested_group_attrs,
+            )
+            .await
+            .context(RequestLdapSnafu)?
+            .success()
+            .context(FindGroupLdapSnafu)?
+            .0
+            .into_iter()
+            .next()
+            .context(GroupNotFoundSnafu {
+                request: ErrorRenderGroupInfoRequest::from(request),
+            })?;
+        let group = SearchEntry::construct(group);
+        tracing::debug!(?group, "got group from LDAP");
+
+        let id = group
+            .bin_attrs
+            .get(LDAP_FIELD_OBJECT_ID)
+            .and_then(|values| values.first())
+            .map(|uuid| Uuid::from_slice_le(uuid).context(ParseIdByLdapSnafu))
+            .transpose()?;
+        let group_sid = group
+            .bin_attrs
+            .get(LDAP_FIELD_OBJECT_SECURITY_ID)
+            .and_then(|values| values.first())
+            .map(|sid| {
+                SecurityId::from_bytes(sid).context(ParseGroupSidSnafu {
+                    group_dn: &group.dn,
+                })
+            })
+            .transpose()?;
+        let custom_attributes = group_attribute_mappings
+            .iter()
+            .filter_map(|(uif_key, ldap_key)| {
+                let values = group.attrs.get(ldap_key)?;
+                Some((
+                    uif_key.clone(),
+                    serde_json::Value::Array(
+                        values
+                            .iter()
+                            .cloned()
+                            .map(serde_json::Value::String)
+                            .collect(),
+                    ),
+                ))
+            })
+            .collect::<HashMap<_, _>>();
+
+        let (members, members_truncated) = group_members(
+            ldap,
+            base_distinguished_name,
+            search,
+            &group.dn,
+            group_sid.as_ref(),
+            max_members,
+        )
+        .await?;
+        Ok(GroupInfo {
+            id: id.map(|id| id.to_string()),
+            name: group.dn,
+            members,
+            members_truncated,
+            custom_attributes,
+        })
+    }
+}
+
+fn select_attribute_mappings(
+    attribute_mappings: &BTreeMap<String, String>,
+    attributes: &AttributeSelection,
+) -> BTreeMap<String, String> {
+    attribute_mappings
+        .iter()
+        .filter(|(uif_key, _)| attributes.includes(uif_key))
+        .map(|(uif_key, ldap_key)| (uif_key.clone(), ldap_key.clone()))
+        .collect()
 }
 
-#[tracing::instrument(skip(ldap, base_dn, user, custom_attribute_mappings), fields(user.dn))]
+impl UserInfoBackend for ResolvedActiveDirectoryBackend {
+    fn get_user_info<'a>(
+        &'a self,
+        req: &'a UserInfoRequest,
+        attributes: &'a AttributeSelection,
+    ) -> BoxFuture<'a, Result<UserInfo, GetUserInfoError>> {
+        ResolvedActiveDirectoryBackend::get_user_info(self, req, attributes)
+            .map(|result| result.context(get_user_info_error::ActiveDirectorySnafu))
+            .boxed()
+    }
+
+    fn get_group_info<'a>(
+        &'a self,
+        req: &'a GroupInfoRequest,
+        max_members: usize,
+    ) -> BoxFuture<'a, Result<GroupInfo, GetGroupInfoError>> {
+        ResolvedActiveDirectoryBackend::get_group_info(self, req, max_members)
+            .map(|result| result.context(get_group_info_error::ActiveDirectorySnafu))
+            .boxed()
+    }
+
+    fn name(&self) -> &'static str {
+        "activeDirectory"
+    }
+}
+
+#[tracing::instrument(
+    skip(
+        ldap,
+        base_dn,
+        search,
+        user,
+        custom_attribute_mappings,
+        group_attribute_mappings
+    ),
+    fields(user.dn)
+)]
 async fn user_attributes(
     ldap: &mut Ldap,
     base_dn: &str,
+    search: &crd::LdapSearch,
     user: &SearchEntry,
     custom_attribute_mappings: &BTreeMap<String, String>,
+    group_attribute_mappings: &BTreeMap<String, String>,
 ) -> Result<UserInfo, Error> {
     let user_sid = user
         .bin_attrs
@@ -202,7 +485,7 @@ async fn user_attributes(
         .get(LDAP_FIELD_USER_NAME)
         .and_then(|values| values.first())
         .cloned();
-    let custom_attributes = custom_attribute_mappings
+    let mut custom_attributes = custom_attribute_mappings
         .iter()
         .filter_map(|(uif_key, ldap_key)| {
             Some((
@@ -242,28 +525,63 @@ async fn user_attributes(
         })
         .collect::<HashMap<_, _>>();
     let groups = if let Some(user_sid) = &user_sid {
-        user_group_distinguished_names(ldap, base_dn, user, user_sid).await?
+        user_groups(
+            ldap,
+            base_dn,
+            search,
+            user,
+            user_sid,
+            group_attribute_mappings,
+        )
+        .await?
     } else {
         tracing::debug!(user.dn, "user has no SID, cannot fetch groups...");
         Vec::new()
     };
+    for (uif_key, ldap_key) in group_attribute_mappings {
+        let group_values = groups
+            .iter()
+            .flat_map(|group| group.attrs.get(ldap_key).into_iter().flatten())
+            .collect::<BTreeSet<_>>();
+        if group_values.is_empty() {
+            continue;
+        }
+        let serde_json::Value::Array(values) = custom_attributes
+            .entry(uif_key.clone())
+            .or_insert_with(|| serde_json::Value::Array(Vec::new()))
+        else {
+            unreachable!("custom attributes are always arrays");
+        };
+        for value in group_values {
+            let value = serde_json::Value::String(value.clone());
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+    }
 
     Ok(UserInfo {
         id: id.map(|id| id.to_string()),
         username,
-        groups,
+        groups: groups.into_iter().map(|group| group.dn).collect(),
         custom_attributes,
     })
 }
 
-/// Gets the distinguished names of all of `user`'s groups, both primary and secondary.
-#[tracing::instrument(skip(ldap, base_dn, user, user_sid))]
-async fn user_group_distinguished_names(
+/// Gets all of `user`'s groups, both primary and secondary, including the attributes
+/// requested by `group_attribute_mappings`.
+///
+/// Fails if the user is a member of more than `search.max_results` groups, since policies could
+/// otherwise be evaluated against an incomplete list of groups.
+#[tracing::instrument(skip(ldap, base_dn, search, user, user_sid, group_attribute_mappings))]
+async fn user_groups(
     ldap: &mut Ldap,
     base_dn: &str,
+    search: &crd::LdapSearch,
     user: &SearchEntry,
     user_sid: &SecurityId,
-) -> Result<Vec<String>, Error> {
+    group_attribute_mappings: &BTreeMap<String, String>,
+) -> Result<Vec<SearchEntry>, Error> {
     // User group memberships are tricky, because users have exactly one *primary* and any number of *secondary* groups.
     // Additionally groups can be members of other groups.
     // Secondary groups are easy to read, either from reading the user's "memberOf" field, or by matching the user against
@@ -313,27 +631,126 @@ async fn user_group_distinguished_names(
     let groups_filter =
         format!("(|{primary_group_filter}{primary_group_parents_filter}{secondary_groups_filter})");
     let groups_query_filter = format!("(&(objectClass=group){groups_filter})");
-    let requested_group_attrs = [LDAP_FIELD_OBJECT_DISTINGUISHED_NAME];
+    let requested_group_attrs = [LDAP_FIELD_OBJECT_DISTINGUISHED_NAME]
+        .into_iter()
+        .chain(group_attribute_mappings.values().map(String::as_str))
+        .collect::<Vec<&str>>();
     tracing::debug!(
         groups_query_filter,
         ?requested_group_attrs,
         "requesting user groups from LDAP",
     );
-    Ok(ldap
-        .search(
-            base_dn,
-            Scope::Subtree,
-            &groups_query_filter,
-            requested_group_attrs,
-        )
-        .await
-        .context(RequestLdapSnafu)?
-        .success()
-        .context(FindUserGroupsLdapSnafu)?
-        .0
+    let (groups, result) = paged_search(
+        ldap,
+        base_dn,
+        &groups_query_filter,
+        requested_group_attrs,
+        search.page_size,
+        search.max_results,
+    )
+    .await
+    .context(RequestLdapSnafu)?;
+    match result {
+        Some(result) => {
+            result.success().context(FindUserGroupsLdapSnafu)?;
+        }
+        None => {
+            return TooManyUserGroupsSnafu {
+                user_dn: &user.dn,
+                max_results: search.max_results,
+            }
+            .fail()
+        }
+    }
+    Ok(groups)
+}
+
+/// Gets the usernames of (at most `max_members` of) the members of the group, including the members
+/// of its nested groups, along with whether further members were omitted.
+#[tracing::instrument(skip(ldap, base_dn, search, group_sid))]
+async fn group_members(
+    ldap: &mut Ldap,
+    base_dn: &str,
+    search: &crd::LdapSearch,
+    group_dn: &str,
+    group_sid: Option<&SecurityId>,
+    max_members: usize,
+) -> Result<(Vec<String>, bool), Error> {
+    // The inverse of user_groups: secondary members are matched recursively against "memberOf",
+    // while users that have the group as their *primary* group are only linked to it by its RID.
+    let secondary_members_filter = format!(
+        "({LDAP_FIELD_MEMBER_OF}{LDAP_MATCHING_RULE_IN_CHAIN}={})",
+        ldap_escape(group_dn)
+    );
+    let primary_members_filter = group_sid
+        .and_then(|group_sid| group_sid.subauthorities.last())
+        .map(|group_relative_id| {
+            format!("({LDAP_FIELD_USER_PRIMARY_GROUP_RID}={group_relative_id})")
+        })
+        .unwrap_or_default();
+    let members_query_filter =
+        format!("(&(objectClass=user)(|{secondary_members_filter}{primary_members_filter}))");
+    tracing::debug!(members_query_filter, "requesting group members from LDAP");
+    let (entries, result) = paged_search(
+        ldap,
+        base_dn,
+        &members_query_filter,
+        vec![LDAP_FIELD_USER_NAME],
+        search.page_size,
+        max_members,
+    )
+    .await
+    .context(RequestLdapSnafu)?;
+    let members_truncated = match result {
+        // Domain controllers can still limit the total size of (paged) searches
+        Some(result) if result.rc == LDAP_RESULT_SIZE_LIMIT_EXCEEDED => true,
+        Some(result) => {
+            result.success().context(FindGroupMembersLdapSnafu)?;
+            false
+        }
+        None => true,
+    };
+    let members = entries
         .into_iter()
-        .map(|group| SearchEntry::construct(group).dn)
-        .collect::<Vec<_>>())
+        // Users without a userPrincipalName cannot be looked up by username, and are omitted
+        .filter_map(|member| member.attrs.get(LDAP_FIELD_USER_NAME)?.first().cloned())
+        .collect();
+    Ok((members, members_truncated))
+}
+
+/// Searches the subtree of `base_dn` using the simple paged results control
+/// ([RFC 2696](https://www.rfc-editor.org/rfc/rfc2696)), so that searches matching more entries
+/// than the size limit of the domain controller are neither rejected nor truncated.
+///
+/// The search is abandoned once it found more than `max_entries` entries, in which case only the
+/// first `max_entries` entries and no result are returned.
+async fn paged_search(
+    ldap: &mut Ldap,
+    base_dn: &str,
+    filter: &str,
+    attrs: Vec<&str>,
+    page_size: u32,
+    max_entries: usize,
+) -> Result<(Vec<SearchEntry>, Option<LdapResult>), LdapError> {
+    let adapters: Vec<Box<dyn Adapter<_, _>>> = vec![
+        Box::new(EntriesOnly::new()),
+        Box::new(PagedResults::new(
+            i32::try_from(page_size.max(1)).unwrap_or(i32::MAX),
+        )),
+    ];
+    let mut stream = ldap
+        .streaming_search_with(adapters, base_dn, Scope::Subtree, filter, attrs)
+        .await?;
+    let mut entries = Vec::new();
+    while let Some(entry) = stream.next().await? {
+        if entries.len() == max_entries {
+            let search_id = stream.last_id();
+            stream.ldap_handle().abandon(search_id).await?;
+            return Ok((entries, None));
+        }
+        entries.push(SearchEntry::construct(entry));
+    }
+    Ok((entries, Some(stream.finish().await)))
 }
 
 /// Escapes raw byte sequences for use in LDAP filter strings.
@@ -1,12 +1,27 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
+use futures::{future::BoxFuture, FutureExt};
 use hyper::StatusCode;
-use serde::Deserialize;
+use serde::{de::DeserializeOwned, Deserialize};
 use snafu::{OptionExt, ResultExt, Snafu};
 use stackable_opa_crd::user_info_fetcher as crd;
 use stackable_operator::commons::authentication::oidc;
+use tokio::sync::Mutex;
+use url::Url;
 
-use crate::{http_error, utils::http::send_json_request, Credentials, UserInfo, UserInfoRequest};
+use crate::{
+    backend::UserInfoBackend, get_group_info_error, get_user_info_error, http_error,
+    utils::http::send_json_request, AttributeSelection, Credentials, GetGroupInfoError,
+    GetUserInfoError, GroupInfo, GroupInfoRequest, UserInfo, UserInfoRequest,
+};
+
+/// The number of entries requested per page from paginated endpoints (such as user searches and group
+/// memberships), which matches the default page size of Keycloak.
+const PAGE_SIZE: usize = 100;
 
 #[derive(Snafu, Debug)]
 pub enum Error {
@@ -37,6 +52,30 @@ pub enum Error {
         user_id: String,
     },
 
+    #[snafu(display("unable to find group with id {group_id:?}"))]
+    GroupNotFoundById {
+        source: crate::utils::http::Error,
+        group_id: String,
+    },
+
+    #[snafu(display("unable to find group with path {path:?}"))]
+    GroupNotFoundByPath {
+        source: crate::utils::http::Error,
+        path: String,
+    },
+
+    #[snafu(display(
+        "failed to request members of group with path {path:?} (group_id: {group_id:?})"
+    ))]
+    RequestGroupMembers {
+        source: crate::utils::http::Error,
+        path: String,
+        group_id: String,
+    },
+
+    #[snafu(display("Keycloak endpoint {url:?} cannot be used as a base URL"))]
+    KeycloakEndpointCannotBeABase { url: String },
+
     #[snafu(display("failed to parse OIDC endpoint url"))]
     ParseOidcEndpointUrl { source: oidc::Error },
 
@@ -44,15 +83,41 @@ pub enum Error {
     ConstructOidcEndpointPath { source: url::ParseError },
 }
 
+impl Error {
+    /// Whether Keycloak rejected the access token of a user lookup.
+    fn is_unauthorized(&self) -> bool {
+        let source = match self {
+            Self::SearchForUser { source }
+            | Self::UserNotFoundById { source, .. }
+            | Self::RequestUserGroups { source, .. }
+            | Self::GroupNotFoundById { source, .. }
+            | Self::GroupNotFoundByPath { source, .. }
+            | Self::RequestGroupMembers { source, .. } => source,
+            _ => return false,
+        };
+        matches!(
+            source,
+            crate::utils::http::Error::HttpClientErrorResponse {
+                status: StatusCode::UNAUTHORIZED,
+                ..
+            }
+        )
+    }
+}
+
 impl http_error::Error for Error {
     fn status_code(&self) -> StatusCode {
         match self {
-            Self::AccessToken { .. } => StatusCode::BAD_GATEWAY,
-            Self::SearchForUser { .. } => StatusCode::BAD_GATEWAY,
+            Self::AccessToken { source } => source.gateway_status_code(),
+            Self::SearchForUser { source } => source.gateway_status_code(),
             Self::UserNotFoundById { .. } => StatusCode::NOT_FOUND,
             Self::UserNotFoundByName { .. } => StatusCode::NOT_FOUND,
             Self::TooManyUsersReturned {} => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::RequestUserGroups { .. } => StatusCode::BAD_GATEWAY,
+            Self::RequestUserGroups { source, .. } => source.gateway_status_code(),
+            Self::GroupNotFoundById { .. } => StatusCode::NOT_FOUND,
+            Self::GroupNotFoundByPath { .. } => StatusCode::NOT_FOUND,
+            Self::RequestGroupMembers { source, .. } => source.gateway_status_code(),
+            Self::KeycloakEndpointCannotBeABase { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Self::ParseOidcEndpointUrl { .. } => StatusCode::INTERNAL_SERVER_ERROR,
             Self::ConstructOidcEndpointPath { .. } => StatusCode::INTERNAL_SERVER_ERROR,
         }
@@ -62,6 +127,8 @@ impl http_error::Error for Error {
 #[derive(Deserialize)]
 struct OAuthResponse {
     access_token: String,
+    /// The lifetime of the access token in seconds.
+    expires_in: Option<u64>,
 }
 
 /// The minimal structure of [UserRepresentation] that is returned by [`/users`][users] and [`/users/{id}`][user-by-id].
@@ -86,55 +153,216 @@ struct GroupMembership {
     path: String,
 }
 
-pub(crate) async fn get_user_info(
-    req: &UserInfoRequest,
-    http: &reqwest::Client,
-    credentials: &Credentials,
-    config: &crd::KeycloakBackend,
-) -> Result<UserInfo, Error> {
-    let crd::KeycloakBackend {
-        client_credentials_secret: _,
-        admin_realm,
-        user_realm,
-        hostname,
-        port,
-        root_path,
-        tls,
-    } = config;
-
-    // We re-use existent functionality from operator-rs, besides it being a bit of miss-use.
-    // Some attributes (such as principal_claim) are irrelevant, and will not be read by the code-flow we trigger.
-    let wrapping_auth_provider = oidc::AuthenticationProvider::new(
-        hostname.clone(),
-        *port,
-        root_path.clone(),
-        tls.clone(),
-        String::new(),
-        Vec::new(),
-        None,
-    );
-    let keycloak_url = wrapping_auth_provider
-        .endpoint_url()
-        .context(ParseOidcEndpointUrlSnafu)?;
-
-    let authn = send_json_request::<OAuthResponse>(
-        http.post(
-            keycloak_url
-                .join(&format!(
-                    "realms/{admin_realm}/protocol/openid-connect/token"
-                ))
-                .context(ConstructOidcEndpointPathSnafu)?,
+/// The minimal structure of [GroupRepresentation] that is returned by [`/groups/{id}`][group-by-id]
+/// and [`/group-by-path/{path}`][group-by-path].
+///
+/// [group-by-id]: https://www.keycloak.org/docs-api/22.0.1/rest-api/index.html#_get_adminrealmsrealmgroupsid
+/// [group-by-path]: https://www.keycloak.org/docs-api/22.0.1/rest-api/index.html#_get_adminrealmsrealmgroup_by_pathpath
+/// [GroupRepresentation]: https://www.keycloak.org/docs-api/22.0.1/rest-api/index.html#GroupRepresentation
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GroupMetadata {
+    id: String,
+    path: String,
+    #[serde(default)]
+    attributes: HashMap<String, serde_json::Value>,
+}
+
+/// Access tokens are refreshed this long before they expire (or after half of their lifetime, if
+/// that is shorter), so that they don't expire while a lookup is in progress.
+const ACCESS_TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+struct CachedAccessToken {
+    access_token: Arc<str>,
+    refresh_at: Instant,
+}
+
+pub struct KeycloakBackend {
+    http: reqwest::Client,
+    credentials: Credentials,
+    config: crd::KeycloakBackend,
+    /// The access token of the service account, which is shared by all lookups until it expires.
+    ///
+    /// The lock is held while a new token is requested, so that concurrent lookups wait for a single
+    /// refresh instead of all requesting their own token.
+    access_token: Mutex<Option<CachedAccessToken>>,
+}
+
+impl KeycloakBackend {
+    pub fn new(
+        http: reqwest::Client,
+        credentials: Credentials,
+        config: crd::KeycloakBackend,
+    ) -> Self {
+        Self {
+            http,
+            credentials,
+            config,
+            access_token: Mutex::default(),
+        }
+    }
+
+    async fn fetch_user_info(&self, req: &UserInfoRequest) -> Result<UserInfo, Error> {
+        let keycloak_url = self.keycloak_url()?;
+        let user_realm = &self.config.user_realm;
+        let users_base_url = keycloak_url
+            .join(&format!("admin/realms/{user_realm}/users/"))
+            .context(ConstructOidcEndpointPathSnafu)?;
+
+        let (access_token, cached) = self.access_token(&keycloak_url).await?;
+        match get_user_info(req, &self.http, &users_base_url, &access_token).await {
+            // The token might have been revoked, or Keycloak might have been restarted with new keys
+            Err(error) if cached && error.is_unauthorized() => {
+                tracing::info!("cached Keycloak access token was rejected, requesting a new one");
+                self.invalidate_access_token(&access_token).await;
+                let (access_token, _) = self.access_token(&keycloak_url).await?;
+                get_user_info(req, &self.http, &users_base_url, &access_token).await
+            }
+            result => result,
+        }
+    }
+
+    async fn fetch_group_info(
+        &self,
+        req: &GroupInfoRequest,
+        max_members: usize,
+    ) -> Result<GroupInfo, Error> {
+        let keycloak_url = self.keycloak_url()?;
+        let user_realm = &self.config.user_realm;
+        let realm_base_url = keycloak_url
+            .join(&format!("admin/realms/{user_realm}/"))
+            .context(ConstructOidcEndpointPathSnafu)?;
+
+        let (access_token, cached) = self.access_token(&keycloak_url).await?;
+        match get_group_info(req, &self.http, &realm_base_url, &access_token, max_members).await {
+            Err(error) if cached && error.is_unauthorized() => {
+                tracing::info!("cached Keycloak access token was rejected, requesting a new one");
+                self.invalidate_access_token(&access_token).await;
+                let (access_token, _) = self.access_token(&keycloak_url).await?;
+                get_group_info(req, &self.http, &realm_base_url, &access_token, max_members).await
+            }
+            result => result,
+        }
+    }
+
+    /// The base URL of Keycloak.
+    fn keycloak_url(&self) -> Result<Url, Error> {
+        let crd::KeycloakBackend {
+            client_credentials_secret: _,
+            client_credentials_secret_class: _,
+            admin_realm: _,
+            user_realm: _,
+            hostname,
+            port,
+            root_path,
+            tls,
+        } = &self.config;
+
+        // We re-use existent functionality from operator-rs, besides it being a bit of miss-use.
+        // Some attributes (such as principal_claim) are irrelevant, and will not be read by the code-flow we trigger.
+        let wrapping_auth_provider = oidc::AuthenticationProvider::new(
+            hostname.clone(),
+            *port,
+            root_path.clone(),
+            tls.clone(),
+            String::new(),
+            Vec::new(),
+            None,
+        );
+        wrapping_auth_provider
+            .endpoint_url()
+            .context(ParseOidcEndpointUrlSnafu)
+    }
+
+    /// Returns the cached access token (or requests a new one if it is about to expire), along with
+    /// whether it was cached.
+    async fn access_token(&self, keycloak_url: &Url) -> Result<(Arc<str>, bool), Error> {
+        let mut cached_token = self.access_token.lock().await;
+        if let Some(cached_token) = &*cached_token {
+            if Instant::now() < cached_token.refresh_at {
+                return Ok((cached_token.access_token.clone(), true));
+            }
+        }
+
+        let admin_realm = &self.config.admin_realm;
+        let requested_at = Instant::now();
+        let authn = send_json_request::<OAuthResponse>(
+            self.http
+                .post(
+                    keycloak_url
+                        .join(&format!(
+                            "realms/{admin_realm}/protocol/openid-connect/token"
+                        ))
+                        .context(ConstructOidcEndpointPathSnafu)?,
+                )
+                .basic_auth(
+                    &self.credentials.client_id,
+                    Some(&self.credentials.client_secret),
+                )
+                .form(&[("grant_type", "client_credentials")]),
         )
-        .basic_auth(&credentials.client_id, Some(&credentials.client_secret))
-        .form(&[("grant_type", "client_credentials")]),
-    )
-    .await
-    .context(AccessTokenSnafu)?;
+        .await
+        .context(AccessTokenSnafu)?;
+        let access_token = Arc::<str>::from(authn.access_token);
+        match authn.expires_in {
+            Some(expires_in) => {
+                let lifetime = Duration::from_secs(expires_in);
+                *cached_token = Some(CachedAccessToken {
+                    access_token: access_token.clone(),
+                    refresh_at: requested_at + lifetime
+                        - ACCESS_TOKEN_EXPIRY_MARGIN.min(lifetime / 2),
+                });
+            }
+            // Tokens without a known lifetime are not cached
+            None => *cached_token = None,
+        }
+        Ok((access_token, false))
+    }
 
-    let users_base_url = keycloak_url
-        .join(&format!("admin/realms/{user_realm}/users/"))
-        .context(ConstructOidcEndpointPathSnafu)?;
+    /// Drops the access token from the cache, unless it has already been replaced by a new token.
+    async fn invalidate_access_token(&self, access_token: &str) {
+        let mut cached_token = self.access_token.lock().await;
+        if cached_token
+            .as_ref()
+            .is_some_and(|cached_token| &*cached_token.access_token == access_token)
+        {
+            *cached_token = None;
+        }
+    }
+}
+
+impl UserInfoBackend for KeycloakBackend {
+    fn get_user_info<'a>(
+        &'a self,
+        req: &'a UserInfoRequest,
+        _attributes: &'a AttributeSelection,
+    ) -> BoxFuture<'a, Result<UserInfo, GetUserInfoError>> {
+        self.fetch_user_info(req)
+            .map(|result| result.context(get_user_info_error::KeycloakSnafu))
+            .boxed()
+    }
+
+    fn get_group_info<'a>(
+        &'a self,
+        req: &'a GroupInfoRequest,
+        max_members: usize,
+    ) -> BoxFuture<'a, Result<GroupInfo, GetGroupInfoError>> {
+        self.fetch_group_info(req, max_members)
+            .map(|result| result.context(get_group_info_error::KeycloakSnafu))
+            .boxed()
+    }
+
+    fn name(&self) -> &'static str {
+        "keycloak"
+    }
+}
 
+async fn get_user_info(
+    req: &UserInfoRequest,
+    http: &reqwest::Client,
+    users_base_url: &Url,
+    access_token: &str,
+) -> Result<UserInfo, Error> {
     let user_info = match req {
         UserInfoRequest::UserInfoRequestById(req) => {
             let user_id = req.id.clone();
@@ -144,7 +372,7 @@ pub(crate) async fn get_user_info(
                         .join(&req.id)
                         .context(ConstructOidcEndpointPathSnafu)?,
                 )
-                .bearer_auth(&authn.access_token),
+                .bearer_auth(access_token),
             )
             .await
             .context(UserNotFoundByIdSnafu { user_id })?
@@ -155,11 +383,10 @@ pub(crate) async fn get_user_info(
                 .join(&format!("?username={username}&exact=true"))
                 .context(ConstructOidcEndpointPathSnafu)?;
 
-            let users = send_json_request::<Vec<UserMetadata>>(
-                http.get(users_url).bearer_auth(&authn.access_token),
-            )
-            .await
-            .context(SearchForUserSnafu)?;
+            let users =
+                send_paginated_request::<UserMetadata>(http, &users_url, access_token, usize::MAX)
+                    .await
+                    .context(SearchForUserSnafu)?;
 
             if users.len() > 1 {
                 return TooManyUsersReturnedSnafu.fail();
@@ -172,13 +399,13 @@ pub(crate) async fn get_user_info(
         }
     };
 
-    let groups = send_json_request::<Vec<GroupMembership>>(
-        http.get(
-            users_base_url
-                .join(&format!("{}/groups", user_info.id))
-                .context(ConstructOidcEndpointPathSnafu)?,
-        )
-        .bearer_auth(&authn.access_token),
+    let groups = send_paginated_request::<GroupMembership>(
+        http,
+        &users_base_url
+            .join(&format!("{}/groups", user_info.id))
+            .context(ConstructOidcEndpointPathSnafu)?,
+        access_token,
+        usize::MAX,
     )
     .await
     .context(RequestUserGroupsSnafu {
@@ -193,3 +420,108 @@ pub(crate) async fn get_user_info(
         custom_attributes: user_info.attributes,
     })
 }
+
+async fn get_group_info(
+    req: &GroupInfoRequest,
+    http: &reqwest::Client,
+    realm_base_url: &Url,
+    access_token: &str,
+    max_members: usize,
+) -> Result<GroupInfo, Error> {
+    let group = match req {
+        GroupInfoRequest::GroupInfoRequestById(req) => {
+            let group_id = req.id.clone();
+            send_json_request::<GroupMetadata>(
+                http.get(realm_url(realm_base_url, ["groups", req.id.as_str()])?)
+                    .bearer_auth(access_token),
+            )
+            .await
+            .context(GroupNotFoundByIdSnafu { group_id })?
+        }
+        // Groups are named by their paths (such as `/parent/child`), like the groups of users
+        GroupInfoRequest::GroupInfoRequestByName(req) => {
+            let path = &req.name;
+            send_json_request::<GroupMetadata>(
+                http.get(realm_url(
+                    realm_base_url,
+                    std::iter::once("group-by-path").chain(path.trim_start_matches('/').split('/')),
+                )?)
+                .bearer_auth(access_token),
+            )
+            .await
+            .context(GroupNotFoundByPathSnafu { path })?
+        }
+    };
+
+    let mut members_url = realm_url(realm_base_url, ["groups", group.id.as_str(), "members"])?;
+    members_url
+        .query_pairs_mut()
+        .append_pair("briefRepresentation", "true");
+    // One more member than allowed is requested, to find out whether there are more members
+    let mut members = send_paginated_request::<UserMetadata>(
+        http,
+        &members_url,
+        access_token,
+        max_members.saturating_add(1),
+    )
+    .await
+    .context(RequestGroupMembersSnafu {
+        path: group.path.clone(),
+        group_id: group.id.clone(),
+    })?;
+    let members_truncated = members.len() > max_members;
+    members.truncate(max_members);
+
+    Ok(GroupInfo {
+        id: Some(group.id),
+        name: group.path,
+        members: members.into_iter().map(|member| member.username).collect(),
+        members_truncated,
+        custom_attributes: group.attributes,
+    })
+}
+
+/// Requests the entries of a paginated endpoint page by page (using `first` and `max`), until a page
+/// is not full or `limit` entries have been received.
+async fn send_paginated_request<T: DeserializeOwned>(
+    http: &reqwest::Client,
+    url: &Url,
+    access_token: &str,
+    limit: usize,
+) -> Result<Vec<T>, crate::utils::http::Error> {
+    let mut entries = Vec::new();
+    while entries.len() < limit {
+        let page_size = PAGE_SIZE.min(limit - entries.len());
+        let mut page_url = url.clone();
+        page_url
+            .query_pairs_mut()
+            .append_pair("first", &entries.len().to_string())
+            .append_pair("max", &page_size.to_string());
+        let page =
+            send_json_request::<Vec<T>>(http.get(page_url).bearer_auth(access_token)).await?;
+        // Versions of Keycloak that ignore `max` return all entries at once
+        let last_page = page.len() != page_size;
+        entries.extend(page);
+        if last_page {
+            break;
+        }
+    }
+    entries.truncate(limit);
+    Ok(entries)
+}
+
+/// Appends the (percent-encoded) path segments to the realm URL.
+fn realm_url<'a>(
+    realm_base_url: &Url,
+    segments: impl IntoIterator<Item = &'a str>,
+) -> Result<Url, Error> {
+    let mut url = realm_base_url.clone();
+    url.path_segments_mut()
+        .ok()
+        .context(KeycloakEndpointCannotBeABaseSnafu {
+            url: realm_base_url.as_str(),
+        })?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
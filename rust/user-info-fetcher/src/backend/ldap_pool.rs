@@ -0,0 +1,219 @@
+//! A pool of LDAP connections to a set of equivalent servers (such as the domain controllers of an
+//! Active Directory domain), which fails over to the next server while one is unavailable.
+
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use ldap3::{exop::WhoAmI, Ldap, LdapConnAsync, LdapConnSettings, LdapError};
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::user_info_fetcher as crd;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to connect to LDAP server {server:?}"))]
+    Connect { source: LdapError, server: String },
+
+    #[snafu(display("failed to send LDAP bind request to {server:?}"))]
+    RequestBind { source: LdapError, server: String },
+
+    #[snafu(display("failed to bind LDAP credentials for {server:?}"))]
+    Bind { source: LdapError, server: String },
+
+    #[snafu(display("timed out binding LDAP credentials for {server:?}"))]
+    BindTimeout {
+        source: tokio::time::error::Elapsed,
+        server: String,
+    },
+}
+
+/// Connection attempts that take longer are aborted, so that slow servers are failed over quickly.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Binds that take longer are aborted, the GSSAPI exchange takes multiple round trips (and Kerberos
+/// might need to fetch a ticket first).
+const BIND_TIMEOUT: Duration = Duration::from_secs(10);
+/// Idle connections that don't answer the health check in time are dropped, since the server (or a
+/// firewall in between) has most likely dropped them silently.
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+/// Idle connections are checked before they are reused if they have been idle for longer than this,
+/// since servers (or firewalls in between) may have closed them in the meantime.
+const HEALTH_CHECK_AFTER: Duration = Duration::from_secs(30);
+/// Servers that could not be connected to are only tried again after this, unless all servers are
+/// unavailable.
+const UNAVAILABLE_BACKOFF: Duration = Duration::from_secs(30);
+
+pub struct LdapPool {
+    servers: Vec<LdapServer>,
+    ldap_tls: native_tls::TlsConnector,
+    uses_tls: bool,
+    max_idle_connections: usize,
+    idle_timeout: Duration,
+}
+
+struct LdapServer {
+    hostname: String,
+    idle_connections: Mutex<Vec<IdleConnection>>,
+    /// Set while the server is considered unavailable.
+    unavailable_until: Mutex<Option<Instant>>,
+}
+
+struct IdleConnection {
+    ldap: Ldap,
+    idle_since: Instant,
+}
+
+/// A connection taken from the pool.
+///
+/// The connection is only returned to the pool by [`PooledLdap::release`], so that connections that
+/// failed are closed rather than reused.
+pub struct PooledLdap<'a> {
+    pub ldap: Ldap,
+    server: &'a LdapServer,
+    pool: &'a LdapPool,
+}
+
+impl PooledLdap<'_> {
+    /// The hostname of the server that the connection is connected to.
+    pub fn server(&self) -> &str {
+        &self.server.hostname
+    }
+
+    /// Returns the connection to the pool, so that it can be reused for further requests.
+    pub fn release(self) {
+        let mut idle_connections = self.server.idle_connections.lock().unwrap();
+        if idle_connections.len() < self.pool.max_idle_connections {
+            idle_connections.push(IdleConnection {
+                ldap: self.ldap,
+                idle_since: Instant::now(),
+            });
+        }
+    }
+}
+
+impl LdapPool {
+    pub fn new(
+        servers: impl IntoIterator<Item = String>,
+        ldap_tls: native_tls::TlsConnector,
+        uses_tls: bool,
+        config: &crd::LdapConnectionPool,
+    ) -> Self {
+        Self {
+            servers: servers
+                .into_iter()
+                .map(|hostname| LdapServer {
+                    hostname,
+                    idle_connections: Mutex::default(),
+                    unavailable_until: Mutex::default(),
+                })
+                .collect(),
+            ldap_tls,
+            uses_tls,
+            max_idle_connections: config.max_idle_connections,
+            idle_timeout: *config.idle_timeout,
+        }
+    }
+
+    /// Takes an idle connection from the pool, or connects to the first available server.
+    ///
+    /// Servers are tried in order, skipping servers that recently failed unless all of them did.
+    pub async fn get(&self) -> Result<PooledLdap<'_>, Error> {
+        let now = Instant::now();
+        let (available, unavailable): (Vec<_>, Vec<_>) = self.servers.iter().partition(|server| {
+            server
+                .unavailable_until
+                .lock()
+                .unwrap()
+                .is_none_or(|until| until <= now)
+        });
+        let mut last_error = None;
+        for server in available.into_iter().chain(unavailable) {
+            if let Some(ldap) = self.take_idle_connection(server).await {
+                return Ok(PooledLdap {
+                    ldap,
+                    server,
+                    pool: self,
+                });
+            }
+            match self.connect(server).await {
+                Ok(ldap) => {
+                    *server.unavailable_until.lock().unwrap() = None;
+                    return Ok(PooledLdap {
+                        ldap,
+                        server,
+                        pool: self,
+                    });
+                }
+                Err(error) => {
+                    tracing::warn!(
+                        error = &error as &dyn std::error::Error,
+                        server = %server.hostname,
+                        "LDAP server is unavailable, failing over to the next server"
+                    );
+                    *server.unavailable_until.lock().unwrap() =
+                        Some(Instant::now() + UNAVAILABLE_BACKOFF);
+                    last_error = Some(error);
+                }
+            }
+        }
+        Err(last_error.expect("LdapPool always has at least one server"))
+    }
+
+    /// Takes the most recently used idle connection that is still healthy, closing the others.
+    async fn take_idle_connection(&self, server: &LdapServer) -> Option<Ldap> {
+        loop {
+            let IdleConnection {
+                mut ldap,
+                idle_since,
+            } = server.idle_connections.lock().unwrap().pop()?;
+            let idle_for = idle_since.elapsed();
+            if idle_for > self.idle_timeout {
+                // All other idle connections are even older
+                server.idle_connections.lock().unwrap().clear();
+                return None;
+            }
+            if idle_for < HEALTH_CHECK_AFTER {
+                return Some(ldap);
+            }
+            match tokio::time::timeout(HEALTH_CHECK_TIMEOUT, ldap.extended(WhoAmI))
+                .await
+                .map(|result| result.and_then(|result| result.success()))
+            {
+                Ok(Ok(_)) => return Some(ldap),
+                Ok(Err(error)) => tracing::debug!(
+                    error = &error as &dyn std::error::Error,
+                    server = %server.hostname,
+                    "dropping idle LDAP connection that failed the health check"
+                ),
+                Err(_) => tracing::debug!(
+                    server = %server.hostname,
+                    timeout = ?HEALTH_CHECK_TIMEOUT,
+                    "dropping idle LDAP connection that timed out the health check"
+                ),
+            }
+        }
+    }
+
+    async fn connect(&self, server: &LdapServer) -> Result<Ldap, Error> {
+        let hostname = &server.hostname;
+        let (ldap_conn, mut ldap) = LdapConnAsync::with_settings(
+            LdapConnSettings::new()
+                .set_connector(self.ldap_tls.clone())
+                .set_conn_timeout(CONNECT_TIMEOUT),
+            &format!(
+                "{protocol}://{hostname}",
+                protocol = if self.uses_tls { "ldaps" } else { "ldap" }
+            ),
+        )
+        .await
+        .context(ConnectSnafu { server: hostname })?;
+        ldap3::drive!(ldap_conn);
+        tokio::time::timeout(BIND_TIMEOUT, ldap.sasl_gssapi_bind(hostname))
+            .await
+            .context(BindTimeoutSnafu { server: hostname })?
+            .context(RequestBindSnafu { server: hostname })?
+            .success()
+            .context(BindSnafu { server: hostname })?;
+        Ok(ldap)
+    }
+}
@@ -1,3 +1,125 @@
+use std::collections::HashMap;
+
+use futures::{
+    future::{self, BoxFuture},
+    FutureExt,
+};
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::user_info_fetcher as crd;
+
+use crate::{
+    get_group_info_error, AttributeSelection, Credentials, GetGroupInfoError, GetUserInfoError,
+    GroupInfo, GroupInfoRequest, UserInfo, UserInfoRequest, UserInfoRequestById,
+    UserInfoRequestByName,
+};
+
 pub mod active_directory;
 pub mod keycloak;
+mod ldap_pool;
+pub mod okta;
+pub mod scim;
 pub mod xfsc_aas;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to resolve Active Directory backend"))]
+    ResolveActiveDirectory { source: active_directory::Error },
+}
+
+/// A directory service that user information is fetched from.
+///
+/// The trait is object safe (hence the boxed futures), so that the backend can be chosen at runtime.
+pub trait UserInfoBackend: Send + Sync {
+    /// Backends should skip fetching custom attributes that are not included in `attributes` where
+    /// possible, the others are removed from the result afterwards.
+    fn get_user_info<'a>(
+        &'a self,
+        req: &'a UserInfoRequest,
+        attributes: &'a AttributeSelection,
+    ) -> BoxFuture<'a, Result<UserInfo, GetUserInfoError>>;
+
+    /// Looks up a group along with (at most `max_members` of) its members, see `groupInfo`.
+    ///
+    /// Backends that cannot look up groups keep the default implementation, which refuses the request.
+    fn get_group_info<'a>(
+        &'a self,
+        _req: &'a GroupInfoRequest,
+        _max_members: usize,
+    ) -> BoxFuture<'a, Result<GroupInfo, GetGroupInfoError>> {
+        future::ready(
+            get_group_info_error::NotSupportedSnafu {
+                backend: self.name(),
+            }
+            .fail(),
+        )
+        .boxed()
+    }
+
+    /// The name of the backend, as used in the configuration.
+    fn name(&self) -> &'static str;
+}
+
+/// Builds the configured backend, with everything that can be prepared once (such as TLS
+/// configuration) resolved at startup.
+pub async fn resolve(
+    config: &crd::Backend,
+    http: reqwest::Client,
+    credentials: Credentials,
+    trust_store: Option<&[u8]>,
+) -> Result<Box<dyn UserInfoBackend>, Error> {
+    Ok(match config {
+        crd::Backend::None {} => Box::new(NoneBackend),
+        crd::Backend::Keycloak(keycloak) => Box::new(keycloak::KeycloakBackend::new(
+            http,
+            credentials,
+            keycloak.clone(),
+        )),
+        crd::Backend::XfscAas(aas) | crd::Backend::ExperimentalXfscAas(aas) => {
+            Box::new(xfsc_aas::XfscAasBackend::new(http, aas.clone()))
+        }
+        crd::Backend::Scim(scim) => {
+            Box::new(scim::ScimBackend::new(http, credentials, scim.clone()))
+        }
+        crd::Backend::Okta(okta) => {
+            Box::new(okta::OktaBackend::new(http, credentials, okta.clone()))
+        }
+        crd::Backend::ActiveDirectory(ad) => Box::new(
+            active_directory::ResolvedActiveDirectoryBackend::resolve(ad, trust_store)
+                .await
+                .context(ResolveActiveDirectorySnafu)?,
+        ),
+    })
+}
+
+/// Dummy backend that adds no extra user information.
+struct NoneBackend;
+
+impl UserInfoBackend for NoneBackend {
+    fn get_user_info<'a>(
+        &'a self,
+        req: &'a UserInfoRequest,
+        _attributes: &'a AttributeSelection,
+    ) -> BoxFuture<'a, Result<UserInfo, GetUserInfoError>> {
+        let user_id = match &req {
+            UserInfoRequest::UserInfoRequestById(UserInfoRequestById { id }) => Some(id),
+            _ => None,
+        };
+        let username = match &req {
+            UserInfoRequest::UserInfoRequestByName(UserInfoRequestByName { username }) => {
+                Some(username)
+            }
+            _ => None,
+        };
+        future::ready(Ok(UserInfo {
+            id: user_id.cloned(),
+            username: username.cloned(),
+            groups: vec![],
+            custom_attributes: HashMap::new(),
+        }))
+        .boxed()
+    }
+
+    fn name(&self) -> &'static str {
+        "none"
+    }
+}
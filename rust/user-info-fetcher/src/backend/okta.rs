@@ -0,0 +1,361 @@
+//! Okta backend, using the [Okta Management API].
+//!
+//! Users are looked up by id or login using the same endpoint (`/api/v1/users/{idOrLogin}`), and
+//! their groups are fetched from `/api/v1/users/{id}/groups`. The attributes of the Okta profile of
+//! the user (other than the login) are returned as custom attributes.
+//!
+//! The backend authenticates either with an API token (sent using the `SSWS` scheme), or as a
+//! service app, which requests access tokens using a JWT signed with its private key
+//! (`private_key_jwt` client authentication). Access tokens are reused until shortly before they
+//! expire. Okta rate limits the Management API, rate limited requests are retried once the limit
+//! resets (see [`send_json_request`]).
+//!
+//! [Okta Management API]: https://developer.okta.com/docs/api/openapi/okta-management/guides/overview/
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    hash::{BuildHasher, Hasher},
+    sync::Arc,
+    time::{Duration, Instant, SystemTime},
+};
+
+use futures::{future::BoxFuture, FutureExt};
+use hyper::StatusCode;
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde::{Deserialize, Serialize};
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_opa_crd::user_info_fetcher as crd;
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::{
+    backend::UserInfoBackend, get_user_info_error, http_error, utils::http::send_json_request,
+    AttributeSelection, Credentials, GetUserInfoError, UserInfo, UserInfoRequest,
+};
+
+/// The scopes requested for the access tokens of service apps.
+const SERVICE_APP_SCOPES: &str = "okta.users.read okta.groups.read";
+/// How long the JWTs service apps authenticate with are valid.
+const CLIENT_ASSERTION_LIFETIME: Duration = Duration::from_secs(5 * 60);
+/// Access tokens are refreshed this long before they expire (or after half of their lifetime, if
+/// that is shorter), so that they don't expire while a lookup is in progress.
+const ACCESS_TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(30);
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to parse Okta endpoint {url:?} as URL"))]
+    ParseOktaEndpointUrl {
+        source: url::ParseError,
+        url: String,
+    },
+
+    #[snafu(display("failed to construct Okta endpoint path"))]
+    ConstructOktaEndpointPath { source: url::ParseError },
+
+    #[snafu(display("Okta endpoint {url:?} can not have path segments"))]
+    OktaEndpointCannotBeABase { url: String },
+
+    #[snafu(display("failed to load the private key of the service app"))]
+    LoadPrivateKey { source: jsonwebtoken::errors::Error },
+
+    #[snafu(display("failed to sign the client assertion of the service app"))]
+    SignClientAssertion { source: jsonwebtoken::errors::Error },
+
+    #[snafu(display("failed to get access token"))]
+    AccessToken { source: crate::utils::http::Error },
+
+    #[snafu(display("unable to find user {user:?}"))]
+    UserNotFound {
+        source: crate::utils::http::Error,
+        user: String,
+    },
+
+    #[snafu(display("user {username:?} was requested by username, but Okta returned it by id"))]
+    UserNotFoundByName { username: String },
+
+    #[snafu(display("failed to request groups for user with id {user_id:?}"))]
+    RequestUserGroups {
+        source: crate::utils::http::Error,
+        user_id: String,
+    },
+}
+
+impl http_error::Error for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::ParseOktaEndpointUrl { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::ConstructOktaEndpointPath { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::OktaEndpointCannotBeABase { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::LoadPrivateKey { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::SignClientAssertion { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::AccessToken { source } => source.gateway_status_code(),
+            Self::UserNotFound { source, .. } => match source {
+                crate::utils::http::Error::HttpClientErrorResponse {
+                    status: StatusCode::NOT_FOUND,
+                    ..
+                } => StatusCode::NOT_FOUND,
+                _ => source.gateway_status_code(),
+            },
+            Self::UserNotFoundByName { .. } => StatusCode::NOT_FOUND,
+            Self::RequestUserGroups { source, .. } => source.gateway_status_code(),
+        }
+    }
+}
+
+/// The minimal structure of an [Okta user].
+///
+/// [Okta user]: https://developer.okta.com/docs/api/openapi/okta-management/management/tag/User/#tag/User/operation/getUser
+#[derive(Deserialize)]
+struct OktaUser {
+    id: String,
+    profile: OktaUserProfile,
+}
+
+#[derive(Deserialize)]
+struct OktaUserProfile {
+    login: String,
+    #[serde(flatten)]
+    attributes: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct OktaGroup {
+    profile: OktaGroupProfile,
+}
+
+#[derive(Deserialize)]
+struct OktaGroupProfile {
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct OAuthResponse {
+    access_token: String,
+    /// The lifetime of the access token in seconds.
+    expires_in: Option<u64>,
+}
+
+/// The claims of the JWT service apps authenticate with, see
+/// <https://developer.okta.com/docs/guides/implement-oauth-for-okta-serviceapp/main/#create-and-sign-the-jwt>.
+#[derive(Serialize)]
+struct ClientAssertionClaims<'a> {
+    iss: &'a str,
+    sub: &'a str,
+    aud: &'a str,
+    iat: u64,
+    exp: u64,
+    jti: String,
+}
+
+struct CachedAccessToken {
+    access_token: Arc<str>,
+    refresh_at: Instant,
+}
+
+pub struct OktaBackend {
+    http: reqwest::Client,
+    /// The API token, or the PEM encoded private key of the service app, as client secret.
+    credentials: Credentials,
+    config: crd::OktaBackend,
+    /// The access token of the service app, which is shared by all lookups until it expires.
+    access_token: Mutex<Option<CachedAccessToken>>,
+}
+
+impl OktaBackend {
+    pub fn new(http: reqwest::Client, credentials: Credentials, config: crd::OktaBackend) -> Self {
+        Self {
+            http,
+            credentials,
+            config,
+            access_token: Mutex::default(),
+        }
+    }
+
+    fn base_url(&self) -> Result<Url, Error> {
+        let crd::OktaBackend {
+            hostname,
+            port,
+            tls,
+            ..
+        } = &self.config;
+        let scheme = if tls.uses_tls() { "https" } else { "http" };
+        let port = port.unwrap_or(if tls.uses_tls() { 443 } else { 80 });
+        let url = format!("{scheme}://{hostname}:{port}/");
+        Url::parse(&url).context(ParseOktaEndpointUrlSnafu { url })
+    }
+
+    async fn fetch_user_info(&self, req: &UserInfoRequest) -> Result<UserInfo, Error> {
+        let base_url = self.base_url()?;
+        let authorization = self.authorization(&base_url).await?;
+
+        // Okta resolves both ids and logins using the same endpoint
+        let (user, by_username) = match req {
+            UserInfoRequest::UserInfoRequestById(req) => (&req.id, false),
+            UserInfoRequest::UserInfoRequestByName(req) => (&req.username, true),
+        };
+        let user_info = self
+            .get_json::<OktaUser>(
+                api_url(&base_url, &["api", "v1", "users", user])?,
+                &authorization,
+            )
+            .await
+            .context(UserNotFoundSnafu { user })?;
+        // A username that happens to be the id of another user must not return that user
+        if by_username && user_info.id == *user && user_info.profile.login != *user {
+            return UserNotFoundByNameSnafu { username: user }.fail();
+        }
+
+        let groups = self
+            .get_json::<Vec<OktaGroup>>(
+                api_url(&base_url, &["api", "v1", "users", &user_info.id, "groups"])?,
+                &authorization,
+            )
+            .await
+            .context(RequestUserGroupsSnafu {
+                user_id: &user_info.id,
+            })?;
+
+        Ok(UserInfo {
+            id: Some(user_info.id),
+            username: Some(user_info.profile.login),
+            groups: groups.into_iter().map(|group| group.profile.name).collect(),
+            custom_attributes: user_info.profile.attributes,
+        })
+    }
+
+    /// The value of the `Authorization` header of requests to the Management API.
+    async fn authorization(&self, base_url: &Url) -> Result<String, Error> {
+        Ok(match &self.config.credentials {
+            crd::OktaCredentials::ApiTokenSecret(_) => {
+                format!("SSWS {}", self.credentials.client_secret)
+            }
+            crd::OktaCredentials::ServiceApp(service_app) => format!(
+                "Bearer {}",
+                self.service_app_access_token(base_url, service_app).await?
+            ),
+        })
+    }
+
+    /// Returns the cached access token of the service app, or requests a new one if it is about to
+    /// expire.
+    async fn service_app_access_token(
+        &self,
+        base_url: &Url,
+        service_app: &crd::OktaServiceApp,
+    ) -> Result<Arc<str>, Error> {
+        let mut cached_token = self.access_token.lock().await;
+        if let Some(cached_token) = &*cached_token {
+            if Instant::now() < cached_token.refresh_at {
+                return Ok(cached_token.access_token.clone());
+            }
+        }
+
+        let token_url = base_url
+            .join("oauth2/v1/token")
+            .context(ConstructOktaEndpointPathSnafu)?;
+        let client_assertion = self.client_assertion(&token_url, service_app)?;
+        let requested_at = Instant::now();
+        let authn = send_json_request::<OAuthResponse>(
+            self.http
+                .post(token_url)
+                .form(&[
+                    ("grant_type", "client_credentials"),
+                    ("scope", SERVICE_APP_SCOPES),
+                    (
+                        "client_assertion_type",
+                        "urn:ietf:params:oauth:client-assertion-type:jwt-bearer",
+                    ),
+                    ("client_assertion", &client_assertion),
+                ])
+                .timeout(*self.config.request_timeout),
+        )
+        .await
+        .context(AccessTokenSnafu)?;
+        let access_token = Arc::<str>::from(authn.access_token);
+        *cached_token = authn.expires_in.map(|expires_in| {
+            let lifetime = Duration::from_secs(expires_in);
+            CachedAccessToken {
+                access_token: access_token.clone(),
+                refresh_at: requested_at + lifetime - ACCESS_TOKEN_EXPIRY_MARGIN.min(lifetime / 2),
+            }
+        });
+        Ok(access_token)
+    }
+
+    /// Signs the JWT the service app authenticates with.
+    fn client_assertion(
+        &self,
+        token_url: &Url,
+        service_app: &crd::OktaServiceApp,
+    ) -> Result<String, Error> {
+        let key = EncodingKey::from_rsa_pem(self.credentials.client_secret.as_bytes())
+            .context(LoadPrivateKeySnafu)?;
+        let header = Header {
+            kid: service_app.key_id.clone(),
+            ..Header::new(Algorithm::RS256)
+        };
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        // RandomState is randomly seeded, which is good enough for a unique token id and saves a dependency
+        let jti = format!(
+            "{:016x}{:016x}",
+            RandomState::new().build_hasher().finish(),
+            RandomState::new().build_hasher().finish()
+        );
+        let claims = ClientAssertionClaims {
+            iss: &service_app.client_id,
+            sub: &service_app.client_id,
+            aud: token_url.as_str(),
+            iat: now,
+            exp: now + CLIENT_ASSERTION_LIFETIME.as_secs(),
+            jti,
+        };
+        jsonwebtoken::encode(&header, &claims, &key).context(SignClientAssertionSnafu)
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: Url,
+        authorization: &str,
+    ) -> Result<T, crate::utils::http::Error> {
+        send_json_request(
+            self.http
+                .get(url)
+                .header(reqwest::header::AUTHORIZATION, authorization)
+                .header(reqwest::header::ACCEPT, "application/json")
+                .timeout(*self.config.request_timeout),
+        )
+        .await
+    }
+}
+
+impl UserInfoBackend for OktaBackend {
+    fn get_user_info<'a>(
+        &'a self,
+        req: &'a UserInfoRequest,
+        _attributes: &'a AttributeSelection,
+    ) -> BoxFuture<'a, Result<UserInfo, GetUserInfoError>> {
+        self.fetch_user_info(req)
+            .map(|result| result.context(get_user_info_error::OktaSnafu))
+            .boxed()
+    }
+
+    fn name(&self) -> &'static str {
+        "okta"
+    }
+}
+
+/// Appends the (percent-encoded) path segments to the base URL, since logins may contain characters
+/// such as `/`.
+fn api_url(base_url: &Url, segments: &[&str]) -> Result<Url, Error> {
+    let mut url = base_url.clone();
+    url.path_segments_mut()
+        .ok()
+        .context(OktaEndpointCannotBeABaseSnafu {
+            url: base_url.as_str(),
+        })?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
@@ -0,0 +1,270 @@
+//! Generic SCIM 2.0 backend, for identity providers that expose their users and groups through a
+//! SCIM service provider API (such as Okta or OneLogin).
+//!
+//! Users are looked up using the `/Users` endpoint (see [RFC 7644]), and authenticated with a
+//! bearer token. The groups of a user are taken from its `groups` attribute, and queried from the
+//! `/Groups` endpoint if the service provider does not return it.
+//! All other attributes of the user (including extensions, such as the enterprise user) are returned
+//! as custom attributes.
+//!
+//! [RFC 7644]: https://datatracker.ietf.org/doc/html/rfc7644
+use std::collections::HashMap;
+
+use futures::{future::BoxFuture, FutureExt};
+use hyper::StatusCode;
+use serde::Deserialize;
+use snafu::{OptionExt, ResultExt, Snafu};
+use stackable_opa_crd::user_info_fetcher as crd;
+use url::Url;
+
+use crate::{
+    backend::UserInfoBackend, get_user_info_error, http_error, utils::http::send_json_request,
+    AttributeSelection, Credentials, GetUserInfoError, UserInfo, UserInfoRequest,
+};
+
+/// Attributes of the user resource that are not returned as custom attributes.
+const CORE_USER_ATTRIBUTES: &[&str] = &["schemas", "id", "userName", "groups", "meta"];
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("failed to parse SCIM endpoint {url:?} as URL"))]
+    ParseScimEndpointUrl {
+        source: url::ParseError,
+        url: String,
+    },
+
+    #[snafu(display("SCIM endpoint {url:?} can not have path segments"))]
+    ScimEndpointCannotBeABase { url: String },
+
+    #[snafu(display("failed to search for user"))]
+    SearchForUser { source: crate::utils::http::Error },
+
+    #[snafu(display("unable to find user with id {user_id:?}"))]
+    UserNotFoundById {
+        source: crate::utils::http::Error,
+        user_id: String,
+    },
+
+    #[snafu(display("unable to find user with username {username:?}"))]
+    UserNotFoundByName { username: String },
+
+    #[snafu(display("more than one user was returned when there should be one or none"))]
+    TooManyUsersReturned,
+
+    #[snafu(display("failed to request groups for user with id {user_id:?}"))]
+    RequestUserGroups {
+        source: crate::utils::http::Error,
+        user_id: String,
+    },
+}
+
+impl http_error::Error for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::ParseScimEndpointUrl { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::ScimEndpointCannotBeABase { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::SearchForUser { source } => source.gateway_status_code(),
+            Self::UserNotFoundById { source, .. } => match source {
+                crate::utils::http::Error::HttpClientErrorResponse {
+                    status: StatusCode::NOT_FOUND,
+                    ..
+                } => StatusCode::NOT_FOUND,
+                _ => source.gateway_status_code(),
+            },
+            Self::UserNotFoundByName { .. } => StatusCode::NOT_FOUND,
+            Self::TooManyUsersReturned {} => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::RequestUserGroups { source, .. } => source.gateway_status_code(),
+        }
+    }
+}
+
+/// The minimal structure of the SCIM [User resource].
+///
+/// [User resource]: https://datatracker.ietf.org/doc/html/rfc7643#section-4.1
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimUser {
+    id: String,
+    user_name: String,
+    /// Not returned by all service providers, the `/Groups` endpoint is queried then.
+    groups: Option<Vec<ScimGroupMembership>>,
+    #[serde(flatten)]
+    attributes: HashMap<String, serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimGroupMembership {
+    display: Option<String>,
+    value: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ScimGroup {
+    id: String,
+    display_name: Option<String>,
+}
+
+/// The response of [queries] of the `/Users` and `/Groups` endpoints.
+///
+/// [queries]: https://datatracker.ietf.org/doc/html/rfc7644#section-3.4.2
+#[derive(Deserialize)]
+struct ListResponse<T> {
+    #[serde(rename = "Resources", default = "Vec::new")]
+    resources: Vec<T>,
+}
+
+pub struct ScimBackend {
+    http: reqwest::Client,
+    credentials: Credentials,
+    config: crd::ScimBackend,
+}
+
+impl ScimBackend {
+    /// The bearer token is passed as the `client_secret` of the `credentials`.
+    pub fn new(http: reqwest::Client, credentials: Credentials, config: crd::ScimBackend) -> Self {
+        Self {
+            http,
+            credentials,
+            config,
+        }
+    }
+
+    /// The base URL of the SCIM API, the endpoints are appended to it with [`api_url`].
+    fn base_url(&self) -> Result<Url, Error> {
+        let crd::ScimBackend {
+            hostname,
+            port,
+            base_path,
+            tls,
+            ..
+        } = &self.config;
+        let scheme = if tls.uses_tls() { "https" } else { "http" };
+        let port = port.unwrap_or(if tls.uses_tls() { 443 } else { 80 });
+        let base_path = base_path.trim_matches('/');
+        let url = if base_path.is_empty() {
+            format!("{scheme}://{hostname}:{port}/")
+        } else {
+            format!("{scheme}://{hostname}:{port}/{base_path}/")
+        };
+        Url::parse(&url).context(ParseScimEndpointUrlSnafu { url })
+    }
+
+    async fn fetch_user_info(&self, req: &UserInfoRequest) -> Result<UserInfo, Error> {
+        let base_url = self.base_url()?;
+
+        let user = match req {
+            UserInfoRequest::UserInfoRequestById(req) => self
+                .get_json::<ScimUser>(api_url(&base_url, &["Users", &req.id])?, &[])
+                .await
+                .context(UserNotFoundByIdSnafu { user_id: &req.id })?,
+            UserInfoRequest::UserInfoRequestByName(req) => {
+                let username = &req.username;
+                let users = self
+                    .get_json::<ListResponse<ScimUser>>(
+                        api_url(&base_url, &["Users"])?,
+                        &[("filter", &format!("userName eq {}", filter_value(username)))],
+                    )
+                    .await
+                    .context(SearchForUserSnafu)?
+                    .resources;
+                if users.len() > 1 {
+                    return TooManyUsersReturnedSnafu.fail();
+                }
+                users
+                    .into_iter()
+                    .next()
+                    .context(UserNotFoundByNameSnafu { username })?
+            }
+        };
+
+        let groups = match user.groups {
+            Some(groups) => groups
+                .into_iter()
+                .map(|group| group.display.unwrap_or(group.value))
+                .collect(),
+            None => self
+                .get_json::<ListResponse<ScimGroup>>(
+                    api_url(&base_url, &["Groups"])?,
+                    &[
+                        (
+                            "filter",
+                            &format!("members.value eq {}", filter_value(&user.id)),
+                        ),
+                        ("attributes", "displayName"),
+                    ],
+                )
+                .await
+                .context(RequestUserGroupsSnafu { user_id: &user.id })?
+                .resources
+                .into_iter()
+                .map(|group| group.display_name.unwrap_or(group.id))
+                .collect(),
+        };
+
+        let mut custom_attributes = user.attributes;
+        custom_attributes
+            .retain(|attribute, _| !CORE_USER_ATTRIBUTES.contains(&attribute.as_str()));
+        Ok(UserInfo {
+            id: Some(user.id),
+            username: Some(user.user_name),
+            groups,
+            custom_attributes,
+        })
+    }
+
+    async fn get_json<T: serde::de::DeserializeOwned>(
+        &self,
+        url: Url,
+        query: &[(&str, &str)],
+    ) -> Result<T, crate::utils::http::Error> {
+        send_json_request(
+            self.http
+                .get(url)
+                .query(query)
+                .header(reqwest::header::ACCEPT, "application/scim+json")
+                .bearer_auth(&self.credentials.client_secret)
+                .timeout(*self.config.request_timeout),
+        )
+        .await
+    }
+}
+
+impl UserInfoBackend for ScimBackend {
+    fn get_user_info<'a>(
+        &'a self,
+        req: &'a UserInfoRequest,
+        _attributes: &'a AttributeSelection,
+    ) -> BoxFuture<'a, Result<UserInfo, GetUserInfoError>> {
+        self.fetch_user_info(req)
+            .map(|result| result.context(get_user_info_error::ScimSnafu))
+            .boxed()
+    }
+
+    fn name(&self) -> &'static str {
+        "scim"
+    }
+}
+
+/// Renders a string value for use in a SCIM [filter].
+///
+/// [filter]: https://datatracker.ietf.org/doc/html/rfc7644#section-3.4.2.2
+fn filter_value(value: &str) -> String {
+    // SCIM filter values are JSON strings
+    serde_json::Value::String(value.to_string()).to_string()
+}
+
+/// Appends the (percent-encoded) path segments to the base URL, since IDs may contain characters
+/// such as `/` or `?`.
+fn api_url(base_url: &Url, segments: &[&str]) -> Result<Url, Error> {
+    let mut url = base_url.clone();
+    url.path_segments_mut()
+        .ok()
+        .context(ScimEndpointCannotBeABaseSnafu {
+            url: base_url.as_str(),
+        })?
+        .pop_if_empty()
+        .extend(segments);
+    Ok(url)
+}
@@ -8,17 +8,21 @@
 //!
 //! Look at the endpoint definition for the API path, required parameters and the type of the returned object.
 //!
-//! This backend is currently in a minimal PoC state, it does not support TLS or authenticating at the endpoint.
-//! This is because the AAS is also still in an early development stage and is likely to change.
+//! This backend does not support authenticating at the endpoint yet.
+//! Failed requests are retried with backoff (see [`send_json_request`]) and time out after the configured `requestTimeout`.
 use std::collections::HashMap;
 
+use futures::{future::BoxFuture, FutureExt};
 use hyper::StatusCode;
 use serde::Deserialize;
 use snafu::{ResultExt, Snafu};
 use stackable_opa_crd::user_info_fetcher as crd;
 use url::Url;
 
-use crate::{http_error, utils::http::send_json_request, UserInfo, UserInfoRequest};
+use crate::{
+    backend::UserInfoBackend, get_user_info_error, http_error, utils::http::send_json_request,
+    AttributeSelection, GetUserInfoError, UserInfo, UserInfoRequest,
+};
 
 static API_PATH: &str = "/cip/claims";
 static SUB_CLAIM: &str = "sub";
@@ -44,7 +48,7 @@ impl http_error::Error for Error {
     fn status_code(&self) -> StatusCode {
         match self {
             Self::ParseAasEndpointUrl { .. } => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::Request { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::Request { source } => source.gateway_status_code(),
             Self::UserInfoByUsernameNotSupported { .. } => StatusCode::NOT_IMPLEMENTED,
         }
     }
@@ -71,6 +75,33 @@ impl TryFrom<UserClaims> for UserInfo {
     }
 }
 
+pub struct XfscAasBackend {
+    http: reqwest::Client,
+    config: crd::AasBackend,
+}
+
+impl XfscAasBackend {
+    pub fn new(http: reqwest::Client, config: crd::AasBackend) -> Self {
+        Self { http, config }
+    }
+}
+
+impl UserInfoBackend for XfscAasBackend {
+    fn get_user_info<'a>(
+        &'a self,
+        req: &'a UserInfoRequest,
+        _attributes: &'a AttributeSelection,
+    ) -> BoxFuture<'a, Result<UserInfo, GetUserInfoError>> {
+        get_user_info(req, &self.http, &self.config)
+            .map(|result| result.context(get_user_info_error::XfscAasSnafu))
+            .boxed()
+    }
+
+    fn name(&self) -> &'static str {
+        "xfscAas"
+    }
+}
+
 /// Request user info from the AAS REST API by querying the
 /// ClaimsInformationPoint (CIP) of the AAS.
 ///
@@ -78,14 +109,20 @@ impl TryFrom<UserClaims> for UserInfo {
 /// `<https://gitlab.eclipse.org/eclipse/xfsc/authenticationauthorization/-/blob/main/service/src/main/java/eu/xfsc/aas/controller/CipController.java>`
 ///
 /// Only `UserInfoRequestById` is supported because the enpoint has no username concept.
-pub(crate) async fn get_user_info(
+async fn get_user_info(
     req: &UserInfoRequest,
     http: &reqwest::Client,
     config: &crd::AasBackend,
 ) -> Result<UserInfo, Error> {
-    let crd::AasBackend { hostname, port } = config;
-
-    let cip_endpoint_raw = format!("http://{hostname}:{port}{API_PATH}");
+    let crd::AasBackend {
+        hostname,
+        port,
+        tls,
+        request_timeout,
+    } = config;
+
+    let scheme = if tls.uses_tls() { "https" } else { "http" };
+    let cip_endpoint_raw = format!("{scheme}://{hostname}:{port}{API_PATH}");
     let cip_endpoint = Url::parse(&cip_endpoint_raw).context(ParseAasEndpointUrlSnafu {
         url: cip_endpoint_raw,
     })?;
@@ -102,10 +139,13 @@ pub(crate) async fn get_user_info(
     ]
     .into();
 
-    let user_claims: UserClaims =
-        send_json_request(http.get(cip_endpoint).query(&query_parameters))
-            .await
-            .context(RequestSnafu)?;
+    let user_claims: UserClaims = send_json_request(
+        http.get(cip_endpoint)
+            .query(&query_parameters)
+            .timeout(**request_timeout),
+    )
+    .await
+    .context(RequestSnafu)?;
 
     user_claims.try_into()
 }
@@ -0,0 +1,130 @@
+//! Metrics of the user information caches, served at `/metrics` in the Prometheus text format.
+//!
+//! The evictions are counted by eviction listeners, the sizes are read from the caches when the
+//! metrics are scraped (and are approximate, since moka applies pending changes lazily).
+
+use std::{
+    fmt::Write,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use moka::notification::RemovalCause;
+
+/// The name of the cache of user information.
+pub const USER_INFO_CACHE: &str = "user-info";
+/// The name of the cache of unknown users.
+pub const NOT_FOUND_CACHE: &str = "user-not-found";
+/// The name of the cache of group information.
+pub const GROUP_INFO_CACHE: &str = "group-info";
+
+/// The number of entries removed from a cache, by cause.
+#[derive(Default)]
+pub struct CacheEvictions {
+    /// Evicted to stay below `maxEntries`.
+    size: AtomicU64,
+    /// Removed after their time to live.
+    expired: AtomicU64,
+    /// Replaced by a newer entry for the same key (for example by a background refresh).
+    replaced: AtomicU64,
+    /// Removed explicitly.
+    explicit: AtomicU64,
+}
+
+impl CacheEvictions {
+    /// Returns an eviction listener for the cache, to be passed to `Cache::builder().eviction_listener()`.
+    pub fn listener<K, V>(
+        self: &Arc<Self>,
+    ) -> impl Fn(Arc<K>, V, RemovalCause) + Send + Sync + 'static {
+        let evictions = self.clone();
+        move |_key, _value, cause| {
+            let counter = match cause {
+                RemovalCause::Size => &evictions.size,
+                RemovalCause::Expired => &evictions.expired,
+                RemovalCause::Replaced => &evictions.replaced,
+                RemovalCause::Explicit => &evictions.explicit,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// The eviction counters of all caches, shared by the eviction listeners and `/metrics`.
+#[derive(Default)]
+pub struct CacheMetrics {
+    pub user_info: Arc<CacheEvictions>,
+    pub not_found: Arc<CacheEvictions>,
+    pub group_info: Arc<CacheEvictions>,
+}
+
+/// The state of a cache, as rendered by [`render_metrics`].
+pub struct CacheStatus<'a> {
+    pub name: &'static str,
+    pub evictions: &'a CacheEvictions,
+    pub entries: u64,
+    /// The total weight of the entries, see `cache.entryWeight`.
+    pub weighted_size: u64,
+    pub max_weighted_size: u64,
+}
+
+pub fn render_metrics(caches: &[CacheStatus]) -> String {
+    let mut metrics = String::new();
+    let mut family = |name: &str, kind: &str, help: &str, value: &dyn Fn(&CacheStatus) -> u64| {
+        let _ = writeln!(metrics, "# HELP {name} {help}");
+        let _ = writeln!(metrics, "# TYPE {name} {kind}");
+        for cache in caches {
+            let _ = writeln!(
+                metrics,
+                "{name}{{cache=\"{cache_name}\"}} {value}",
+                cache_name = cache.name,
+                value = value(cache)
+            );
+        }
+    };
+    family(
+        "opa_user_info_fetcher_cache_entries",
+        "gauge",
+        "The approximate number of cached entries.",
+        &|cache| cache.entries,
+    );
+    family(
+        "opa_user_info_fetcher_cache_weighted_size",
+        "gauge",
+        "The approximate total weight of the cached entries.",
+        &|cache| cache.weighted_size,
+    );
+    family(
+        "opa_user_info_fetcher_cache_max_weighted_size",
+        "gauge",
+        "The maximum total weight of the cached entries (maxEntries).",
+        &|cache| cache.max_weighted_size,
+    );
+
+    let _ = writeln!(
+        metrics,
+        "# HELP opa_user_info_fetcher_cache_evictions_total The number of entries removed from the cache, by cause."
+    );
+    let _ = writeln!(
+        metrics,
+        "# TYPE opa_user_info_fetcher_cache_evictions_total counter"
+    );
+    for cache in caches {
+        let evictions = cache.evictions;
+        for (cause, counter) in [
+            ("size", &evictions.size),
+            ("expired", &evictions.expired),
+            ("replaced", &evictions.replaced),
+            ("explicit", &evictions.explicit),
+        ] {
+            let _ = writeln!(
+                metrics,
+                "opa_user_info_fetcher_cache_evictions_total{{cache=\"{cache_name}\",cause=\"{cause}\"}} {count}",
+                cache_name = cache.name,
+                count = counter.load(Ordering::Relaxed),
+            );
+        }
+    }
+    metrics
+}
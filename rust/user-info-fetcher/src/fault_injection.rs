@@ -0,0 +1,96 @@
+//! Injects the faults configured for the user-info-fetcher, see [`stackable_opa_crd::fault_injection`].
+//!
+//! Only compiled with the `fault-injection` feature, which must never be enabled for production builds.
+
+use std::{
+    collections::BTreeMap,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use hyper::StatusCode;
+use stackable_opa_crd::fault_injection::{Faults, USER_INFO_FETCHER_COMPONENT};
+
+#[derive(clap::Args)]
+pub struct FaultInjectionArgs {
+    /// Path to the fault injection file written by the operator.
+    /// It is read again for every request, so that faults can be changed without restarting.
+    #[clap(long, env)]
+    fault_injection_file: Option<PathBuf>,
+}
+
+pub struct FaultInjector {
+    file: Option<PathBuf>,
+    /// The number of requests so far, used to fail the configured fraction of them.
+    requests: AtomicU64,
+}
+
+impl FaultInjector {
+    pub fn new(args: &FaultInjectionArgs) -> Self {
+        if let Some(file) = &args.fault_injection_file {
+            tracing::warn!(
+                ?file,
+                "fault injection is enabled, do not use this build in production"
+            );
+        }
+        Self {
+            file: args.fault_injection_file.clone(),
+            requests: AtomicU64::new(0),
+        }
+    }
+
+    async fn faults(&self) -> Faults {
+        let Some(file) = &self.file else {
+            return Faults::default();
+        };
+        let annotations = match tokio::fs::read(file).await {
+            Ok(data) => serde_json::from_slice::<BTreeMap<String, String>>(&data)
+                .inspect_err(|error| {
+                    tracing::warn!(
+                        error = error as &dyn std::error::Error,
+                        "ignoring invalid fault injection file"
+                    )
+                })
+                .unwrap_or_default(),
+            // The operator only writes the file if any faults are configured
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(error) => {
+                tracing::warn!(
+                    error = &error as &dyn std::error::Error,
+                    "failed to read fault injection file"
+                );
+                BTreeMap::new()
+            }
+        };
+        Faults::from_annotations(&annotations, USER_INFO_FETCHER_COMPONENT)
+    }
+}
+
+/// Delays requests by `latency`, and fails `error-rate` of them with `503 Service Unavailable`.
+pub async fn inject_request_faults(
+    State(injector): State<Arc<FaultInjector>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let faults = injector.faults().await;
+    if let Some(latency) = faults.latency {
+        tokio::time::sleep(*latency).await;
+    }
+    // Deterministic rather than random, so that resilience tests are reproducible
+    let request_number = injector.requests.fetch_add(1, Ordering::Relaxed) as f64;
+    if ((request_number + 1.0) * faults.error_rate).floor()
+        > (request_number * faults.error_rate).floor()
+    {
+        tracing::warn!(path = %request.uri().path(), "injecting request failure");
+        return (StatusCode::SERVICE_UNAVAILABLE, "injected fault").into_response();
+    }
+    next.run(request).await
+}
@@ -2,7 +2,7 @@ use std::sync::Arc;
 
 use axum::{response::IntoResponse, Json};
 use hyper::StatusCode;
-use serde::Serialize;
+use stackable_opa_crd::user_info_fetcher::api::{ErrorResponse, UserInfoError};
 
 pub trait Error: std::error::Error {
     fn status_code(&self) -> StatusCode;
@@ -28,28 +28,10 @@ impl<E: Error> IntoResponse for JsonResponse<E> {
     fn into_response(self) -> axum::response::Response {
         (
             self.error.status_code(),
-            Json(Container {
-                error: Payload {
-                    message: self.error.to_string(),
-                    causes: std::iter::successors(self.error.source(), |err| err.source())
-                        .map(|err| err.to_string())
-                        .collect(),
-                },
+            Json(ErrorResponse {
+                error: UserInfoError::new(&self.error),
             }),
         )
             .into_response()
     }
 }
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct Container {
-    error: Payload,
-}
-
-#[derive(Serialize)]
-#[serde(rename_all = "camelCase")]
-struct Payload {
-    message: String,
-    causes: Vec<String>,
-}
@@ -1,32 +1,113 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeSet, HashMap, HashSet},
     fmt::Display,
+    net::SocketAddr,
     path::{Path, PathBuf},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
 };
 
-use axum::{extract::State, routing::post, Json, Router};
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::IntoResponse,
+    routing::{get, post},
+    Json, Router,
+};
+use cache_metrics::{
+    CacheMetrics, CacheStatus, GROUP_INFO_CACHE, NOT_FOUND_CACHE, USER_INFO_CACHE,
+};
 use clap::Parser;
-use futures::{future, pin_mut, FutureExt};
+use futures::{future, pin_mut, stream, FutureExt, StreamExt};
 use moka::future::Cache;
 use reqwest::ClientBuilder;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use snafu::{ResultExt, Snafu};
-use stackable_opa_crd::user_info_fetcher as crd;
+use stackable_opa_crd::user_info_fetcher::{
+    self as crd,
+    api::{
+        BatchUserInfoResult, GroupInfo, GroupInfoRequest, GroupInfoRequestById,
+        GroupInfoRequestByName, UserInfo, UserInfoError, UserInfoRequest, UserInfoRequestById,
+        UserInfoRequestByName,
+    },
+};
 use tokio::net::TcpListener;
+use unknown_users::{HotUnknownUser, UnknownUserTracker};
+use utils::{group_mapping::GroupMapper, trace_context::TraceContext};
 
 mod backend;
+mod cache_metrics;
+#[cfg(feature = "fault-injection")]
+mod fault_injection;
 mod http_error;
+mod readiness;
+mod unknown_users;
 mod utils;
 
 pub const APP_NAME: &str = "opa-user-info-fetcher";
+/// The maximum number of users that can be looked up in a single batch request.
+const MAX_BATCH_SIZE: usize = 1000;
+/// How many distinct users of a batch request are looked up concurrently.
+const BATCH_CONCURRENCY: usize = 16;
 
+/// The command line interface is considered stable, so that the user-info-fetcher can also be
+/// run standalone, outside of OpaClusters.
 #[derive(clap::Parser)]
 pub struct Args {
+    /// Path to the configuration file, which contains the `userInfo` section of an OpaCluster as JSON.
+    #[clap(long, env, required_unless_present = "config_json")]
+    config: Option<PathBuf>,
+
+    /// The configuration as inline JSON, as an alternative to `--config`.
+    #[clap(long, env, conflicts_with = "config")]
+    config_json: Option<String>,
+
+    /// Directory containing the credentials of the backend, as the files `clientId` and `clientSecret`.
+    #[clap(long, env)]
+    credentials_dir: Option<PathBuf>,
+
+    /// Path to the file containing the client ID, overrides `clientId` in `--credentials-dir`.
+    #[clap(long, env)]
+    client_id_file: Option<PathBuf>,
+
+    /// Path to the file containing the client secret, overrides `clientSecret` in `--credentials-dir`.
     #[clap(long, env)]
-    config: PathBuf,
+    client_secret_file: Option<PathBuf>,
+
+    /// The address to serve the API on.
+    #[clap(long, env, default_value = "127.0.0.1:9476")]
+    listen_address: SocketAddr,
+
+    /// Path to the PEM encoded certificate (chain) to serve the API over HTTPS with.
+    #[clap(long, env, requires = "tls_key_file")]
+    tls_cert_file: Option<PathBuf>,
+
+    /// Path to the PEM encoded private key of `--tls-cert-file`.
+    #[clap(long, env, requires = "tls_cert_file")]
+    tls_key_file: Option<PathBuf>,
+
+    /// Path to PEM encoded CA certificates to trust for connections to the backend, in addition to
+    /// the system CA certificates. Ignored if the backend's TLS settings specify CA certificates.
     #[clap(long, env)]
-    credentials_dir: PathBuf,
+    trust_store_file: Option<PathBuf>,
+
+    /// Validate the configuration (including the credentials and TLS settings) and exit, instead of
+    /// starting the server.
+    #[clap(long)]
+    validate_config: bool,
+
+    /// Refuse to start if the configuration contains unknown keys (such as misspelled ones), which
+    /// are otherwise only logged as a warning.
+    #[clap(long, env)]
+    strict_config: bool,
+
+    #[cfg(feature = "fault-injection")]
+    #[clap(flatten)]
+    fault_injection: fault_injection::FaultInjectionArgs,
+
     #[clap(flatten)]
     common: stackable_operator::cli::ProductOperatorRun,
 }
@@ -34,9 +115,65 @@ pub struct Args {
 #[derive(Clone)]
 struct AppState {
     config: Arc<crd::Config>,
-    http: reqwest::Client,
-    credentials: Arc<Credentials>,
-    user_info_cache: Cache<UserInfoRequest, UserInfo>,
+    backend: Arc<dyn backend::UserInfoBackend>,
+    group_mapper: Arc<GroupMapper>,
+    user_info_cache: Cache<UserInfoCacheKey, CachedUserInfo>,
+    /// Caches lookups of users that do not exist, only set if `notFoundTimeToLive` is configured.
+    not_found_cache: Option<Cache<UserInfoRequest, Arc<GetUserInfoError>>>,
+    /// Requests that are currently being refreshed in the background (stale-while-revalidate).
+    refreshing: Arc<Mutex<HashSet<UserInfoCacheKey>>>,
+    unknown_users: Arc<UnknownUserTracker>,
+    /// Caches group lookups, only set if `groupInfo` is enabled.
+    group_info_cache: Option<Cache<GroupInfoRequest, CachedGroupInfo>>,
+    cache_metrics: Arc<CacheMetrics>,
+    readiness: Arc<readiness::ReadinessCheck>,
+}
+
+/// Users are cached separately for every selection of custom attributes, since the entries only
+/// contain the selected attributes.
+type UserInfoCacheKey = (UserInfoRequest, AttributeSelection);
+
+#[derive(Clone)]
+struct CachedUserInfo {
+    user_info: UserInfo,
+    fetched_at: Instant,
+}
+
+impl CachedUserInfo {
+    fn new(user_info: UserInfo) -> Self {
+        Self {
+            user_info,
+            fetched_at: Instant::now(),
+        }
+    }
+
+    /// The weight of the entry towards `maxEntries`, see [`crd::CacheEntryWeight`].
+    fn weight(&self, entry_weight: crd::CacheEntryWeight) -> u32 {
+        match entry_weight {
+            crd::CacheEntryWeight::Uniform => 1,
+            crd::CacheEntryWeight::GroupsAndAttributes => {
+                let user_info = &self.user_info;
+                u32::try_from(user_info.groups.len() + user_info.custom_attributes.len())
+                    .unwrap_or(u32::MAX)
+                    .saturating_add(1)
+            }
+        }
+    }
+}
+
+#[derive(Clone)]
+struct CachedGroupInfo {
+    group_info: GroupInfo,
+    fetched_at: Instant,
+}
+
+impl CachedGroupInfo {
+    fn new(group_info: GroupInfo) -> Self {
+        Self {
+            group_info,
+            fetched_at: Instant::now(),
+        }
+    }
 }
 
 struct Credentials {
@@ -56,6 +193,18 @@ enum StartupError {
     #[snafu(display("failed to parse config file"))]
     ParseConfig { source: serde_json::Error },
 
+    #[snafu(display("config contains unknown keys (strict mode): {}", keys.join(", ")))]
+    UnknownConfigKeys { keys: Vec<String> },
+
+    #[snafu(display("unable to read trust store from {path:?}"))]
+    ReadTrustStore {
+        source: std::io::Error,
+        path: PathBuf,
+    },
+
+    #[snafu(display("no path configured for the credential {file_name:?}, set either --credentials-dir or the path to the file"))]
+    CredentialPathMissing { file_name: String },
+
     #[snafu(display("failed to register SIGTERM handler"))]
     RegisterSigterm { source: std::io::Error },
 
@@ -65,11 +214,24 @@ enum StartupError {
     #[snafu(display("failed to run server"))]
     RunServer { source: std::io::Error },
 
+    #[snafu(display("failed to load TLS certificate {cert_file:?} and key {key_file:?}"))]
+    LoadServerTls {
+        source: std::io::Error,
+        cert_file: PathBuf,
+        key_file: PathBuf,
+    },
+
     #[snafu(display("failed to construct http client"))]
     ConstructHttpClient { source: reqwest::Error },
 
     #[snafu(display("failed to configure TLS"))]
     ConfigureTls { source: utils::tls::Error },
+
+    #[snafu(display("failed to resolve backend"))]
+    ResolveBackend { source: backend::Error },
+
+    #[snafu(display("invalid group mapping"))]
+    InvalidGroupMapping { source: utils::group_mapping::Error },
 }
 
 async fn read_config_file(path: &Path) -> Result<String, StartupError> {
@@ -78,6 +240,38 @@ async fn read_config_file(path: &Path) -> Result<String, StartupError> {
         .context(ReadConfigFileSnafu { path })
 }
 
+/// Parses the configuration, warning about (or, in strict mode, rejecting) unknown keys.
+fn parse_config(config: &str, strict: bool) -> Result<crd::Config, StartupError> {
+    let original = serde_json::from_str::<serde_json::Value>(config).context(ParseConfigSnafu)?;
+    let config = crd::Config::deserialize(&original).context(ParseConfigSnafu)?;
+    let parsed = serde_json::to_value(&config).context(ParseConfigSnafu)?;
+    let unknown_keys = utils::unknown_keys::unknown_keys(&original, &parsed);
+    if !unknown_keys.is_empty() {
+        if strict {
+            return UnknownConfigKeysSnafu { keys: unknown_keys }.fail();
+        }
+        tracing::warn!(
+            ?unknown_keys,
+            "ignoring unknown keys in the configuration, they might be misspelled"
+        );
+    }
+    Ok(config)
+}
+
+/// Reads a credential from the explicitly configured file, falling back to `file_name` in the credentials directory.
+async fn read_credential(
+    path: Option<&Path>,
+    credentials_dir: Option<&Path>,
+    file_name: &str,
+) -> Result<String, StartupError> {
+    let path = match (path, credentials_dir) {
+        (Some(path), _) => path.to_owned(),
+        (None, Some(credentials_dir)) => credentials_dir.join(file_name),
+        (None, None) => return CredentialPathMissingSnafu { file_name }.fail(),
+    };
+    read_config_file(&path).await
+}
+
 #[tokio::main]
 async fn main() -> Result<(), StartupError> {
     let args = Args::parse();
@@ -88,32 +282,37 @@ async fn main() -> Result<(), StartupError> {
         args.common.tracing_target,
     );
 
-    let shutdown_requested = tokio::signal::ctrl_c().map(|_| ());
-    #[cfg(unix)]
-    let shutdown_requested = {
-        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
-            .context(RegisterSigtermSnafu)?;
-        async move {
-            let sigterm = sigterm.recv().map(|_| ());
-            pin_mut!(shutdown_requested, sigterm);
-            future::select(shutdown_requested, sigterm).await;
-        }
-    };
+    // Use the same crypto provider as the rest of the dependency tree instead of pulling in aws-lc-rs
+    // as well. Installing fails if a provider has already been installed, which is fine.
+    let _ = rustls::crypto::ring::default_provider().install_default();
 
-    let config = Arc::<crd::Config>::new(
-        serde_json::from_str(&read_config_file(&args.config).await?).context(ParseConfigSnafu)?,
-    );
-    let credentials = Arc::new(match &config.backend {
+    let config = match (&args.config_json, &args.config) {
+        (Some(config_json), _) => config_json.clone(),
+        (None, Some(config)) => read_config_file(config).await?,
+        (None, None) => unreachable!("clap requires either --config or --config-json"),
+    };
+    let config = Arc::new(parse_config(&config, args.strict_config)?);
+    let credentials = match &config.backend {
         // TODO: factor this out into each backend (e.g. when we add LDAP support)
         crd::Backend::None {} => Credentials {
             client_id: "".to_string(),
             client_secret: "".to_string(),
         },
         crd::Backend::Keycloak(_) => Credentials {
-            client_id: read_config_file(&args.credentials_dir.join("clientId")).await?,
-            client_secret: read_config_file(&args.credentials_dir.join("clientSecret")).await?,
+            client_id: read_credential(
+                args.client_id_file.as_deref(),
+                args.credentials_dir.as_deref(),
+                "clientId",
+            )
+            .await?,
+            client_secret: read_credential(
+                args.client_secret_file.as_deref(),
+                args.credentials_dir.as_deref(),
+                "clientSecret",
+            )
+            .await?,
         },
-        crd::Backend::ExperimentalXfscAas(_) => Credentials {
+        crd::Backend::XfscAas(_) | crd::Backend::ExperimentalXfscAas(_) => Credentials {
             client_id: "".to_string(),
             client_secret: "".to_string(),
         },
@@ -121,7 +320,44 @@ async fn main() -> Result<(), StartupError> {
             client_id: "".to_string(),
             client_secret: "".to_string(),
         },
-    });
+        // The SCIM backend authenticates with the bearer token passed as client secret
+        crd::Backend::Scim(_) => Credentials {
+            client_id: "".to_string(),
+            client_secret: read_credential(
+                args.client_secret_file.as_deref(),
+                args.credentials_dir.as_deref(),
+                crd::ScimBackend::BEARER_TOKEN_SECRET_KEY,
+            )
+            .await?
+            .trim()
+            .to_string(),
+        },
+        // The Okta backend authenticates either with an API token or with the private key of a
+        // service app, both are passed as client secret
+        crd::Backend::Okta(okta) => Credentials {
+            client_id: match &okta.credentials {
+                crd::OktaCredentials::ServiceApp(service_app) => service_app.client_id.clone(),
+                crd::OktaCredentials::ApiTokenSecret(_) => "".to_string(),
+            },
+            client_secret: read_credential(
+                args.client_secret_file.as_deref(),
+                args.credentials_dir.as_deref(),
+                okta.credentials.secret_key(),
+            )
+            .await?
+            .trim()
+            .to_string(),
+        },
+    };
+
+    let trust_store = match &args.trust_store_file {
+        Some(path) => Some(
+            tokio::fs::read(path)
+                .await
+                .context(ReadTrustStoreSnafu { path })?,
+        ),
+        None => None,
+    };
 
     let mut client_builder = ClientBuilder::new();
 
@@ -129,31 +365,139 @@ async fn main() -> Result<(), StartupError> {
     // We could factor it out in the provider specific implementation (e.g. when we add LDAP support).
     // I know it is for setting up the client, but an idea: make a trait for implementing backends
     // The trait can do all this for a genric client using an implementation on the trait (eg: get_http_client() which will call self.uses_tls())
-    if let crd::Backend::Keycloak(keycloak) = &config.backend {
-        client_builder = utils::tls::configure_reqwest(&keycloak.tls, client_builder)
-            .await
-            .context(ConfigureTlsSnafu)?;
+    let backend_tls = match &config.backend {
+        crd::Backend::Keycloak(keycloak) => Some(&keycloak.tls),
+        crd::Backend::XfscAas(aas) | crd::Backend::ExperimentalXfscAas(aas) => Some(&aas.tls),
+        crd::Backend::Scim(scim) => Some(&scim.tls),
+        crd::Backend::Okta(okta) => Some(&okta.tls),
+        crd::Backend::None {} | crd::Backend::ActiveDirectory(_) => None,
+    };
+    if let Some(backend_tls) = backend_tls {
+        client_builder =
+            utils::tls::configure_reqwest(backend_tls, trust_store.as_deref(), client_builder)
+                .await
+                .context(ConfigureTlsSnafu)?;
     }
     let http = client_builder.build().context(ConstructHttpClientSnafu)?;
+    let backend: Arc<dyn backend::UserInfoBackend> =
+        backend::resolve(&config.backend, http, credentials, trust_store.as_deref())
+            .await
+            .context(ResolveBackendSnafu)?
+            .into();
+    let group_mapper =
+        Arc::new(GroupMapper::new(&config.group_mapping).context(InvalidGroupMappingSnafu)?);
 
-    let user_info_cache = {
-        let crd::Cache { entry_time_to_live } = config.cache;
+    let server_tls = match (&args.tls_cert_file, &args.tls_key_file) {
+        (Some(cert_file), Some(key_file)) => Some(
+            axum_server::tls_rustls::RustlsConfig::from_pem_file(cert_file, key_file)
+                .await
+                .context(LoadServerTlsSnafu {
+                    cert_file,
+                    key_file,
+                })?,
+        ),
+        _ => None,
+    };
+
+    if args.validate_config {
+        tracing::info!("configuration is valid");
+        return Ok(());
+    }
+
+    let shutdown_requested = tokio::signal::ctrl_c().map(|_| ());
+    #[cfg(unix)]
+    let shutdown_requested = {
+        let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .context(RegisterSigtermSnafu)?;
+        async move {
+            let sigterm = sigterm.recv().map(|_| ());
+            pin_mut!(shutdown_requested, sigterm);
+            future::select(shutdown_requested, sigterm).await;
+        }
+    };
+
+    let crd::Cache {
+        entry_time_to_live,
+        not_found_time_to_live,
+        stale_while_revalidate,
+        max_entries,
+        entry_weight,
+    } = config.cache;
+    let cache_metrics = Arc::new(CacheMetrics::default());
+    let user_info_cache = Cache::builder()
+        .name(USER_INFO_CACHE)
+        .max_capacity(max_entries)
+        .weigher(move |_key, cached: &CachedUserInfo| cached.weight(entry_weight))
+        .eviction_listener(cache_metrics.user_info.listener())
+        // Stale entries must be kept around until they can no longer be served
+        .time_to_live(
+            *entry_time_to_live + stale_while_revalidate.map_or(std::time::Duration::ZERO, |d| *d),
+        )
+        .build();
+    let not_found_cache = not_found_time_to_live.map(|not_found_time_to_live| {
         Cache::builder()
-            .name("user-info")
-            .time_to_live(*entry_time_to_live)
+            .name(NOT_FOUND_CACHE)
+            .max_capacity(max_entries)
+            .eviction_listener(cache_metrics.not_found.listener())
+            .time_to_live(*not_found_time_to_live)
             .build()
-    };
+    });
+    let group_info_cache = config.group_info.enabled.then(|| {
+        Cache::builder()
+            .name(GROUP_INFO_CACHE)
+            .max_capacity(config.group_info.max_entries)
+            .eviction_listener(cache_metrics.group_info.listener())
+            .time_to_live(*config.group_info.entry_time_to_live)
+            .build()
+    });
     let app = Router::new()
         .route("/user", post(get_user_info))
+        .route("/users:batch", post(get_user_info_batch))
+        .route("/group", post(get_group_info))
+        .route("/config", get(get_config))
+        .route("/config/schema", get(get_config_schema))
+        .route("/status/unknown-users", get(get_unknown_users))
+        .route("/metrics", get(get_metrics))
+        .route("/healthz", get(get_health))
+        .route("/readyz", get(get_readiness))
         .with_state(AppState {
             config,
-            http,
-            credentials,
+            backend,
+            group_mapper,
             user_info_cache,
+            not_found_cache,
+            refreshing: Arc::default(),
+            unknown_users: Arc::default(),
+            group_info_cache,
+            cache_metrics,
+            readiness: Arc::default(),
+        });
+    #[cfg(feature = "fault-injection")]
+    let app = app.layer(axum::middleware::from_fn_with_state(
+        Arc::new(fault_injection::FaultInjector::new(&args.fault_injection)),
+        fault_injection::inject_request_faults,
+    ));
+    if let Some(server_tls) = server_tls {
+        let handle = axum_server::Handle::new();
+        tokio::spawn({
+            let handle = handle.clone();
+            async move {
+                shutdown_requested.await;
+                handle.graceful_shutdown(None);
+            }
         });
-    let listener = TcpListener::bind("127.0.0.1:9476")
+        tracing::info!(address = %args.listen_address, "listening (HTTPS)");
+        return axum_server::bind_rustls(args.listen_address, server_tls)
+            .handle(handle)
+            .serve(app.into_make_service())
+            .await
+            .context(RunServerSnafu);
+    }
+
+    let listener = TcpListener::bind(args.listen_address)
         .await
         .context(BindListenerSnafu)?;
+    tracing::info!(address = %args.listen_address, "listening");
 
     axum::serve(listener, app.into_make_service())
         .with_graceful_shutdown(shutdown_requested)
@@ -161,23 +505,105 @@ async fn main() -> Result<(), StartupError> {
         .context(RunServerSnafu)
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Hash, Clone)]
-#[serde(rename_all = "camelCase", untagged)]
-enum UserInfoRequest {
-    UserInfoRequestById(UserInfoRequestById),
-    UserInfoRequestByName(UserInfoRequestByName),
+/// Returns the effective configuration, including the defaults of all settings that were not configured.
+///
+/// The configuration never contains the credentials of the backend, which are read from separate files.
+async fn get_config(State(state): State<AppState>) -> Json<Arc<crd::Config>> {
+    Json(state.config)
+}
+
+/// Returns the users that were looked up repeatedly, but do not exist.
+async fn get_unknown_users(State(state): State<AppState>) -> Json<Vec<HotUnknownUser>> {
+    Json(state.unknown_users.hot_unknown_users())
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Hash, Clone)]
-#[serde(rename_all = "camelCase")]
-struct UserInfoRequestById {
-    id: String,
+/// The state of the caches in the Prometheus text format.
+async fn get_metrics(State(state): State<AppState>) -> impl IntoResponse {
+    let max_entries = state.config.cache.max_entries;
+    let user_info_cache = &state.user_info_cache;
+    let mut caches = vec![CacheStatus {
+        name: USER_INFO_CACHE,
+        evictions: &state.cache_metrics.user_info,
+        entries: user_info_cache.entry_count(),
+        weighted_size: user_info_cache.weighted_size(),
+        max_weighted_size: max_entries,
+    }];
+    if let Some(not_found_cache) = &state.not_found_cache {
+        caches.push(CacheStatus {
+            name: NOT_FOUND_CACHE,
+            evictions: &state.cache_metrics.not_found,
+            entries: not_found_cache.entry_count(),
+            weighted_size: not_found_cache.weighted_size(),
+            max_weighted_size: max_entries,
+        });
+    }
+    if let Some(group_info_cache) = &state.group_info_cache {
+        caches.push(CacheStatus {
+            name: GROUP_INFO_CACHE,
+            evictions: &state.cache_metrics.group_info,
+            entries: group_info_cache.entry_count(),
+            weighted_size: group_info_cache.weighted_size(),
+            max_weighted_size: state.config.group_info.max_entries,
+        });
+    }
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        cache_metrics::render_metrics(&caches),
+    )
+}
+
+/// Always succeeds while the server is running (the liveness probe).
+async fn get_health() -> &'static str {
+    "ok"
+}
+
+/// Succeeds if the backend is reachable (the readiness probe), see [`readiness::ReadinessCheck`].
+async fn get_readiness(
+    State(state): State<AppState>,
+) -> Result<&'static str, http_error::JsonResponse<readiness::Error>> {
+    state.readiness.check(state.backend.as_ref()).await?;
+    Ok("ok")
+}
+
+/// Returns the JSON Schema of the configuration.
+async fn get_config_schema() -> Json<stackable_operator::schemars::schema::RootSchema> {
+    Json(stackable_operator::schemars::schema_for!(crd::Config))
 }
 
-#[derive(Debug, Deserialize, PartialEq, Eq, Hash, Clone)]
+/// The query parameters of [`get_user_info`] and [`get_user_info_batch`].
+#[derive(Debug, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
-struct UserInfoRequestByName {
-    username: String,
+struct UserInfoQuery {
+    /// Comma-separated list of the custom attributes to fetch. All custom attributes are fetched if
+    /// unset, and none if empty.
+    custom_attributes: Option<String>,
+}
+
+/// The custom attributes that the caller needs, so that backends can skip fetching the others.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Default)]
+struct AttributeSelection(Option<BTreeSet<String>>);
+
+impl AttributeSelection {
+    fn from_query(query: &UserInfoQuery) -> Self {
+        Self(query.custom_attributes.as_ref().map(|attributes| {
+            attributes
+                .split(',')
+                .map(str::trim)
+                .filter(|attribute| !attribute.is_empty())
+                .map(str::to_string)
+                .collect()
+        }))
+    }
+
+    /// Whether the custom attribute was selected.
+    fn includes(&self, attribute: &str) -> bool {
+        self.0
+            .as_ref()
+            .is_none_or(|attributes| attributes.contains(attribute))
+    }
 }
 
 /// Renders [`UserInfoRequest`] for use in error messages.
@@ -204,32 +630,106 @@ impl From<&UserInfoRequest> for ErrorRenderUserInfoRequest {
     }
 }
 
-#[derive(Serialize, Clone, Debug, Default)]
-#[serde(rename_all = "camelCase")]
-struct UserInfo {
-    /// This might be null in case the id is not known (e.g. the backend does not have this info).
-    id: Option<String>,
-    /// This might be null in case the username is not known (e.g. the backend does not have this info).
-    username: Option<String>,
-    groups: Vec<String>,
-    custom_attributes: HashMap<String, serde_json::Value>,
+/// Renders [`GroupInfoRequest`] for use in error messages, see [`ErrorRenderUserInfoRequest`].
+#[derive(Debug, Clone)]
+struct ErrorRenderGroupInfoRequest(GroupInfoRequest);
+impl Display for ErrorRenderGroupInfoRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.0 {
+            GroupInfoRequest::GroupInfoRequestById(GroupInfoRequestById { id }) => {
+                write!(f, "with id {id:?}")
+            }
+            GroupInfoRequest::GroupInfoRequestByName(GroupInfoRequestByName { name }) => {
+                write!(f, "with name {name:?}")
+            }
+        }
+    }
+}
+impl From<&GroupInfoRequest> for ErrorRenderGroupInfoRequest {
+    fn from(value: &GroupInfoRequest) -> Self {
+        Self(value.clone())
+    }
 }
 
 #[derive(Snafu, Debug)]
 #[snafu(module)]
 enum GetUserInfoError {
+    #[snafu(display("access to the user information of the user {user} is denied"))]
+    AccessDenied { user: ErrorRenderUserInfoRequest },
+
     #[snafu(display("failed to get user information from Keycloak"))]
     Keycloak { source: backend::keycloak::Error },
 
     #[snafu(display(
         "failed to get user information from the XFSC Authentication & Authorization Service"
     ))]
-    ExperimentalXfscAas { source: backend::xfsc_aas::Error },
+    XfscAas { source: backend::xfsc_aas::Error },
 
     #[snafu(display("failed to get user information from Active Directory"))]
     ActiveDirectory {
         source: backend::active_directory::Error,
     },
+
+    #[snafu(display("failed to get user information from the SCIM service provider"))]
+    Scim { source: backend::scim::Error },
+
+    #[snafu(display("failed to get user information from Okta"))]
+    Okta { source: backend::okta::Error },
+}
+
+impl GetUserInfoError {
+    fn backend_status_code(&self) -> hyper::StatusCode {
+        match self {
+            Self::AccessDenied { .. } => hyper::StatusCode::FORBIDDEN,
+            Self::Keycloak { source } => http_error::Error::status_code(source),
+            Self::XfscAas { source } => http_error::Error::status_code(source),
+            Self::ActiveDirectory { source } => http_error::Error::status_code(source),
+            Self::Scim { source } => http_error::Error::status_code(source),
+            Self::Okta { source } => http_error::Error::status_code(source),
+        }
+    }
+
+    fn is_user_not_found(&self) -> bool {
+        self.backend_status_code() == hyper::StatusCode::NOT_FOUND
+    }
+
+    /// Whether the backend does not support the kind of lookup, such as lookups by username.
+    fn is_not_supported(&self) -> bool {
+        self.backend_status_code() == hyper::StatusCode::NOT_IMPLEMENTED
+    }
+}
+
+#[derive(Snafu, Debug)]
+#[snafu(module)]
+enum GetGroupInfoError {
+    #[snafu(display("group lookups are disabled, see groupInfo.enabled"))]
+    Disabled,
+
+    #[snafu(display("the {backend} backend does not support group lookups"))]
+    NotSupported { backend: &'static str },
+
+    #[snafu(display("access to the group information of the group {group} is denied"))]
+    AccessDenied { group: ErrorRenderGroupInfoRequest },
+
+    #[snafu(display("failed to get group information from Keycloak"))]
+    Keycloak { source: backend::keycloak::Error },
+
+    #[snafu(display("failed to get group information from Active Directory"))]
+    ActiveDirectory {
+        source: backend::active_directory::Error,
+    },
+}
+
+impl http_error::Error for GetGroupInfoError {
+    fn status_code(&self) -> hyper::StatusCode {
+        match self {
+            Self::Disabled => hyper::StatusCode::NOT_FOUND,
+            Self::NotSupported { .. } => hyper::StatusCode::NOT_IMPLEMENTED,
+            Self::AccessDenied { .. } => hyper::StatusCode::FORBIDDEN,
+            Self::Keycloak { source } => http_error::Error::status_code(source),
+            Self::ActiveDirectory { source } => http_error::Error::status_code(source),
+        }
+    }
 }
 
 impl http_error::Error for GetUserInfoError {
@@ -240,69 +740,351 @@ impl http_error::Error for GetUserInfoError {
             error = self as &dyn std::error::Error,
             "Error while processing request"
         );
-        match self {
-            Self::Keycloak { source } => source.status_code(),
-            Self::ExperimentalXfscAas { source } => source.status_code(),
-            Self::ActiveDirectory { source } => source.status_code(),
-        }
+        self.backend_status_code()
     }
 }
 
+#[tracing::instrument(
+    skip_all,
+    fields(
+        backend = state.backend.name(),
+        trace_id = tracing::field::Empty,
+        cache.hit = tracing::field::Empty,
+        user_info.groups = tracing::field::Empty,
+        user_info.custom_attributes = tracing::field::Empty,
+    )
+)]
 async fn get_user_info(
     State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<UserInfoQuery>,
     Json(req): Json<UserInfoRequest>,
-) -> Result<Json<UserInfo>, http_error::JsonResponse<Arc<GetUserInfoError>>> {
+) -> Result<
+    ([(header::HeaderName, HeaderValue); 1], Json<UserInfo>),
+    http_error::JsonResponse<Arc<GetUserInfoError>>,
+> {
+    let trace_context = TraceContext::from_headers(&headers);
+    let span = tracing::Span::current();
+    if let Some(trace_id) = trace_context.as_ref().and_then(TraceContext::trace_id) {
+        span.record("trace_id", trace_id);
+    }
+    let attributes = AttributeSelection::from_query(&query);
+    let entry_time_to_live = *state.config.cache.entry_time_to_live;
+    let cached = TraceContext::scope(trace_context, lookup_user_info(state, req, attributes))
+        .await
+        .map_err(http_error::JsonResponse::from)?;
+    // Lets clients (such as the inter-query cache of OPA) cache the response for as long as it is
+    // fresh in our cache
+    let max_age = entry_time_to_live.saturating_sub(cached.fetched_at.elapsed());
+    Ok((
+        [(
+            header::CACHE_CONTROL,
+            HeaderValue::from_str(&format!("max-age={}", max_age.as_secs()))
+                .expect("Cache-Control value is always valid"),
+        )],
+        Json(cached.user_info),
+    ))
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(
+        backend = state.backend.name(),
+        trace_id = tracing::field::Empty,
+        cache.hit = tracing::field::Empty,
+        group_info.members = tracing::field::Empty,
+    )
+)]
+async fn get_group_info(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<GroupInfoRequest>,
+) -> Result<
+    ([(header::HeaderName, HeaderValue); 1], Json<GroupInfo>),
+    http_error::JsonResponse<Arc<GetGroupInfoError>>,
+> {
+    let trace_context = TraceContext::from_headers(&headers);
+    let span = tracing::Span::current();
+    if let Some(trace_id) = trace_context.as_ref().and_then(TraceContext::trace_id) {
+        span.record("trace_id", trace_id);
+    }
+    let Some(group_info_cache) = &state.group_info_cache else {
+        return Err(Arc::new(GetGroupInfoError::Disabled).into());
+    };
+    let access_control = &state.config.access_control;
+    let requested_group = match &req {
+        GroupInfoRequest::GroupInfoRequestById(GroupInfoRequestById { id }) => id,
+        GroupInfoRequest::GroupInfoRequestByName(GroupInfoRequestByName { name }) => name,
+    };
+    if access_control.denies_group([requested_group.as_str()]) {
+        return Err(Arc::new(GetGroupInfoError::AccessDenied {
+            group: (&req).into(),
+        })
+        .into());
+    }
+
+    let fetched = AtomicBool::new(false);
+    let lookup = group_info_cache.try_get_with_by_ref(&req, async {
+        fetched.store(true, Ordering::Relaxed);
+        state
+            .backend
+            .get_group_info(&req, state.config.group_info.max_members)
+            .await
+            .map(CachedGroupInfo::new)
+    });
+    let cached = TraceContext::scope(trace_context, lookup)
+        .await
+        .map_err(http_error::JsonResponse::from)?;
+    span.record("cache.hit", !fetched.load(Ordering::Relaxed));
+    span.record("group_info.members", cached.group_info.members.len());
+
+    let CachedGroupInfo {
+        mut group_info,
+        fetched_at,
+    } = cached;
+    let group_names = [Some(group_info.name.as_str()), group_info.id.as_deref()];
+    if access_control.denies_group(group_names.into_iter().flatten()) {
+        return Err(Arc::new(GetGroupInfoError::AccessDenied {
+            group: (&req).into(),
+        })
+        .into());
+    }
+    // Denied users are not revealed as members either
+    group_info
+        .members
+        .retain(|member| !access_control.deny.matches([member.as_str()], &[]));
+    let max_age =
+        (*state.config.group_info.entry_time_to_live).saturating_sub(fetched_at.elapsed());
+    Ok((
+        [(
+            header::CACHE_CONTROL,
+            HeaderValue::from_str(&format!("max-age={}", max_age.as_secs()))
+                .expect("Cache-Control value is always valid"),
+        )],
+        Json(group_info),
+    ))
+}
+
+#[derive(Snafu, Debug)]
+enum BatchError {
+    #[snafu(display("batch of {size} users exceeds the limit of {limit} users"))]
+    TooLarge { size: usize, limit: usize },
+}
+
+impl http_error::Error for BatchError {
+    fn status_code(&self) -> hyper::StatusCode {
+        match self {
+            Self::TooLarge { .. } => hyper::StatusCode::PAYLOAD_TOO_LARGE,
+        }
+    }
+}
+
+#[tracing::instrument(
+    skip_all,
+    fields(
+        backend = state.backend.name(),
+        trace_id = tracing::field::Empty,
+        batch.size = reqs.len(),
+        batch.unique = tracing::field::Empty,
+    )
+)]
+async fn get_user_info_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<UserInfoQuery>,
+    Json(reqs): Json<Vec<UserInfoRequest>>,
+) -> Result<Json<Vec<BatchUserInfoResult>>, http_error::JsonResponse<BatchError>> {
+    if reqs.len() > MAX_BATCH_SIZE {
+        return Err(TooLargeSnafu {
+            size: reqs.len(),
+            limit: MAX_BATCH_SIZE,
+        }
+        .build()
+        .into());
+    }
+    let trace_context = TraceContext::from_headers(&headers);
+    let span = tracing::Span::current();
+    if let Some(trace_id) = trace_context.as_ref().and_then(TraceContext::trace_id) {
+        span.record("trace_id", trace_id);
+    }
+
+    let attributes = AttributeSelection::from_query(&query);
+    // Users requested multiple times are only looked up once
+    let unique_reqs = reqs.iter().cloned().collect::<HashSet<_>>();
+    span.record("batch.unique", unique_reqs.len());
+    let lookups = stream::iter(unique_reqs)
+        .map(|req| {
+            let state = state.clone();
+            let attributes = attributes.clone();
+            async move {
+                let result = lookup_user_info(state, req.clone(), attributes).await;
+                (req, result)
+            }
+        })
+        .buffer_unordered(BATCH_CONCURRENCY)
+        .collect::<HashMap<_, _>>();
+    let results = TraceContext::scope(trace_context, lookups).await;
+
+    Ok(Json(
+        reqs.iter()
+            .map(|req| match &results[req] {
+                Ok(cached) => BatchUserInfoResult::UserInfo(cached.user_info.clone()),
+                Err(error) => BatchUserInfoResult::Error {
+                    status: http_error::Error::status_code(error).as_u16(),
+                    error: UserInfoError::new(error.as_ref()),
+                },
+            })
+            .collect(),
+    ))
+}
+
+/// Looks up the user in the caches, falling back to the backend.
+///
+/// Records the outcome in the current span, see [`get_user_info`].
+async fn lookup_user_info(
+    state: AppState,
+    req: UserInfoRequest,
+    attributes: AttributeSelection,
+) -> Result<CachedUserInfo, Arc<GetUserInfoError>> {
+    let span = tracing::Span::current();
     let AppState {
         config,
-        http,
-        credentials,
+        backend,
+        group_mapper,
         user_info_cache,
-    } = state;
-    Ok(Json(
-        user_info_cache
-            .try_get_with_by_ref(&req, async {
-                match &config.backend {
-                    crd::Backend::None {} => {
-                        let user_id = match &req {
-                            UserInfoRequest::UserInfoRequestById(UserInfoRequestById { id }) => {
-                                Some(id)
-                            }
-                            _ => None,
-                        };
-                        let username = match &req {
-                            UserInfoRequest::UserInfoRequestByName(UserInfoRequestByName {
-                                username,
-                            }) => Some(username),
-                            _ => None,
-                        };
-                        Ok(UserInfo {
-                            id: user_id.cloned(),
-                            username: username.cloned(),
-                            groups: vec![],
-                            custom_attributes: HashMap::new(),
-                        })
-                    }
-                    crd::Backend::Keycloak(keycloak) => {
-                        backend::keycloak::get_user_info(&req, &http, &credentials, keycloak)
-                            .await
-                            .context(get_user_info_error::KeycloakSnafu)
-                    }
-                    crd::Backend::ExperimentalXfscAas(aas) => {
-                        backend::xfsc_aas::get_user_info(&req, &http, aas)
-                            .await
-                            .context(get_user_info_error::ExperimentalXfscAasSnafu)
-                    }
-                    crd::Backend::ActiveDirectory(ad) => backend::active_directory::get_user_info(
-                        &req,
-                        &ad.ldap_server,
-                        &ad.tls,
-                        &ad.base_distinguished_name,
-                        &ad.custom_attribute_mappings,
-                    )
-                    .await
-                    .context(get_user_info_error::ActiveDirectorySnafu),
+        not_found_cache,
+        refreshing: _,
+        unknown_users,
+        group_info_cache: _,
+        cache_metrics: _,
+        readiness: _,
+    } = &state;
+    // Denied users are refused before hitting the backend, as far as that is possible without
+    // knowing their groups
+    check_access(&config.access_control, &req, None)?;
+    if let Some(not_found_cache) = not_found_cache {
+        if let Some(error) = not_found_cache.get(&req).await {
+            span.record("cache.hit", true);
+            unknown_users.record_not_found(&req);
+            return Err(error);
+        }
+    }
+    let fetched = AtomicBool::new(false);
+    let cache_key = (req.clone(), attributes.clone());
+    let cached = user_info_cache
+        .try_get_with_by_ref(&cache_key, async {
+            fetched.store(true, Ordering::Relaxed);
+            fetch_user_info(&req, &attributes, backend.as_ref(), group_mapper)
+                .await
+                .map(CachedUserInfo::new)
+        })
+        .await;
+    span.record("cache.hit", !fetched.load(Ordering::Relaxed));
+    match cached {
+        Ok(cached) => {
+            span.record("user_info.groups", cached.user_info.groups.len());
+            span.record(
+                "user_info.custom_attributes",
+                cached.user_info.custom_attributes.len(),
+            );
+            if config.cache.stale_while_revalidate.is_some()
+                && cached.fetched_at.elapsed() > *config.cache.entry_time_to_live
+            {
+                refresh_user_info_in_background(state.clone(), cache_key);
+            }
+            check_access(&config.access_control, &req, Some(&cached.user_info))?;
+            Ok(cached)
+        }
+        Err(error) => {
+            if error.is_user_not_found() {
+                unknown_users.record_not_found(&req);
+                if let Some(not_found_cache) = not_found_cache {
+                    not_found_cache.insert(req, error.clone()).await;
                 }
-            })
-            .await?,
-    ))
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Checks the `accessControl` configuration for the requested user.
+///
+/// Before the user has been looked up (`user_info` is `None`), only the denied users are checked.
+fn check_access(
+    access_control: &crd::AccessControl,
+    req: &UserInfoRequest,
+    user_info: Option<&UserInfo>,
+) -> Result<(), Arc<GetUserInfoError>> {
+    let requested_user = match req {
+        UserInfoRequest::UserInfoRequestById(UserInfoRequestById { id }) => id,
+        UserInfoRequest::UserInfoRequestByName(UserInfoRequestByName { username }) => username,
+    };
+    let denied = match user_info {
+        None => access_control.deny.matches([requested_user.as_str()], &[]),
+        Some(user_info) => {
+            let users = [
+                Some(requested_user.as_str()),
+                user_info.id.as_deref(),
+                user_info.username.as_deref(),
+            ];
+            access_control.denies(users.into_iter().flatten(), &user_info.groups)
+        }
+    };
+    if denied {
+        return Err(Arc::new(GetUserInfoError::AccessDenied {
+            user: req.into(),
+        }));
+    }
+    Ok(())
+}
+
+/// Refreshes an expired (but still served) cache entry, unless a refresh is already in progress.
+fn refresh_user_info_in_background(state: AppState, cache_key: UserInfoCacheKey) {
+    if !state.refreshing.lock().unwrap().insert(cache_key.clone()) {
+        return;
+    }
+    tokio::spawn(async move {
+        let AppState {
+            config: _,
+            backend,
+            group_mapper,
+            user_info_cache,
+            not_found_cache: _,
+            refreshing,
+            unknown_users: _,
+            group_info_cache: _,
+            cache_metrics: _,
+            readiness: _,
+        } = state;
+        let (req, attributes) = &cache_key;
+        match fetch_user_info(req, attributes, backend.as_ref(), &group_mapper).await {
+            Ok(user_info) => {
+                user_info_cache
+                    .insert(cache_key.clone(), CachedUserInfo::new(user_info))
+                    .await
+            }
+            Err(error) => tracing::warn!(
+                error = &error as &dyn std::error::Error,
+                user = %ErrorRenderUserInfoRequest::from(req),
+                "failed to refresh user information, serving stale entry until it expires"
+            ),
+        }
+        refreshing.lock().unwrap().remove(&cache_key);
+    });
+}
+
+#[tracing::instrument(skip_all)]
+async fn fetch_user_info(
+    req: &UserInfoRequest,
+    attributes: &AttributeSelection,
+    backend: &dyn backend::UserInfoBackend,
+    group_mapper: &GroupMapper,
+) -> Result<UserInfo, GetUserInfoError> {
+    let mut user_info = backend.get_user_info(req, attributes).await?;
+    // Not all backends can restrict the attributes they fetch
+    user_info
+        .custom_attributes
+        .retain(|attribute, _| attributes.includes(attribute));
+    user_info.groups = group_mapper.map_groups(user_info.groups);
+    Ok(user_info)
 }
@@ -0,0 +1,87 @@
+//! The readiness check of the user-info-fetcher, served at `/readyz`.
+//!
+//! The check looks up a user that is not expected to exist, which proves that the backend is
+//! reachable and accepts the credentials without depending on any real user. The result is cached
+//! for [`CHECK_INTERVAL`], so that frequent probes do not put load on the backend.
+//!
+//! The operator does not use the check as a probe, since an unavailable backend would then take all
+//! OPA Pods out of their Service. It is meant for monitoring.
+
+use std::{
+    collections::BTreeSet,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use hyper::StatusCode;
+use snafu::Snafu;
+use tokio::sync::Mutex;
+
+use crate::{
+    backend::UserInfoBackend, http_error, AttributeSelection, GetUserInfoError, UserInfoRequest,
+    UserInfoRequestByName,
+};
+
+/// How long the result of a check is reused.
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// The user looked up by the check.
+const CHECK_USERNAME: &str = "stackable-user-info-fetcher-readiness-check";
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("the backend is not reachable"))]
+    BackendUnavailable { source: Arc<GetUserInfoError> },
+}
+
+impl http_error::Error for Error {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            Self::BackendUnavailable { .. } => StatusCode::SERVICE_UNAVAILABLE,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ReadinessCheck {
+    /// The result of the last check. The lock is held during the check, so that concurrent probes
+    /// share a single lookup.
+    last_check: Mutex<Option<(Instant, Result<(), Arc<GetUserInfoError>>)>>,
+}
+
+impl ReadinessCheck {
+    pub async fn check(&self, backend: &dyn UserInfoBackend) -> Result<(), Error> {
+        let mut last_check = self.last_check.lock().await;
+        let result = match &*last_check {
+            Some((checked_at, result)) if checked_at.elapsed() < CHECK_INTERVAL => result.clone(),
+            _ => {
+                let result = lookup_check_user(backend).await;
+                if let Err(error) = &result {
+                    tracing::warn!(
+                        error = error as &dyn std::error::Error,
+                        "readiness check failed"
+                    );
+                }
+                *last_check = Some((Instant::now(), result.clone()));
+                result
+            }
+        };
+        result.map_err(|source| Error::BackendUnavailable { source })
+    }
+}
+
+async fn lookup_check_user(backend: &dyn UserInfoBackend) -> Result<(), Arc<GetUserInfoError>> {
+    let req = UserInfoRequest::UserInfoRequestByName(UserInfoRequestByName {
+        username: CHECK_USERNAME.to_string(),
+    });
+    // No custom attributes are needed, which keeps the lookup cheap if the user does exist
+    let attributes = AttributeSelection(Some(BTreeSet::new()));
+    match backend.get_user_info(&req, &attributes).await {
+        Ok(_) => Ok(()),
+        // The backend answered, which is all the check is about
+        Err(error) if error.is_user_not_found() => Ok(()),
+        // Backends that can't look up users by name (such as the XFSC AAS) can't be checked this
+        // way, so they are assumed to be reachable
+        Err(error) if error.is_not_supported() => Ok(()),
+        Err(error) => Err(Arc::new(error)),
+    }
+}
@@ -0,0 +1,104 @@
+//! Detection of users that are looked up repeatedly, but do not exist.
+//!
+//! This usually means that a product sends the wrong claim as the username (or ID), which would
+//! otherwise only show up as silently denied requests.
+
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use serde::Serialize;
+
+use crate::{ErrorRenderUserInfoRequest, UserInfoRequest};
+
+/// Users are reported once they have not been found this often within [`WINDOW`].
+const THRESHOLD: u32 = 10;
+const WINDOW: Duration = Duration::from_secs(5 * 60);
+/// The warning is logged at most once per user within this interval.
+const WARNING_INTERVAL: Duration = Duration::from_secs(10 * 60);
+/// Bounds the memory used if many different unknown users are looked up.
+const MAX_TRACKED_USERS: usize = 10_000;
+
+#[derive(Default)]
+pub struct UnknownUserTracker {
+    users: Mutex<HashMap<UserInfoRequest, UnknownUser>>,
+}
+
+struct UnknownUser {
+    window_start: Instant,
+    /// The misses within the current window.
+    misses: u32,
+    total_misses: u64,
+    last_warning: Option<Instant>,
+}
+
+/// A user that has not been found at least [`THRESHOLD`] times within the current window.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HotUnknownUser {
+    #[serde(flatten)]
+    user: UserInfoRequest,
+    misses: u32,
+    total_misses: u64,
+}
+
+impl UnknownUserTracker {
+    pub fn record_not_found(&self, req: &UserInfoRequest) {
+        let now = Instant::now();
+        let mut users = self.users.lock().unwrap();
+        if users.len() >= MAX_TRACKED_USERS && !users.contains_key(req) {
+            users.retain(|_, user| now.duration_since(user.window_start) < WINDOW);
+            if users.len() >= MAX_TRACKED_USERS {
+                return;
+            }
+        }
+        let user = users.entry(req.clone()).or_insert_with(|| UnknownUser {
+            window_start: now,
+            misses: 0,
+            total_misses: 0,
+            last_warning: None,
+        });
+        if now.duration_since(user.window_start) >= WINDOW {
+            user.window_start = now;
+            user.misses = 0;
+        }
+        user.misses += 1;
+        user.total_misses += 1;
+        if user.misses >= THRESHOLD
+            && user
+                .last_warning
+                .is_none_or(|last_warning| now.duration_since(last_warning) >= WARNING_INTERVAL)
+        {
+            user.last_warning = Some(now);
+            tracing::warn!(
+                user = %ErrorRenderUserInfoRequest::from(req),
+                misses = user.misses,
+                window = ?WINDOW,
+                "user is looked up repeatedly but does not exist, the product querying OPA might send the wrong claim as username"
+            );
+        }
+    }
+
+    /// The users that have reached the threshold within their current window, most misses first.
+    pub fn hot_unknown_users(&self) -> Vec<HotUnknownUser> {
+        let now = Instant::now();
+        let mut hot_users = self
+            .users
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(_, user)| {
+                user.misses >= THRESHOLD && now.duration_since(user.window_start) < WINDOW
+            })
+            .map(|(req, user)| HotUnknownUser {
+                user: req.clone(),
+                misses: user.misses,
+                total_misses: user.total_misses,
+            })
+            .collect::<Vec<_>>();
+        hot_users.sort_by(|a, b| b.misses.cmp(&a.misses));
+        hot_users
+    }
+}
@@ -0,0 +1,72 @@
+//! Normalizes the group names returned by the backends (such as DNs from Active Directory or paths
+//! from Keycloak), so that policies can rely on a consistent naming scheme.
+
+use regex::Regex;
+use snafu::{ResultExt, Snafu};
+use stackable_opa_crd::user_info_fetcher as crd;
+
+#[derive(Snafu, Debug)]
+pub enum Error {
+    #[snafu(display("invalid groupMapping regex {regex:?}"))]
+    InvalidRegex { source: regex::Error, regex: String },
+}
+
+pub struct GroupMapper {
+    strip_prefix: Option<String>,
+    regex: Option<(Regex, String)>,
+    lowercase: bool,
+}
+
+impl GroupMapper {
+    pub fn new(config: &crd::GroupMapping) -> Result<Self, Error> {
+        let crd::GroupMapping {
+            strip_prefix,
+            regex,
+            replacement,
+            lowercase,
+        } = config;
+        let regex = regex
+            .as_deref()
+            .map(|regex| {
+                // Only the whole group name is replaced, rather than every match within it
+                let anchored = format!("^(?:{regex})$");
+                Regex::new(&anchored).context(InvalidRegexSnafu { regex })
+            })
+            .transpose()?;
+        Ok(Self {
+            strip_prefix: strip_prefix.clone(),
+            regex: regex.map(|regex| (regex, replacement.clone())),
+            lowercase: *lowercase,
+        })
+    }
+
+    /// Maps all groups, dropping duplicates created by the mapping.
+    pub fn map_groups(&self, groups: Vec<String>) -> Vec<String> {
+        let mut mapped_groups = Vec::<String>::with_capacity(groups.len());
+        for group in groups {
+            let group = self.map_group(group);
+            if !mapped_groups.contains(&group) {
+                mapped_groups.push(group);
+            }
+        }
+        mapped_groups
+    }
+
+    /// Strips the prefix, then replaces the group name if it matches the regex, and finally lowercases it.
+    fn map_group(&self, group: String) -> String {
+        let mut group = match &self.strip_prefix {
+            Some(prefix) => group
+                .strip_prefix(prefix.as_str())
+                .map(str::to_string)
+                .unwrap_or(group),
+            None => group,
+        };
+        if let Some((regex, replacement)) = &self.regex {
+            group = regex.replace(&group, replacement.as_str()).into_owned();
+        }
+        if self.lowercase {
+            group = group.to_lowercase();
+        }
+        group
+    }
+}
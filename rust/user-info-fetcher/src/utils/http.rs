@@ -1,39 +1,175 @@
-use hyper::StatusCode;
-use reqwest::{RequestBuilder, Response};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{Duration, SystemTime},
+};
+
+use hyper::{header, HeaderMap, StatusCode};
+use reqwest::{Method, RequestBuilder, Response};
 use serde::de::DeserializeOwned;
 use snafu::{ResultExt, Snafu};
 
+use crate::utils::trace_context;
+
+/// Responses larger than this are rejected, to protect the user-info-fetcher from misbehaving backends.
+const MAX_RESPONSE_SIZE: usize = 16 * 1024 * 1024;
+/// How often idempotent requests are attempted before giving up on transient errors.
+const MAX_ATTEMPTS: u32 = 3;
+/// The backoff before the first retry, doubled for every further retry.
+const INITIAL_RETRY_BACKOFF: Duration = Duration::from_millis(100);
+/// Retries are delayed by at most this long when the backend asks for a longer delay (such as when
+/// rate limiting), so that lookups don't hang.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(10);
+
 #[derive(Snafu, Debug)]
 pub enum Error {
+    #[snafu(display("failed to build request"))]
+    BuildRequest { source: reqwest::Error },
+
     #[snafu(display("failed to execute request"))]
     HttpRequest { source: reqwest::Error },
 
-    #[snafu(display("failed to parse json response"))]
-    ParseJson { source: reqwest::Error },
+    #[snafu(display("failed to read response body from {url:?}"))]
+    ReadResponse { source: reqwest::Error, url: String },
+
+    #[snafu(display("response from {url:?} is larger than {limit} bytes"))]
+    ResponseTooLarge { url: String, limit: usize },
+
+    #[snafu(display("failed to parse json response from {url:?}"))]
+    ParseJson {
+        source: serde_json::Error,
+        url: String,
+    },
 
     #[snafu(display("http response {status:?} for {url:?} with response body {text:?}"))]
-    HttpErrorResponse {
+    HttpClientErrorResponse {
         status: StatusCode,
         url: String,
         text: String,
     },
 
-    #[snafu(display("http response {status:?} for {url:?} with an undecodable response body"))]
-    HttpErrorResponseUndecodableText {
+    #[snafu(display(
+        "http response {status:?} (transient) for {url:?} with response body {text:?}"
+    ))]
+    HttpTransientErrorResponse {
         status: StatusCode,
         url: String,
-        encoding_error: reqwest::Error,
+        text: String,
+        /// How long the backend asked to wait before retrying, if it did.
+        retry_after: Option<Duration>,
     },
 }
 
+impl Error {
+    /// Whether the error is likely to go away when retrying later, such as rate limiting (429),
+    /// server errors (5xx) and connection problems.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::HttpTransientErrorResponse { .. } => true,
+            Self::HttpRequest { source } => source.is_connect() || source.is_timeout(),
+            Self::BuildRequest { .. }
+            | Self::ReadResponse { .. }
+            | Self::ResponseTooLarge { .. }
+            | Self::ParseJson { .. }
+            | Self::HttpClientErrorResponse { .. } => false,
+        }
+    }
+
+    /// The status code to report when a backend request failed with this error.
+    ///
+    /// Transient errors are reported as `503 Service Unavailable` so that callers know that retrying later may help.
+    pub fn gateway_status_code(&self) -> StatusCode {
+        if self.is_transient() {
+            StatusCode::SERVICE_UNAVAILABLE
+        } else {
+            StatusCode::BAD_GATEWAY
+        }
+    }
+}
+
+/// Sends the request and parses the JSON response.
+///
+/// Idempotent `GET` requests are retried (with exponential backoff and jitter) on transient errors.
+/// The trace context of the incoming request is propagated, see [`trace_context`].
 pub async fn send_json_request<T: DeserializeOwned>(req: RequestBuilder) -> Result<T, Error> {
+    let (client, request) = trace_context::inject(req).build_split();
+    let mut request = request.context(BuildRequestSnafu)?;
+    let retryable = request.method() == Method::GET;
+
+    let mut attempt = 1;
+    loop {
+        // Requests with streaming bodies can't be cloned, and are never retried
+        let retry_request = if retryable && attempt < MAX_ATTEMPTS {
+            request.try_clone()
+        } else {
+            None
+        };
+        let result = send_request(&client, request).await;
+        match (result, retry_request) {
+            (Err(error), Some(retry_request)) if error.is_transient() => {
+                let backoff = match &error {
+                    Error::HttpTransientErrorResponse {
+                        retry_after: Some(retry_after),
+                        ..
+                    } => (*retry_after).min(MAX_RETRY_AFTER),
+                    _ => retry_backoff(attempt),
+                };
+                tracing::info!(
+                    error = &error as &dyn std::error::Error,
+                    attempt,
+                    ?backoff,
+                    "request failed with transient error, retrying"
+                );
+                tokio::time::sleep(backoff).await;
+                request = retry_request;
+                attempt += 1;
+            }
+            (result, _) => return result,
+        }
+    }
+}
+
+async fn send_request<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    request: reqwest::Request,
+) -> Result<T, Error> {
     // make the request
-    let response = req.send().await.context(HttpRequestSnafu)?;
+    let response = client.execute(request).await.context(HttpRequestSnafu)?;
     // check for client or server errors
     let non_error_response = error_for_status(response).await?;
     // parse the result
-    let result = non_error_response.json().await.context(ParseJsonSnafu)?;
-    Ok(result)
+    let url = non_error_response.url().to_string();
+    let body = read_limited_body(non_error_response).await?;
+    serde_json::from_slice(&body).context(ParseJsonSnafu { url })
+}
+
+/// Exponential backoff with full jitter, see <https://aws.amazon.com/blogs/architecture/exponential-backoff-and-jitter/>.
+fn retry_backoff(attempt: u32) -> Duration {
+    let max_backoff = INITIAL_RETRY_BACKOFF * 2u32.pow(attempt - 1);
+    // RandomState is randomly seeded, which is good enough for jitter and saves a dependency
+    let random = RandomState::new().build_hasher().finish();
+    max_backoff.mul_f64((random as f64) / (u64::MAX as f64))
+}
+
+/// Reads the response body, failing if it exceeds [`MAX_RESPONSE_SIZE`].
+async fn read_limited_body(mut response: Response) -> Result<Vec<u8>, Error> {
+    let url = response.url().to_string();
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .context(ReadResponseSnafu { url: &url })?
+    {
+        if body.len() + chunk.len() > MAX_RESPONSE_SIZE {
+            return ResponseTooLargeSnafu {
+                url,
+                limit: MAX_RESPONSE_SIZE,
+            }
+            .fail();
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok(body)
 }
 
 /// Wraps a Response into a Result. If there is an HTTP Client or Server error,
@@ -44,20 +180,40 @@ async fn error_for_status(response: Response) -> Result<Response, Error> {
     let status = response.status();
     if status.is_client_error() || status.is_server_error() {
         let url = response.url().to_string();
-        return match response.text().await {
-            Ok(text) => HttpErrorResponseSnafu {
+        let retry_after = retry_after(response.headers());
+        let body = read_limited_body(response).await?;
+        let text = String::from_utf8_lossy(&body);
+        return if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+            HttpTransientErrorResponseSnafu {
                 status,
                 url,
                 text: text.trim(),
+                retry_after,
             }
-            .fail(),
-            Err(encoding_error) => HttpErrorResponseUndecodableTextSnafu {
+            .fail()
+        } else {
+            HttpClientErrorResponseSnafu {
                 status,
                 url,
-                encoding_error,
+                text: text.trim(),
             }
-            .fail(),
+            .fail()
         };
     }
     Ok(response)
 }
+
+/// How long the backend asked to wait before retrying, either using the `Retry-After` header (in
+/// seconds), or the `X-Rate-Limit-Reset` header (the Unix time the rate limit resets, as sent by Okta).
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<u64>().ok();
+    if let Some(seconds) = header(header::RETRY_AFTER.as_str()) {
+        return Some(Duration::from_secs(seconds));
+    }
+    let reset = header("x-rate-limit-reset")?;
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some(Duration::from_secs(reset.saturating_sub(now)))
+}
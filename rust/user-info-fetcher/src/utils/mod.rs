@@ -1,2 +1,5 @@
+pub mod group_mapping;
 pub mod http;
 pub mod tls;
+pub mod trace_context;
+pub mod unknown_keys;
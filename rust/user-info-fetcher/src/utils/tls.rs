@@ -23,9 +23,13 @@ pub enum Error {
 }
 
 /// Configures a [`reqwest`] client according to the specified TLS configuration
+///
+/// The PEM encoded CA certificates of the `trust_store` are trusted in addition to the system CA
+/// certificates, unless the TLS configuration specifies its own CA certificates.
 // NOTE: MUST be kept in sync with all configure_* functions
 pub async fn configure_reqwest(
     tls: &TlsClientDetails,
+    trust_store: Option<&[u8]>,
     builder: reqwest::ClientBuilder,
 ) -> Result<reqwest::ClientBuilder, Error> {
     Ok(if tls.uses_tls() && !tls.uses_tls_verification() {
@@ -42,36 +46,54 @@ pub async fn configure_reqwest(
             builder.tls_built_in_root_certs(false),
             reqwest::ClientBuilder::add_root_certificate,
         )
+    } else if let Some(trust_store) = trust_store {
+        reqwest::Certificate::from_pem_bundle(trust_store)
+            .context(ParseCaBundleReqwestSnafu)?
+            .into_iter()
+            .fold(builder, reqwest::ClientBuilder::add_root_certificate)
     } else {
         builder
     })
 }
 
 /// Configures a [`native_tls`] connector according to the specified TLS configuration
+///
+/// See [`configure_reqwest`] for how the `trust_store` is used.
 // NOTE: MUST be kept in sync with all configure_* functions
 pub async fn configure_native_tls(
     tls: &TlsClientDetails,
+    trust_store: Option<&[u8]>,
 ) -> Result<native_tls::TlsConnector, Error> {
     let mut builder = native_tls::TlsConnector::builder();
     if tls.uses_tls() && !tls.uses_tls_verification() {
         builder.danger_accept_invalid_certs(true);
     } else if let Some(tls_ca_cert_mount_path) = tls.tls_ca_cert_mount_path() {
         builder.disable_built_in_roots(true);
-        // native-tls doesn't support parsing CA *bundles*, so split them using rustls first
-        for ca_cert in rustls_pemfile::certs(&mut Cursor::new(
-            read_file(&tls_ca_cert_mount_path)
+        for ca_cert in native_tls_certificates(
+            &read_file(&tls_ca_cert_mount_path)
                 .await
                 .context(ReadCaBundleSnafu)?,
-        )) {
-            builder.add_root_certificate(
-                native_tls::Certificate::from_der(&ca_cert.context(SplitCaBundleSnafu)?)
-                    .context(ParseCaCertNativeTlsSnafu)?,
-            );
+        )? {
+            builder.add_root_certificate(ca_cert);
+        }
+    } else if let Some(trust_store) = trust_store {
+        for ca_cert in native_tls_certificates(trust_store)? {
+            builder.add_root_certificate(ca_cert);
         }
     }
     builder.build().context(BuildNativeTlsConnectorSnafu)
 }
 
+fn native_tls_certificates(ca_bundle: &[u8]) -> Result<Vec<native_tls::Certificate>, Error> {
+    // native-tls doesn't support parsing CA *bundles*, so split them using rustls first
+    rustls_pemfile::certs(&mut Cursor::new(ca_bundle))
+        .map(|ca_cert| {
+            native_tls::Certificate::from_der(&ca_cert.context(SplitCaBundleSnafu)?)
+                .context(ParseCaCertNativeTlsSnafu)
+        })
+        .collect()
+}
+
 async fn read_file(path: &impl AsRef<Path>) -> Result<Vec<u8>, std::io::Error> {
     let mut buf = Vec::<u8>::new();
     File::open(path).await?.read_to_end(&mut buf).await?;
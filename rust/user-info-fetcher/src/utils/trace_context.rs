@@ -0,0 +1,58 @@
+//! Propagation of the [W3C trace context](https://www.w3.org/TR/trace-context/) from incoming
+//! requests to the HTTP requests made to the backends, so that slow lookups can be followed end to end.
+
+use std::future::Future;
+
+use reqwest::{
+    header::{HeaderMap, HeaderValue},
+    RequestBuilder,
+};
+
+const TRACEPARENT_HEADER: &str = "traceparent";
+const TRACESTATE_HEADER: &str = "tracestate";
+
+tokio::task_local! {
+    static TRACE_CONTEXT: TraceContext;
+}
+
+#[derive(Clone, Debug)]
+pub struct TraceContext {
+    traceparent: HeaderValue,
+    tracestate: Option<HeaderValue>,
+}
+
+impl TraceContext {
+    /// Extracts the trace context of an incoming request, if any.
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        Some(Self {
+            traceparent: headers.get(TRACEPARENT_HEADER)?.clone(),
+            tracestate: headers.get(TRACESTATE_HEADER).cloned(),
+        })
+    }
+
+    /// The ID of the trace, which is the second field of the `traceparent` header
+    /// (`{version}-{trace-id}-{parent-id}-{trace-flags}`).
+    pub fn trace_id(&self) -> Option<&str> {
+        self.traceparent.to_str().ok()?.split('-').nth(1)
+    }
+
+    /// Runs `fut` with the trace context being propagated to all backend requests made by it.
+    pub async fn scope<F: Future>(trace_context: Option<Self>, fut: F) -> F::Output {
+        match trace_context {
+            Some(trace_context) => TRACE_CONTEXT.scope(trace_context, fut).await,
+            None => fut.await,
+        }
+    }
+}
+
+/// Adds the trace context of the current request (if any) to the backend request.
+pub fn inject(req: RequestBuilder) -> RequestBuilder {
+    let Ok(trace_context) = TRACE_CONTEXT.try_with(TraceContext::clone) else {
+        return req;
+    };
+    let req = req.header(TRACEPARENT_HEADER, trace_context.traceparent);
+    match trace_context.tracestate {
+        Some(tracestate) => req.header(TRACESTATE_HEADER, tracestate),
+        None => req,
+    }
+}
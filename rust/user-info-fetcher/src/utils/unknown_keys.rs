@@ -0,0 +1,64 @@
+//! Finds keys of the configuration that are not part of the configuration schema (such as
+//! misspelled keys), which serde ignores silently.
+//!
+//! The parsed configuration is serialized again and compared with the original JSON, since
+//! `#[serde(deny_unknown_fields)]` does not support the flattened TLS settings of the backends.
+
+use serde_json::Value;
+
+/// Returns the paths (such as `backend.experimentalActiveDirectory.groupSearchBase`) of the keys of
+/// `original` that are missing in `parsed`.
+///
+/// Keys with empty values (`null`, `[]` and `{}`) are not reported, since they are usually left out
+/// when serializing.
+pub fn unknown_keys(original: &Value, parsed: &Value) -> Vec<String> {
+    let mut unknown_keys = Vec::new();
+    collect_unknown_keys(original, parsed, "", &mut unknown_keys);
+    unknown_keys
+}
+
+fn collect_unknown_keys(
+    original: &Value,
+    parsed: &Value,
+    path: &str,
+    unknown_keys: &mut Vec<String>,
+) {
+    match (original, parsed) {
+        (Value::Object(original), Value::Object(parsed)) => {
+            for (key, original_value) in original {
+                let key_path = if path.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{path}.{key}")
+                };
+                match parsed.get(key) {
+                    Some(parsed_value) => {
+                        collect_unknown_keys(original_value, parsed_value, &key_path, unknown_keys)
+                    }
+                    None if !is_empty(original_value) => unknown_keys.push(key_path),
+                    None => {}
+                }
+            }
+        }
+        (Value::Array(original), Value::Array(parsed)) => {
+            for (i, (original_value, parsed_value)) in original.iter().zip(parsed).enumerate() {
+                collect_unknown_keys(
+                    original_value,
+                    parsed_value,
+                    &format!("{path}[{i}]"),
+                    unknown_keys,
+                );
+            }
+        }
+        _ => {}
+    }
+}
+
+fn is_empty(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(array) => array.is_empty(),
+        Value::Object(object) => object.is_empty(),
+        _ => false,
+    }
+}